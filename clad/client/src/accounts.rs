@@ -0,0 +1,130 @@
+use {
+    anchor_lang::{error::ErrorCode, prelude::*, AccountDeserialize, Discriminator},
+    clad::state::{
+        Clad, Globalpool, LiquidityPosition, MarginAccount, PositionBundle, PositionOperator,
+        TickArray, TickArrayBitmap, TickArrayExtension, TokenPriceFeed, TradePosition,
+    },
+};
+
+//
+// Decoders of Clad's accounts from their raw data, checking the account discriminator
+//
+
+pub fn decode_account<T: AccountDeserialize>(data: &[u8]) -> Result<T> {
+    let mut data = data;
+    T::try_deserialize(&mut data)
+}
+
+pub fn decode_clad(data: &[u8]) -> Result<Clad> {
+    decode_account(data)
+}
+
+pub fn decode_globalpool(data: &[u8]) -> Result<Globalpool> {
+    decode_account(data)
+}
+
+pub fn decode_liquidity_position(data: &[u8]) -> Result<LiquidityPosition> {
+    decode_account(data)
+}
+
+pub fn decode_trade_position(data: &[u8]) -> Result<TradePosition> {
+    decode_account(data)
+}
+
+pub fn decode_position_bundle(data: &[u8]) -> Result<PositionBundle> {
+    decode_account(data)
+}
+
+pub fn decode_position_operator(data: &[u8]) -> Result<PositionOperator> {
+    decode_account(data)
+}
+
+pub fn decode_margin_account(data: &[u8]) -> Result<MarginAccount> {
+    decode_account(data)
+}
+
+pub fn decode_tick_array_bitmap(data: &[u8]) -> Result<TickArrayBitmap> {
+    decode_account(data)
+}
+
+pub fn decode_token_price_feed(data: &[u8]) -> Result<TokenPriceFeed> {
+    decode_account(data)
+}
+
+// TickArray is a zero-copy account, so its data is the struct itself after the discriminator
+pub fn decode_tick_array(data: &[u8]) -> Result<TickArray> {
+    if data.len() < TickArray::LEN {
+        return Err(ErrorCode::AccountDidNotDeserialize.into());
+    }
+    if data[..8] != TickArray::discriminator() {
+        return Err(ErrorCode::AccountDiscriminatorMismatch.into());
+    }
+
+    Ok(*bytemuck::from_bytes::<TickArray>(&data[8..TickArray::LEN]))
+}
+
+// The extension holding range orders follows the TickArray, none until the account is migrated
+pub fn decode_tick_array_extension(data: &[u8]) -> Result<Option<TickArrayExtension>> {
+    decode_tick_array(data)?;
+    if data.len() < TickArrayExtension::TICK_ARRAY_LEN {
+        return Ok(None);
+    }
+
+    Ok(Some(*bytemuck::from_bytes::<TickArrayExtension>(
+        &data[TickArray::LEN..TickArrayExtension::TICK_ARRAY_LEN],
+    )))
+}
+
+#[cfg(test)]
+mod decode_tests {
+    use super::*;
+
+    #[test]
+    fn decode_clad_account() {
+        let mut data = Vec::new();
+        Clad {
+            protocol_fee_rate: 300,
+            clad_bump: 254,
+            ..Clad::default()
+        }
+        .try_serialize(&mut data)
+        .unwrap();
+
+        let clad = decode_clad(&data).unwrap();
+        assert_eq!(clad.protocol_fee_rate, 300);
+        assert_eq!(clad.clad_bump, 254);
+    }
+
+    #[test]
+    fn decode_wrong_account() {
+        let mut data = Vec::new();
+        Clad::default().try_serialize(&mut data).unwrap();
+
+        assert!(decode_globalpool(&data).is_err());
+    }
+
+    #[test]
+    fn decode_tick_array_account() {
+        let mut data = vec![0; TickArray::LEN];
+        data[..8].copy_from_slice(&TickArray::discriminator());
+        data[8..12].copy_from_slice(&(-5_632i32).to_le_bytes());
+        let tick_array = decode_tick_array(&data).unwrap();
+        assert_eq!({ tick_array.start_tick_index }, -5_632);
+
+        assert!(decode_tick_array(&data[..TickArray::LEN - 1]).is_err());
+        data[0] ^= 1;
+        assert!(decode_tick_array(&data).is_err());
+    }
+
+    #[test]
+    fn decode_tick_array_extension_account() {
+        let mut data = vec![0; TickArray::LEN];
+        data[..8].copy_from_slice(&TickArray::discriminator());
+        assert!(decode_tick_array_extension(&data).unwrap().is_none());
+
+        data.resize(TickArrayExtension::TICK_ARRAY_LEN, 0);
+        data[TickArray::LEN + 24] = 1;
+        let extension = decode_tick_array_extension(&data).unwrap().unwrap();
+        assert_eq!({ extension.range_orders[0].a_to_b }, true);
+    }
+}
@@ -0,0 +1,395 @@
+pub use clad::instructions::{
+    ClaimRangeOrderParams, CompoundFeesParams, CreatePoolParams, CreatePoolWithLiquidityParams,
+    DecreaseLiquidityParams, DepositMarginCollateralParams, FlashBorrowParams,
+    IncreaseLiquidityByTokenAmountParams, IncreaseLiquidityParams, InitializeCladParams,
+    InitializeTickArrayParams, OpenBundledLiquidityPositionParams,
+    OpenLeveragedLiquidityPositionParams, OpenLiquidityPositionParams, OpenRangeOrderParams,
+    OpenTradePositionParams, QuoteSwapParams, QuoteSwapResult, QuoteTradePositionResult,
+    RepayLeveragedLiquidityPositionParams, RepayTradePositionParams, SetDynamicFeeParametersParams,
+    SetPositionOperatorParams, SetRiskParametersParams, SplitLiquidityPositionParams, SwapParams,
+    WithdrawMarginCollateralParams,
+};
+use {
+    anchor_lang::{prelude::*, solana_program::instruction::Instruction, InstructionData},
+    clad::{accounts, instruction},
+};
+
+//
+// Builders of every Clad instruction. Accounts are given as the program's typed account structs,
+// so that they are ordered and flagged as the program expects; see `crate::pda` to derive them.
+//
+
+fn build_instruction(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: clad::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+// Appends accounts passed to the program as remaining accounts
+pub fn with_remaining_accounts(
+    mut instruction: Instruction,
+    remaining_accounts: Vec<AccountMeta>,
+) -> Instruction {
+    instruction.accounts.extend(remaining_accounts);
+    instruction
+}
+
+// Enables native SOL mode: the authority's SOL is wrapped into its temporary wSOL account (see
+// `crate::pda::get_native_sol_account_address`), given as the token owner account of the native
+// mint, and unwrapped back within the instruction. Appends the System Program, so it must be applied
+// before any other remaining account, and makes the authority writable to fund the account.
+pub fn with_native_sol(mut instruction: Instruction, authority: &Pubkey) -> Instruction {
+    for account in instruction.accounts.iter_mut() {
+        if account.pubkey == *authority {
+            account.is_writable = true;
+        }
+    }
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(System::id(), false));
+    instruction
+}
+
+// Appends the hook program of a Globalpool and the accounts the hook reads, which `swap` and the
+// liquidity instructions require of pools with a hook. Must be applied after any other remaining
+// account.
+pub fn with_hook(
+    instruction: Instruction,
+    hook_program: &Pubkey,
+    hook_accounts: Vec<AccountMeta>,
+) -> Instruction {
+    let mut remaining_accounts = vec![AccountMeta::new_readonly(*hook_program, false)];
+    remaining_accounts.extend(hook_accounts);
+    with_remaining_accounts(instruction, remaining_accounts)
+}
+
+pub fn initialize_clad(
+    accounts: accounts::InitializeClad,
+    params: InitializeCladParams,
+) -> Instruction {
+    build_instruction(accounts, instruction::InitializeClad { params })
+}
+
+pub fn initialize_tick_array(
+    accounts: accounts::InitializeTickArray,
+    params: InitializeTickArrayParams,
+) -> Instruction {
+    build_instruction(accounts, instruction::InitializeTickArray { params })
+}
+
+pub fn close_tick_array(accounts: accounts::CloseTickArray) -> Instruction {
+    build_instruction(accounts, instruction::CloseTickArray {})
+}
+
+pub fn migrate_tick_array(accounts: accounts::MigrateTickArray) -> Instruction {
+    build_instruction(accounts, instruction::MigrateTickArray {})
+}
+
+// The tick arrays to record in the bitmap are passed as remaining accounts
+pub fn backfill_tick_array_bitmap(
+    accounts: accounts::BackfillTickArrayBitmap,
+    tick_arrays: &[Pubkey],
+) -> Instruction {
+    with_remaining_accounts(
+        build_instruction(accounts, instruction::BackfillTickArrayBitmap {}),
+        tick_arrays
+            .iter()
+            .map(|tick_array| AccountMeta::new_readonly(*tick_array, false))
+            .collect(),
+    )
+}
+
+pub fn set_tick_array_bitmap_backfilled(
+    accounts: accounts::SetTickArrayBitmapBackfilled,
+) -> Instruction {
+    build_instruction(accounts, instruction::SetTickArrayBitmapBackfilled {})
+}
+
+pub fn create_pool(accounts: accounts::CreatePool, params: CreatePoolParams) -> Instruction {
+    build_instruction(accounts, instruction::CreatePool { params })
+}
+
+// The seed position's tick arrays are the PDAs of `crate::pda::get_tick_array_address`, passed
+// twice if the range is within one tick array
+pub fn create_pool_with_liquidity(
+    accounts: accounts::CreatePoolWithLiquidity,
+    params: CreatePoolWithLiquidityParams,
+) -> Instruction {
+    build_instruction(accounts, instruction::CreatePoolWithLiquidity { params })
+}
+
+pub fn open_liquidity_position(
+    accounts: accounts::OpenLiquidityPosition,
+    params: OpenLiquidityPositionParams,
+) -> Instruction {
+    build_instruction(accounts, instruction::OpenLiquidityPosition { params })
+}
+
+pub fn open_liquidity_position_with_metadata(
+    accounts: accounts::OpenLiquidityPositionWithMetadata,
+    params: OpenLiquidityPositionParams,
+) -> Instruction {
+    build_instruction(
+        accounts,
+        instruction::OpenLiquidityPositionWithMetadata { params },
+    )
+}
+
+pub fn close_liquidity_position(accounts: accounts::CloseLiquidityPosition) -> Instruction {
+    build_instruction(accounts, instruction::CloseLiquidityPosition {})
+}
+
+pub fn split_liquidity_position(
+    accounts: accounts::SplitLiquidityPosition,
+    params: SplitLiquidityPositionParams,
+) -> Instruction {
+    build_instruction(accounts, instruction::SplitLiquidityPosition { params })
+}
+
+pub fn merge_liquidity_positions(accounts: accounts::MergeLiquidityPositions) -> Instruction {
+    build_instruction(accounts, instruction::MergeLiquidityPositions {})
+}
+
+pub fn initialize_position_bundle(accounts: accounts::InitializePositionBundle) -> Instruction {
+    build_instruction(accounts, instruction::InitializePositionBundle {})
+}
+
+pub fn delete_position_bundle(accounts: accounts::DeletePositionBundle) -> Instruction {
+    build_instruction(accounts, instruction::DeletePositionBundle {})
+}
+
+pub fn open_bundled_liquidity_position(
+    accounts: accounts::OpenBundledLiquidityPosition,
+    params: OpenBundledLiquidityPositionParams,
+) -> Instruction {
+    build_instruction(
+        accounts,
+        instruction::OpenBundledLiquidityPosition { params },
+    )
+}
+
+pub fn close_bundled_liquidity_position(
+    accounts: accounts::CloseBundledLiquidityPosition,
+    bundle_index: u16,
+) -> Instruction {
+    build_instruction(
+        accounts,
+        instruction::CloseBundledLiquidityPosition { bundle_index },
+    )
+}
+
+// Followed by the liquidity position collateral accounts, if any, and the Jupiter swap accounts as
+// remaining accounts (see `with_remaining_accounts`). Likewise for the `_with_metadata` variant.
+pub fn open_trade_position(
+    accounts: accounts::OpenTradePosition,
+    params: OpenTradePositionParams,
+) -> Instruction {
+    build_instruction(accounts, instruction::OpenTradePosition { params })
+}
+
+pub fn open_trade_position_with_metadata(
+    accounts: accounts::OpenTradePositionWithMetadata,
+    params: OpenTradePositionParams,
+) -> Instruction {
+    build_instruction(
+        accounts,
+        instruction::OpenTradePositionWithMetadata { params },
+    )
+}
+
+// Followed by the oracle accounts of a liquidated perpetual position, the liquidity position
+// collateral accounts, if any, and the Jupiter swap accounts as remaining accounts (see
+// `with_remaining_accounts`)
+pub fn repay_trade_position(
+    accounts: accounts::RepayTradePosition,
+    params: RepayTradePositionParams,
+) -> Instruction {
+    build_instruction(accounts, instruction::RepayTradePosition { params })
+}
+
+pub fn close_trade_position(accounts: accounts::CloseTradePosition) -> Instruction {
+    build_instruction(accounts, instruction::CloseTradePosition {})
+}
+
+pub fn open_leveraged_liquidity_position(
+    accounts: accounts::OpenLeveragedLiquidityPosition,
+    params: OpenLeveragedLiquidityPositionParams,
+) -> Instruction {
+    build_instruction(
+        accounts,
+        instruction::OpenLeveragedLiquidityPosition { params },
+    )
+}
+
+pub fn repay_leveraged_liquidity_position(
+    accounts: accounts::RepayLeveragedLiquidityPosition,
+    params: RepayLeveragedLiquidityPositionParams,
+) -> Instruction {
+    build_instruction(
+        accounts,
+        instruction::RepayLeveragedLiquidityPosition { params },
+    )
+}
+
+pub fn increase_liquidity(
+    accounts: accounts::ModifyLiquidity,
+    params: IncreaseLiquidityParams,
+) -> Instruction {
+    build_instruction(accounts, instruction::IncreaseLiquidity { params })
+}
+
+pub fn increase_liquidity_by_token_amount(
+    accounts: accounts::ModifyLiquidity,
+    params: IncreaseLiquidityByTokenAmountParams,
+) -> Instruction {
+    build_instruction(
+        accounts,
+        instruction::IncreaseLiquidityByTokenAmount { params },
+    )
+}
+
+pub fn decrease_liquidity(
+    accounts: accounts::ModifyLiquidity,
+    params: DecreaseLiquidityParams,
+) -> Instruction {
+    build_instruction(accounts, instruction::DecreaseLiquidity { params })
+}
+
+pub fn open_range_order(
+    accounts: accounts::ModifyLiquidity,
+    params: OpenRangeOrderParams,
+) -> Instruction {
+    build_instruction(accounts, instruction::OpenRangeOrder { params })
+}
+
+pub fn claim_range_order(
+    accounts: accounts::ModifyLiquidity,
+    params: ClaimRangeOrderParams,
+) -> Instruction {
+    build_instruction(accounts, instruction::ClaimRangeOrder { params })
+}
+
+pub fn collect_fees(accounts: accounts::CollectFees) -> Instruction {
+    build_instruction(accounts, instruction::CollectFees {})
+}
+
+pub fn compound_fees(accounts: accounts::CompoundFees, params: CompoundFeesParams) -> Instruction {
+    build_instruction(accounts, instruction::CompoundFees { params })
+}
+
+pub fn set_position_operator(
+    accounts: accounts::SetPositionOperator,
+    params: SetPositionOperatorParams,
+) -> Instruction {
+    build_instruction(accounts, instruction::SetPositionOperator { params })
+}
+
+pub fn remove_position_operator(accounts: accounts::RemovePositionOperator) -> Instruction {
+    build_instruction(accounts, instruction::RemovePositionOperator {})
+}
+
+pub fn collect_protocol_fees(accounts: accounts::CollectProtocolFees) -> Instruction {
+    build_instruction(accounts, instruction::CollectProtocolFees {})
+}
+
+pub fn flash_borrow(accounts: accounts::FlashBorrow, params: FlashBorrowParams) -> Instruction {
+    build_instruction(accounts, instruction::FlashBorrow { params })
+}
+
+pub fn flash_repay(accounts: accounts::FlashRepay) -> Instruction {
+    build_instruction(accounts, instruction::FlashRepay {})
+}
+
+pub fn crank_funding(accounts: accounts::CrankFunding) -> Instruction {
+    build_instruction(accounts, instruction::CrankFunding {})
+}
+
+pub fn set_flash_fee_rate(accounts: accounts::SetFlashFeeRate, flash_fee_rate: u16) -> Instruction {
+    build_instruction(accounts, instruction::SetFlashFeeRate { flash_fee_rate })
+}
+
+pub fn set_risk_parameters(
+    accounts: accounts::SetRiskParameters,
+    params: SetRiskParametersParams,
+) -> Instruction {
+    build_instruction(accounts, instruction::SetRiskParameters { params })
+}
+
+pub fn set_dynamic_fee_parameters(
+    accounts: accounts::SetDynamicFeeParameters,
+    params: SetDynamicFeeParametersParams,
+) -> Instruction {
+    build_instruction(accounts, instruction::SetDynamicFeeParameters { params })
+}
+
+pub fn set_token_price_feed(accounts: accounts::SetTokenPriceFeed) -> Instruction {
+    build_instruction(accounts, instruction::SetTokenPriceFeed {})
+}
+
+pub fn initialize_margin_account(accounts: accounts::InitializeMarginAccount) -> Instruction {
+    build_instruction(accounts, instruction::InitializeMarginAccount {})
+}
+
+pub fn deposit_margin_collateral(
+    accounts: accounts::DepositMarginCollateral,
+    params: DepositMarginCollateralParams,
+) -> Instruction {
+    build_instruction(accounts, instruction::DepositMarginCollateral { params })
+}
+
+pub fn withdraw_margin_collateral(
+    accounts: accounts::WithdrawMarginCollateral,
+    params: WithdrawMarginCollateralParams,
+) -> Instruction {
+    build_instruction(accounts, instruction::WithdrawMarginCollateral { params })
+}
+
+pub fn link_trade_position(accounts: accounts::LinkTradePosition) -> Instruction {
+    build_instruction(accounts, instruction::LinkTradePosition {})
+}
+
+pub fn unlink_trade_position(accounts: accounts::UnlinkTradePosition) -> Instruction {
+    build_instruction(accounts, instruction::UnlinkTradePosition {})
+}
+
+pub fn liquidate_margin_account(accounts: accounts::LiquidateMarginAccount) -> Instruction {
+    build_instruction(accounts, instruction::LiquidateMarginAccount {})
+}
+
+// See `swap_with_tick_arrays` to pass the tick array bitmap and tick arrays beyond `tick_array_2`
+pub fn swap(accounts: accounts::Swap, params: SwapParams) -> Instruction {
+    build_instruction(accounts, instruction::Swap { params })
+}
+
+// `swap` crossing more than three tick arrays. The globalpool's tick array bitmap, if given, lets
+// `additional_tick_arrays` skip tick arrays that are not initialized.
+pub fn swap_with_tick_arrays(
+    accounts: accounts::Swap,
+    params: SwapParams,
+    tick_array_bitmap: Option<Pubkey>,
+    additional_tick_arrays: &[Pubkey],
+) -> Instruction {
+    let remaining_accounts = tick_array_bitmap
+        .iter()
+        .map(|tick_array_bitmap| AccountMeta::new_readonly(*tick_array_bitmap, false))
+        .chain(
+            additional_tick_arrays
+                .iter()
+                .map(|tick_array| AccountMeta::new(*tick_array, false)),
+        )
+        .collect();
+
+    with_remaining_accounts(swap(accounts, params), remaining_accounts)
+}
+
+// Read-only quotes. Their results are returned as borsh-encoded `QuoteSwapResult` and
+// `QuoteTradePositionResult` in the transaction's return data.
+pub fn quote_swap(accounts: accounts::QuoteSwap, params: QuoteSwapParams) -> Instruction {
+    build_instruction(accounts, instruction::QuoteSwap { params })
+}
+
+pub fn quote_trade_position(accounts: accounts::QuoteTradePosition) -> Instruction {
+    build_instruction(accounts, instruction::QuoteTradePosition {})
+}
@@ -0,0 +1,9 @@
+//! Client of the Clad program: instruction builders, PDA and tick array helpers, and account
+//! decoders and swap quotes for off-chain programs.
+
+pub mod accounts;
+pub mod instructions;
+pub mod pda;
+pub mod tick_array;
+
+pub use clad::{quote, ID};
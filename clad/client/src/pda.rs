@@ -0,0 +1,123 @@
+use anchor_lang::prelude::*;
+
+//
+// Addresses of Clad's program-derived accounts, with the seeds used by the program
+//
+
+pub fn get_clad_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"clad"], &clad::ID).0
+}
+
+pub fn get_globalpool_address(
+    token_mint_a: &Pubkey,
+    token_mint_b: &Pubkey,
+    fee_rate: u16,
+    tick_spacing: u16,
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"globalpool",
+            token_mint_a.as_ref(),
+            token_mint_b.as_ref(),
+            &fee_rate.to_le_bytes(),
+            &tick_spacing.to_le_bytes(),
+        ],
+        &clad::ID,
+    )
+    .0
+}
+
+// Token vaults of a Globalpool are its associated token accounts
+pub fn get_token_vault_address(
+    globalpool: &Pubkey,
+    token_mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Pubkey {
+    spl_associated_token_account::get_associated_token_address_with_program_id(
+        globalpool,
+        token_mint,
+        token_program,
+    )
+}
+
+pub fn get_tick_array_address(globalpool: &Pubkey, start_tick_index: i32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"tick_array",
+            globalpool.as_ref(),
+            start_tick_index.to_string().as_bytes(),
+        ],
+        &clad::ID,
+    )
+    .0
+}
+
+pub fn get_tick_array_bitmap_address(globalpool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"tick_array_bitmap", globalpool.as_ref()], &clad::ID).0
+}
+
+pub fn get_liquidity_position_address(position_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"liquidity_position", position_mint.as_ref()], &clad::ID).0
+}
+
+pub fn get_trade_position_address(position_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"trade_position", position_mint.as_ref()], &clad::ID).0
+}
+
+pub fn get_position_bundle_address(position_bundle_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"position_bundle", position_bundle_mint.as_ref()],
+        &clad::ID,
+    )
+    .0
+}
+
+pub fn get_bundled_liquidity_position_address(
+    position_bundle_mint: &Pubkey,
+    bundle_index: u16,
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"bundled_liquidity_position",
+            position_bundle_mint.as_ref(),
+            &bundle_index.to_le_bytes(),
+        ],
+        &clad::ID,
+    )
+    .0
+}
+
+pub fn get_position_operator_address(position: &Pubkey, operator: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"position_operator", position.as_ref(), operator.as_ref()],
+        &clad::ID,
+    )
+    .0
+}
+
+// Temporary wSOL account of an authority in native SOL mode, see `instructions::with_native_sol`
+pub fn get_native_sol_account_address(authority: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"native_sol", authority.as_ref()], &clad::ID).0
+}
+
+pub fn get_margin_account_address(owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"margin_account", owner.as_ref()], &clad::ID).0
+}
+
+pub fn get_token_price_feed_address(token_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"token_price_feed", token_mint.as_ref()], &clad::ID).0
+}
+
+// ProgramData of Clad, holding the upgrade authority that acts as protocol admin
+pub fn get_program_data_address() -> Pubkey {
+    Pubkey::find_program_address(
+        &[clad::ID.as_ref()],
+        &anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+    )
+    .0
+}
+
+// Metaplex metadata of a position token
+pub fn get_position_metadata_address(position_mint: &Pubkey) -> Pubkey {
+    mpl_token_metadata::pda::find_metadata_account(position_mint).0
+}
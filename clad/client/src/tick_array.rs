@@ -0,0 +1,91 @@
+use clad::state::{MAX_TICK_INDEX, MIN_TICK_INDEX, TICK_ARRAY_SIZE};
+
+// Maximum number of tick arrays in the fixed accounts of a swap
+pub const SWAP_TICK_ARRAYS: usize = 3;
+
+pub fn ticks_in_array(tick_spacing: u16) -> i32 {
+    TICK_ARRAY_SIZE * tick_spacing as i32
+}
+
+// Start index of the tick array holding `tick_index`, moved by `offset` tick arrays
+pub fn get_start_tick_index(tick_index: i32, tick_spacing: u16, offset: i32) -> i32 {
+    let ticks_in_array = ticks_in_array(tick_spacing);
+    (tick_index.div_euclid(ticks_in_array) + offset) * ticks_in_array
+}
+
+// Start indices of the `count` tick arrays a swap from `tick_current_index` goes through,
+// stopping at the tick array holding the min/max tick index
+pub fn get_swap_start_tick_indices(
+    tick_current_index: i32,
+    tick_spacing: u16,
+    a_to_b: bool,
+    count: usize,
+) -> Vec<i32> {
+    let ticks_in_array = ticks_in_array(tick_spacing);
+
+    // A b_to_a swap starting on the last tick of a tick array searches from the next one
+    let shift = if a_to_b { 0 } else { tick_spacing as i32 };
+    let mut start_tick_index = get_start_tick_index(tick_current_index + shift, tick_spacing, 0);
+
+    let mut start_tick_indices = Vec::with_capacity(count);
+    while start_tick_indices.len() < count
+        && start_tick_index + ticks_in_array > MIN_TICK_INDEX
+        && start_tick_index <= MAX_TICK_INDEX
+    {
+        start_tick_indices.push(start_tick_index);
+        start_tick_index += if a_to_b {
+            -ticks_in_array
+        } else {
+            ticks_in_array
+        };
+    }
+
+    start_tick_indices
+}
+
+#[cfg(test)]
+mod tick_array_tests {
+    use super::*;
+
+    #[test]
+    fn start_tick_index() {
+        assert_eq!(get_start_tick_index(0, 64, 0), 0);
+        assert_eq!(get_start_tick_index(5_631, 64, 0), 0);
+        assert_eq!(get_start_tick_index(5_632, 64, 0), 5_632);
+        assert_eq!(get_start_tick_index(-1, 64, 0), -5_632);
+        assert_eq!(get_start_tick_index(-1, 64, 2), 5_632);
+        assert_eq!(get_start_tick_index(100, 1, -1), 0);
+    }
+
+    #[test]
+    fn swap_start_tick_indices() {
+        assert_eq!(
+            get_swap_start_tick_indices(100, 64, true, SWAP_TICK_ARRAYS),
+            vec![0, -5_632, -11_264]
+        );
+        assert_eq!(
+            get_swap_start_tick_indices(100, 64, false, SWAP_TICK_ARRAYS),
+            vec![0, 5_632, 11_264]
+        );
+        // On the last tick of a tick array, b_to_a swaps start from the next tick array
+        assert_eq!(
+            get_swap_start_tick_indices(5_568, 64, false, 2),
+            vec![5_632, 11_264]
+        );
+    }
+
+    #[test]
+    fn swap_start_tick_indices_stop_at_bounds() {
+        let max_start_tick_index = get_start_tick_index(MAX_TICK_INDEX, 64, 0);
+        assert_eq!(
+            get_swap_start_tick_indices(MAX_TICK_INDEX - 1, 64, false, SWAP_TICK_ARRAYS),
+            vec![max_start_tick_index]
+        );
+
+        let min_start_tick_index = get_start_tick_index(MIN_TICK_INDEX, 64, 0);
+        assert_eq!(
+            get_swap_start_tick_indices(MIN_TICK_INDEX, 64, true, SWAP_TICK_ARRAYS),
+            vec![min_start_tick_index]
+        );
+    }
+}
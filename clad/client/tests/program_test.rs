@@ -0,0 +1,937 @@
+use {
+    anchor_lang::{
+        prelude::*,
+        solana_program::{
+            program::{invoke, invoke_signed},
+            system_program,
+        },
+    },
+    clad::{
+        accounts,
+        manager::loan_manager::calculate_trade_position_repayment,
+        math::{get_amount_delta_b, sqrt_price_from_tick_index, MIN_SQRT_PRICE_X64},
+        quote::{quote_swap, SwapQuoteParams},
+        state::Permissions,
+    },
+    clad_client::{
+        accounts::{
+            decode_clad, decode_globalpool, decode_liquidity_position, decode_tick_array,
+            decode_tick_array_bitmap, decode_tick_array_extension, decode_trade_position,
+        },
+        instructions::{
+            backfill_tick_array_bitmap, close_tick_array, close_trade_position, create_pool,
+            decrease_liquidity, flash_borrow, flash_repay, increase_liquidity, initialize_clad,
+            initialize_tick_array, open_liquidity_position, open_trade_position,
+            repay_trade_position, set_flash_fee_rate, swap, with_remaining_accounts,
+            CreatePoolParams, DecreaseLiquidityParams, FlashBorrowParams, IncreaseLiquidityParams,
+            InitializeCladParams, InitializeTickArrayParams, OpenLiquidityPositionParams,
+            OpenTradePositionParams, RepayTradePositionParams, SwapParams,
+        },
+        pda::{
+            get_clad_address, get_globalpool_address, get_liquidity_position_address,
+            get_tick_array_address, get_tick_array_bitmap_address, get_token_vault_address,
+            get_trade_position_address,
+        },
+        tick_array::{get_start_tick_index, get_swap_start_tick_indices, SWAP_TICK_ARRAYS},
+    },
+    solana_program_test::{processor, ProgramTest, ProgramTestContext},
+    solana_sdk::{
+        instruction::Instruction,
+        program_pack::Pack,
+        signature::{Keypair, Signer},
+        system_instruction, sysvar,
+        transaction::Transaction,
+    },
+    spl_associated_token_account::{
+        get_associated_token_address, instruction::create_associated_token_account,
+    },
+};
+
+const FEE_RATE: u16 = 3000;
+const TICK_SPACING: u16 = 64;
+
+// Liquidity position of `create_globalpool_with_liquidity`, around the initial tick 0
+const POSITION_TICK_LOWER_INDEX: i32 = -2560;
+const POSITION_TICK_UPPER_INDEX: i32 = 2560;
+const POSITION_LIQUIDITY: u128 = 1_000_000_000;
+const OWNER_TOKEN_AMOUNT: u64 = 1_000_000_000_000;
+
+// Seed of the mock Jupiter router's authority over its market token accounts
+const MARKET_AUTHORITY_SEED: &[u8] = b"market";
+
+// Anchor's entrypoint ties the accounts to the lifetime of their slice
+fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    clad::entry(program_id, accounts, instruction_data)
+}
+
+// Stands in for the Jupiter router that trade positions swap through. Moves `amount_in` from the
+// vault the globalpool signs for to the market, and `amount_out` from the market to the other
+// vault, both given as little-endian u64 in the instruction data.
+//
+// Accounts: [token_program, globalpool, vault_in, market_in, market_out, vault_out, market_authority]
+fn process_mock_jupiter_swap(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let amount_in = u64::from_le_bytes(instruction_data[..8].try_into().unwrap());
+    let amount_out = u64::from_le_bytes(instruction_data[8..16].try_into().unwrap());
+    let (token_program, globalpool, vault_in, market_in, market_out, vault_out, market_authority) = (
+        &accounts[0],
+        &accounts[1],
+        &accounts[2],
+        &accounts[3],
+        &accounts[4],
+        &accounts[5],
+        &accounts[6],
+    );
+
+    invoke(
+        &spl_token::instruction::transfer(
+            token_program.key,
+            vault_in.key,
+            market_in.key,
+            globalpool.key,
+            &[],
+            amount_in,
+        )?,
+        &[
+            vault_in.clone(),
+            market_in.clone(),
+            globalpool.clone(),
+            token_program.clone(),
+        ],
+    )?;
+
+    let (_, bump) = Pubkey::find_program_address(&[MARKET_AUTHORITY_SEED], program_id);
+    invoke_signed(
+        &spl_token::instruction::transfer(
+            token_program.key,
+            market_out.key,
+            vault_out.key,
+            market_authority.key,
+            &[],
+            amount_out,
+        )?,
+        &[
+            market_out.clone(),
+            vault_out.clone(),
+            market_authority.clone(),
+            token_program.clone(),
+        ],
+        &[&[MARKET_AUTHORITY_SEED, &[bump]]],
+    )
+}
+
+async fn start() -> ProgramTestContext {
+    let mut program_test = ProgramTest::new("clad", clad::ID, processor!(process_instruction));
+    program_test.add_program(
+        "jupiter",
+        clad::jupiter::ID,
+        processor!(process_mock_jupiter_swap),
+    );
+    program_test.start_with_context().await
+}
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) {
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+async fn get_account_data(context: &mut ProgramTestContext, address: &Pubkey) -> Vec<u8> {
+    context
+        .banks_client
+        .get_account(*address)
+        .await
+        .unwrap()
+        .unwrap()
+        .data
+}
+
+async fn get_token_balance(context: &mut ProgramTestContext, address: &Pubkey) -> u64 {
+    spl_token::state::Account::unpack(&get_account_data(context, address).await)
+        .unwrap()
+        .amount
+}
+
+async fn initialize(context: &mut ProgramTestContext) {
+    let instruction = initialize_clad(
+        accounts::InitializeClad {
+            admin: context.payer.pubkey(),
+            clad: get_clad_address(),
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        InitializeCladParams {
+            permissions: Permissions::default(),
+            protocol_fee_rate: 300,
+        },
+    );
+    process(context, &[instruction], &[]).await;
+}
+
+async fn create_mint(context: &mut ProgramTestContext, mint: &Keypair) {
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let instructions = [
+        system_instruction::create_account(
+            &context.payer.pubkey(),
+            &mint.pubkey(),
+            rent.minimum_balance(spl_token::state::Mint::LEN),
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::initialize_mint(
+            &spl_token::ID,
+            &mint.pubkey(),
+            &context.payer.pubkey(),
+            None,
+            6,
+        )
+        .unwrap(),
+    ];
+    process(context, &instructions, &[mint]).await;
+}
+
+// Creates the associated token account of `owner`, holding `amount` of `mint`
+async fn create_token_account(
+    context: &mut ProgramTestContext,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+) -> Pubkey {
+    let token_account = get_associated_token_address(owner, mint);
+    let instructions = [
+        create_associated_token_account(&context.payer.pubkey(), owner, mint, &spl_token::ID),
+        spl_token::instruction::mint_to(
+            &spl_token::ID,
+            mint,
+            &token_account,
+            &context.payer.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap(),
+    ];
+    process(context, &instructions, &[]).await;
+
+    token_account
+}
+
+async fn create_tick_array(
+    context: &mut ProgramTestContext,
+    globalpool: &Pubkey,
+    start_tick_index: i32,
+) -> Pubkey {
+    let tick_array = get_tick_array_address(globalpool, start_tick_index);
+    let instruction = initialize_tick_array(
+        accounts::InitializeTickArray {
+            globalpool: *globalpool,
+            funder: context.payer.pubkey(),
+            tick_array,
+            tick_array_bitmap: get_tick_array_bitmap_address(globalpool),
+            system_program: system_program::ID,
+        },
+        InitializeTickArrayParams { start_tick_index },
+    );
+    process(context, &[instruction], &[]).await;
+
+    tick_array
+}
+
+// Creates a pool at tick 0 with two new mints, returning its address
+async fn create_globalpool(context: &mut ProgramTestContext) -> Pubkey {
+    let mut mints = [Keypair::new(), Keypair::new()];
+    mints.sort_by_key(|mint| mint.pubkey());
+    for mint in mints.iter() {
+        create_mint(context, mint).await;
+    }
+
+    let token_mint_a = mints[0].pubkey();
+    let token_mint_b = mints[1].pubkey();
+    let globalpool = get_globalpool_address(&token_mint_a, &token_mint_b, FEE_RATE, TICK_SPACING);
+
+    let instruction = create_pool(
+        accounts::CreatePool {
+            funder: context.payer.pubkey(),
+            clad: get_clad_address(),
+            globalpool,
+            token_mint_a,
+            token_mint_b,
+            token_vault_a: get_token_vault_address(&globalpool, &token_mint_a, &spl_token::ID),
+            token_vault_b: get_token_vault_address(&globalpool, &token_mint_b, &spl_token::ID),
+            token_program_a: spl_token::ID,
+            token_program_b: spl_token::ID,
+            associated_token_program: spl_associated_token_account::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        CreatePoolParams {
+            fee_rate: FEE_RATE,
+            tick_spacing: TICK_SPACING,
+            initial_sqrt_price: 1 << 64,
+            hook_program: None,
+        },
+    );
+    process(context, &[instruction], &[]).await;
+
+    globalpool
+}
+
+// A pool with a liquidity position of the payer, which holds both tokens
+struct GlobalpoolWithLiquidity {
+    globalpool: Pubkey,
+    token_mint_a: Pubkey,
+    token_mint_b: Pubkey,
+    token_vault_a: Pubkey,
+    token_vault_b: Pubkey,
+    token_owner_account_a: Pubkey,
+    token_owner_account_b: Pubkey,
+    position: Pubkey,
+    position_token_account: Pubkey,
+}
+
+impl GlobalpoolWithLiquidity {
+    fn modify_liquidity_accounts(&self, authority: Pubkey) -> accounts::ModifyLiquidity {
+        accounts::ModifyLiquidity {
+            position_authority: authority,
+            globalpool: self.globalpool,
+            position: self.position,
+            position_token_account: self.position_token_account,
+            token_mint_a: self.token_mint_a,
+            token_mint_b: self.token_mint_b,
+            token_owner_account_a: self.token_owner_account_a,
+            token_owner_account_b: self.token_owner_account_b,
+            token_vault_a: self.token_vault_a,
+            token_vault_b: self.token_vault_b,
+            tick_array_lower: get_tick_array_address(
+                &self.globalpool,
+                get_start_tick_index(POSITION_TICK_LOWER_INDEX, TICK_SPACING, 0),
+            ),
+            tick_array_upper: get_tick_array_address(
+                &self.globalpool,
+                get_start_tick_index(POSITION_TICK_UPPER_INDEX, TICK_SPACING, 0),
+            ),
+            token_program_a: spl_token::ID,
+            token_program_b: spl_token::ID,
+        }
+    }
+}
+
+// Creates a pool, the tick arrays around tick 0 and a liquidity position holding
+// `POSITION_LIQUIDITY`
+async fn create_globalpool_with_liquidity(
+    context: &mut ProgramTestContext,
+) -> GlobalpoolWithLiquidity {
+    initialize(context).await;
+    let globalpool = create_globalpool(context).await;
+    for start_tick_index in [-11_264, -5_632, 0, 5_632] {
+        create_tick_array(context, &globalpool, start_tick_index).await;
+    }
+
+    let globalpool_account =
+        decode_globalpool(&get_account_data(context, &globalpool).await).unwrap();
+    let payer = context.payer.pubkey();
+    let token_owner_account_a = create_token_account(
+        context,
+        &globalpool_account.token_mint_a,
+        &payer,
+        OWNER_TOKEN_AMOUNT,
+    )
+    .await;
+    let token_owner_account_b = create_token_account(
+        context,
+        &globalpool_account.token_mint_b,
+        &payer,
+        OWNER_TOKEN_AMOUNT,
+    )
+    .await;
+
+    let position_mint = Keypair::new();
+    let position = get_liquidity_position_address(&position_mint.pubkey());
+    let position_token_account = get_associated_token_address(&payer, &position_mint.pubkey());
+    let instruction = open_liquidity_position(
+        accounts::OpenLiquidityPosition {
+            position_authority: payer,
+            globalpool,
+            position,
+            position_mint: position_mint.pubkey(),
+            position_token_account,
+            token_program: spl_token::ID,
+            associated_token_program: spl_associated_token_account::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        OpenLiquidityPositionParams {
+            tick_lower_index: POSITION_TICK_LOWER_INDEX,
+            tick_upper_index: POSITION_TICK_UPPER_INDEX,
+        },
+    );
+    process(context, &[instruction], &[&position_mint]).await;
+
+    let pool = GlobalpoolWithLiquidity {
+        globalpool,
+        token_mint_a: globalpool_account.token_mint_a,
+        token_mint_b: globalpool_account.token_mint_b,
+        token_vault_a: globalpool_account.token_vault_a,
+        token_vault_b: globalpool_account.token_vault_b,
+        token_owner_account_a,
+        token_owner_account_b,
+        position,
+        position_token_account,
+    };
+
+    let instruction = increase_liquidity(
+        pool.modify_liquidity_accounts(payer),
+        IncreaseLiquidityParams {
+            liquidity_amount: POSITION_LIQUIDITY,
+            token_max_a: u64::MAX,
+            token_max_b: u64::MAX,
+        },
+    );
+    process(context, &[instruction], &[]).await;
+
+    pool
+}
+
+#[tokio::test]
+async fn initialize_clad_account() {
+    let mut context = start().await;
+    initialize(&mut context).await;
+
+    let clad = decode_clad(&get_account_data(&mut context, &get_clad_address()).await).unwrap();
+    assert_eq!(clad.protocol_fee_rate, 300);
+    assert!(clad.permissions.allow_swap);
+}
+
+#[tokio::test]
+async fn create_pool_and_tick_array() {
+    let mut context = start().await;
+    initialize(&mut context).await;
+    let globalpool = create_globalpool(&mut context).await;
+
+    let globalpool_account =
+        decode_globalpool(&get_account_data(&mut context, &globalpool).await).unwrap();
+    assert_eq!(globalpool_account.tick_current_index, 0);
+    assert_eq!(globalpool_account.fee_rate, FEE_RATE);
+
+    // The tick array below the current tick
+    let start_tick_index = get_start_tick_index(0, TICK_SPACING, -1);
+    let tick_array = get_tick_array_address(&globalpool, start_tick_index);
+    let tick_array_bitmap = get_tick_array_bitmap_address(&globalpool);
+
+    let instruction = initialize_tick_array(
+        accounts::InitializeTickArray {
+            globalpool,
+            funder: context.payer.pubkey(),
+            tick_array,
+            tick_array_bitmap,
+            system_program: system_program::ID,
+        },
+        InitializeTickArrayParams { start_tick_index },
+    );
+    process(&mut context, &[instruction], &[]).await;
+
+    let tick_array_data = get_account_data(&mut context, &tick_array).await;
+    let tick_array_account = decode_tick_array(&tick_array_data).unwrap();
+    assert_eq!({ tick_array_account.start_tick_index }, start_tick_index);
+    assert_eq!(tick_array_account.globalpool, globalpool);
+    let extension = decode_tick_array_extension(&tick_array_data)
+        .unwrap()
+        .unwrap();
+    assert_eq!(extension.funder, context.payer.pubkey());
+
+    let tick_array_bitmap_account =
+        decode_tick_array_bitmap(&get_account_data(&mut context, &tick_array_bitmap).await)
+            .unwrap();
+    assert!(tick_array_bitmap_account.is_initialized(start_tick_index, TICK_SPACING));
+    assert!(!tick_array_bitmap_account.is_initialized(0, TICK_SPACING));
+    assert!(!tick_array_bitmap_account.backfilled);
+
+    // Recording an existing tick array again is harmless. Only the protocol admin, the upgrade
+    // authority of the deployed program, can then attest that the bitmap is complete.
+    let instruction = backfill_tick_array_bitmap(
+        accounts::BackfillTickArrayBitmap {
+            globalpool,
+            funder: context.payer.pubkey(),
+            tick_array_bitmap,
+            system_program: system_program::ID,
+        },
+        &[tick_array],
+    );
+    process(&mut context, &[instruction], &[]).await;
+
+    let tick_array_bitmap_account =
+        decode_tick_array_bitmap(&get_account_data(&mut context, &tick_array_bitmap).await)
+            .unwrap();
+    assert!(tick_array_bitmap_account.is_initialized(start_tick_index, TICK_SPACING));
+    assert!(!tick_array_bitmap_account.backfilled);
+
+    // The funder closes the empty tick array, which is cleared from the bitmap
+    let instruction = close_tick_array(accounts::CloseTickArray {
+        globalpool,
+        tick_array,
+        tick_array_bitmap,
+        funder: context.payer.pubkey(),
+    });
+    process(&mut context, &[instruction], &[]).await;
+
+    let tick_array_account = context.banks_client.get_account(tick_array).await.unwrap();
+    assert!(tick_array_account.is_none());
+    let tick_array_bitmap_account =
+        decode_tick_array_bitmap(&get_account_data(&mut context, &tick_array_bitmap).await)
+            .unwrap();
+    assert!(!tick_array_bitmap_account.is_initialized(start_tick_index, TICK_SPACING));
+}
+
+#[tokio::test]
+async fn increase_and_decrease_liquidity() {
+    let mut context = start().await;
+    let pool = create_globalpool_with_liquidity(&mut context).await;
+
+    let position =
+        decode_liquidity_position(&get_account_data(&mut context, &pool.position).await).unwrap();
+    assert_eq!(position.liquidity, POSITION_LIQUIDITY);
+    let globalpool =
+        decode_globalpool(&get_account_data(&mut context, &pool.globalpool).await).unwrap();
+    assert_eq!(globalpool.liquidity_available, POSITION_LIQUIDITY);
+
+    // The position is in range, so it holds both tokens
+    let deposit_a = get_token_balance(&mut context, &pool.token_vault_a).await;
+    let deposit_b = get_token_balance(&mut context, &pool.token_vault_b).await;
+    assert!(deposit_a > 0 && deposit_b > 0);
+    assert_eq!(
+        get_token_balance(&mut context, &pool.token_owner_account_a).await,
+        OWNER_TOKEN_AMOUNT - deposit_a
+    );
+
+    let instruction = decrease_liquidity(
+        pool.modify_liquidity_accounts(context.payer.pubkey()),
+        DecreaseLiquidityParams {
+            liquidity_amount: POSITION_LIQUIDITY,
+            token_min_a: deposit_a - 1,
+            token_min_b: deposit_b - 1,
+        },
+    );
+    process(&mut context, &[instruction], &[]).await;
+
+    let position =
+        decode_liquidity_position(&get_account_data(&mut context, &pool.position).await).unwrap();
+    assert_eq!(position.liquidity, 0);
+    let globalpool =
+        decode_globalpool(&get_account_data(&mut context, &pool.globalpool).await).unwrap();
+    assert_eq!(globalpool.liquidity_available, 0);
+
+    // Deposits round up and withdrawals down, so at most one token of each is left in the vaults
+    assert!(get_token_balance(&mut context, &pool.token_vault_a).await <= 1);
+    assert!(get_token_balance(&mut context, &pool.token_vault_b).await <= 1);
+}
+
+#[tokio::test]
+async fn swap_matches_quote() {
+    let mut context = start().await;
+    let pool = create_globalpool_with_liquidity(&mut context).await;
+
+    let globalpool =
+        decode_globalpool(&get_account_data(&mut context, &pool.globalpool).await).unwrap();
+    let mut tick_arrays = vec![];
+    let mut tick_array_extensions = vec![];
+    for start_tick_index in get_swap_start_tick_indices(
+        globalpool.tick_current_index,
+        TICK_SPACING,
+        true,
+        SWAP_TICK_ARRAYS,
+    ) {
+        let tick_array_data = get_account_data(
+            &mut context,
+            &get_tick_array_address(&pool.globalpool, start_tick_index),
+        )
+        .await;
+        tick_arrays.push(decode_tick_array(&tick_array_data).unwrap());
+        tick_array_extensions.push(decode_tick_array_extension(&tick_array_data).unwrap());
+    }
+
+    let amount = 1_000_000;
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let quote = quote_swap(&SwapQuoteParams {
+        globalpool: &globalpool,
+        tick_arrays: &tick_arrays,
+        tick_array_extensions: &tick_array_extensions,
+        tick_array_bitmap: None,
+        amount,
+        sqrt_price_limit: MIN_SQRT_PRICE_X64,
+        amount_specified_is_input: true,
+        a_to_b: true,
+        timestamp: clock.unix_timestamp as u64,
+        transfer_fee_input: None,
+        transfer_fee_output: None,
+    })
+    .unwrap();
+
+    // The swap stays in the 2nd tick array, which is repeated as the 3rd one
+    assert_eq!(
+        quote.tick_arrays.tick_array_1,
+        quote.tick_arrays.tick_array_2
+    );
+    assert!(quote.tick_arrays.remaining_tick_arrays.is_empty());
+
+    let instruction = swap(
+        accounts::Swap {
+            token_program_a: spl_token::ID,
+            token_program_b: spl_token::ID,
+            token_authority: context.payer.pubkey(),
+            globalpool: pool.globalpool,
+            token_mint_a: pool.token_mint_a,
+            token_mint_b: pool.token_mint_b,
+            token_owner_account_a: pool.token_owner_account_a,
+            token_vault_a: pool.token_vault_a,
+            token_owner_account_b: pool.token_owner_account_b,
+            token_vault_b: pool.token_vault_b,
+            tick_array_0: quote.tick_arrays.tick_array_0,
+            tick_array_1: quote.tick_arrays.tick_array_1,
+            tick_array_2: quote.tick_arrays.tick_array_2,
+        },
+        SwapParams {
+            amount,
+            other_amount_threshold: quote.amount_out,
+            sqrt_price_limit: MIN_SQRT_PRICE_X64,
+            amount_specified_is_input: true,
+            a_to_b: true,
+        },
+    );
+    let balance_a = get_token_balance(&mut context, &pool.token_owner_account_a).await;
+    let balance_b = get_token_balance(&mut context, &pool.token_owner_account_b).await;
+    process(&mut context, &[instruction], &[]).await;
+
+    assert_eq!(
+        get_token_balance(&mut context, &pool.token_owner_account_a).await,
+        balance_a - quote.amount_in
+    );
+    assert_eq!(
+        get_token_balance(&mut context, &pool.token_owner_account_b).await,
+        balance_b + quote.amount_out
+    );
+    let globalpool =
+        decode_globalpool(&get_account_data(&mut context, &pool.globalpool).await).unwrap();
+    assert_eq!(globalpool.sqrt_price, quote.end_sqrt_price);
+    assert_eq!(globalpool.tick_current_index, quote.end_tick_index);
+}
+
+#[tokio::test]
+async fn open_repay_and_close_trade_position() {
+    let mut context = start().await;
+    let pool = create_globalpool_with_liquidity(&mut context).await;
+    let payer = context.payer.pubkey();
+
+    // The mock router trades through its own token accounts
+    let (market_authority, _) =
+        Pubkey::find_program_address(&[MARKET_AUTHORITY_SEED], &clad::jupiter::ID);
+    let market_a = create_token_account(
+        &mut context,
+        &pool.token_mint_a,
+        &market_authority,
+        OWNER_TOKEN_AMOUNT,
+    )
+    .await;
+    let market_b = create_token_account(
+        &mut context,
+        &pool.token_mint_b,
+        &market_authority,
+        OWNER_TOKEN_AMOUNT,
+    )
+    .await;
+    let mock_swap_accounts = |a_to_b: bool| {
+        let (vault_in, market_in, market_out, vault_out) = if a_to_b {
+            (pool.token_vault_a, market_a, market_b, pool.token_vault_b)
+        } else {
+            (pool.token_vault_b, market_b, market_a, pool.token_vault_a)
+        };
+        vec![
+            AccountMeta::new_readonly(clad::jupiter::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(pool.globalpool, false),
+            AccountMeta::new(vault_in, false),
+            AccountMeta::new(market_in, false),
+            AccountMeta::new(market_out, false),
+            AccountMeta::new(vault_out, false),
+            AccountMeta::new_readonly(market_authority, false),
+        ]
+    };
+    let mock_swap_data = |amount_in: u64, amount_out: u64| {
+        [amount_in.to_le_bytes(), amount_out.to_le_bytes()].concat()
+    };
+
+    // Borrow token B below the current price, and swap all of it to token A at a price of 1
+    let tick_lower_index = -2560;
+    let tick_upper_index = -1280;
+    let liquidity_amount = 100_000_000;
+    let borrow_amount = get_amount_delta_b(
+        sqrt_price_from_tick_index(tick_lower_index),
+        sqrt_price_from_tick_index(tick_upper_index),
+        liquidity_amount,
+        true,
+    )
+    .unwrap();
+
+    let tick_array = get_tick_array_address(
+        &pool.globalpool,
+        get_start_tick_index(tick_lower_index, TICK_SPACING, 0),
+    );
+    let position_mint = Keypair::new();
+    let position = get_trade_position_address(&position_mint.pubkey());
+    let position_token_account = get_associated_token_address(&payer, &position_mint.pubkey());
+    let instruction = with_remaining_accounts(
+        open_trade_position(
+            accounts::OpenTradePosition {
+                owner: payer,
+                globalpool: pool.globalpool,
+                position,
+                position_mint: position_mint.pubkey(),
+                position_token_account,
+                token_owner_account_a: pool.token_owner_account_a,
+                token_vault_a: pool.token_vault_a,
+                token_mint_a: pool.token_mint_a,
+                token_owner_account_b: pool.token_owner_account_b,
+                token_vault_b: pool.token_vault_b,
+                token_mint_b: pool.token_mint_b,
+                tick_array_lower: tick_array,
+                tick_array_upper: tick_array,
+                token_program: spl_token::ID,
+                associated_token_program: spl_associated_token_account::ID,
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            },
+            OpenTradePositionParams {
+                liquidity_amount,
+                tick_lower_index,
+                tick_upper_index,
+                loan_duration: 0,
+                borrow_a: false,
+                is_perpetual: true,
+                liquidity_position_collateral: false,
+                margin_account_collateral: false,
+                swap_instruction_data: mock_swap_data(borrow_amount, borrow_amount),
+            },
+        ),
+        mock_swap_accounts(false),
+    );
+    let balance_a = get_token_balance(&mut context, &pool.token_owner_account_a).await;
+    process(&mut context, &[instruction], &[&position_mint]).await;
+
+    let trade_position =
+        decode_trade_position(&get_account_data(&mut context, &position).await).unwrap();
+    assert_eq!(trade_position.liquidity_borrowed, liquidity_amount);
+    assert_eq!(trade_position.loan_token_swapped, borrow_amount);
+    assert_eq!(trade_position.trade_token_amount, borrow_amount);
+    // The collateral covers the debt in token A, were the price to fall through the range
+    let collateral_amount = trade_position.collateral_amount;
+    assert!(collateral_amount > 0);
+    assert_eq!(
+        get_token_balance(&mut context, &pool.token_owner_account_a).await,
+        balance_a - collateral_amount
+    );
+    let globalpool =
+        decode_globalpool(&get_account_data(&mut context, &pool.globalpool).await).unwrap();
+    assert_eq!(globalpool.liquidity_borrowed, liquidity_amount);
+
+    // The owner repays the debt in token B by swapping token A back at a price of 1
+    let repayment = calculate_trade_position_repayment(&trade_position, &globalpool, 0, 0).unwrap();
+    let swap_amount = repayment.swap_amount_needed_b;
+    assert!(swap_amount > 0);
+    let instruction = with_remaining_accounts(
+        repay_trade_position(
+            accounts::RepayTradePosition {
+                liquidator: payer,
+                owner: payer,
+                globalpool: pool.globalpool,
+                position,
+                position_token_account,
+                token_owner_account_a: pool.token_owner_account_a,
+                token_liquidator_account_a: pool.token_owner_account_a,
+                token_vault_a: pool.token_vault_a,
+                token_mint_a: pool.token_mint_a,
+                token_owner_account_b: pool.token_owner_account_b,
+                token_liquidator_account_b: pool.token_owner_account_b,
+                token_vault_b: pool.token_vault_b,
+                token_mint_b: pool.token_mint_b,
+                token_program: spl_token::ID,
+                associated_token_program: spl_associated_token_account::ID,
+                system_program: system_program::ID,
+            },
+            RepayTradePositionParams {
+                swap_instruction_data: mock_swap_data(swap_amount, swap_amount),
+            },
+        ),
+        mock_swap_accounts(true),
+    );
+    process(&mut context, &[instruction], &[]).await;
+
+    // The loan is settled, but the borrowed liquidity is only returned to the ticks on closing
+    let trade_position =
+        decode_trade_position(&get_account_data(&mut context, &position).await).unwrap();
+    assert_eq!(trade_position.loan_token_swapped, 0);
+    assert_eq!(trade_position.trade_token_amount, 0);
+    let globalpool =
+        decode_globalpool(&get_account_data(&mut context, &pool.globalpool).await).unwrap();
+    assert_eq!(globalpool.liquidity_borrowed, 0);
+
+    // Closing the repaid position returns the leftover collateral, less the funding paid
+    let leftover_collateral = trade_position.collateral_amount;
+    assert!(leftover_collateral > 0);
+    let balance_a = get_token_balance(&mut context, &pool.token_owner_account_a).await;
+    let instruction = close_trade_position(accounts::CloseTradePosition {
+        owner: payer,
+        globalpool: pool.globalpool,
+        receiver: payer,
+        position,
+        position_mint: position_mint.pubkey(),
+        position_token_account,
+        token_owner_account_a: pool.token_owner_account_a,
+        token_vault_a: pool.token_vault_a,
+        token_mint_a: pool.token_mint_a,
+        token_owner_account_b: pool.token_owner_account_b,
+        token_vault_b: pool.token_vault_b,
+        token_mint_b: pool.token_mint_b,
+        tick_array_lower: tick_array,
+        tick_array_upper: tick_array,
+        token_program: spl_token::ID,
+    });
+    process(&mut context, &[instruction], &[]).await;
+
+    assert!(context
+        .banks_client
+        .get_account(position)
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        get_token_balance(&mut context, &pool.token_owner_account_a).await,
+        balance_a + leftover_collateral
+    );
+}
+
+#[tokio::test]
+async fn flash_borrow_and_repay() {
+    let mut context = start().await;
+    let pool = create_globalpool_with_liquidity(&mut context).await;
+    let payer = context.payer.pubkey();
+
+    // 0.3% flash fee, set by the fee authority of the pool, its creator
+    let instruction = set_flash_fee_rate(
+        accounts::SetFlashFeeRate {
+            globalpool: pool.globalpool,
+            fee_authority: payer,
+        },
+        3_000,
+    );
+    process(&mut context, &[instruction], &[]).await;
+
+    let vault_balance_a = get_token_balance(&mut context, &pool.token_vault_a).await;
+    let vault_balance_b = get_token_balance(&mut context, &pool.token_vault_b).await;
+    let globalpool =
+        decode_globalpool(&get_account_data(&mut context, &pool.globalpool).await).unwrap();
+    let fee_growth_global_a = globalpool.fee_growth_global_a;
+
+    // The borrowed tokens are repaid with the fee in the same transaction
+    let amount_a = 1_000_000;
+    let instructions = [
+        flash_borrow(
+            accounts::FlashBorrow {
+                borrower: payer,
+                globalpool: pool.globalpool,
+                token_mint_a: pool.token_mint_a,
+                token_mint_b: pool.token_mint_b,
+                token_borrower_account_a: pool.token_owner_account_a,
+                token_vault_a: pool.token_vault_a,
+                token_borrower_account_b: pool.token_owner_account_b,
+                token_vault_b: pool.token_vault_b,
+                instructions_sysvar: sysvar::instructions::ID,
+                token_program_a: spl_token::ID,
+                token_program_b: spl_token::ID,
+            },
+            FlashBorrowParams {
+                amount_a,
+                amount_b: 0,
+            },
+        ),
+        flash_repay(accounts::FlashRepay {
+            repayer: payer,
+            globalpool: pool.globalpool,
+            token_mint_a: pool.token_mint_a,
+            token_mint_b: pool.token_mint_b,
+            token_repayer_account_a: pool.token_owner_account_a,
+            token_vault_a: pool.token_vault_a,
+            token_repayer_account_b: pool.token_owner_account_b,
+            token_vault_b: pool.token_vault_b,
+            token_program_a: spl_token::ID,
+            token_program_b: spl_token::ID,
+        }),
+    ];
+    process(&mut context, &instructions, &[]).await;
+
+    assert_eq!(
+        get_token_balance(&mut context, &pool.token_vault_a).await,
+        vault_balance_a + 3_000
+    );
+    assert_eq!(
+        get_token_balance(&mut context, &pool.token_vault_b).await,
+        vault_balance_b
+    );
+    let globalpool =
+        decode_globalpool(&get_account_data(&mut context, &pool.globalpool).await).unwrap();
+    assert!(!globalpool.has_flash_loan());
+    // The fee is shared by the liquidity providers
+    assert!(globalpool.fee_growth_global_a > fee_growth_global_a);
+
+    // A flash loan without its repayment fails
+    let instruction = flash_borrow(
+        accounts::FlashBorrow {
+            borrower: payer,
+            globalpool: pool.globalpool,
+            token_mint_a: pool.token_mint_a,
+            token_mint_b: pool.token_mint_b,
+            token_borrower_account_a: pool.token_owner_account_a,
+            token_vault_a: pool.token_vault_a,
+            token_borrower_account_b: pool.token_owner_account_b,
+            token_vault_b: pool.token_vault_b,
+            instructions_sysvar: sysvar::instructions::ID,
+            token_program_a: spl_token::ID,
+            token_program_b: spl_token::ID,
+        },
+        FlashBorrowParams {
+            amount_a,
+            amount_b: 0,
+        },
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    assert!(context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .is_err());
+}
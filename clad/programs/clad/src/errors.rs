@@ -140,6 +140,166 @@ pub enum ErrorCode {
     InvalidRepaymentAmount,
     #[msg("Invalid loan duration")]
     InvalidLoanDuration,
+
+    // Risk parameter errors
+    #[msg("Risk parameter exceeds max basis points")]
+    InvalidRiskParameter,
+    #[msg("Total borrowed liquidity exceeds the pool cap")]
+    BorrowedLiquidityCapExceeded,
+    #[msg("Tick utilization exceeds the pool cap")]
+    TickUtilizationCapExceeded,
+    #[msg("Position notional exceeds the pool cap")]
+    PositionNotionalCapExceeded,
+
+    // Margin account errors
+    #[msg("No price feed is registered for this mint")]
+    PriceFeedNotSet,
+    #[msg("Signer is not the protocol admin")]
+    InvalidProtocolAdmin,
+    #[msg("No free collateral slot in margin account")]
+    MarginCollateralSlotsFull,
+    #[msg("Collateral not found in margin account")]
+    MarginCollateralNotFound,
+    #[msg("No free position slot in margin account")]
+    MarginPositionSlotsFull,
+    #[msg("Trade position is already linked to margin account")]
+    MarginPositionAlreadyLinked,
+    #[msg("Trade position is not linked to margin account")]
+    MarginPositionNotLinked,
+    #[msg("Trade position is not owned by the margin account owner")]
+    MarginPositionNotOwned,
+    #[msg("Invalid accounts provided for margin health")]
+    InvalidMarginHealthAccounts,
+    #[msg("Margin account is below maintenance margin")]
+    MarginAccountUnhealthy,
+    #[msg("Margin account is above maintenance margin")]
+    MarginAccountHealthy,
+    #[msg("Margin account is being liquidated")]
+    MarginAccountLiquidating,
+    #[msg("Perpetual trade positions cannot be linked to a margin account")]
+    MarginPerpetualPositionNotSupported,
+    #[msg("Trade position backed by the margin account must be repaid first")]
+    MarginBackedPositionNotRepaid,
+
+    // Perpetual position errors
+    #[msg("Perpetual trade position is above maintenance margin")]
+    PerpetualPositionHealthy,
+    #[msg("Trade position is not perpetual")]
+    PositionNotPerpetual,
+    #[msg("Instruction does not support this type of trade position")]
+    InvalidTradePositionType,
+    #[msg("Invalid accounts provided to price a perpetual trade position")]
+    InvalidPerpetualHealthAccounts,
+    #[msg("Oracle price is outside of the supported sqrt price range")]
+    OraclePriceOutOfRange,
+
+    // Leveraged liquidity position errors
+    #[msg("Liquidity position must deposit all of the borrowed tokens")]
+    LeveragedLiquidityLoanNotDeposited,
+    #[msg("Leveraged liquidity position is below maintenance margin")]
+    LeveragedPositionUnhealthy,
+    #[msg("Leveraged liquidity position has not matured and is above maintenance margin")]
+    LeveragedPositionNotLiquidatable,
+
+    // Liquidity position collateral errors
+    #[msg("Invalid or missing liquidity position collateral accounts")]
+    InvalidLiquidityCollateralAccounts,
+
+    // Flash loan errors
+    #[msg("Flash loan amount must be greater than zero")]
+    FlashLoanAmountZero,
+    #[msg("Globalpool already has an outstanding flash loan")]
+    FlashLoanInProgress,
+    #[msg("Globalpool has no outstanding flash loan")]
+    FlashLoanNotFound,
+    #[msg("Flash borrow must be followed by a flash repay in the same transaction")]
+    FlashRepayMissing,
+    #[msg("Flash borrow cannot be invoked via CPI")]
+    FlashBorrowCpiNotAllowed,
+
+    // Position bundle errors
+    #[msg("Bundle index is out of range")]
+    InvalidBundleIndex,
+    #[msg("Bundled position is already opened")]
+    BundledPositionAlreadyOpened,
+    #[msg("Bundled position is already closed")]
+    BundledPositionAlreadyClosed,
+    #[msg("Position bundle has opened positions and cannot be deleted")]
+    PositionBundleNotDeletable,
+
+    // Token extension errors
+    #[msg("Token program is not supported or does not own the mint")]
+    InvalidTokenProgram,
+    #[msg("Token mint has an unsupported extension")]
+    UnsupportedTokenMintExtension,
+    #[msg("Transfer fee calculation failed")]
+    TransferFeeCalculationError,
+    #[msg("Token-2022 mints are not supported by this instruction")]
+    Token2022MintUnsupported,
+
+    // Dynamic fee errors
+    #[msg("Invalid dynamic fee parameter")]
+    InvalidDynamicFeeParameter,
+
+    // Range order errors
+    #[msg("Range order must span one tick spacing of a tick array, on the side of the price holding its input token")]
+    InvalidRangeOrderTicks,
+    #[msg("Tick holds unfilled range orders in the opposite direction")]
+    RangeOrderDirectionMismatch,
+    #[msg("Range order positions are not supported by this instruction")]
+    RangeOrderPositionUnsupported,
+    #[msg("Position is not a range order")]
+    NotRangeOrderPosition,
+    #[msg("Range orders must be opened in an empty position")]
+    RangeOrderPositionNotEmpty,
+
+    // Tick array errors
+    #[msg("Tick array has ticks with liquidity or unclaimed range orders")]
+    TickArrayNotClosable,
+    #[msg("Tick array has no range order extension, see migrate_tick_array")]
+    TickArrayExtensionMissing,
+    #[msg("Tick array was not funded by this account")]
+    InvalidTickArrayFunder,
+
+    // Deposit by token amount errors
+    #[msg("Position range does not take the input token at the current price")]
+    InvalidDepositToken,
+    #[msg("Pool price is outside of the accepted sqrt price range")]
+    SqrtPriceOutOfRange,
+
+    // Position operator errors
+    #[msg("Account is not a liquidity or trade position")]
+    InvalidPositionAccount,
+    #[msg("Invalid position operator permissions")]
+    InvalidPositionOperatorPermissions,
+    #[msg("Position operator does not belong to this position and owner")]
+    InvalidPositionOperator,
+    #[msg("Position operator is not permitted to call this instruction")]
+    PositionOperatorNotPermitted,
+    #[msg("Position operators can only send tokens to the position owner")]
+    InvalidOperatorTokenAccount,
+
+    // Split and merge position errors
+    #[msg("Positions must be in the same pool with identical ranges")]
+    PositionRangeMismatch,
+    #[msg("Split liquidity must be greater than zero and at most the position's liquidity")]
+    InvalidSplitLiquidity,
+
+    // Native SOL errors
+    #[msg("Native SOL mode requires one of the pool tokens to be the native mint")]
+    NativeSolMintNotFound,
+    #[msg("Token account is not the temporary wSOL account of the authority")]
+    InvalidNativeSolAccount,
+    #[msg("Native SOL mode requires the authority to be a writable signer")]
+    InvalidNativeSolAuthority,
+
+    // Hook errors
+    #[msg("Hook program must be an executable program other than Clad")]
+    InvalidHookProgram,
+    #[msg("Hook program of the globalpool is missing from the remaining accounts")]
+    HookProgramMissing,
+    #[msg("Instruction is not supported by globalpools with a hook program")]
+    HookUnsupported,
 }
 
 impl From<TryFromIntError> for ErrorCode {
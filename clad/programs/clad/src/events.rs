@@ -0,0 +1,262 @@
+use anchor_lang::prelude::*;
+
+//
+// Events emitted by instructions, so that indexers can follow Clad's state changes without
+// diffing accounts. Token amounts are the amounts that reached or left the vaults; sqrt prices
+// and fee growths are Q64.64 values of the Globalpool after the change.
+//
+
+#[event]
+pub struct PoolInitialized {
+    pub globalpool: Pubkey,
+    pub token_mint_a: Pubkey,
+    pub token_mint_b: Pubkey,
+    pub tick_spacing: u16,
+    pub fee_rate: u16,
+    pub sqrt_price: u128,
+    pub tick_current_index: i32,
+    pub hook_program: Pubkey, // The default key if the pool has no hook
+}
+
+#[event]
+pub struct LiquidityIncreased {
+    pub globalpool: Pubkey,
+    pub position: Pubkey,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    pub liquidity_amount: u128,
+    pub token_amount_a: u64,
+    pub token_amount_b: u64,
+    pub sqrt_price: u128,
+    pub tick_current_index: i32,
+    pub fee_growth_global_a: u128,
+    pub fee_growth_global_b: u128,
+}
+
+#[event]
+pub struct LiquidityDecreased {
+    pub globalpool: Pubkey,
+    pub position: Pubkey,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    pub liquidity_amount: u128,
+    pub token_amount_a: u64,
+    pub token_amount_b: u64,
+    pub sqrt_price: u128,
+    pub tick_current_index: i32,
+    pub fee_growth_global_a: u128,
+    pub fee_growth_global_b: u128,
+}
+
+#[event]
+pub struct RangeOrderOpened {
+    pub globalpool: Pubkey,
+    pub position: Pubkey,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    pub liquidity_amount: u128,
+    pub a_to_b: bool,
+    pub token_amount: u64, // Of token A if a_to_b, else of token B
+    pub sqrt_price: u128,
+    pub tick_current_index: i32,
+}
+
+#[event]
+pub struct RangeOrderClaimed {
+    pub globalpool: Pubkey,
+    pub position: Pubkey,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    pub liquidity_amount: u128,
+    pub a_to_b: bool,
+    pub filled: bool, // false if the order was cancelled
+    pub token_amount_a: u64,
+    pub token_amount_b: u64,
+    pub sqrt_price: u128,
+    pub tick_current_index: i32,
+}
+
+#[event]
+pub struct FeesCollected {
+    pub globalpool: Pubkey,
+    pub position: Pubkey,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    pub fee_amount_a: u64,
+    pub fee_amount_b: u64,
+    pub fee_growth_checkpoint_a: u128,
+    pub fee_growth_checkpoint_b: u128,
+}
+
+#[event]
+pub struct FeesCompounded {
+    pub globalpool: Pubkey,
+    pub position: Pubkey,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    pub fee_amount_a: u64,
+    pub fee_amount_b: u64,
+    pub a_to_b: bool,
+    pub swap_amount_in: u64,
+    pub swap_amount_out: u64,
+    pub liquidity_amount: u128,
+    pub token_amount_a: u64,
+    pub token_amount_b: u64,
+    pub sqrt_price: u128,
+    pub tick_current_index: i32,
+    pub fee_growth_global_a: u128,
+    pub fee_growth_global_b: u128,
+}
+
+#[event]
+pub struct Swapped {
+    pub globalpool: Pubkey,
+    pub a_to_b: bool,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub pre_sqrt_price: u128,
+    pub post_sqrt_price: u128,
+    pub pre_tick_index: i32,
+    pub post_tick_index: i32,
+    pub fee_growth_global_a: u128,
+    pub fee_growth_global_b: u128,
+}
+
+#[event]
+pub struct TradePositionOpened {
+    pub globalpool: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    pub liquidity_borrowed: u128,
+    pub loan_token_swapped: u64,
+    pub trade_token_amount: u64,
+    pub collateral_amount: u64,
+    pub interest_amount: u64,
+    pub is_borrow_a: bool,
+    pub is_perpetual: bool,
+    pub sqrt_price: u128,
+    pub tick_current_index: i32,
+    pub fee_growth_global_a: u128,
+    pub fee_growth_global_b: u128,
+}
+
+#[event]
+pub struct TradePositionRepaid {
+    pub globalpool: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    pub liquidity_repaid: u128,
+    pub repay_amount_a: u64,
+    pub repay_amount_b: u64,
+    pub collateral_returned: u64,
+    pub profit_returned: u64,
+    pub sqrt_price: u128,
+    pub tick_current_index: i32,
+    pub fee_growth_global_a: u128,
+    pub fee_growth_global_b: u128,
+}
+
+#[event]
+pub struct TradePositionClosed {
+    pub globalpool: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub collateral_returned: u64,
+}
+
+#[event]
+pub struct LeveragedLiquidityPositionOpened {
+    pub globalpool: Pubkey,
+    pub position: Pubkey,
+    pub liquidity_position: Pubkey,
+    pub owner: Pubkey,
+    pub loan_tick_lower_index: i32,
+    pub loan_tick_upper_index: i32,
+    pub liquidity_borrowed: u128,
+    pub loan_token_amount: u64,
+    pub is_borrow_a: bool,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    pub liquidity_amount: u128,
+    pub margin_amount_a: u64,
+    pub margin_amount_b: u64,
+    pub interest_amount: u64,
+    pub sqrt_price: u128,
+    pub tick_current_index: i32,
+    pub fee_growth_global_a: u128,
+    pub fee_growth_global_b: u128,
+}
+
+#[event]
+pub struct LeveragedLiquidityPositionRepaid {
+    pub globalpool: Pubkey,
+    pub position: Pubkey,
+    pub liquidity_position: Pubkey,
+    pub owner: Pubkey,
+    pub liquidator: Pubkey, // The owner, unless liquidated
+    pub liquidity_repaid: u128,
+    pub liquidity_withdrawn: u128,
+    pub repay_amount_a: u64,
+    pub repay_amount_b: u64,
+    pub shortfall_amount_a: u64,
+    pub shortfall_amount_b: u64,
+    pub leftover_to_owner_a: u64,
+    pub leftover_to_owner_b: u64,
+    pub leftover_to_liquidator_a: u64,
+    pub leftover_to_liquidator_b: u64,
+    pub sqrt_price: u128,
+    pub tick_current_index: i32,
+    pub fee_growth_global_a: u128,
+    pub fee_growth_global_b: u128,
+}
+
+#[event]
+pub struct TradePositionLiquidated {
+    pub globalpool: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub liquidator: Pubkey,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    pub liquidity_repaid: u128,
+    pub repay_amount_a: u64,
+    pub repay_amount_b: u64,
+    pub collateral_returned: u64,
+    pub collateral_to_liquidator: u64,
+    pub profit_returned: u64,
+    pub sqrt_price: u128,
+    pub tick_current_index: i32,
+    pub fee_growth_global_a: u128,
+    pub fee_growth_global_b: u128,
+}
+
+#[event]
+pub struct FlashLoanBorrowed {
+    pub globalpool: Pubkey,
+    pub borrower: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+#[event]
+pub struct FlashLoanRepaid {
+    pub globalpool: Pubkey,
+    pub repayer: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub fee_amount_a: u64,
+    pub fee_amount_b: u64,
+    pub fee_growth_global_a: u128,
+    pub fee_growth_global_b: u128,
+}
+
+#[event]
+pub struct ProtocolFeesCollected {
+    pub globalpool: Pubkey,
+    pub protocol_fee_amount_a: u64,
+    pub protocol_fee_amount_b: u64,
+}
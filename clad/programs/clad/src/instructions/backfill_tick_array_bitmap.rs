@@ -0,0 +1,47 @@
+use {
+    crate::{state::*, util::load_tick_arrays},
+    anchor_lang::prelude::*,
+};
+
+#[derive(Accounts)]
+pub struct BackfillTickArrayBitmap<'info> {
+    pub globalpool: Account<'info, Globalpool>,
+
+    #[account(mut)]
+    pub funder: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = funder,
+        seeds = [b"tick_array_bitmap", globalpool.key().as_ref()],
+        bump,
+        space = TickArrayBitmap::LEN
+    )]
+    pub tick_array_bitmap: Account<'info, TickArrayBitmap>,
+
+    pub system_program: Program<'info, System>,
+    // remaining accounts: existing tick arrays of the globalpool to record in the bitmap
+}
+
+///
+/// Records tick arrays that predate the TickArrayBitmap of their Globalpool, creating the bitmap
+/// if the Globalpool has none. Only accounts that are TickArrays of the Globalpool are recorded.
+///
+pub fn backfill_tick_array_bitmap(ctx: Context<BackfillTickArrayBitmap>) -> Result<()> {
+    let globalpool = &ctx.accounts.globalpool;
+
+    let tick_array_bitmap = &mut ctx.accounts.tick_array_bitmap;
+    if tick_array_bitmap.globalpool == Pubkey::default() {
+        tick_array_bitmap.initialize(globalpool.key(), false);
+    }
+
+    for tick_array in load_tick_arrays(ctx.remaining_accounts, &globalpool.key())? {
+        tick_array_bitmap.set_initialized(
+            tick_array.load()?.start_tick_index,
+            globalpool.tick_spacing,
+            true,
+        )?;
+    }
+
+    Ok(())
+}
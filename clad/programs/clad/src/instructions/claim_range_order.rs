@@ -0,0 +1,179 @@
+use {
+    super::ModifyLiquidity,
+    crate::{
+        errors::ErrorCode,
+        events::RangeOrderClaimed,
+        manager::{
+            liquidity_manager::{
+                calculate_liquidity_token_deltas, calculate_modify_liquidity,
+                sync_modify_liquidity_values,
+            },
+            range_order_manager::{
+                calculate_range_order_filled_amount, is_range_order_filled,
+                next_range_order_cancel_update, next_range_order_claim_update,
+                sync_range_order_liquidity,
+            },
+        },
+        math::convert_to_liquidity_delta,
+        state::*,
+        util::{
+            calculate_transfer_fee_excluded_amount, get_epoch_transfer_fee, to_timestamp_u64,
+            transfer_from_vault_to_owner_checked, verify_position_authority,
+        },
+    },
+    anchor_lang::prelude::*,
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ClaimRangeOrderParams {
+    pub token_min_a: u64,
+    pub token_min_b: u64,
+}
+
+///
+/// Withdraws a range order. A filled order receives the token it was converted into, an unfilled
+/// order is cancelled and withdrawn like decrease_liquidity. Either way, the position is emptied.
+///
+pub fn claim_range_order(
+    ctx: Context<ModifyLiquidity>,
+    params: &ClaimRangeOrderParams,
+) -> Result<()> {
+    verify_position_authority(
+        &ctx.accounts.position_token_account,
+        &ctx.accounts.position_authority,
+    )?;
+
+    let position = &ctx.accounts.position;
+    if !position.is_range_order {
+        return Err(ErrorCode::NotRangeOrderPosition.into());
+    }
+
+    let tick_spacing = ctx.accounts.globalpool.tick_spacing;
+    let fill_tick_index = position.range_order_fill_tick_index();
+    let fill_tick_array = if position.range_order_a_to_b {
+        &ctx.accounts.tick_array_upper
+    } else {
+        &ctx.accounts.tick_array_lower
+    };
+
+    let range_order = load_range_order(fill_tick_array, fill_tick_index, tick_spacing)?;
+
+    let liquidity_amount = position.liquidity;
+    let a_to_b = position.range_order_a_to_b;
+    let filled = is_range_order_filled(position, &range_order);
+
+    let (delta_a, delta_b) = if filled {
+        // The order's liquidity was removed from its ticks when filled
+        store_range_order(
+            fill_tick_array,
+            fill_tick_index,
+            tick_spacing,
+            &next_range_order_claim_update(&range_order)?,
+        )?;
+
+        let filled_amount = calculate_range_order_filled_amount(position)?;
+        if position.range_order_a_to_b {
+            (0, filled_amount)
+        } else {
+            (filled_amount, 0)
+        }
+    } else {
+        let liquidity = position.liquidity;
+        let liquidity_delta = convert_to_liquidity_delta(liquidity, false)?;
+        let timestamp = to_timestamp_u64(Clock::get()?.unix_timestamp)?;
+
+        let update = calculate_modify_liquidity(
+            &ctx.accounts.globalpool,
+            &ctx.accounts.position,
+            &ctx.accounts.tick_array_lower,
+            &ctx.accounts.tick_array_upper,
+            liquidity_delta,
+            timestamp,
+        )?;
+
+        sync_modify_liquidity_values(
+            &mut ctx.accounts.globalpool,
+            &mut ctx.accounts.position,
+            &ctx.accounts.tick_array_lower,
+            &ctx.accounts.tick_array_upper,
+            update,
+        )?;
+
+        store_range_order(
+            fill_tick_array,
+            fill_tick_index,
+            tick_spacing,
+            &next_range_order_cancel_update(&range_order, liquidity)?,
+        )?;
+
+        sync_range_order_liquidity(
+            &mut ctx.accounts.globalpool,
+            &ctx.accounts.position,
+            &ctx.accounts.tick_array_lower,
+            liquidity_delta,
+        )?;
+
+        calculate_liquidity_token_deltas(
+            ctx.accounts.position.tick_lower_index,
+            ctx.accounts.position.tick_upper_index,
+            ctx.accounts.globalpool.tick_current_index,
+            ctx.accounts.globalpool.sqrt_price,
+            liquidity_delta,
+        )?
+    };
+
+    // Range orders don't earn fees, as their liquidity is left out of the fee growth
+    ctx.accounts.position.close_range_order();
+
+    // Minimums apply to the amounts received, after any transfer fee
+    let received_amount_a = calculate_transfer_fee_excluded_amount(
+        &get_epoch_transfer_fee(&ctx.accounts.token_mint_a)?,
+        delta_a,
+    )?;
+    let received_amount_b = calculate_transfer_fee_excluded_amount(
+        &get_epoch_transfer_fee(&ctx.accounts.token_mint_b)?,
+        delta_b,
+    )?;
+
+    if received_amount_a < params.token_min_a {
+        return Err(ErrorCode::TokenMinSubceeded.into());
+    } else if received_amount_b < params.token_min_b {
+        return Err(ErrorCode::TokenMinSubceeded.into());
+    }
+
+    transfer_from_vault_to_owner_checked(
+        &ctx.accounts.globalpool,
+        &ctx.accounts.token_mint_a,
+        &ctx.accounts.token_vault_a,
+        &ctx.accounts.token_owner_account_a,
+        &ctx.accounts.token_program_a,
+        delta_a,
+    )?;
+
+    transfer_from_vault_to_owner_checked(
+        &ctx.accounts.globalpool,
+        &ctx.accounts.token_mint_b,
+        &ctx.accounts.token_vault_b,
+        &ctx.accounts.token_owner_account_b,
+        &ctx.accounts.token_program_b,
+        delta_b,
+    )?;
+
+    let globalpool = &ctx.accounts.globalpool;
+    let position = &ctx.accounts.position;
+    emit!(RangeOrderClaimed {
+        globalpool: globalpool.key(),
+        position: position.key(),
+        tick_lower_index: position.tick_lower_index,
+        tick_upper_index: position.tick_upper_index,
+        liquidity_amount,
+        a_to_b,
+        filled,
+        token_amount_a: delta_a,
+        token_amount_b: delta_b,
+        sqrt_price: globalpool.sqrt_price,
+        tick_current_index: globalpool.tick_current_index,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use {
+    crate::{errors::ErrorCode, state::*, util::verify_position_authority},
+    anchor_lang::prelude::*,
+    anchor_spl::token::TokenAccount,
+};
+
+#[derive(Accounts)]
+#[instruction(bundle_index: u16)]
+pub struct CloseBundledLiquidityPosition<'info> {
+    pub position_authority: Signer<'info>,
+
+    /// CHECK: safe, for receiving rent only
+    #[account(mut)]
+    pub receiver: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub position_bundle: Box<Account<'info, PositionBundle>>,
+
+    #[account(
+        constraint = position_bundle_token_account.mint == position_bundle.position_bundle_mint,
+        constraint = position_bundle_token_account.amount == 1
+    )]
+    pub position_bundle_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        close = receiver,
+        seeds = [
+            b"bundled_liquidity_position".as_ref(),
+            position_bundle.position_bundle_mint.as_ref(),
+            bundle_index.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub position: Account<'info, LiquidityPosition>,
+}
+
+pub fn close_bundled_liquidity_position(
+    ctx: Context<CloseBundledLiquidityPosition>,
+    bundle_index: u16,
+) -> Result<()> {
+    verify_position_authority(
+        &ctx.accounts.position_bundle_token_account,
+        &ctx.accounts.position_authority,
+    )?;
+
+    if !LiquidityPosition::is_position_empty(&ctx.accounts.position) {
+        return Err(ErrorCode::CloseLiquidityPositionNotEmpty.into());
+    }
+
+    ctx.accounts
+        .position_bundle
+        .close_bundled_position(bundle_index)
+}
@@ -0,0 +1,47 @@
+use {
+    crate::{errors::ErrorCode, state::*},
+    anchor_lang::prelude::*,
+};
+
+#[derive(Accounts)]
+pub struct CloseTickArray<'info> {
+    pub globalpool: Account<'info, Globalpool>,
+
+    #[account(mut, has_one = globalpool, close = funder)]
+    pub tick_array: AccountLoader<'info, TickArray>,
+
+    #[account(
+        mut,
+        has_one = globalpool,
+        seeds = [b"tick_array_bitmap", globalpool.key().as_ref()],
+        bump
+    )]
+    pub tick_array_bitmap: Account<'info, TickArrayBitmap>,
+
+    // Receives the rent, checked to be the funder of the tick array
+    #[account(mut)]
+    pub funder: Signer<'info>,
+}
+
+///
+/// Closes a TickArray without liquidity, refunding its rent to the account that funded it.
+///
+pub fn close_tick_array(ctx: Context<CloseTickArray>) -> Result<()> {
+    let (tick_array, extension) = load_tick_array_mut(&ctx.accounts.tick_array)?;
+
+    // The funder is recorded in the extension, so tick arrays created before it can't be closed
+    let extension = extension.ok_or(ErrorCode::TickArrayExtensionMissing)?;
+    if extension.funder != ctx.accounts.funder.key() {
+        return Err(ErrorCode::InvalidTickArrayFunder.into());
+    }
+
+    if !tick_array.is_closable() || !extension.is_closable() {
+        return Err(ErrorCode::TickArrayNotClosable.into());
+    }
+
+    ctx.accounts.tick_array_bitmap.set_initialized(
+        tick_array.start_tick_index,
+        ctx.accounts.globalpool.tick_spacing,
+        false,
+    )
+}
@@ -1,10 +1,13 @@
 use {
     crate::{
         errors::ErrorCode,
+        events::TradePositionClosed,
         manager::{liquidity_manager, loan_manager},
         state::*,
         util::{
             // burn_and_close_user_position_token,
+            load_native_sol_account,
+            load_token_owner_account,
             transfer_from_vault_to_owner,
             verify_position_authority,
         },
@@ -18,7 +21,11 @@ pub struct CloseTradePosition<'info> {
     #[account(mut)]
     pub owner: Signer<'info>,
 
-    #[account(mut)]
+    // The vaults are lent out while a flash loan is outstanding
+    #[account(
+        mut,
+        constraint = !globalpool.has_flash_loan() @ ErrorCode::FlashLoanInProgress
+    )]
     pub globalpool: Box<Account<'info, Globalpool>>,
 
     /// CHECK: safe, for receiving rent only
@@ -45,8 +52,9 @@ pub struct CloseTradePosition<'info> {
 	)]
     pub position_token_account: Box<Account<'info, TokenAccount>>,
 
-    #[account(mut, constraint = token_owner_account_a.mint == globalpool.token_mint_a)]
-    pub token_owner_account_a: Box<Account<'info, TokenAccount>>,
+    /// CHECK: mint is checked in the handler, as it may be the temporary wSOL account
+    #[account(mut)]
+    pub token_owner_account_a: UncheckedAccount<'info>,
 
     #[account(mut, address = globalpool.token_vault_a)]
     pub token_vault_a: Box<Account<'info, TokenAccount>>,
@@ -54,8 +62,9 @@ pub struct CloseTradePosition<'info> {
     #[account(address = globalpool.token_mint_a)]
     pub token_mint_a: Box<Account<'info, Mint>>,
 
-    #[account(mut, constraint = token_owner_account_b.mint == globalpool.token_mint_b)]
-    pub token_owner_account_b: Box<Account<'info, TokenAccount>>,
+    /// CHECK: mint is checked in the handler, as it may be the temporary wSOL account
+    #[account(mut)]
+    pub token_owner_account_b: UncheckedAccount<'info>,
 
     #[account(mut, address = globalpool.token_vault_b)]
     pub token_vault_b: Box<Account<'info, TokenAccount>>,
@@ -80,6 +89,38 @@ pub fn close_trade_position(ctx: Context<CloseTradePosition>) -> Result<()> {
         return Err(ErrorCode::CloseTradePositionNotEmpty.into());
     }
 
+    // In native SOL mode, the collateral returned in SOL is unwrapped to the owner
+    let (native_sol_account, _) = load_native_sol_account(
+        ctx.remaining_accounts,
+        &ctx.accounts.owner,
+        [
+            &ctx.accounts.token_mint_a.to_account_info(),
+            &ctx.accounts.token_mint_b.to_account_info(),
+        ],
+        [
+            &ctx.accounts.token_owner_account_a,
+            &ctx.accounts.token_owner_account_b,
+        ],
+        [
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+        ],
+    )?;
+    if let Some(native_sol_account) = native_sol_account.as_ref() {
+        native_sol_account.open(0)?;
+    }
+
+    let token_owner_account_a = load_token_owner_account(
+        &ctx.accounts.token_owner_account_a,
+        &ctx.accounts.globalpool.token_mint_a,
+        None,
+    )?;
+    let token_owner_account_b = load_token_owner_account(
+        &ctx.accounts.token_owner_account_b,
+        &ctx.accounts.globalpool.token_mint_b,
+        None,
+    )?;
+
     let liquidity_borrowed = ctx.accounts.position.liquidity_borrowed;
     let loan_token_available = ctx.accounts.position.loan_token_available;
 
@@ -109,10 +150,10 @@ pub fn close_trade_position(ctx: Context<CloseTradePosition>) -> Result<()> {
 
     // borrow a => collateral is b (vice versa)
     if is_borrow_a {
-        collateral_token_owner_account = &ctx.accounts.token_owner_account_b;
+        collateral_token_owner_account = &token_owner_account_b;
         collateral_token_vault = &ctx.accounts.token_vault_b;
     } else {
-        collateral_token_owner_account = &ctx.accounts.token_owner_account_a;
+        collateral_token_owner_account = &token_owner_account_a;
         collateral_token_vault = &ctx.accounts.token_vault_a;
     };
 
@@ -128,6 +169,10 @@ pub fn close_trade_position(ctx: Context<CloseTradePosition>) -> Result<()> {
 
     ctx.accounts.position.collateral_amount = 0; // nothing left
 
+    if let Some(native_sol_account) = native_sol_account.as_ref() {
+        native_sol_account.close()?;
+    }
+
     //
     // Burn loan position token
     //
@@ -140,5 +185,12 @@ pub fn close_trade_position(ctx: Context<CloseTradePosition>) -> Result<()> {
     //     &ctx.accounts.token_program,
     // )
 
+    emit!(TradePositionClosed {
+        globalpool: ctx.accounts.globalpool.key(),
+        position: ctx.accounts.position.key(),
+        owner: ctx.accounts.owner.key(),
+        collateral_returned: collateral_amount,
+    });
+
     Ok(())
 }
@@ -1,11 +1,17 @@
 use {
     crate::{
+        errors::ErrorCode,
+        events::FeesCollected,
         manager::liquidity_manager::calculate_fee_growths,
         state::*,
-        util::{to_timestamp_u64, transfer_from_vault_to_owner, verify_position_authority},
+        util::{
+            get_token_account_owner, load_native_sol_account, load_position_operator,
+            to_timestamp_u64, transfer_from_vault_to_owner_checked,
+            verify_position_authority_or_operator,
+        },
     },
     anchor_lang::prelude::*,
-    anchor_spl::token::{self, Token, TokenAccount},
+    anchor_spl::token::TokenAccount,
 };
 
 #[derive(Accounts)]
@@ -14,7 +20,12 @@ pub struct CollectFees<'info> {
 
     pub position_authority: Signer<'info>,
 
-    #[account(mut, has_one = globalpool)]
+    // Range orders don't earn fees
+    #[account(
+        mut,
+        has_one = globalpool,
+        constraint = !position.is_range_order @ ErrorCode::RangeOrderPositionUnsupported
+    )]
     pub position: Box<Account<'info, LiquidityPosition>>,
     #[account(
         constraint = position_token_account.mint == position.position_mint,
@@ -27,26 +38,72 @@ pub struct CollectFees<'info> {
     #[account(has_one = globalpool)]
     pub tick_array_upper: AccountLoader<'info, TickArray>,
 
-    #[account(mut, constraint = token_owner_account_a.mint == globalpool.token_mint_a)]
-    pub token_owner_account_a: Box<Account<'info, TokenAccount>>,
+    /// CHECK: address is checked
+    #[account(address = globalpool.token_mint_a)]
+    pub token_mint_a: UncheckedAccount<'info>,
+    /// CHECK: address is checked
+    #[account(address = globalpool.token_mint_b)]
+    pub token_mint_b: UncheckedAccount<'info>,
+
+    /// CHECK: mint & authority are checked by the token program
+    #[account(mut)]
+    pub token_owner_account_a: UncheckedAccount<'info>,
+    /// CHECK: address is checked
     #[account(mut, address = globalpool.token_vault_a)]
-    pub token_vault_a: Box<Account<'info, TokenAccount>>,
+    pub token_vault_a: UncheckedAccount<'info>,
 
-    #[account(mut, constraint = token_owner_account_b.mint == globalpool.token_mint_b)]
-    pub token_owner_account_b: Box<Account<'info, TokenAccount>>,
+    /// CHECK: mint & authority are checked by the token program
+    #[account(mut)]
+    pub token_owner_account_b: UncheckedAccount<'info>,
+    /// CHECK: address is checked
     #[account(mut, address = globalpool.token_vault_b)]
-    pub token_vault_b: Box<Account<'info, TokenAccount>>,
+    pub token_vault_b: UncheckedAccount<'info>,
 
-    #[account(address = token::ID)]
-    pub token_program: Program<'info, Token>,
+    /// CHECK: owner of token_mint_a, checked by constraint
+    #[account(constraint = token_program_a.key() == *token_mint_a.owner @ ErrorCode::InvalidTokenProgram)]
+    pub token_program_a: UncheckedAccount<'info>,
+    /// CHECK: owner of token_mint_b, checked by constraint
+    #[account(constraint = token_program_b.key() == *token_mint_b.owner @ ErrorCode::InvalidTokenProgram)]
+    pub token_program_b: UncheckedAccount<'info>,
 }
 
 pub fn collect_fees(ctx: Context<CollectFees>) -> Result<()> {
-    verify_position_authority(
+    // The remaining accounts are the optional System Program enabling native SOL mode, then the
+    // optional PositionOperator
+    let (native_sol_account, remaining_accounts) = load_native_sol_account(
+        ctx.remaining_accounts,
+        &ctx.accounts.position_authority,
+        [&ctx.accounts.token_mint_a, &ctx.accounts.token_mint_b],
+        [
+            &ctx.accounts.token_owner_account_a,
+            &ctx.accounts.token_owner_account_b,
+        ],
+        [&ctx.accounts.token_program_a, &ctx.accounts.token_program_b],
+    )?;
+    let (position_operator, _) = load_position_operator(remaining_accounts)?;
+    verify_position_authority_or_operator(
         &ctx.accounts.position_token_account,
         &ctx.accounts.position_authority,
+        &ctx.accounts.position.key(),
+        position_operator.as_deref(),
+        OPERATOR_COLLECT_FEES,
     )?;
 
+    if let Some(native_sol_account) = native_sol_account.as_ref() {
+        native_sol_account.open(0)?;
+    }
+
+    // Operators collect on behalf of the owner, so the temporary wSOL account of an operator is
+    // rejected too
+    if position_operator.is_some() {
+        let owner = ctx.accounts.position_token_account.owner;
+        if get_token_account_owner(&ctx.accounts.token_owner_account_a)? != owner
+            || get_token_account_owner(&ctx.accounts.token_owner_account_b)? != owner
+        {
+            return Err(ErrorCode::InvalidOperatorTokenAccount.into());
+        }
+    }
+
     let globalpool = &mut ctx.accounts.globalpool;
     // let position = &mut ctx.accounts.position;
 
@@ -67,21 +124,39 @@ pub fn collect_fees(ctx: Context<CollectFees>) -> Result<()> {
     // Reset the fee before transfer.
     (&mut ctx.accounts.position).reset_fees_owed();
 
-    transfer_from_vault_to_owner(
+    transfer_from_vault_to_owner_checked(
         globalpool,
+        &ctx.accounts.token_mint_a,
         &ctx.accounts.token_vault_a,
         &ctx.accounts.token_owner_account_a,
-        &ctx.accounts.token_program,
+        &ctx.accounts.token_program_a,
         fee_owed_a,
     )?;
 
-    transfer_from_vault_to_owner(
+    transfer_from_vault_to_owner_checked(
         globalpool,
+        &ctx.accounts.token_mint_b,
         &ctx.accounts.token_vault_b,
         &ctx.accounts.token_owner_account_b,
-        &ctx.accounts.token_program,
+        &ctx.accounts.token_program_b,
         fee_owed_b,
     )?;
 
+    if let Some(native_sol_account) = native_sol_account.as_ref() {
+        native_sol_account.close()?;
+    }
+
+    let position = &ctx.accounts.position;
+    emit!(FeesCollected {
+        globalpool: globalpool.key(),
+        position: position.key(),
+        tick_lower_index: position.tick_lower_index,
+        tick_upper_index: position.tick_upper_index,
+        fee_amount_a: fee_owed_a,
+        fee_amount_b: fee_owed_b,
+        fee_growth_checkpoint_a: position.fee_growth_checkpoint_a,
+        fee_growth_checkpoint_b: position.fee_growth_checkpoint_b,
+    });
+
     Ok(())
 }
@@ -1,6 +1,8 @@
-use crate::{state::*, util::transfer_from_vault_to_owner};
+use crate::{
+    errors::ErrorCode, events::ProtocolFeesCollected, state::*,
+    util::transfer_from_vault_to_owner_checked,
+};
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount};
 
 #[derive(Accounts)]
 pub struct CollectProtocolFees<'info> {
@@ -10,40 +12,63 @@ pub struct CollectProtocolFees<'info> {
     #[account(address = globalpool.fee_authority)]
     pub collect_protocol_fees_authority: Signer<'info>,
 
+    /// CHECK: address is checked
+    #[account(address = globalpool.token_mint_a)]
+    pub token_mint_a: UncheckedAccount<'info>,
+    /// CHECK: address is checked
+    #[account(address = globalpool.token_mint_b)]
+    pub token_mint_b: UncheckedAccount<'info>,
+
+    /// CHECK: address is checked
     #[account(mut, address = globalpool.token_vault_a)]
-    pub token_vault_a: Account<'info, TokenAccount>,
+    pub token_vault_a: UncheckedAccount<'info>,
 
+    /// CHECK: address is checked
     #[account(mut, address = globalpool.token_vault_b)]
-    pub token_vault_b: Account<'info, TokenAccount>,
+    pub token_vault_b: UncheckedAccount<'info>,
 
-    #[account(mut, constraint = token_destination_a.mint == globalpool.token_mint_a)]
-    pub token_destination_a: Account<'info, TokenAccount>,
+    /// CHECK: mint is checked by the token program
+    #[account(mut)]
+    pub token_destination_a: UncheckedAccount<'info>,
 
-    #[account(mut, constraint = token_destination_b.mint == globalpool.token_mint_b)]
-    pub token_destination_b: Account<'info, TokenAccount>,
+    /// CHECK: mint is checked by the token program
+    #[account(mut)]
+    pub token_destination_b: UncheckedAccount<'info>,
 
-    #[account(address = token::ID)]
-    pub token_program: Program<'info, Token>,
+    /// CHECK: owner of token_mint_a, checked by constraint
+    #[account(constraint = token_program_a.key() == *token_mint_a.owner @ ErrorCode::InvalidTokenProgram)]
+    pub token_program_a: UncheckedAccount<'info>,
+    /// CHECK: owner of token_mint_b, checked by constraint
+    #[account(constraint = token_program_b.key() == *token_mint_b.owner @ ErrorCode::InvalidTokenProgram)]
+    pub token_program_b: UncheckedAccount<'info>,
 }
 
 pub fn collect_protocol_fees(ctx: Context<CollectProtocolFees>) -> Result<()> {
     let globalpool = &ctx.accounts.globalpool;
 
-    transfer_from_vault_to_owner(
+    transfer_from_vault_to_owner_checked(
         globalpool,
+        &ctx.accounts.token_mint_a,
         &ctx.accounts.token_vault_a,
         &ctx.accounts.token_destination_a,
-        &ctx.accounts.token_program,
+        &ctx.accounts.token_program_a,
         globalpool.protocol_fee_owed_a,
     )?;
 
-    transfer_from_vault_to_owner(
+    transfer_from_vault_to_owner_checked(
         globalpool,
+        &ctx.accounts.token_mint_b,
         &ctx.accounts.token_vault_b,
         &ctx.accounts.token_destination_b,
-        &ctx.accounts.token_program,
+        &ctx.accounts.token_program_b,
         globalpool.protocol_fee_owed_b,
     )?;
 
+    emit!(ProtocolFeesCollected {
+        globalpool: globalpool.key(),
+        protocol_fee_amount_a: globalpool.protocol_fee_owed_a,
+        protocol_fee_amount_b: globalpool.protocol_fee_owed_b,
+    });
+
     Ok(ctx.accounts.globalpool.reset_protocol_fees_owed())
 }
@@ -0,0 +1,243 @@
+use {
+    crate::{
+        errors::ErrorCode,
+        events::FeesCompounded,
+        manager::{
+            liquidity_manager::{
+                calculate_fee_growths, calculate_liquidity_from_token_amounts,
+                calculate_liquidity_token_deltas, calculate_modify_liquidity,
+                calculate_rebalance_swap, sync_modify_liquidity_values,
+            },
+            swap_manager,
+        },
+        math::convert_to_liquidity_delta,
+        state::*,
+        util::{
+            load_position_operator, load_tick_array_bitmap, load_tick_arrays, to_timestamp_u64,
+            verify_position_authority_or_operator, TickSequence,
+        },
+    },
+    anchor_lang::prelude::*,
+    anchor_spl::token::TokenAccount,
+};
+
+#[derive(Accounts)]
+pub struct CompoundFees<'info> {
+    pub position_authority: Signer<'info>,
+
+    // Hooks are only called by `swap` and the liquidity instructions, and the vaults are lent
+    // out while a flash loan is outstanding
+    #[account(
+        mut,
+        constraint = globalpool.get_hook_program().is_none() @ ErrorCode::HookUnsupported,
+        constraint = !globalpool.has_flash_loan() @ ErrorCode::FlashLoanInProgress
+    )]
+    pub globalpool: Box<Account<'info, Globalpool>>,
+
+    // Range orders don't earn fees
+    #[account(
+        mut,
+        has_one = globalpool,
+        constraint = !position.is_range_order @ ErrorCode::RangeOrderPositionUnsupported
+    )]
+    pub position: Box<Account<'info, LiquidityPosition>>,
+    #[account(
+        constraint = position_token_account.mint == position.position_mint,
+        constraint = position_token_account.amount == 1
+    )]
+    pub position_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, has_one = globalpool)]
+    pub tick_array_lower: AccountLoader<'info, TickArray>,
+    #[account(mut, has_one = globalpool)]
+    pub tick_array_upper: AccountLoader<'info, TickArray>,
+
+    // Tick arrays of the rebalancing swap, like in `swap`
+    #[account(mut, has_one = globalpool)]
+    pub tick_array_0: AccountLoader<'info, TickArray>,
+    #[account(mut, has_one = globalpool)]
+    pub tick_array_1: AccountLoader<'info, TickArray>,
+    #[account(mut, has_one = globalpool)]
+    pub tick_array_2: AccountLoader<'info, TickArray>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CompoundFeesParams {
+    pub sqrt_price_lower_limit: u128,
+    pub sqrt_price_upper_limit: u128,
+}
+
+pub fn compound_fees(ctx: Context<CompoundFees>, params: &CompoundFeesParams) -> Result<()> {
+    let (position_operator, remaining_accounts) = load_position_operator(ctx.remaining_accounts)?;
+    verify_position_authority_or_operator(
+        &ctx.accounts.position_token_account,
+        &ctx.accounts.position_authority,
+        &ctx.accounts.position.key(),
+        position_operator.as_deref(),
+        OPERATOR_COMPOUND_FEES,
+    )?;
+
+    let globalpool = &mut ctx.accounts.globalpool;
+    if globalpool.sqrt_price < params.sqrt_price_lower_limit
+        || globalpool.sqrt_price > params.sqrt_price_upper_limit
+    {
+        return Err(ErrorCode::SqrtPriceOutOfRange.into());
+    }
+
+    let timestamp = to_timestamp_u64(Clock::get()?.unix_timestamp)?;
+
+    // Update fee accrued for the position, then take it out to be deposited
+    let position_update = &calculate_fee_growths(
+        globalpool,
+        &ctx.accounts.position,
+        &ctx.accounts.tick_array_lower,
+        &ctx.accounts.tick_array_upper,
+        timestamp,
+    )?;
+    ctx.accounts.position.update(position_update);
+
+    let fee_amount_a = ctx.accounts.position.fee_owed_a;
+    let fee_amount_b = ctx.accounts.position.fee_owed_b;
+    ctx.accounts.position.reset_fees_owed();
+
+    let tick_lower_index = ctx.accounts.position.tick_lower_index;
+    let tick_upper_index = ctx.accounts.position.tick_upper_index;
+
+    //
+    // The fees are already held by the vaults, so the swap only moves them between the position and
+    // the pool's reserves: no tokens are transferred.
+    //
+    let (swap_amount, a_to_b) = calculate_rebalance_swap(
+        tick_lower_index,
+        tick_upper_index,
+        globalpool.tick_current_index,
+        globalpool.sqrt_price,
+        fee_amount_a,
+        fee_amount_b,
+    )?;
+
+    let (mut amount_a, mut amount_b) = (fee_amount_a, fee_amount_b);
+    let (mut swap_amount_in, mut swap_amount_out) = (0, 0);
+    if swap_amount > 0 {
+        let swap_update = {
+            let (tick_array_bitmap, remaining_accounts) =
+                load_tick_array_bitmap(remaining_accounts, &globalpool.key())?;
+            let remaining_tick_arrays = load_tick_arrays(remaining_accounts, &globalpool.key())?;
+
+            let mut tick_arrays = vec![load_tick_array_mut(&ctx.accounts.tick_array_0)?];
+            tick_arrays.extend(load_tick_array_mut(&ctx.accounts.tick_array_1).ok());
+            tick_arrays.extend(load_tick_array_mut(&ctx.accounts.tick_array_2).ok());
+            for tick_array in remaining_tick_arrays.iter() {
+                tick_arrays.push(load_tick_array_mut(tick_array)?);
+            }
+            let mut swap_tick_sequence = TickSequence::from_arrays_with_extensions(tick_arrays)?;
+            if let Some(tick_array_bitmap) = tick_array_bitmap.as_ref() {
+                swap_tick_sequence.set_tick_array_bitmap(tick_array_bitmap);
+            }
+
+            let sqrt_price_limit = if a_to_b {
+                params.sqrt_price_lower_limit
+            } else {
+                params.sqrt_price_upper_limit
+            };
+
+            swap_manager::swap(
+                globalpool,
+                &mut swap_tick_sequence,
+                swap_amount,
+                sqrt_price_limit,
+                true,
+                a_to_b,
+                timestamp,
+            )?
+        };
+
+        if a_to_b {
+            swap_amount_in = swap_update.amount_a;
+            swap_amount_out = swap_update.amount_b;
+            amount_a -= swap_amount_in;
+            amount_b += swap_amount_out;
+        } else {
+            swap_amount_in = swap_update.amount_b;
+            swap_amount_out = swap_update.amount_a;
+            amount_b -= swap_amount_in;
+            amount_a += swap_amount_out;
+        }
+
+        globalpool.update_after_swap(
+            swap_update.next_liquidity,
+            swap_update.next_range_order_liquidity,
+            swap_update.next_tick_index,
+            swap_update.next_sqrt_price,
+            swap_update.next_fee_growth_global,
+            swap_update.next_protocol_fee,
+            &swap_update.next_volatility,
+            timestamp,
+            a_to_b,
+        );
+    }
+
+    let liquidity_amount = calculate_liquidity_from_token_amounts(
+        tick_lower_index,
+        tick_upper_index,
+        globalpool.tick_current_index,
+        globalpool.sqrt_price,
+        amount_a,
+        amount_b,
+    )?;
+    if liquidity_amount == 0 {
+        return Err(ErrorCode::LiquidityZero.into());
+    }
+    let liquidity_delta = convert_to_liquidity_delta(liquidity_amount, true)?;
+
+    let update = calculate_modify_liquidity(
+        globalpool,
+        &ctx.accounts.position,
+        &ctx.accounts.tick_array_lower,
+        &ctx.accounts.tick_array_upper,
+        liquidity_delta,
+        timestamp,
+    )?;
+    sync_modify_liquidity_values(
+        globalpool,
+        &mut ctx.accounts.position,
+        &ctx.accounts.tick_array_lower,
+        &ctx.accounts.tick_array_upper,
+        update,
+    )?;
+
+    let (delta_a, delta_b) = calculate_liquidity_token_deltas(
+        tick_lower_index,
+        tick_upper_index,
+        globalpool.tick_current_index,
+        globalpool.sqrt_price,
+        liquidity_delta,
+    )?;
+
+    // Dust that doesn't fit the position ratio stays owed to the position
+    ctx.accounts
+        .position
+        .add_fees_owed(amount_a - delta_a, amount_b - delta_b);
+
+    let position = &ctx.accounts.position;
+    emit!(FeesCompounded {
+        globalpool: globalpool.key(),
+        position: position.key(),
+        tick_lower_index,
+        tick_upper_index,
+        fee_amount_a,
+        fee_amount_b,
+        a_to_b,
+        swap_amount_in,
+        swap_amount_out,
+        liquidity_amount,
+        token_amount_a: delta_a,
+        token_amount_b: delta_b,
+        sqrt_price: globalpool.sqrt_price,
+        tick_current_index: globalpool.tick_current_index,
+        fee_growth_global_a: globalpool.fee_growth_global_a,
+        fee_growth_global_b: globalpool.fee_growth_global_b,
+    });
+
+    Ok(())
+}
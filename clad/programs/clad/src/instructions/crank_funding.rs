@@ -0,0 +1,40 @@
+use {
+    crate::{
+        errors::ErrorCode,
+        manager::loan_manager::{next_funding_index, pay_funding},
+        state::*,
+        util::to_timestamp_u64,
+    },
+    anchor_lang::prelude::*,
+};
+
+#[derive(Accounts)]
+pub struct CrankFunding<'info> {
+    #[account(mut)]
+    pub globalpool: Box<Account<'info, Globalpool>>,
+    // remaining accounts: perpetual trade positions of the globalpool (writable) to collect funding from
+}
+
+pub fn crank_funding(ctx: Context<CrankFunding>) -> Result<()> {
+    let timestamp = to_timestamp_u64(Clock::get()?.unix_timestamp)?;
+    let funding_index = next_funding_index(&ctx.accounts.globalpool, timestamp)?;
+    ctx.accounts
+        .globalpool
+        .update_funding_index(funding_index, timestamp);
+
+    for position_info in ctx.remaining_accounts.iter() {
+        let mut position = Account::<TradePosition>::try_from(position_info)?;
+
+        if position.globalpool != ctx.accounts.globalpool.key() {
+            return Err(anchor_lang::error::ErrorCode::ConstraintHasOne.into());
+        }
+        if !position.is_perpetual {
+            return Err(ErrorCode::PositionNotPerpetual.into());
+        }
+
+        pay_funding(&mut ctx.accounts.globalpool, &mut position)?;
+        position.exit(&crate::ID)?;
+    }
+
+    Ok(())
+}
@@ -1,10 +1,11 @@
 use {
-    crate::state::*,
-    anchor_lang::prelude::*,
-    anchor_spl::{
-        associated_token::AssociatedToken,
-        token::{self, Mint, Token, TokenAccount},
+    crate::{
+        events::PoolInitialized,
+        state::*,
+        util::{create_token_vault, validate_token_mint},
     },
+    anchor_lang::prelude::*,
+    spl_associated_token_account::get_associated_token_address_with_program_id,
 };
 
 #[derive(Accounts)]
@@ -34,36 +35,46 @@ pub struct CreatePool<'info> {
     )]
     pub globalpool: Box<Account<'info, Globalpool>>,
 
-    pub token_mint_a: Account<'info, Mint>,
+    /// CHECK: SPL Token or Token-2022 mint, validated in the handler
+    pub token_mint_a: UncheckedAccount<'info>,
 
-    pub token_mint_b: Account<'info, Mint>,
+    /// CHECK: SPL Token or Token-2022 mint, validated in the handler
+    pub token_mint_b: UncheckedAccount<'info>,
 
+    /// CHECK: associated token account of the globalpool, created in the handler
     #[account(
-        init_if_needed,
-        payer = funder,
-        constraint = token_mint_a.key() == token_vault_a.mint,
-        associated_token::mint = token_mint_a,
-        associated_token::authority = globalpool
+        mut,
+        address = get_associated_token_address_with_program_id(
+            &globalpool.key(),
+            &token_mint_a.key(),
+            &token_program_a.key(),
+        )
     )]
-    pub token_vault_a: Box<Account<'info, TokenAccount>>,
+    pub token_vault_a: UncheckedAccount<'info>,
 
+    /// CHECK: associated token account of the globalpool, created in the handler
     #[account(
-        init_if_needed,
-        payer = funder,
-        constraint = token_mint_b.key() == token_vault_b.mint,
-        associated_token::mint = token_mint_b,
-        associated_token::authority = globalpool
+        mut,
+        address = get_associated_token_address_with_program_id(
+            &globalpool.key(),
+            &token_mint_b.key(),
+            &token_program_b.key(),
+        )
     )]
-    pub token_vault_b: Box<Account<'info, TokenAccount>>,
+    pub token_vault_b: UncheckedAccount<'info>,
 
     // Need to read from Pyth to calculate collateral amount. We read both Token A and B since 
     // the prices are returned in USD and we calculate collateral denominated in either token A or B.
     // pub token_price_feed_a: Account<'info, PriceFeed>,
     // pub token_price_feed_b: Account<'info, PriceFeed>,
 
-    #[account(address = token::ID)]
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// CHECK: owner of token_mint_a (SPL Token or Token-2022), validated in the handler
+    pub token_program_a: UncheckedAccount<'info>,
+    /// CHECK: owner of token_mint_b (SPL Token or Token-2022), validated in the handler
+    pub token_program_b: UncheckedAccount<'info>,
+    /// CHECK: address is checked
+    #[account(address = spl_associated_token_account::ID)]
+    pub associated_token_program: UncheckedAccount<'info>,
 
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
@@ -74,9 +85,33 @@ pub struct CreatePoolParams {
     pub fee_rate: u16,
     pub tick_spacing: u16,
     pub initial_sqrt_price: u128,
+    // Program called before & after swaps and liquidity changes, which can't be changed later
+    pub hook_program: Option<Pubkey>,
 }
 
 pub fn create_pool(ctx: Context<CreatePool>, params: &CreatePoolParams) -> Result<()> {
+    validate_token_mint(&ctx.accounts.token_mint_a, &ctx.accounts.token_program_a)?;
+    validate_token_mint(&ctx.accounts.token_mint_b, &ctx.accounts.token_program_b)?;
+
+    create_token_vault(
+        &ctx.accounts.funder,
+        &ctx.accounts.globalpool,
+        &ctx.accounts.token_mint_a,
+        &ctx.accounts.token_vault_a,
+        &ctx.accounts.token_program_a,
+        &ctx.accounts.associated_token_program,
+        &ctx.accounts.system_program,
+    )?;
+    create_token_vault(
+        &ctx.accounts.funder,
+        &ctx.accounts.globalpool,
+        &ctx.accounts.token_mint_b,
+        &ctx.accounts.token_vault_b,
+        &ctx.accounts.token_program_b,
+        &ctx.accounts.associated_token_program,
+        &ctx.accounts.system_program,
+    )?;
+
     let clad = &ctx.accounts.clad;
 
     let globalpool = &mut ctx.accounts.globalpool;
@@ -85,7 +120,7 @@ pub fn create_pool(ctx: Context<CreatePool>, params: &CreatePoolParams) -> Resul
         .get("globalpool")
         .ok_or(ProgramError::InvalidSeeds)?;
 
-    Ok(globalpool.initialize(
+    globalpool.initialize(
         globalpool_bump,
         params.tick_spacing,
         params.initial_sqrt_price,
@@ -98,5 +133,19 @@ pub fn create_pool(ctx: Context<CreatePool>, params: &CreatePoolParams) -> Resul
         ctx.accounts.token_vault_b.key(),
         // ctx.accounts.token_price_feed_a.key(),
         // ctx.accounts.token_price_feed_b.key(),
-    )?)
+    )?;
+    globalpool.initialize_hook_program(params.hook_program)?;
+
+    emit!(PoolInitialized {
+        globalpool: globalpool.key(),
+        token_mint_a: globalpool.token_mint_a,
+        token_mint_b: globalpool.token_mint_b,
+        tick_spacing: globalpool.tick_spacing,
+        fee_rate: globalpool.fee_rate,
+        sqrt_price: globalpool.sqrt_price,
+        tick_current_index: globalpool.tick_current_index,
+        hook_program: globalpool.hook_program,
+    });
+
+    Ok(())
 }
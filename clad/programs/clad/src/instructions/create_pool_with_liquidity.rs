@@ -0,0 +1,369 @@
+use {
+    crate::{
+        errors::ErrorCode,
+        events::LiquidityIncreased,
+        instructions::create_pool::*,
+        manager::liquidity_manager::{
+            calculate_liquidity_token_deltas, calculate_modify_liquidity,
+            sync_modify_liquidity_values,
+        },
+        math::convert_to_liquidity_delta,
+        state::*,
+        util::{
+            calculate_transfer_fee_included_amount, get_epoch_transfer_fee,
+            mint_position_token_and_remove_authority, to_timestamp_u64,
+            transfer_from_owner_to_vault_checked,
+        },
+    },
+    anchor_lang::{
+        prelude::*,
+        system_program::{
+            allocate, assign, create_account, transfer, Allocate, Assign, CreateAccount, Transfer,
+        },
+    },
+    anchor_spl::{
+        associated_token::AssociatedToken,
+        token::{self, Mint, Token, TokenAccount},
+    },
+};
+
+#[derive(Accounts)]
+#[instruction(params: CreatePoolWithLiquidityParams)]
+pub struct CreatePoolWithLiquidity<'info> {
+    // The funder creates the pool and owns the seed position
+    pub create_pool: CreatePool<'info>,
+
+    #[account(
+        init,
+        payer = create_pool.funder,
+        seeds = [b"tick_array_bitmap", create_pool.globalpool.key().as_ref()],
+        bump,
+        space = TickArrayBitmap::LEN
+    )]
+    pub tick_array_bitmap: Box<Account<'info, TickArrayBitmap>>,
+
+    /// CHECK: PDA of the tick array holding the lower tick, created in the handler
+    #[account(mut)]
+    pub tick_array_lower: UncheckedAccount<'info>,
+
+    /// CHECK: PDA of the tick array holding the upper tick, created in the handler. The same
+    /// account as `tick_array_lower` if the range is within one tick array.
+    #[account(mut)]
+    pub tick_array_upper: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = create_pool.funder,
+        space = LiquidityPosition::LEN,
+        seeds = [
+            b"liquidity_position".as_ref(),
+            position_mint.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub position: Box<Account<'info, LiquidityPosition>>,
+
+    #[account(
+        init,
+        payer = create_pool.funder,
+        mint::authority = create_pool.globalpool,
+        mint::decimals = 0,
+    )]
+    pub position_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = create_pool.funder,
+        associated_token::mint = position_mint,
+        associated_token::authority = create_pool.funder,
+    )]
+    pub position_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: mint & authority are checked by the token program
+    #[account(mut)]
+    pub token_owner_account_a: UncheckedAccount<'info>,
+
+    /// CHECK: mint & authority are checked by the token program
+    #[account(mut)]
+    pub token_owner_account_b: UncheckedAccount<'info>,
+
+    #[account(address = token::ID)]
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CreatePoolWithLiquidityParams {
+    // Leads the params, as `CreatePool` reads its params from the start of the instruction data
+    pub pool: CreatePoolParams,
+    // If set, the position spans the full range of usable ticks and the tick indexes are ignored
+    pub full_range: bool,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    pub liquidity_amount: u128,
+    pub token_max_a: u64,
+    pub token_max_b: u64,
+}
+
+pub fn create_pool_with_liquidity(
+    ctx: Context<CreatePoolWithLiquidity>,
+    params: &CreatePoolWithLiquidityParams,
+) -> Result<()> {
+    if params.liquidity_amount == 0 {
+        return Err(ErrorCode::LiquidityZero.into());
+    }
+    // The seed position would be funded without calling the hook
+    if params.pool.hook_program.is_some() {
+        return Err(ErrorCode::HookUnsupported.into());
+    }
+
+    create_pool(
+        Context::new(
+            ctx.program_id,
+            &mut ctx.accounts.create_pool,
+            ctx.remaining_accounts,
+            ctx.bumps.clone(),
+        ),
+        &params.pool,
+    )?;
+
+    let accounts = ctx.accounts;
+    let funder = &accounts.create_pool.funder;
+    let tick_spacing = accounts.create_pool.globalpool.tick_spacing;
+
+    let (tick_lower_index, tick_upper_index) = if params.full_range {
+        Tick::full_range_tick_indexes(tick_spacing)
+    } else {
+        (params.tick_lower_index, params.tick_upper_index)
+    };
+
+    //
+    // Tick arrays & bitmap, as `initialize_tick_array` creates them
+    //
+
+    // The Globalpool is new, so the bitmap records all of its tick arrays from the start
+    accounts
+        .tick_array_bitmap
+        .initialize(accounts.create_pool.globalpool.key(), true);
+
+    let tick_array_lower_start_index = Tick::get_start_tick_index(tick_lower_index, tick_spacing);
+    let tick_array_upper_start_index = Tick::get_start_tick_index(tick_upper_index, tick_spacing);
+
+    create_tick_array(
+        funder,
+        &accounts.create_pool.globalpool,
+        &accounts.tick_array_lower,
+        &mut accounts.tick_array_bitmap,
+        tick_array_lower_start_index,
+        &accounts.system_program,
+    )?;
+    if tick_array_upper_start_index != tick_array_lower_start_index {
+        create_tick_array(
+            funder,
+            &accounts.create_pool.globalpool,
+            &accounts.tick_array_upper,
+            &mut accounts.tick_array_bitmap,
+            tick_array_upper_start_index,
+            &accounts.system_program,
+        )?;
+    } else if accounts.tick_array_upper.key() != accounts.tick_array_lower.key() {
+        return Err(anchor_lang::error::ErrorCode::ConstraintSeeds.into());
+    }
+
+    let tick_array_lower = AccountLoader::<TickArray>::try_from(&accounts.tick_array_lower)?;
+    let tick_array_upper = AccountLoader::<TickArray>::try_from(&accounts.tick_array_upper)?;
+
+    //
+    // Seed position, as `open_liquidity_position` & `increase_liquidity` fund it
+    //
+
+    let globalpool = &mut accounts.create_pool.globalpool;
+    let position = &mut accounts.position;
+    position.open_position(
+        globalpool,
+        accounts.position_mint.key(),
+        tick_lower_index,
+        tick_upper_index,
+    )?;
+
+    mint_position_token_and_remove_authority(
+        globalpool,
+        &accounts.position_mint,
+        &accounts.position_token_account,
+        &accounts.token_program,
+    )?;
+
+    let liquidity_delta = convert_to_liquidity_delta(params.liquidity_amount, true)?;
+
+    let update = calculate_modify_liquidity(
+        globalpool,
+        position,
+        &tick_array_lower,
+        &tick_array_upper,
+        liquidity_delta,
+        to_timestamp_u64(Clock::get()?.unix_timestamp)?,
+    )?;
+    sync_modify_liquidity_values(
+        globalpool,
+        position,
+        &tick_array_lower,
+        &tick_array_upper,
+        update,
+    )?;
+
+    let (delta_a, delta_b) = calculate_liquidity_token_deltas(
+        tick_lower_index,
+        tick_upper_index,
+        globalpool.tick_current_index,
+        globalpool.sqrt_price,
+        liquidity_delta,
+    )?;
+
+    // The vaults must receive the deltas, so any transfer fee is paid on top
+    let create_pool_accounts = &accounts.create_pool;
+    let transfer_amount_a = calculate_transfer_fee_included_amount(
+        &get_epoch_transfer_fee(&create_pool_accounts.token_mint_a)?,
+        delta_a,
+    )?;
+    let transfer_amount_b = calculate_transfer_fee_included_amount(
+        &get_epoch_transfer_fee(&create_pool_accounts.token_mint_b)?,
+        delta_b,
+    )?;
+
+    if transfer_amount_a > params.token_max_a || transfer_amount_b > params.token_max_b {
+        return Err(ErrorCode::TokenMaxExceeded.into());
+    }
+
+    transfer_from_owner_to_vault_checked(
+        funder,
+        &create_pool_accounts.token_mint_a,
+        &accounts.token_owner_account_a,
+        &create_pool_accounts.token_vault_a,
+        &create_pool_accounts.token_program_a,
+        transfer_amount_a,
+    )?;
+
+    transfer_from_owner_to_vault_checked(
+        funder,
+        &create_pool_accounts.token_mint_b,
+        &accounts.token_owner_account_b,
+        &create_pool_accounts.token_vault_b,
+        &create_pool_accounts.token_program_b,
+        transfer_amount_b,
+    )?;
+
+    let globalpool = &create_pool_accounts.globalpool;
+    emit!(LiquidityIncreased {
+        globalpool: globalpool.key(),
+        position: accounts.position.key(),
+        tick_lower_index,
+        tick_upper_index,
+        liquidity_amount: params.liquidity_amount,
+        token_amount_a: delta_a,
+        token_amount_b: delta_b,
+        sqrt_price: globalpool.sqrt_price,
+        tick_current_index: globalpool.tick_current_index,
+        fee_growth_global_a: globalpool.fee_growth_global_a,
+        fee_growth_global_b: globalpool.fee_growth_global_b,
+    });
+
+    Ok(())
+}
+
+// Creates and initializes the TickArray PDA starting at `start_tick_index`, recording it in the
+// bitmap of the Globalpool
+fn create_tick_array<'info>(
+    funder: &Signer<'info>,
+    globalpool: &Account<'info, Globalpool>,
+    tick_array: &UncheckedAccount<'info>,
+    tick_array_bitmap: &mut TickArrayBitmap,
+    start_tick_index: i32,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let globalpool_key = globalpool.key();
+    let start_tick_index_seed = start_tick_index.to_string();
+    let (tick_array_address, tick_array_bump) = Pubkey::find_program_address(
+        &[
+            b"tick_array",
+            globalpool_key.as_ref(),
+            start_tick_index_seed.as_bytes(),
+        ],
+        &crate::ID,
+    );
+    if tick_array.key() != tick_array_address {
+        return Err(anchor_lang::error::ErrorCode::ConstraintSeeds.into());
+    }
+
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        b"tick_array",
+        globalpool_key.as_ref(),
+        start_tick_index_seed.as_bytes(),
+        &[tick_array_bump],
+    ]];
+    let rent = Rent::get()?.minimum_balance(TickArrayExtension::TICK_ARRAY_LEN);
+    let lamports = tick_array.lamports();
+
+    if lamports == 0 {
+        create_account(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                CreateAccount {
+                    from: funder.to_account_info(),
+                    to: tick_array.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            rent,
+            TickArrayExtension::TICK_ARRAY_LEN as u64,
+            &crate::ID,
+        )?;
+    } else {
+        // Lamports sent to the address beforehand make `create_account` fail, so the account is
+        // topped up to the rent, then allocated & assigned as `init` would
+        if lamports < rent {
+            transfer(
+                CpiContext::new(
+                    system_program.to_account_info(),
+                    Transfer {
+                        from: funder.to_account_info(),
+                        to: tick_array.to_account_info(),
+                    },
+                ),
+                rent - lamports,
+            )?;
+        }
+        allocate(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                Allocate {
+                    account_to_allocate: tick_array.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            TickArrayExtension::TICK_ARRAY_LEN as u64,
+        )?;
+        assign(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                Assign {
+                    account_to_assign: tick_array.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            &crate::ID,
+        )?;
+    }
+
+    let tick_array = AccountLoader::<TickArray>::try_from_unchecked(&crate::ID, tick_array)?;
+    {
+        let (mut array, mut extension) = load_tick_array_init(&tick_array)?;
+        array.initialize(globalpool, start_tick_index)?;
+        extension.initialize(funder.key());
+    }
+    // Writes the account discriminator
+    tick_array.exit(&crate::ID)?;
+
+    tick_array_bitmap.set_initialized(start_tick_index, globalpool.tick_spacing, true)
+}
@@ -2,21 +2,29 @@ use {
     super::ModifyLiquidity,
     crate::{
         errors::ErrorCode,
-        manager::liquidity_manager::{
-            calculate_liquidity_token_deltas, calculate_modify_liquidity,
-            sync_modify_liquidity_values,
+        events::LiquidityDecreased,
+        manager::{
+            hook_manager::{load_globalpool_hook, HookOperation, HookParams},
+            liquidity_manager::{
+                calculate_liquidity_token_deltas, calculate_modify_liquidity,
+                sync_modify_liquidity_values,
+            },
         },
         math::convert_to_liquidity_delta,
-        util::{to_timestamp_u64, transfer_from_vault_to_owner, verify_position_authority},
+        util::{
+            calculate_transfer_fee_excluded_amount, get_epoch_transfer_fee,
+            load_native_sol_account, to_timestamp_u64, transfer_from_vault_to_owner_checked,
+            verify_position_authority,
+        },
     },
     anchor_lang::prelude::*,
 };
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct DecreaseLiquidityParams {
-    liquidity_amount: u128,
-    token_min_a: u64,
-    token_min_b: u64,
+    pub liquidity_amount: u128,
+    pub token_min_a: u64,
+    pub token_min_b: u64,
 }
 
 /*
@@ -31,6 +39,30 @@ pub fn decrease_liquidity(
         &ctx.accounts.position_authority,
     )?;
 
+    if ctx.accounts.position.is_range_order {
+        return Err(ErrorCode::RangeOrderPositionUnsupported.into());
+    }
+
+    // The remaining accounts are the optional System Program enabling native SOL mode, then the
+    // hook program of the globalpool followed by its accounts, if it has a hook
+    let (hook, remaining_accounts) =
+        load_globalpool_hook(&ctx.accounts.globalpool, ctx.remaining_accounts)?;
+
+    // In native SOL mode, SOL is received in the temporary wSOL account and unwrapped at the end
+    let (native_sol_account, _) = load_native_sol_account(
+        remaining_accounts,
+        &ctx.accounts.position_authority,
+        [&ctx.accounts.token_mint_a, &ctx.accounts.token_mint_b],
+        [
+            &ctx.accounts.token_owner_account_a,
+            &ctx.accounts.token_owner_account_b,
+        ],
+        [&ctx.accounts.token_program_a, &ctx.accounts.token_program_b],
+    )?;
+    if let Some(native_sol_account) = native_sol_account.as_ref() {
+        native_sol_account.open(0)?;
+    }
+
     let clock = Clock::get()?;
 
     if params.liquidity_amount == 0 {
@@ -48,6 +80,31 @@ pub fn decrease_liquidity(
         timestamp,
     )?;
 
+    let (delta_a, delta_b) = calculate_liquidity_token_deltas(
+        ctx.accounts.position.tick_lower_index,
+        ctx.accounts.position.tick_upper_index,
+        ctx.accounts.globalpool.tick_current_index,
+        ctx.accounts.globalpool.sqrt_price,
+        liquidity_delta,
+    )?;
+
+    let hook_params = HookParams::new(
+        HookOperation::DecreaseLiquidity,
+        &ctx.accounts.globalpool,
+        ctx.accounts.position_authority.key,
+        ctx.accounts.position.key(),
+        liquidity_delta,
+        -(delta_a as i128),
+        -(delta_b as i128),
+    );
+    if let Some(hook) = hook.as_ref() {
+        hook.before_operation(
+            &ctx.accounts.globalpool,
+            &ctx.accounts.position_authority,
+            &hook_params,
+        )?;
+    }
+
     sync_modify_liquidity_values(
         &mut ctx.accounts.globalpool,
         &mut ctx.accounts.position,
@@ -56,35 +113,67 @@ pub fn decrease_liquidity(
         update,
     )?;
 
-    let (delta_a, delta_b) = calculate_liquidity_token_deltas(
-        ctx.accounts.position.tick_lower_index,
-        ctx.accounts.position.tick_upper_index,
-        ctx.accounts.globalpool.tick_current_index,
-        ctx.accounts.globalpool.sqrt_price,
-        liquidity_delta,
+    // Minimums apply to the amounts received, after any transfer fee
+    let received_amount_a = calculate_transfer_fee_excluded_amount(
+        &get_epoch_transfer_fee(&ctx.accounts.token_mint_a)?,
+        delta_a,
+    )?;
+    let received_amount_b = calculate_transfer_fee_excluded_amount(
+        &get_epoch_transfer_fee(&ctx.accounts.token_mint_b)?,
+        delta_b,
     )?;
 
-    if delta_a < params.token_min_a {
+    if received_amount_a < params.token_min_a {
         return Err(ErrorCode::TokenMinSubceeded.into());
-    } else if delta_b < params.token_min_b {
+    } else if received_amount_b < params.token_min_b {
         return Err(ErrorCode::TokenMinSubceeded.into());
     }
 
-    transfer_from_vault_to_owner(
+    transfer_from_vault_to_owner_checked(
         &ctx.accounts.globalpool,
+        &ctx.accounts.token_mint_a,
         &ctx.accounts.token_vault_a,
         &ctx.accounts.token_owner_account_a,
-        &ctx.accounts.token_program,
+        &ctx.accounts.token_program_a,
         delta_a,
     )?;
 
-    transfer_from_vault_to_owner(
+    transfer_from_vault_to_owner_checked(
         &ctx.accounts.globalpool,
+        &ctx.accounts.token_mint_b,
         &ctx.accounts.token_vault_b,
         &ctx.accounts.token_owner_account_b,
-        &ctx.accounts.token_program,
+        &ctx.accounts.token_program_b,
         delta_b,
     )?;
 
+    if let Some(native_sol_account) = native_sol_account.as_ref() {
+        native_sol_account.close()?;
+    }
+
+    if let Some(hook) = hook.as_ref() {
+        hook.after_operation(
+            &ctx.accounts.globalpool,
+            &ctx.accounts.position_authority,
+            hook_params,
+        )?;
+    }
+
+    let globalpool = &ctx.accounts.globalpool;
+    let position = &ctx.accounts.position;
+    emit!(LiquidityDecreased {
+        globalpool: globalpool.key(),
+        position: position.key(),
+        tick_lower_index: position.tick_lower_index,
+        tick_upper_index: position.tick_upper_index,
+        liquidity_amount: params.liquidity_amount,
+        token_amount_a: delta_a,
+        token_amount_b: delta_b,
+        sqrt_price: globalpool.sqrt_price,
+        tick_current_index: globalpool.tick_current_index,
+        fee_growth_global_a: globalpool.fee_growth_global_a,
+        fee_growth_global_b: globalpool.fee_growth_global_b,
+    });
+
     Ok(())
 }
@@ -0,0 +1,59 @@
+use {
+    crate::{
+        errors::ErrorCode,
+        state::*,
+        util::{burn_and_close_user_position_token, verify_position_authority},
+    },
+    anchor_lang::prelude::*,
+    anchor_spl::token::{self, Mint, Token, TokenAccount},
+};
+
+#[derive(Accounts)]
+pub struct DeletePositionBundle<'info> {
+    pub position_bundle_authority: Signer<'info>,
+
+    /// CHECK: safe, for receiving rent only
+    #[account(mut)]
+    pub receiver: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        close = receiver,
+        seeds = [
+            b"position_bundle".as_ref(),
+            position_bundle_mint.key().as_ref()
+        ],
+        bump = position_bundle.bump[0],
+    )]
+    pub position_bundle: Account<'info, PositionBundle>,
+
+    #[account(mut, address = position_bundle.position_bundle_mint)]
+    pub position_bundle_mint: Account<'info, Mint>,
+
+    #[account(mut,
+        constraint = position_bundle_token_account.amount == 1,
+        constraint = position_bundle_token_account.mint == position_bundle.position_bundle_mint)]
+    pub position_bundle_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(address = token::ID)]
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn delete_position_bundle(ctx: Context<DeletePositionBundle>) -> Result<()> {
+    verify_position_authority(
+        &ctx.accounts.position_bundle_token_account,
+        &ctx.accounts.position_bundle_authority,
+    )?;
+
+    if !ctx.accounts.position_bundle.is_deletable() {
+        return Err(ErrorCode::PositionBundleNotDeletable.into());
+    }
+
+    burn_and_close_user_position_token(
+        &ctx.accounts.position_bundle_authority,
+        &ctx.accounts.receiver,
+        &ctx.accounts.position_bundle_mint,
+        &ctx.accounts.position_bundle_token_account,
+        &ctx.accounts.token_program,
+    )
+}
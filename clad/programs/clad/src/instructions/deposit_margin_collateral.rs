@@ -0,0 +1,87 @@
+use {
+    crate::{
+        errors::ErrorCode,
+        state::*,
+        util::{get_mint_decimals, load_token_owner_account, transfer_from_owner_to_vault},
+    },
+    anchor_lang::prelude::*,
+    anchor_spl::{
+        associated_token::AssociatedToken,
+        token::{self, Token, TokenAccount},
+    },
+};
+
+#[derive(Accounts)]
+pub struct DepositMarginCollateral<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"margin_account".as_ref(), owner.key().as_ref()],
+        bump = margin_account.bump[0],
+    )]
+    pub margin_account: Box<Account<'info, MarginAccount>>,
+
+    /// CHECK: the token program is checked, a price feed has to be registered for the mint
+    #[account(constraint = *token_mint.owner == token::ID @ ErrorCode::Token2022MintUnsupported)]
+    pub token_mint: UncheckedAccount<'info>,
+
+    // Price feed registered for the collateral token by the protocol admin, which values it
+    #[account(
+        seeds = [b"token_price_feed".as_ref(), token_mint.key().as_ref()],
+        bump = token_price_feed.bump[0],
+    )]
+    pub token_price_feed: Box<Account<'info, TokenPriceFeed>>,
+
+    /// CHECK: mint is checked in the handler, once the token program is checked
+    #[account(mut)]
+    pub token_owner_account: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = token_mint,
+        associated_token::authority = margin_account,
+    )]
+    pub margin_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(address = token::ID)]
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct DepositMarginCollateralParams {
+    pub amount: u64,
+}
+
+pub fn deposit_margin_collateral(
+    ctx: Context<DepositMarginCollateral>,
+    params: &DepositMarginCollateralParams,
+) -> Result<()> {
+    if params.amount == 0 {
+        return Err(ErrorCode::InvalidArgument.into());
+    }
+
+    let token_mint = &ctx.accounts.token_mint;
+    let token_owner_account =
+        load_token_owner_account(&ctx.accounts.token_owner_account, &token_mint.key(), None)?;
+
+    ctx.accounts.margin_account.deposit_collateral(
+        token_mint.key(),
+        get_mint_decimals(token_mint)?,
+        params.amount,
+    )?;
+
+    transfer_from_owner_to_vault(
+        &ctx.accounts.owner,
+        &token_owner_account,
+        &ctx.accounts.margin_vault,
+        &ctx.accounts.token_program,
+        params.amount,
+    )
+}
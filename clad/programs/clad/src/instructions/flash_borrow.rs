@@ -0,0 +1,116 @@
+use {
+    crate::{
+        errors::ErrorCode, events::FlashLoanBorrowed,
+        manager::flash_loan_manager::verify_flash_repay_follows, state::*,
+        util::transfer_from_vault_to_owner_checked,
+    },
+    anchor_lang::{prelude::*, solana_program::sysvar},
+};
+
+#[derive(Accounts)]
+pub struct FlashBorrow<'info> {
+    pub borrower: Signer<'info>,
+
+    // Hooks are only called by `swap` and the liquidity instructions
+    #[account(
+        mut,
+        constraint = globalpool.get_hook_program().is_none() @ ErrorCode::HookUnsupported
+    )]
+    pub globalpool: Box<Account<'info, Globalpool>>,
+
+    /// CHECK: address is checked
+    #[account(address = globalpool.token_mint_a)]
+    pub token_mint_a: UncheckedAccount<'info>,
+    /// CHECK: address is checked
+    #[account(address = globalpool.token_mint_b)]
+    pub token_mint_b: UncheckedAccount<'info>,
+
+    /// CHECK: mint is checked by the token program
+    #[account(mut)]
+    pub token_borrower_account_a: UncheckedAccount<'info>,
+    /// CHECK: address is checked
+    #[account(mut, address = globalpool.token_vault_a)]
+    pub token_vault_a: UncheckedAccount<'info>,
+
+    /// CHECK: mint is checked by the token program
+    #[account(mut)]
+    pub token_borrower_account_b: UncheckedAccount<'info>,
+    /// CHECK: address is checked
+    #[account(mut, address = globalpool.token_vault_b)]
+    pub token_vault_b: UncheckedAccount<'info>,
+
+    /// CHECK: instructions sysvar, used to find the matching `flash_repay`
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    /// CHECK: owner of token_mint_a, checked by constraint
+    #[account(constraint = token_program_a.key() == *token_mint_a.owner @ ErrorCode::InvalidTokenProgram)]
+    pub token_program_a: UncheckedAccount<'info>,
+    /// CHECK: owner of token_mint_b, checked by constraint
+    #[account(constraint = token_program_b.key() == *token_mint_b.owner @ ErrorCode::InvalidTokenProgram)]
+    pub token_program_b: UncheckedAccount<'info>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct FlashBorrowParams {
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+pub fn flash_borrow(ctx: Context<FlashBorrow>, params: &FlashBorrowParams) -> Result<()> {
+    if params.amount_a == 0 && params.amount_b == 0 {
+        return Err(ErrorCode::FlashLoanAmountZero.into());
+    }
+
+    if ctx.accounts.globalpool.has_flash_loan() {
+        return Err(ErrorCode::FlashLoanInProgress.into());
+    }
+
+    verify_flash_repay_follows(
+        &ctx.accounts.instructions_sysvar,
+        &ctx.accounts.globalpool.key(),
+    )?;
+
+    // Only the outstanding amounts are recorded. Liquidity & price of the pool are unchanged.
+    ctx.accounts
+        .globalpool
+        .update_after_flash_borrow(params.amount_a, params.amount_b);
+
+    // The amounts leave the vaults, the borrower receives them less the transfer fee, if any
+    for (amount, token_mint, token_vault, token_borrower_account, token_program) in [
+        (
+            params.amount_a,
+            &ctx.accounts.token_mint_a,
+            &ctx.accounts.token_vault_a,
+            &ctx.accounts.token_borrower_account_a,
+            &ctx.accounts.token_program_a,
+        ),
+        (
+            params.amount_b,
+            &ctx.accounts.token_mint_b,
+            &ctx.accounts.token_vault_b,
+            &ctx.accounts.token_borrower_account_b,
+            &ctx.accounts.token_program_b,
+        ),
+    ] {
+        if amount > 0 {
+            transfer_from_vault_to_owner_checked(
+                &ctx.accounts.globalpool,
+                token_mint,
+                token_vault,
+                token_borrower_account,
+                token_program,
+                amount,
+            )?;
+        }
+    }
+
+    emit!(FlashLoanBorrowed {
+        globalpool: ctx.accounts.globalpool.key(),
+        borrower: ctx.accounts.borrower.key(),
+        amount_a: params.amount_a,
+        amount_b: params.amount_b,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use {
+    crate::{
+        errors::ErrorCode,
+        events::FlashLoanRepaid,
+        manager::flash_loan_manager::calculate_flash_fee,
+        state::*,
+        util::{
+            calculate_transfer_fee_included_amount, get_epoch_transfer_fee,
+            transfer_from_owner_to_vault_checked,
+        },
+    },
+    anchor_lang::prelude::*,
+};
+
+// NOTE: `globalpool` must stay the 2nd account (see `FLASH_REPAY_GLOBALPOOL_ACCOUNT_INDEX`)
+#[derive(Accounts)]
+pub struct FlashRepay<'info> {
+    pub repayer: Signer<'info>,
+
+    #[account(mut)]
+    pub globalpool: Box<Account<'info, Globalpool>>,
+
+    /// CHECK: address is checked
+    #[account(address = globalpool.token_mint_a)]
+    pub token_mint_a: UncheckedAccount<'info>,
+    /// CHECK: address is checked
+    #[account(address = globalpool.token_mint_b)]
+    pub token_mint_b: UncheckedAccount<'info>,
+
+    /// CHECK: mint & authority are checked by the token program
+    #[account(mut)]
+    pub token_repayer_account_a: UncheckedAccount<'info>,
+    /// CHECK: address is checked
+    #[account(mut, address = globalpool.token_vault_a)]
+    pub token_vault_a: UncheckedAccount<'info>,
+
+    /// CHECK: mint & authority are checked by the token program
+    #[account(mut)]
+    pub token_repayer_account_b: UncheckedAccount<'info>,
+    /// CHECK: address is checked
+    #[account(mut, address = globalpool.token_vault_b)]
+    pub token_vault_b: UncheckedAccount<'info>,
+
+    /// CHECK: owner of token_mint_a, checked by constraint
+    #[account(constraint = token_program_a.key() == *token_mint_a.owner @ ErrorCode::InvalidTokenProgram)]
+    pub token_program_a: UncheckedAccount<'info>,
+    /// CHECK: owner of token_mint_b, checked by constraint
+    #[account(constraint = token_program_b.key() == *token_mint_b.owner @ ErrorCode::InvalidTokenProgram)]
+    pub token_program_b: UncheckedAccount<'info>,
+}
+
+pub fn flash_repay(ctx: Context<FlashRepay>) -> Result<()> {
+    if !ctx.accounts.globalpool.has_flash_loan() {
+        return Err(ErrorCode::FlashLoanNotFound.into());
+    }
+
+    let flash_fee_rate = ctx.accounts.globalpool.flash_fee_rate;
+    let amount_a = ctx.accounts.globalpool.flash_loan_amount_a;
+    let amount_b = ctx.accounts.globalpool.flash_loan_amount_b;
+
+    let fee_a = calculate_flash_fee(amount_a, flash_fee_rate)?;
+    let fee_b = calculate_flash_fee(amount_b, flash_fee_rate)?;
+
+    // The vaults must receive the loan plus the fee, so the repayer also pays the transfer fee
+    for (amount, fee, token_mint, token_repayer_account, token_vault, token_program) in [
+        (
+            amount_a,
+            fee_a,
+            &ctx.accounts.token_mint_a,
+            &ctx.accounts.token_repayer_account_a,
+            &ctx.accounts.token_vault_a,
+            &ctx.accounts.token_program_a,
+        ),
+        (
+            amount_b,
+            fee_b,
+            &ctx.accounts.token_mint_b,
+            &ctx.accounts.token_repayer_account_b,
+            &ctx.accounts.token_vault_b,
+            &ctx.accounts.token_program_b,
+        ),
+    ] {
+        if amount > 0 {
+            let repay_amount = amount
+                .checked_add(fee)
+                .ok_or(ErrorCode::AmountCalcOverflow)?;
+            transfer_from_owner_to_vault_checked(
+                &ctx.accounts.repayer,
+                token_mint,
+                token_repayer_account,
+                token_vault,
+                token_program,
+                calculate_transfer_fee_included_amount(
+                    &get_epoch_transfer_fee(token_mint)?,
+                    repay_amount,
+                )?,
+            )?;
+        }
+    }
+
+    ctx.accounts
+        .globalpool
+        .update_after_flash_repay(fee_a, fee_b);
+
+    let globalpool = &ctx.accounts.globalpool;
+    emit!(FlashLoanRepaid {
+        globalpool: globalpool.key(),
+        repayer: ctx.accounts.repayer.key(),
+        amount_a,
+        amount_b,
+        fee_amount_a: fee_a,
+        fee_amount_b: fee_b,
+        fee_growth_global_a: globalpool.fee_growth_global_a,
+        fee_growth_global_b: globalpool.fee_growth_global_b,
+    });
+
+    Ok(())
+}
@@ -1,23 +1,35 @@
 use {
     crate::{
         errors::ErrorCode,
-        manager::liquidity_manager::{
-            calculate_liquidity_token_deltas, calculate_modify_liquidity,
-            sync_modify_liquidity_values,
+        events::LiquidityIncreased,
+        manager::{
+            hook_manager::{load_globalpool_hook, GlobalpoolHook, HookOperation, HookParams},
+            liquidity_manager::{
+                calculate_liquidity_token_deltas, calculate_modify_liquidity,
+                sync_modify_liquidity_values,
+            },
         },
         math::convert_to_liquidity_delta,
         state::*,
-        util::{to_timestamp_u64, transfer_from_owner_to_vault, verify_position_authority},
+        util::{
+            calculate_transfer_fee_included_amount, get_epoch_transfer_fee,
+            load_native_sol_account, to_timestamp_u64, transfer_from_owner_to_vault_checked,
+            verify_position_authority,
+        },
     },
     anchor_lang::prelude::*,
-    anchor_spl::token::{self, Token, TokenAccount},
+    anchor_spl::token::TokenAccount,
 };
 
 #[derive(Accounts)]
 pub struct ModifyLiquidity<'info> {
     pub position_authority: Signer<'info>,
 
-    #[account(mut)]
+    // The vaults are lent out while a flash loan is outstanding
+    #[account(
+        mut,
+        constraint = !globalpool.has_flash_loan() @ ErrorCode::FlashLoanInProgress
+    )]
     pub globalpool: Account<'info, Globalpool>,
 
     #[account(mut, has_one = globalpool)]
@@ -29,17 +41,29 @@ pub struct ModifyLiquidity<'info> {
     )]
     pub position_token_account: Box<Account<'info, TokenAccount>>,
 
-    #[account(mut, constraint = token_owner_account_a.mint == globalpool.token_mint_a)]
-    pub token_owner_account_a: Box<Account<'info, TokenAccount>>,
+    /// CHECK: address is checked
+    #[account(address = globalpool.token_mint_a)]
+    pub token_mint_a: UncheckedAccount<'info>,
 
-    #[account(mut, constraint = token_owner_account_b.mint == globalpool.token_mint_b)]
-    pub token_owner_account_b: Box<Account<'info, TokenAccount>>,
+    /// CHECK: address is checked
+    #[account(address = globalpool.token_mint_b)]
+    pub token_mint_b: UncheckedAccount<'info>,
 
-    #[account(mut, constraint = token_vault_a.key() == globalpool.token_vault_a)]
-    pub token_vault_a: Box<Account<'info, TokenAccount>>,
+    /// CHECK: mint & authority are checked by the token program
+    #[account(mut)]
+    pub token_owner_account_a: UncheckedAccount<'info>,
 
-    #[account(mut, constraint = token_vault_b.key() == globalpool.token_vault_b)]
-    pub token_vault_b: Box<Account<'info, TokenAccount>>,
+    /// CHECK: mint & authority are checked by the token program
+    #[account(mut)]
+    pub token_owner_account_b: UncheckedAccount<'info>,
+
+    /// CHECK: address is checked
+    #[account(mut, address = globalpool.token_vault_a)]
+    pub token_vault_a: UncheckedAccount<'info>,
+
+    /// CHECK: address is checked
+    #[account(mut, address = globalpool.token_vault_b)]
+    pub token_vault_b: UncheckedAccount<'info>,
 
     #[account(mut, has_one = globalpool)]
     pub tick_array_lower: AccountLoader<'info, TickArray>,
@@ -47,8 +71,13 @@ pub struct ModifyLiquidity<'info> {
     #[account(mut, has_one = globalpool)]
     pub tick_array_upper: AccountLoader<'info, TickArray>,
 
-    #[account(address = token::ID)]
-    pub token_program: Program<'info, Token>,
+    /// CHECK: owner of token_mint_a, checked by constraint
+    #[account(constraint = token_program_a.key() == *token_mint_a.owner @ ErrorCode::InvalidTokenProgram)]
+    pub token_program_a: UncheckedAccount<'info>,
+
+    /// CHECK: owner of token_mint_b, checked by constraint
+    #[account(constraint = token_program_b.key() == *token_mint_b.owner @ ErrorCode::InvalidTokenProgram)]
+    pub token_program_b: UncheckedAccount<'info>,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
@@ -67,77 +96,182 @@ pub fn increase_liquidity(
         &ctx.accounts.position_authority,
     )?;
 
-    if params.liquidity_amount == 0 {
+    if ctx.accounts.position.is_range_order {
+        return Err(ErrorCode::RangeOrderPositionUnsupported.into());
+    }
+
+    // The remaining accounts are the optional System Program enabling native SOL mode, then the
+    // hook program of the globalpool followed by its accounts, if it has a hook
+    let (hook, remaining_accounts) =
+        load_globalpool_hook(&ctx.accounts.globalpool, ctx.remaining_accounts)?;
+
+    // In native SOL mode, SOL is wrapped up to its token max and the unused rest unwrapped
+    let (native_sol_account, _) = load_native_sol_account(
+        remaining_accounts,
+        &ctx.accounts.position_authority,
+        [&ctx.accounts.token_mint_a, &ctx.accounts.token_mint_b],
+        [
+            &ctx.accounts.token_owner_account_a,
+            &ctx.accounts.token_owner_account_b,
+        ],
+        [&ctx.accounts.token_program_a, &ctx.accounts.token_program_b],
+    )?;
+    if let Some(native_sol_account) = native_sol_account.as_ref() {
+        native_sol_account.open(if native_sol_account.is_token_a {
+            params.token_max_a
+        } else {
+            params.token_max_b
+        })?;
+    }
+
+    increase_position_liquidity(
+        ctx.accounts,
+        hook.as_ref(),
+        params.liquidity_amount,
+        params.token_max_a,
+        params.token_max_b,
+    )?;
+
+    match native_sol_account {
+        Some(native_sol_account) => native_sol_account.close(),
+        None => Ok(()),
+    }
+}
+
+// Adds `liquidity_amount` to the position, transferring the token deltas (plus transfer fees)
+// from the owner accounts as long as they are within `token_max_a` & `token_max_b`. The hook of the
+// globalpool, if any, is called before and after.
+pub fn increase_position_liquidity<'info>(
+    accounts: &mut ModifyLiquidity<'info>,
+    hook: Option<&GlobalpoolHook<'_, 'info>>,
+    liquidity_amount: u128,
+    token_max_a: u64,
+    token_max_b: u64,
+) -> Result<()> {
+    if liquidity_amount == 0 {
         return Err(ErrorCode::LiquidityZero.into());
     }
-    let liquidity_delta = convert_to_liquidity_delta(params.liquidity_amount, true)?;
+    let liquidity_delta = convert_to_liquidity_delta(liquidity_amount, true)?;
     let timestamp = to_timestamp_u64(Clock::get()?.unix_timestamp)?;
 
     let update = calculate_modify_liquidity(
-        &ctx.accounts.globalpool,
-        &ctx.accounts.position,
-        &ctx.accounts.tick_array_lower,
-        &ctx.accounts.tick_array_upper,
+        &accounts.globalpool,
+        &accounts.position,
+        &accounts.tick_array_lower,
+        &accounts.tick_array_upper,
         liquidity_delta,
         timestamp,
     )?;
 
     msg!(
         "Current Tick Index: {:?}",
-        ctx.accounts.globalpool.tick_current_index
+        accounts.globalpool.tick_current_index
     );
     msg!(
         "Position Ticks: [{:?}, {:?})",
-        ctx.accounts.position.tick_lower_index,
-        ctx.accounts.position.tick_upper_index
+        accounts.position.tick_lower_index,
+        accounts.position.tick_upper_index
     );
-    // let tick_array_lower_start = ctx.accounts.tick_array_lower.load()?.start_tick_index;
-    // let tick_array_upper_start = ctx.accounts.tick_array_upper.load()?.start_tick_index;
+    // let tick_array_lower_start = accounts.tick_array_lower.load()?.start_tick_index;
+    // let tick_array_upper_start = accounts.tick_array_upper.load()?.start_tick_index;
     // msg!("TickArray Lower: {:?}", tick_array_lower_start);
     // msg!("TickArray Upper: {:?}", tick_array_upper_start);
 
     // msg!("Msg:\n {:?}", update);
 
-    sync_modify_liquidity_values(
-        &mut ctx.accounts.globalpool,
-        &mut ctx.accounts.position,
-        &ctx.accounts.tick_array_lower,
-        &ctx.accounts.tick_array_upper,
-        update,
+    let (delta_a, delta_b) = calculate_liquidity_token_deltas(
+        accounts.position.tick_lower_index,
+        accounts.position.tick_upper_index,
+        accounts.globalpool.tick_current_index,
+        accounts.globalpool.sqrt_price,
+        liquidity_delta,
     )?;
 
-    let (delta_a, delta_b) = calculate_liquidity_token_deltas(
-        ctx.accounts.position.tick_lower_index,
-        ctx.accounts.position.tick_upper_index,
-        ctx.accounts.globalpool.tick_current_index,
-        ctx.accounts.globalpool.sqrt_price,
+    let hook_params = HookParams::new(
+        HookOperation::IncreaseLiquidity,
+        &accounts.globalpool,
+        accounts.position_authority.key,
+        accounts.position.key(),
         liquidity_delta,
+        delta_a as i128,
+        delta_b as i128,
+    );
+    if let Some(hook) = hook {
+        hook.before_operation(
+            &accounts.globalpool,
+            &accounts.position_authority,
+            &hook_params,
+        )?;
+    }
+
+    sync_modify_liquidity_values(
+        &mut accounts.globalpool,
+        &mut accounts.position,
+        &accounts.tick_array_lower,
+        &accounts.tick_array_upper,
+        update,
     )?;
 
     // msg!("Delta A: {:?}", delta_a);
     // msg!("Delta B: {:?}", delta_b);
 
-    if delta_a > params.token_max_a {
+    // The vaults must receive the deltas, so any transfer fee is paid on top
+    let transfer_amount_a = calculate_transfer_fee_included_amount(
+        &get_epoch_transfer_fee(&accounts.token_mint_a)?,
+        delta_a,
+    )?;
+    let transfer_amount_b = calculate_transfer_fee_included_amount(
+        &get_epoch_transfer_fee(&accounts.token_mint_b)?,
+        delta_b,
+    )?;
+
+    if transfer_amount_a > token_max_a {
         return Err(ErrorCode::TokenMaxExceeded.into());
-    } else if delta_b > params.token_max_b {
+    } else if transfer_amount_b > token_max_b {
         return Err(ErrorCode::TokenMaxExceeded.into());
     }
 
-    transfer_from_owner_to_vault(
-        &ctx.accounts.position_authority,
-        &ctx.accounts.token_owner_account_a,
-        &ctx.accounts.token_vault_a,
-        &ctx.accounts.token_program,
-        delta_a,
+    transfer_from_owner_to_vault_checked(
+        &accounts.position_authority,
+        &accounts.token_mint_a,
+        &accounts.token_owner_account_a,
+        &accounts.token_vault_a,
+        &accounts.token_program_a,
+        transfer_amount_a,
     )?;
 
-    transfer_from_owner_to_vault(
-        &ctx.accounts.position_authority,
-        &ctx.accounts.token_owner_account_b,
-        &ctx.accounts.token_vault_b,
-        &ctx.accounts.token_program,
-        delta_b,
+    transfer_from_owner_to_vault_checked(
+        &accounts.position_authority,
+        &accounts.token_mint_b,
+        &accounts.token_owner_account_b,
+        &accounts.token_vault_b,
+        &accounts.token_program_b,
+        transfer_amount_b,
     )?;
 
+    if let Some(hook) = hook {
+        hook.after_operation(
+            &accounts.globalpool,
+            &accounts.position_authority,
+            hook_params,
+        )?;
+    }
+
+    let globalpool = &accounts.globalpool;
+    let position = &accounts.position;
+    emit!(LiquidityIncreased {
+        globalpool: globalpool.key(),
+        position: position.key(),
+        tick_lower_index: position.tick_lower_index,
+        tick_upper_index: position.tick_upper_index,
+        liquidity_amount,
+        token_amount_a: delta_a,
+        token_amount_b: delta_b,
+        sqrt_price: globalpool.sqrt_price,
+        tick_current_index: globalpool.tick_current_index,
+        fee_growth_global_a: globalpool.fee_growth_global_a,
+        fee_growth_global_b: globalpool.fee_growth_global_b,
+    });
+
     Ok(())
 }
@@ -0,0 +1,82 @@
+use {
+    super::{increase_position_liquidity, ModifyLiquidity},
+    crate::{
+        errors::ErrorCode,
+        manager::{
+            hook_manager::load_globalpool_hook,
+            liquidity_manager::calculate_liquidity_from_token_amount,
+        },
+        util::{
+            calculate_transfer_fee_excluded_amount, get_epoch_transfer_fee,
+            verify_position_authority,
+        },
+    },
+    anchor_lang::prelude::*,
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct IncreaseLiquidityByTokenAmountParams {
+    pub token_amount: u64, // Input token amount sent by the owner, including the transfer fee
+    pub is_token_a: bool,  // Whether the input token is Token A
+    pub sqrt_price_lower_limit: u128,
+    pub sqrt_price_upper_limit: u128,
+}
+
+pub fn increase_liquidity_by_token_amount(
+    ctx: Context<ModifyLiquidity>,
+    params: &IncreaseLiquidityByTokenAmountParams,
+) -> Result<()> {
+    verify_position_authority(
+        &ctx.accounts.position_token_account,
+        &ctx.accounts.position_authority,
+    )?;
+
+    if ctx.accounts.position.is_range_order {
+        return Err(ErrorCode::RangeOrderPositionUnsupported.into());
+    }
+
+    // The price sets how much of the other token is deposited, so it must not have moved
+    let globalpool = &ctx.accounts.globalpool;
+    if globalpool.sqrt_price < params.sqrt_price_lower_limit
+        || globalpool.sqrt_price > params.sqrt_price_upper_limit
+    {
+        return Err(ErrorCode::SqrtPriceOutOfRange.into());
+    }
+
+    let token_mint_input = if params.is_token_a {
+        &ctx.accounts.token_mint_a
+    } else {
+        &ctx.accounts.token_mint_b
+    };
+    let deposit_amount = calculate_transfer_fee_excluded_amount(
+        &get_epoch_transfer_fee(token_mint_input)?,
+        params.token_amount,
+    )?;
+
+    let liquidity_amount = calculate_liquidity_from_token_amount(
+        ctx.accounts.position.tick_lower_index,
+        ctx.accounts.position.tick_upper_index,
+        globalpool.tick_current_index,
+        globalpool.sqrt_price,
+        deposit_amount,
+        params.is_token_a,
+    )?;
+
+    // The other token is bounded by the sqrt price range
+    let (token_max_a, token_max_b) = if params.is_token_a {
+        (params.token_amount, u64::MAX)
+    } else {
+        (u64::MAX, params.token_amount)
+    };
+
+    // The hook program of the globalpool, followed by its accounts, are the remaining accounts
+    let (hook, _) = load_globalpool_hook(&ctx.accounts.globalpool, ctx.remaining_accounts)?;
+
+    increase_position_liquidity(
+        ctx.accounts,
+        hook.as_ref(),
+        liquidity_amount,
+        token_max_a,
+        token_max_b,
+    )
+}
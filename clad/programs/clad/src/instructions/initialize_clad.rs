@@ -23,8 +23,8 @@ pub struct InitializeClad<'info> {
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct InitializeCladParams {
-    permissions: Permissions,
-    protocol_fee_rate: u16,
+    pub permissions: Permissions,
+    pub protocol_fee_rate: u16,
 }
 
 pub fn initialize_clad(
@@ -0,0 +1,30 @@
+use {crate::state::*, anchor_lang::prelude::*};
+
+#[derive(Accounts)]
+pub struct InitializeMarginAccount<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = MarginAccount::LEN,
+        seeds = [b"margin_account".as_ref(), owner.key().as_ref()],
+        bump,
+    )]
+    pub margin_account: Box<Account<'info, MarginAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+pub fn initialize_margin_account(ctx: Context<InitializeMarginAccount>) -> Result<()> {
+    let margin_account_bump = *ctx
+        .bumps
+        .get("margin_account")
+        .ok_or(ProgramError::InvalidSeeds)?;
+
+    ctx.accounts
+        .margin_account
+        .initialize(ctx.accounts.owner.key(), margin_account_bump)
+}
@@ -0,0 +1,68 @@
+use {
+    crate::{state::*, util::mint_position_bundle_token_and_remove_authority},
+    anchor_lang::prelude::*,
+    anchor_spl::{
+        associated_token::AssociatedToken,
+        token::{self, Mint, Token, TokenAccount},
+    },
+};
+
+#[derive(Accounts)]
+pub struct InitializePositionBundle<'info> {
+    // Funder is the owner for now
+    #[account(mut)]
+    pub position_bundle_authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = position_bundle_authority,
+        space = PositionBundle::LEN,
+        seeds = [
+            b"position_bundle".as_ref(),
+            position_bundle_mint.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub position_bundle: Box<Account<'info, PositionBundle>>,
+
+    #[account(
+        init,
+        payer = position_bundle_authority,
+        mint::authority = position_bundle,
+        mint::decimals = 0,
+    )]
+    pub position_bundle_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = position_bundle_authority,
+        associated_token::mint = position_bundle_mint,
+        associated_token::authority = position_bundle_authority,
+    )]
+    pub position_bundle_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(address = token::ID)]
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+///
+/// Initializes an empty PositionBundle & mints its token to the authority.
+///
+pub fn initialize_position_bundle(ctx: Context<InitializePositionBundle>) -> Result<()> {
+    ctx.accounts.position_bundle.initialize(
+        ctx.accounts.position_bundle_mint.key(),
+        *ctx.bumps
+            .get("position_bundle")
+            .ok_or(ProgramError::InvalidSeeds)?,
+    );
+
+    mint_position_bundle_token_and_remove_authority(
+        &ctx.accounts.position_bundle,
+        &ctx.accounts.position_bundle_mint,
+        &ctx.accounts.position_bundle_token_account,
+        &ctx.accounts.token_program,
+    )
+}
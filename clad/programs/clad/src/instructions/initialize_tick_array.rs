@@ -13,19 +13,43 @@ pub struct InitializeTickArray<'info> {
         payer = funder,
         seeds = [b"tick_array", globalpool.key().as_ref(), start_tick_index.to_string().as_bytes()],
         bump,
-        space = TickArray::LEN
+        space = TickArrayExtension::TICK_ARRAY_LEN
     )]
     pub tick_array: AccountLoader<'info, TickArray>,
 
+    #[account(
+        init_if_needed,
+        payer = funder,
+        seeds = [b"tick_array_bitmap", globalpool.key().as_ref()],
+        bump,
+        space = TickArrayBitmap::LEN
+    )]
+    pub tick_array_bitmap: Account<'info, TickArrayBitmap>,
+
     pub system_program: Program<'info, System>,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct InitializeTickArrayParams {
-    start_tick_index: i32
+    pub start_tick_index: i32,
 }
 
-pub fn initialize_tick_array(ctx: Context<InitializeTickArray>, params: &InitializeTickArrayParams) -> Result<()> {
-    let mut tick_array = ctx.accounts.tick_array.load_init()?;
-    Ok(tick_array.initialize(&ctx.accounts.globalpool, params.start_tick_index)?)
+pub fn initialize_tick_array(
+    ctx: Context<InitializeTickArray>,
+    params: &InitializeTickArrayParams,
+) -> Result<()> {
+    let globalpool = &ctx.accounts.globalpool;
+
+    let (mut tick_array, mut extension) = load_tick_array_init(&ctx.accounts.tick_array)?;
+    tick_array.initialize(globalpool, params.start_tick_index)?;
+    extension.initialize(ctx.accounts.funder.key());
+
+    // The bitmap is created along with the first TickArray of the Globalpool. Arrays that
+    // predate it are missing until `backfill_tick_array_bitmap` records them.
+    let tick_array_bitmap = &mut ctx.accounts.tick_array_bitmap;
+    if tick_array_bitmap.globalpool == Pubkey::default() {
+        tick_array_bitmap.initialize(globalpool.key(), false);
+    }
+
+    tick_array_bitmap.set_initialized(params.start_tick_index, globalpool.tick_spacing, true)
 }
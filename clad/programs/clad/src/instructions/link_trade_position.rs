@@ -0,0 +1,55 @@
+use {
+    crate::{errors::ErrorCode, state::*, util::verify_position_authority},
+    anchor_lang::prelude::*,
+    anchor_spl::token::TokenAccount,
+    spl_associated_token_account::get_associated_token_address,
+};
+
+#[derive(Accounts)]
+pub struct LinkTradePosition<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"margin_account".as_ref(), owner.key().as_ref()],
+        bump = margin_account.bump[0],
+    )]
+    pub margin_account: Box<Account<'info, MarginAccount>>,
+
+    pub position: Box<Account<'info, TradePosition>>,
+
+    // Linked positions are valued while their token is held in this account
+    #[account(
+        address = get_associated_token_address(&owner.key(), &position.position_mint),
+        constraint = position_token_account.amount == 1
+    )]
+    pub position_token_account: Box<Account<'info, TokenAccount>>,
+}
+
+pub fn link_trade_position(ctx: Context<LinkTradePosition>) -> Result<()> {
+    verify_position_authority(&ctx.accounts.position_token_account, &ctx.accounts.owner)?;
+
+    // Perpetual positions are liquidated on their own health (see `repay_trade_position`)
+    if ctx.accounts.position.is_perpetual {
+        return Err(ErrorCode::MarginPerpetualPositionNotSupported.into());
+    }
+
+    // Leveraged liquidity positions are valued by their locked liquidity position
+    if ctx.accounts.position.is_leveraged_liquidity_position() {
+        return Err(ErrorCode::InvalidTradePositionType.into());
+    }
+
+    // Positions backed by a margin account are linked to it when opened
+    if ctx.accounts.position.is_margin_backed() {
+        return Err(ErrorCode::MarginPositionAlreadyLinked.into());
+    }
+
+    if ctx.accounts.margin_account.is_liquidating {
+        return Err(ErrorCode::MarginAccountLiquidating.into());
+    }
+
+    ctx.accounts
+        .margin_account
+        .link_position(ctx.accounts.position.key())
+}
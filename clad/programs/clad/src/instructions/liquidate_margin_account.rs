@@ -0,0 +1,60 @@
+use {
+    crate::{
+        errors::ErrorCode,
+        manager::margin_manager::{
+            calculate_margin_health, MARGIN_POSITION_ACCOUNTS_LEN, TOKEN_PRICE_ACCOUNTS_LEN,
+        },
+        state::*,
+        util::to_timestamp_u64,
+    },
+    anchor_lang::prelude::*,
+};
+
+#[derive(Accounts)]
+pub struct LiquidateMarginAccount<'info> {
+    pub liquidator: Signer<'info>,
+
+    #[account(mut)]
+    pub margin_account: Box<Account<'info, MarginAccount>>,
+    // remaining accounts: registered price feeds & linked positions (writable), see `calculate_margin_health`
+}
+
+pub fn liquidate_margin_account(ctx: Context<LiquidateMarginAccount>) -> Result<()> {
+    let clock = Clock::get()?;
+
+    let health = calculate_margin_health(
+        &ctx.accounts.margin_account,
+        ctx.remaining_accounts,
+        clock.unix_timestamp,
+    )?;
+    if health.is_healthy() {
+        return Err(ErrorCode::MarginAccountHealthy.into());
+    }
+
+    //
+    // Liquidate the account as a whole: every linked position is force-matured, so that
+    // liquidators can repay it through `repay_trade_position`, and the margin collateral is
+    // locked until all positions are repaid & unlinked. Positions backed by the account draw its
+    // collateral on repayment to cover their debt, and the liquidator's share.
+    //
+    let timestamp = to_timestamp_u64(clock.unix_timestamp)?;
+    let collateral_accounts_len =
+        ctx.accounts.margin_account.deposited_collaterals().count() * TOKEN_PRICE_ACCOUNTS_LEN;
+
+    for accounts in
+        ctx.remaining_accounts[collateral_accounts_len..].chunks(MARGIN_POSITION_ACCOUNTS_LEN)
+    {
+        let position_info = &accounts[0];
+        if position_info.data_is_empty() {
+            continue;
+        }
+
+        let mut position = Account::<TradePosition>::try_from(position_info)?;
+        position.force_mature(timestamp);
+        position.exit(&crate::ID)?;
+    }
+
+    ctx.accounts.margin_account.is_liquidating = true;
+
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use {
+    crate::{
+        errors::ErrorCode,
+        manager::liquidity_manager::calculate_fee_growths,
+        state::*,
+        util::{burn_and_close_user_position_token, to_timestamp_u64, verify_position_authority},
+    },
+    anchor_lang::prelude::*,
+    anchor_spl::token::{self, Mint, Token, TokenAccount},
+};
+
+#[derive(Accounts)]
+pub struct MergeLiquidityPositions<'info> {
+    pub position_authority: Signer<'info>,
+
+    /// CHECK: safe, for receiving rent only
+    #[account(mut)]
+    pub receiver: UncheckedAccount<'info>,
+
+    pub globalpool: Box<Account<'info, Globalpool>>,
+
+    // Position receiving the liquidity
+    #[account(
+        mut,
+        has_one = globalpool,
+        constraint = !position.is_range_order @ ErrorCode::RangeOrderPositionUnsupported
+    )]
+    pub position: Box<Account<'info, LiquidityPosition>>,
+    #[account(
+        constraint = position_token_account.mint == position.position_mint,
+        constraint = position_token_account.amount == 1
+    )]
+    pub position_token_account: Box<Account<'info, TokenAccount>>,
+
+    // Position merged into `position`, then closed. Bundled positions can't be merged away.
+    #[account(
+        mut,
+        close = receiver,
+        has_one = globalpool,
+        constraint = source_position.key() != position.key() @ ErrorCode::InvalidArgument,
+        constraint = !source_position.is_range_order @ ErrorCode::RangeOrderPositionUnsupported,
+        seeds = [
+            b"liquidity_position".as_ref(),
+            source_position_mint.key().as_ref()
+        ],
+        bump,
+    )]
+    pub source_position: Box<Account<'info, LiquidityPosition>>,
+
+    #[account(mut, address = source_position.position_mint)]
+    pub source_position_mint: Account<'info, Mint>,
+
+    #[account(mut,
+        constraint = source_position_token_account.amount == 1,
+        constraint = source_position_token_account.mint == source_position.position_mint)]
+    pub source_position_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(has_one = globalpool)]
+    pub tick_array_lower: AccountLoader<'info, TickArray>,
+    #[account(has_one = globalpool)]
+    pub tick_array_upper: AccountLoader<'info, TickArray>,
+
+    #[account(address = token::ID)]
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn merge_liquidity_positions(ctx: Context<MergeLiquidityPositions>) -> Result<()> {
+    verify_position_authority(
+        &ctx.accounts.position_token_account,
+        &ctx.accounts.position_authority,
+    )?;
+    verify_position_authority(
+        &ctx.accounts.source_position_token_account,
+        &ctx.accounts.position_authority,
+    )?;
+
+    // Settle the fees earned so far by both positions. Fee growths can't be calculated for
+    // positions without liquidity, which earn no fees.
+    let timestamp = to_timestamp_u64(Clock::get()?.unix_timestamp)?;
+    for position in [
+        &mut ctx.accounts.position,
+        &mut ctx.accounts.source_position,
+    ] {
+        if position.liquidity == 0 {
+            continue;
+        }
+        let position_update = &calculate_fee_growths(
+            &ctx.accounts.globalpool,
+            position,
+            &ctx.accounts.tick_array_lower,
+            &ctx.accounts.tick_array_upper,
+            timestamp,
+        )?;
+        position.update(position_update);
+    }
+
+    // No tokens move: the ticks of the range keep the same total liquidity
+    ctx.accounts
+        .position
+        .merge_from(&mut ctx.accounts.source_position)?;
+
+    burn_and_close_user_position_token(
+        &ctx.accounts.position_authority,
+        &ctx.accounts.receiver,
+        &ctx.accounts.source_position_mint,
+        &ctx.accounts.source_position_token_account,
+        &ctx.accounts.token_program,
+    )
+}
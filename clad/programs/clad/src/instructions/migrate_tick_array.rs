@@ -0,0 +1,25 @@
+use {crate::state::*, anchor_lang::prelude::*};
+
+#[derive(Accounts)]
+pub struct MigrateTickArray<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        realloc = TickArrayExtension::TICK_ARRAY_LEN,
+        realloc::payer = payer,
+        realloc::zero = true
+    )]
+    pub tick_array: AccountLoader<'info, TickArray>,
+
+    pub system_program: Program<'info, System>,
+}
+
+///
+/// Reallocates a TickArray created before range orders to append its extension, whose zeroed
+/// data holds no range orders. The ticks are left as they are.
+///
+pub fn migrate_tick_array(_ctx: Context<MigrateTickArray>) -> Result<()> {
+    Ok(())
+}
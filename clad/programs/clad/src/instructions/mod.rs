@@ -1,20 +1,68 @@
+pub mod backfill_tick_array_bitmap;
+pub mod claim_range_order;
+pub mod close_bundled_liquidity_position;
 pub mod close_liquidity_position;
+pub mod close_tick_array;
 pub mod close_trade_position;
 pub mod collect_fees;
 pub mod collect_protocol_fees;
+pub mod compound_fees;
+pub mod crank_funding;
 pub mod create_pool;
+pub mod create_pool_with_liquidity;
 pub mod decrease_liquidity;
+pub mod delete_position_bundle;
+pub mod deposit_margin_collateral;
+pub mod flash_borrow;
+pub mod flash_repay;
 pub mod increase_liquidity;
+pub mod increase_liquidity_by_token_amount;
 pub mod initialize_clad;
+pub mod initialize_margin_account;
+pub mod initialize_position_bundle;
 pub mod initialize_tick_array;
+pub mod link_trade_position;
+pub mod liquidate_margin_account;
+pub mod merge_liquidity_positions;
+pub mod migrate_tick_array;
+pub mod open_bundled_liquidity_position;
+pub mod open_leveraged_liquidity_position;
 pub mod open_liquidity_position;
+pub mod open_liquidity_position_with_metadata;
+pub mod open_range_order;
 pub mod open_trade_position;
+pub mod open_trade_position_with_metadata;
+pub mod quote_swap;
+pub mod quote_trade_position;
+pub mod remove_position_operator;
+pub mod repay_leveraged_liquidity_position;
 pub mod repay_trade_position;
+pub mod set_dynamic_fee_parameters;
+pub mod set_flash_fee_rate;
+pub mod set_position_operator;
+pub mod set_risk_parameters;
+pub mod set_tick_array_bitmap_backfilled;
+pub mod set_token_price_feed;
+pub mod split_liquidity_position;
 pub mod swap;
+pub mod unlink_trade_position;
+pub mod withdraw_margin_collateral;
 
 pub use {
-    close_liquidity_position::*, close_trade_position::*, collect_fees::*,
-    collect_protocol_fees::*, create_pool::*, decrease_liquidity::*, increase_liquidity::*,
-    initialize_clad::*, initialize_tick_array::*, open_liquidity_position::*,
-    open_trade_position::*, repay_trade_position::*, swap::*,
+    backfill_tick_array_bitmap::*, claim_range_order::*, close_bundled_liquidity_position::*,
+    close_liquidity_position::*, close_tick_array::*, close_trade_position::*, collect_fees::*,
+    collect_protocol_fees::*, compound_fees::*, crank_funding::*, create_pool::*,
+    create_pool_with_liquidity::*, decrease_liquidity::*, delete_position_bundle::*,
+    deposit_margin_collateral::*, flash_borrow::*, flash_repay::*, increase_liquidity::*,
+    increase_liquidity_by_token_amount::*, initialize_clad::*, initialize_margin_account::*,
+    initialize_position_bundle::*, initialize_tick_array::*, link_trade_position::*,
+    liquidate_margin_account::*, merge_liquidity_positions::*, migrate_tick_array::*,
+    open_bundled_liquidity_position::*, open_leveraged_liquidity_position::*,
+    open_liquidity_position::*, open_liquidity_position_with_metadata::*, open_range_order::*,
+    open_trade_position::*, open_trade_position_with_metadata::*, quote_swap::*,
+    quote_trade_position::*, remove_position_operator::*, repay_leveraged_liquidity_position::*,
+    repay_trade_position::*, set_dynamic_fee_parameters::*, set_flash_fee_rate::*,
+    set_position_operator::*, set_risk_parameters::*, set_tick_array_bitmap_backfilled::*,
+    set_token_price_feed::*, split_liquidity_position::*, swap::*, unlink_trade_position::*,
+    withdraw_margin_collateral::*,
 };
@@ -0,0 +1,71 @@
+use {
+    crate::{state::*, util::verify_position_authority},
+    anchor_lang::prelude::*,
+    anchor_spl::token::TokenAccount,
+};
+
+#[derive(Accounts)]
+#[instruction(params: OpenBundledLiquidityPositionParams)]
+pub struct OpenBundledLiquidityPosition<'info> {
+    // Funder is the owner for now
+    #[account(mut)]
+    pub position_authority: Signer<'info>,
+
+    pub globalpool: Box<Account<'info, Globalpool>>,
+
+    #[account(mut)]
+    pub position_bundle: Box<Account<'info, PositionBundle>>,
+
+    #[account(
+        constraint = position_bundle_token_account.mint == position_bundle.position_bundle_mint,
+        constraint = position_bundle_token_account.amount == 1
+    )]
+    pub position_bundle_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = position_authority,
+        space = LiquidityPosition::LEN,
+        seeds = [
+            b"bundled_liquidity_position".as_ref(),
+            position_bundle.position_bundle_mint.as_ref(),
+            params.bundle_index.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub position: Box<Account<'info, LiquidityPosition>>,
+
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct OpenBundledLiquidityPositionParams {
+    pub bundle_index: u16,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+}
+
+///
+/// Opens a liquidity position in a GlobalPool, represented by the token of a PositionBundle.
+///
+pub fn open_bundled_liquidity_position(
+    ctx: Context<OpenBundledLiquidityPosition>,
+    params: &OpenBundledLiquidityPositionParams,
+) -> Result<()> {
+    verify_position_authority(
+        &ctx.accounts.position_bundle_token_account,
+        &ctx.accounts.position_authority,
+    )?;
+
+    ctx.accounts
+        .position_bundle
+        .open_bundled_position(params.bundle_index)?;
+
+    ctx.accounts.position.open_position(
+        &ctx.accounts.globalpool,
+        ctx.accounts.position_bundle.position_bundle_mint,
+        params.tick_lower_index,
+        params.tick_upper_index,
+    )
+}
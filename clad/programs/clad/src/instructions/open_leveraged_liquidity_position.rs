@@ -0,0 +1,423 @@
+use {
+    crate::{
+        errors::ErrorCode,
+        events::LeveragedLiquidityPositionOpened,
+        manager::{
+            liquidity_manager::{
+                calculate_liquidity_token_deltas, calculate_modify_liquidity,
+                sync_modify_liquidity_values, sync_modify_liquidity_values_for_loan,
+            },
+            loan_manager,
+        },
+        math::*,
+        state::*,
+        util::{
+            mint_position_token_and_remove_authority, to_timestamp_u64,
+            transfer_from_owner_to_vault,
+        },
+    },
+    anchor_lang::prelude::*,
+    anchor_spl::{
+        associated_token::AssociatedToken,
+        token::{self, Mint, Token, TokenAccount},
+    },
+};
+
+#[derive(Accounts)]
+#[instruction(params: OpenLeveragedLiquidityPositionParams)]
+pub struct OpenLeveragedLiquidityPosition<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    // Hooks are only called by `swap` and the liquidity instructions, and the vaults are lent
+    // out while a flash loan is outstanding
+    #[account(
+        mut,
+        constraint = globalpool.get_hook_program().is_none() @ ErrorCode::HookUnsupported,
+        constraint = !globalpool.has_flash_loan() @ ErrorCode::FlashLoanInProgress
+    )]
+    pub globalpool: Box<Account<'info, Globalpool>>,
+
+    //
+    // Loan
+    //
+    #[account(
+        init,
+        payer = owner,
+        space = TradePosition::LEN,
+        seeds = [
+            b"trade_position".as_ref(),
+            position_mint.key().as_ref()
+        ],
+        bump,
+    )]
+    pub position: Box<Account<'info, TradePosition>>,
+
+    #[account(
+        init,
+        payer = owner,
+        mint::authority = globalpool,
+        mint::decimals = 0,
+    )]
+    pub position_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = position_mint,
+        associated_token::authority = owner,
+    )]
+    pub position_token_account: Box<Account<'info, TokenAccount>>,
+
+    //
+    // Liquidity position, locked as collateral of the loan
+    //
+    #[account(
+        init,
+        payer = owner,
+        space = LiquidityPosition::LEN,
+        seeds = [
+            b"liquidity_position".as_ref(),
+            liquidity_position_mint.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub liquidity_position: Box<Account<'info, LiquidityPosition>>,
+
+    #[account(
+        init,
+        payer = owner,
+        mint::authority = globalpool,
+        mint::decimals = 0,
+    )]
+    pub liquidity_position_mint: Account<'info, Mint>,
+
+    // Escrow of the liquidity position token, held by the trade position until the loan is repaid
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = liquidity_position_mint,
+        associated_token::authority = position,
+    )]
+    pub liquidity_position_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, token::mint = globalpool.token_mint_a)]
+    pub token_owner_account_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = globalpool.token_vault_a)]
+    pub token_vault_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, token::mint = globalpool.token_mint_b)]
+    pub token_owner_account_b: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = globalpool.token_vault_b)]
+    pub token_vault_b: Box<Account<'info, TokenAccount>>,
+
+    // Tick arrays of the loan range
+    #[account(mut, has_one = globalpool)]
+    pub tick_array_lower: AccountLoader<'info, TickArray>,
+
+    #[account(mut, has_one = globalpool)]
+    pub tick_array_upper: AccountLoader<'info, TickArray>,
+
+    // Tick arrays of the liquidity position range
+    #[account(mut, has_one = globalpool)]
+    pub liquidity_tick_array_lower: AccountLoader<'info, TickArray>,
+
+    #[account(mut, has_one = globalpool)]
+    pub liquidity_tick_array_upper: AccountLoader<'info, TickArray>,
+
+    #[account(address = token::ID)]
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct OpenLeveragedLiquidityPositionParams {
+    // Token A or B amount to borrow (in liquidity), from a range out of the current tick
+    pub loan_liquidity_amount: u128,
+    pub loan_tick_lower_index: i32,
+    pub loan_tick_upper_index: i32,
+
+    // Lifetime of loan, in seconds
+    pub loan_duration: u64,
+
+    // true: borrow token A | false: borrow token B
+    pub borrow_a: bool,
+
+    // Range & liquidity of the new liquidity position, funded by the loan and the owner's margin
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    pub liquidity_amount: u128,
+
+    // Max margin (tokens not covered by the loan) the owner is willing to deposit
+    pub token_max_a: u64,
+    pub token_max_b: u64,
+}
+
+pub fn open_leveraged_liquidity_position(
+    ctx: Context<OpenLeveragedLiquidityPosition>,
+    params: &OpenLeveragedLiquidityPositionParams,
+) -> Result<()> {
+    let current_tick_index = ctx.accounts.globalpool.tick_current_index;
+
+    if params.loan_liquidity_amount == 0 || params.liquidity_amount == 0 {
+        return Err(ErrorCode::LiquidityZero.into());
+    }
+
+    if params.loan_duration < loan_manager::MIN_LOAN_DURATION
+        || params.loan_duration > loan_manager::MAX_LOAN_DURATION
+    {
+        return Err(ErrorCode::InvalidLoanDuration.into());
+    }
+
+    loan_manager::validate_loan_tick_range(
+        current_tick_index,
+        params.loan_tick_lower_index,
+        params.loan_tick_upper_index,
+        params.borrow_a,
+    )?;
+
+    // Accrue funding at the current utilization, before the loan changes it
+    let timestamp = to_timestamp_u64(Clock::get()?.unix_timestamp)?;
+    let funding_index = loan_manager::next_funding_index(&ctx.accounts.globalpool, timestamp)?;
+    ctx.accounts
+        .globalpool
+        .update_funding_index(funding_index, timestamp);
+
+    //
+    // 1. Initialize & mint the trade position (loan) and the liquidity position (collateral).
+    //    The liquidity position token is minted to the escrow owned by the trade position.
+    //
+
+    ctx.accounts.position.init_position(
+        &ctx.accounts.globalpool,
+        ctx.accounts.position_mint.key(),
+        params.loan_liquidity_amount,
+        params.loan_tick_lower_index,
+        params.loan_tick_upper_index,
+        params.loan_duration,
+        0, // to be updated later
+        *ctx.bumps
+            .get("position")
+            .ok_or(ProgramError::InvalidSeeds)?,
+    )?;
+
+    mint_position_token_and_remove_authority(
+        &ctx.accounts.globalpool,
+        &ctx.accounts.position_mint,
+        &ctx.accounts.position_token_account,
+        &ctx.accounts.token_program,
+    )?;
+
+    ctx.accounts.liquidity_position.open_position(
+        &ctx.accounts.globalpool,
+        ctx.accounts.liquidity_position_mint.key(),
+        params.tick_lower_index,
+        params.tick_upper_index,
+    )?;
+
+    mint_position_token_and_remove_authority(
+        &ctx.accounts.globalpool,
+        &ctx.accounts.liquidity_position_mint,
+        &ctx.accounts.liquidity_position_token_account,
+        &ctx.accounts.token_program,
+    )?;
+
+    ctx.accounts
+        .position
+        .lock_liquidity_position(ctx.accounts.liquidity_position.key());
+
+    //
+    // 2. Borrow liquidity from the loan range (fails if insufficient liquidity for loan)
+    //
+
+    let loan_liquidity_delta = convert_to_liquidity_delta(params.loan_liquidity_amount, true)?;
+
+    let (token_borrow_amount, is_borrow_token_a) =
+        loan_manager::calculate_loan_liquidity_token_delta(
+            current_tick_index,
+            params.loan_tick_lower_index,
+            params.loan_tick_upper_index,
+            loan_liquidity_delta,
+        )?;
+
+    require!(
+        is_borrow_token_a == params.borrow_a,
+        ErrorCode::InvalidLoanParameters
+    );
+
+    let loan_update = loan_manager::calculate_modify_loan(
+        &ctx.accounts.globalpool,
+        &ctx.accounts.position,
+        &ctx.accounts.tick_array_lower,
+        &ctx.accounts.tick_array_upper,
+        loan_liquidity_delta,
+        token_borrow_amount as i64,
+        true,
+    )?;
+
+    sync_modify_liquidity_values_for_loan(
+        &mut ctx.accounts.globalpool,
+        &mut ctx.accounts.position,
+        &ctx.accounts.tick_array_lower,
+        &ctx.accounts.tick_array_upper,
+        &loan_update,
+    )?;
+
+    let (token_mint_loan, token_mint_other) = if is_borrow_token_a {
+        (
+            ctx.accounts.globalpool.token_mint_a,
+            ctx.accounts.globalpool.token_mint_b,
+        )
+    } else {
+        (
+            ctx.accounts.globalpool.token_mint_b,
+            ctx.accounts.globalpool.token_mint_a,
+        )
+    };
+
+    ctx.accounts
+        .position
+        .update_position_mints(token_mint_loan, token_mint_other);
+
+    //
+    // 3. Deposit the borrowed tokens and the owner's margin into the liquidity position.
+    //    Borrowed tokens never leave the vault, so only the margin is transferred.
+    //
+
+    let liquidity_delta = convert_to_liquidity_delta(params.liquidity_amount, true)?;
+
+    let update = calculate_modify_liquidity(
+        &ctx.accounts.globalpool,
+        &ctx.accounts.liquidity_position,
+        &ctx.accounts.liquidity_tick_array_lower,
+        &ctx.accounts.liquidity_tick_array_upper,
+        liquidity_delta,
+        timestamp,
+    )?;
+
+    sync_modify_liquidity_values(
+        &mut ctx.accounts.globalpool,
+        &mut ctx.accounts.liquidity_position,
+        &ctx.accounts.liquidity_tick_array_lower,
+        &ctx.accounts.liquidity_tick_array_upper,
+        update,
+    )?;
+
+    let (delta_a, delta_b) = calculate_liquidity_token_deltas(
+        ctx.accounts.liquidity_position.tick_lower_index,
+        ctx.accounts.liquidity_position.tick_upper_index,
+        ctx.accounts.globalpool.tick_current_index,
+        ctx.accounts.globalpool.sqrt_price,
+        liquidity_delta,
+    )?;
+
+    let (margin_a, margin_b) = if is_borrow_token_a {
+        (delta_a.checked_sub(token_borrow_amount), Some(delta_b))
+    } else {
+        (Some(delta_a), delta_b.checked_sub(token_borrow_amount))
+    };
+    let margin_a = margin_a.ok_or(ErrorCode::LeveragedLiquidityLoanNotDeposited)?;
+    let margin_b = margin_b.ok_or(ErrorCode::LeveragedLiquidityLoanNotDeposited)?;
+
+    if margin_a > params.token_max_a || margin_b > params.token_max_b {
+        return Err(ErrorCode::TokenMaxExceeded.into());
+    }
+
+    transfer_from_owner_to_vault(
+        &ctx.accounts.owner,
+        &ctx.accounts.token_owner_account_a,
+        &ctx.accounts.token_vault_a,
+        &ctx.accounts.token_program,
+        margin_a,
+    )?;
+
+    transfer_from_owner_to_vault(
+        &ctx.accounts.owner,
+        &ctx.accounts.token_owner_account_b,
+        &ctx.accounts.token_vault_b,
+        &ctx.accounts.token_program,
+        margin_b,
+    )?;
+
+    // All of the loan token is now deposited in the liquidity position
+    ctx.accounts
+        .position
+        .update_liquidity_swapped(token_borrow_amount as i64, 0)?;
+
+    require!(
+        loan_manager::is_leveraged_liquidity_position_healthy(
+            &ctx.accounts.position,
+            &ctx.accounts.liquidity_position,
+            &ctx.accounts.globalpool,
+        )?,
+        ErrorCode::LeveragedPositionUnhealthy
+    );
+
+    //
+    // 4. Prorated interest on the borrowed amount, paid upfront in the loan token
+    //
+
+    let interest_amount = u64::try_from(
+        (token_borrow_amount as u128)
+            .checked_mul(loan_update.loan_interest_annual_bps as u128)
+            .and_then(|amount| amount.checked_mul(params.loan_duration as u128))
+            .ok_or(ErrorCode::AmountCalcOverflow)?
+            / ANNUAL_INTEREST_DENOMINATOR,
+    )
+    .map_err(|_| ErrorCode::AmountCalcOverflow)?;
+
+    let (interest_token_owner_account, interest_token_vault) = if is_borrow_token_a {
+        (
+            &ctx.accounts.token_owner_account_a,
+            &ctx.accounts.token_vault_a,
+        )
+    } else {
+        (
+            &ctx.accounts.token_owner_account_b,
+            &ctx.accounts.token_vault_b,
+        )
+    };
+
+    transfer_from_owner_to_vault(
+        &ctx.accounts.owner,
+        interest_token_owner_account,
+        interest_token_vault,
+        &ctx.accounts.token_program,
+        interest_amount,
+    )?;
+
+    ctx.accounts.globalpool.update_after_loan(
+        loan_liquidity_delta,
+        interest_amount,
+        is_borrow_token_a,
+    );
+
+    let globalpool = &ctx.accounts.globalpool;
+    emit!(LeveragedLiquidityPositionOpened {
+        globalpool: globalpool.key(),
+        position: ctx.accounts.position.key(),
+        liquidity_position: ctx.accounts.liquidity_position.key(),
+        owner: ctx.accounts.owner.key(),
+        loan_tick_lower_index: params.loan_tick_lower_index,
+        loan_tick_upper_index: params.loan_tick_upper_index,
+        liquidity_borrowed: params.loan_liquidity_amount,
+        loan_token_amount: token_borrow_amount,
+        is_borrow_a: is_borrow_token_a,
+        tick_lower_index: params.tick_lower_index,
+        tick_upper_index: params.tick_upper_index,
+        liquidity_amount: params.liquidity_amount,
+        margin_amount_a: margin_a,
+        margin_amount_b: margin_b,
+        interest_amount,
+        sqrt_price: globalpool.sqrt_price,
+        tick_current_index: globalpool.tick_current_index,
+        fee_growth_global_a: globalpool.fee_growth_global_a,
+        fee_growth_global_b: globalpool.fee_growth_global_b,
+    });
+
+    Ok(())
+}
@@ -56,8 +56,8 @@ pub struct OpenLiquidityPosition<'info> {
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct OpenLiquidityPositionParams {
-    tick_lower_index: i32,
-    tick_upper_index: i32,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
 }
 
 ///
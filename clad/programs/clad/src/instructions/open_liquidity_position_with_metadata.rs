@@ -0,0 +1,61 @@
+use {
+    crate::{
+        instructions::open_liquidity_position::*,
+        util::{
+            create_position_metadata, position_metadata_uri, LIQUIDITY_POSITION_METADATA_NAME,
+            LIQUIDITY_POSITION_METADATA_SYMBOL,
+        },
+    },
+    anchor_lang::prelude::*,
+};
+
+#[derive(Accounts)]
+#[instruction(params: OpenLiquidityPositionParams)]
+pub struct OpenLiquidityPositionWithMetadata<'info> {
+    pub open_liquidity_position: OpenLiquidityPosition<'info>,
+
+    /// CHECK: checked by the metadata program (PDA of the position mint)
+    #[account(mut)]
+    pub position_metadata_account: UncheckedAccount<'info>,
+
+    /// CHECK: address is checked
+    #[account(address = mpl_token_metadata::ID)]
+    pub metadata_program: UncheckedAccount<'info>,
+}
+
+///
+/// Opens a liquidity position in a GlobalPool, with Metaplex metadata for its position token.
+///
+pub fn open_liquidity_position_with_metadata(
+    ctx: Context<OpenLiquidityPositionWithMetadata>,
+    params: &OpenLiquidityPositionParams,
+) -> Result<()> {
+    let accounts = &ctx.accounts.open_liquidity_position;
+
+    create_position_metadata(
+        &accounts.globalpool,
+        &accounts.position_mint,
+        &ctx.accounts.position_metadata_account,
+        &accounts.position_authority,
+        &ctx.accounts.metadata_program,
+        &accounts.system_program,
+        &accounts.rent,
+        LIQUIDITY_POSITION_METADATA_NAME,
+        LIQUIDITY_POSITION_METADATA_SYMBOL,
+        position_metadata_uri(
+            &accounts.globalpool.key(),
+            params.tick_lower_index,
+            params.tick_upper_index,
+        ),
+    )?;
+
+    open_liquidity_position(
+        Context::new(
+            ctx.program_id,
+            &mut ctx.accounts.open_liquidity_position,
+            ctx.remaining_accounts,
+            ctx.bumps.clone(),
+        ),
+        params,
+    )
+}
@@ -0,0 +1,183 @@
+use {
+    super::ModifyLiquidity,
+    crate::{
+        errors::ErrorCode,
+        events::RangeOrderOpened,
+        manager::{
+            liquidity_manager::{
+                calculate_liquidity_token_deltas, calculate_modify_liquidity,
+                sync_modify_liquidity_values,
+            },
+            range_order_manager::{
+                next_range_order_open_update, sync_range_order_liquidity, validate_range_order,
+            },
+        },
+        math::convert_to_liquidity_delta,
+        state::*,
+        util::{
+            calculate_transfer_fee_included_amount, get_epoch_transfer_fee, to_timestamp_u64,
+            transfer_from_owner_to_vault_checked, verify_position_authority,
+        },
+    },
+    anchor_lang::prelude::*,
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct OpenRangeOrderParams {
+    pub liquidity_amount: u128,
+    pub token_max: u64,
+    pub a_to_b: bool,
+}
+
+///
+/// Places a range order in an empty liquidity position spanning one tick spacing.
+/// The order is filled once a swap fully crosses its range, converting it into the other token.
+///
+pub fn open_range_order(
+    ctx: Context<ModifyLiquidity>,
+    params: &OpenRangeOrderParams,
+) -> Result<()> {
+    verify_position_authority(
+        &ctx.accounts.position_token_account,
+        &ctx.accounts.position_authority,
+    )?;
+
+    // Hooks are only called by `swap` and the liquidity instructions
+    if ctx.accounts.globalpool.get_hook_program().is_some() {
+        return Err(ErrorCode::HookUnsupported.into());
+    }
+
+    let position = &ctx.accounts.position;
+    if position.is_range_order {
+        return Err(ErrorCode::RangeOrderPositionUnsupported.into());
+    }
+    if !LiquidityPosition::is_position_empty(position) {
+        return Err(ErrorCode::RangeOrderPositionNotEmpty.into());
+    }
+
+    validate_range_order(
+        &ctx.accounts.globalpool,
+        position.tick_lower_index,
+        position.tick_upper_index,
+        params.a_to_b,
+    )?;
+
+    if params.liquidity_amount == 0 {
+        return Err(ErrorCode::LiquidityZero.into());
+    }
+    let liquidity_delta = convert_to_liquidity_delta(params.liquidity_amount, true)?;
+    let timestamp = to_timestamp_u64(Clock::get()?.unix_timestamp)?;
+
+    let tick_spacing = ctx.accounts.globalpool.tick_spacing;
+    let fill_tick_index = if params.a_to_b {
+        position.tick_upper_index
+    } else {
+        position.tick_lower_index
+    };
+    let fill_tick_array = if params.a_to_b {
+        &ctx.accounts.tick_array_upper
+    } else {
+        &ctx.accounts.tick_array_lower
+    };
+
+    let range_order_update = next_range_order_open_update(
+        &load_range_order(fill_tick_array, fill_tick_index, tick_spacing)?,
+        params.liquidity_amount,
+        params.a_to_b,
+    )?;
+
+    let update = calculate_modify_liquidity(
+        &ctx.accounts.globalpool,
+        &ctx.accounts.position,
+        &ctx.accounts.tick_array_lower,
+        &ctx.accounts.tick_array_upper,
+        liquidity_delta,
+        timestamp,
+    )?;
+
+    sync_modify_liquidity_values(
+        &mut ctx.accounts.globalpool,
+        &mut ctx.accounts.position,
+        &ctx.accounts.tick_array_lower,
+        &ctx.accounts.tick_array_upper,
+        update,
+    )?;
+
+    store_range_order(
+        fill_tick_array,
+        fill_tick_index,
+        tick_spacing,
+        &range_order_update,
+    )?;
+
+    sync_range_order_liquidity(
+        &mut ctx.accounts.globalpool,
+        &ctx.accounts.position,
+        &ctx.accounts.tick_array_lower,
+        liquidity_delta,
+    )?;
+
+    ctx.accounts
+        .position
+        .open_range_order(params.a_to_b, range_order_update.fill_count);
+
+    // Only the input token is deposited, as the range is on its side of the price
+    let (delta_a, delta_b) = calculate_liquidity_token_deltas(
+        ctx.accounts.position.tick_lower_index,
+        ctx.accounts.position.tick_upper_index,
+        ctx.accounts.globalpool.tick_current_index,
+        ctx.accounts.globalpool.sqrt_price,
+        liquidity_delta,
+    )?;
+
+    let (token_mint, token_owner_account, token_vault, token_program, delta) = if params.a_to_b {
+        (
+            &ctx.accounts.token_mint_a,
+            &ctx.accounts.token_owner_account_a,
+            &ctx.accounts.token_vault_a,
+            &ctx.accounts.token_program_a,
+            delta_a,
+        )
+    } else {
+        (
+            &ctx.accounts.token_mint_b,
+            &ctx.accounts.token_owner_account_b,
+            &ctx.accounts.token_vault_b,
+            &ctx.accounts.token_program_b,
+            delta_b,
+        )
+    };
+
+    // The vault must receive the delta, so any transfer fee is paid on top
+    let transfer_amount =
+        calculate_transfer_fee_included_amount(&get_epoch_transfer_fee(token_mint)?, delta)?;
+
+    if transfer_amount > params.token_max {
+        return Err(ErrorCode::TokenMaxExceeded.into());
+    }
+
+    transfer_from_owner_to_vault_checked(
+        &ctx.accounts.position_authority,
+        token_mint,
+        token_owner_account,
+        token_vault,
+        token_program,
+        transfer_amount,
+    )?;
+
+    let globalpool = &ctx.accounts.globalpool;
+    let position = &ctx.accounts.position;
+    emit!(RangeOrderOpened {
+        globalpool: globalpool.key(),
+        position: position.key(),
+        tick_lower_index: position.tick_lower_index,
+        tick_upper_index: position.tick_upper_index,
+        liquidity_amount: params.liquidity_amount,
+        a_to_b: params.a_to_b,
+        token_amount: delta,
+        sqrt_price: globalpool.sqrt_price,
+        tick_current_index: globalpool.tick_current_index,
+    });
+
+    Ok(())
+}
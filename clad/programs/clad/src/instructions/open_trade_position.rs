@@ -1,14 +1,17 @@
 use {
     crate::{
         errors::ErrorCode,
+        events::TradePositionOpened,
         manager::{
-            liquidity_manager, loan_manager, swap_manager::execute_jupiter_swap_for_globalpool,
+            liquidity_manager, loan_manager, margin_manager,
+            swap_manager::execute_jupiter_swap_for_globalpool,
         },
         math::*,
         state::*,
         util::{
-            mint_position_token_and_remove_authority, sort_token_amount_for_loan,
-            transfer_from_owner_to_vault,
+            load_native_sol_account, load_token_owner_account,
+            mint_position_token_and_remove_authority, sort_token_amount_for_loan, to_timestamp_u64,
+            transfer_from_owner_to_vault, verify_position_authority,
         },
     },
     anchor_lang::prelude::*,
@@ -24,7 +27,13 @@ pub struct OpenTradePosition<'info> {
     #[account(mut)]
     pub owner: Signer<'info>,
 
-    #[account(mut)]
+    // Hooks are only called by `swap` and the liquidity instructions, and the vaults are lent
+    // out while a flash loan is outstanding
+    #[account(
+        mut,
+        constraint = globalpool.get_hook_program().is_none() @ ErrorCode::HookUnsupported,
+        constraint = !globalpool.has_flash_loan() @ ErrorCode::FlashLoanInProgress
+    )]
     pub globalpool: Box<Account<'info, Globalpool>>,
 
     #[account(
@@ -55,23 +64,35 @@ pub struct OpenTradePosition<'info> {
 	)]
     pub position_token_account: Box<Account<'info, TokenAccount>>,
 
-    #[account(mut, token::mint = globalpool.token_mint_a)]
-    pub token_owner_account_a: Box<Account<'info, TokenAccount>>,
+    /// CHECK: mint is checked in the handler, as it may be the temporary wSOL account
+    #[account(mut)]
+    pub token_owner_account_a: UncheckedAccount<'info>,
 
+    /// CHECK: address is checked, loaded in the handler once the token program is checked
     #[account(mut, address = globalpool.token_vault_a)]
-    pub token_vault_a: Box<Account<'info, TokenAccount>>,
+    pub token_vault_a: UncheckedAccount<'info>,
 
-    #[account(address = globalpool.token_mint_a)]
-    pub token_mint_a: Box<Account<'info, Mint>>,
+    /// CHECK: address is checked
+    #[account(
+        address = globalpool.token_mint_a,
+        constraint = *token_mint_a.owner == token::ID @ ErrorCode::Token2022MintUnsupported
+    )]
+    pub token_mint_a: UncheckedAccount<'info>,
 
-    #[account(mut, token::mint = globalpool.token_mint_b)]
-    pub token_owner_account_b: Box<Account<'info, TokenAccount>>,
+    /// CHECK: mint is checked in the handler, as it may be the temporary wSOL account
+    #[account(mut)]
+    pub token_owner_account_b: UncheckedAccount<'info>,
 
+    /// CHECK: address is checked, loaded in the handler once the token program is checked
     #[account(mut, address = globalpool.token_vault_b)]
-    pub token_vault_b: Box<Account<'info, TokenAccount>>,
+    pub token_vault_b: UncheckedAccount<'info>,
 
-    #[account(address = globalpool.token_mint_b)]
-    pub token_mint_b: Box<Account<'info, Mint>>,
+    /// CHECK: address is checked
+    #[account(
+        address = globalpool.token_mint_b,
+        constraint = *token_mint_b.owner == token::ID @ ErrorCode::Token2022MintUnsupported
+    )]
+    pub token_mint_b: UncheckedAccount<'info>,
 
     #[account(mut, has_one = globalpool)]
     pub tick_array_lower: AccountLoader<'info, TickArray>,
@@ -98,12 +119,26 @@ pub struct OpenTradePositionParams {
     pub tick_lower_index: i32,
     pub tick_upper_index: i32,
 
-    // Lifetime of loan, in seconds
+    // Lifetime of loan, in seconds (must be zero for perpetual positions)
     pub loan_duration: u64,
 
     // true: borrow token A | false: borrow token B
     pub borrow_a: bool,
 
+    // true: no maturity, pays funding out of the collateral instead of upfront interest
+    pub is_perpetual: bool,
+
+    // true: lock a liquidity position (held by the globalpool) as collateral instead of
+    // transferring the collateral token. Its accounts lead `remaining_accounts`, after the System
+    // Program enabling native SOL mode if any:
+    // [liquidity_position, position_token_owner_account, position_token_escrow]
+    pub liquidity_position_collateral: bool,
+
+    // true: back the position with the owner's MarginAccount instead of transferring the
+    // collateral token. The account must stay above initial margin with the position. Its accounts
+    // follow those of the above (see `MARGIN_BACKED_OPEN_ACCOUNTS_LEN`).
+    pub margin_account_collateral: bool,
+
     pub swap_instruction_data: Vec<u8>, // Jupiter router data
 }
 
@@ -119,8 +154,14 @@ pub fn open_trade_position<'info>(
         return Err(ErrorCode::LiquidityZero.into());
     }
 
-    // Loan must be at least 1 hour long and at most 10 days
-    if params.loan_duration < 3600 || params.loan_duration > 864_000 {
+    // Loan must be at least 1 hour long and at most 10 days, unless perpetual
+    if params.is_perpetual {
+        if params.loan_duration != 0 {
+            return Err(ErrorCode::InvalidLoanDuration.into());
+        }
+    } else if params.loan_duration < loan_manager::MIN_LOAN_DURATION
+        || params.loan_duration > loan_manager::MAX_LOAN_DURATION
+    {
         return Err(ErrorCode::InvalidLoanDuration.into());
     }
 
@@ -128,32 +169,102 @@ pub fn open_trade_position<'info>(
         return Err(ProgramError::AccountAlreadyInitialized.into());
     }
 
-    if params.tick_lower_index > params.tick_upper_index {
-        return Err(ErrorCode::InvalidTickRange.into());
+    // Perpetual positions pay funding out of the collateral token
+    if params.is_perpetual && params.liquidity_position_collateral {
+        return Err(ErrorCode::InvalidTradePositionType.into());
     }
 
-    // Require that both TickArrays (from which token liquidity is borrowed) are either
-    // below or above the current globalpool tick since a trader can only borrow one asset.
-    if (params.tick_lower_index < current_tick_index
-        && params.tick_upper_index > current_tick_index)
-        || (params.tick_upper_index == current_tick_index)
-        || (params.tick_lower_index == current_tick_index)
-    {
-        return Err(ErrorCode::InvalidTickRangeAgainstCurrentTick.into());
+    // Perpetual positions are liquidated on their own health (see `repay_trade_position`)
+    if params.is_perpetual && params.margin_account_collateral {
+        return Err(ErrorCode::MarginPerpetualPositionNotSupported.into());
+    }
+    if params.liquidity_position_collateral && params.margin_account_collateral {
+        return Err(ErrorCode::InvalidTradePositionType.into());
     }
 
-    // Require that if borrow_a = true, then the Ticks are ABOVE the current globalpool tick.
-    // Conversely, if borrow_a = false, then the Ticks are BELOW the current globalpool tick.
-    if (params.borrow_a && params.tick_lower_index < current_tick_index)
-        || (!params.borrow_a && params.tick_upper_index > current_tick_index)
-    {
-        return Err(ErrorCode::InvalidTickRangeAgainstBorrowCondition.into());
+    // In native SOL mode, the collateral & interest paid in SOL are wrapped as they are transferred
+    let (native_sol_account, remaining_accounts) = load_native_sol_account(
+        ctx.remaining_accounts,
+        &ctx.accounts.owner,
+        [
+            &ctx.accounts.token_mint_a.to_account_info(),
+            &ctx.accounts.token_mint_b.to_account_info(),
+        ],
+        [
+            &ctx.accounts.token_owner_account_a,
+            &ctx.accounts.token_owner_account_b,
+        ],
+        [
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+        ],
+    )?;
+    if let Some(native_sol_account) = native_sol_account.as_ref() {
+        native_sol_account.open(0)?;
     }
 
+    let token_owner_account_a = load_token_owner_account(
+        &ctx.accounts.token_owner_account_a,
+        &ctx.accounts.globalpool.token_mint_a,
+        None,
+    )?;
+    let token_owner_account_b = load_token_owner_account(
+        &ctx.accounts.token_owner_account_b,
+        &ctx.accounts.globalpool.token_mint_b,
+        None,
+    )?;
+    let mut token_vault_a = Box::new(Account::<TokenAccount>::try_from(
+        &ctx.accounts.token_vault_a,
+    )?);
+    let mut token_vault_b = Box::new(Account::<TokenAccount>::try_from(
+        &ctx.accounts.token_vault_b,
+    )?);
+
+    let (liquidity_collateral_accounts, remaining_accounts) =
+        if params.liquidity_position_collateral {
+            if remaining_accounts.len() < loan_manager::LIQUIDITY_COLLATERAL_OPEN_ACCOUNTS_LEN {
+                return Err(ErrorCode::InvalidLiquidityCollateralAccounts.into());
+            }
+            remaining_accounts.split_at(loan_manager::LIQUIDITY_COLLATERAL_OPEN_ACCOUNTS_LEN)
+        } else {
+            remaining_accounts.split_at(0)
+        };
+
+    // The margin account is followed by the accounts valuing it, whose number depends on it
+    let (margin_accounts, swap_accounts) = if params.margin_account_collateral {
+        let margin_account = Account::<MarginAccount>::try_from(
+            remaining_accounts
+                .first()
+                .ok_or(ErrorCode::InvalidMarginHealthAccounts)?,
+        )?;
+        let margin_accounts_len = margin_manager::MARGIN_BACKED_OPEN_ACCOUNTS_LEN
+            + margin_manager::margin_health_accounts_len(&margin_account);
+        if remaining_accounts.len() < margin_accounts_len {
+            return Err(ErrorCode::InvalidMarginHealthAccounts.into());
+        }
+        remaining_accounts.split_at(margin_accounts_len)
+    } else {
+        remaining_accounts.split_at(0)
+    };
+
+    loan_manager::validate_loan_tick_range(
+        current_tick_index,
+        params.tick_lower_index,
+        params.tick_upper_index,
+        params.borrow_a,
+    )?;
+
     let liquidity_delta = convert_to_liquidity_delta(u128::from(params.liquidity_amount), true)?;
 
     msg!("Requesting loan");
 
+    // Accrue funding at the current utilization, before the loan changes it
+    let timestamp = to_timestamp_u64(Clock::get()?.unix_timestamp)?;
+    let funding_index = loan_manager::next_funding_index(&ctx.accounts.globalpool, timestamp)?;
+    ctx.accounts
+        .globalpool
+        .update_funding_index(funding_index, timestamp);
+
     //
     // 1. Initialize & mint the trade position
     //
@@ -166,8 +277,15 @@ pub fn open_trade_position<'info>(
         params.tick_upper_index,
         params.loan_duration,
         0, // to be updated later
+        *ctx.bumps
+            .get("position")
+            .ok_or(ProgramError::InvalidSeeds)?,
     )?;
 
+    if params.is_perpetual {
+        ctx.accounts.position.init_funding(funding_index);
+    }
+
     mint_position_token_and_remove_authority(
         &ctx.accounts.globalpool,
         position_mint,
@@ -214,11 +332,11 @@ pub fn open_trade_position<'info>(
     let collateral_token_mint;
     let borrowed_token_mint;
     if is_collateral_token_a {
-        collateral_token_owner_account = &ctx.accounts.token_owner_account_a;
+        collateral_token_owner_account = &token_owner_account_a;
         collateral_token_mint = &ctx.accounts.token_mint_a;
         borrowed_token_mint = &ctx.accounts.token_mint_b;
     } else {
-        collateral_token_owner_account = &ctx.accounts.token_owner_account_b;
+        collateral_token_owner_account = &token_owner_account_b;
         collateral_token_mint = &ctx.accounts.token_mint_b;
         borrowed_token_mint = &ctx.accounts.token_mint_a;
     };
@@ -261,11 +379,8 @@ pub fn open_trade_position<'info>(
 
     msg!("Opening trade position from loan");
 
-    let (initial_loan_vault_balance, initial_swapped_vault_balance) = sort_token_amount_for_loan(
-        &ctx.accounts.token_vault_a,
-        &ctx.accounts.token_vault_b,
-        is_borrow_token_a,
-    );
+    let (initial_loan_vault_balance, initial_swapped_vault_balance) =
+        sort_token_amount_for_loan(&token_vault_a, &token_vault_b, is_borrow_token_a);
 
     //
     // TODO: Validate that the receiver of the token swap is the globalpool's token vault
@@ -273,7 +388,7 @@ pub fn open_trade_position<'info>(
 
     execute_jupiter_swap_for_globalpool(
         &ctx.accounts.globalpool,
-        &ctx.remaining_accounts,
+        swap_accounts,
         &params.swap_instruction_data,
     )?;
 
@@ -282,14 +397,11 @@ pub fn open_trade_position<'info>(
     //
 
     // Update token vault amounts
-    ctx.accounts.token_vault_a.reload()?;
-    ctx.accounts.token_vault_b.reload()?;
+    token_vault_a.reload()?;
+    token_vault_b.reload()?;
 
-    let (post_loan_vault_balance, post_swapped_vault_balance) = sort_token_amount_for_loan(
-        &ctx.accounts.token_vault_a,
-        &ctx.accounts.token_vault_b,
-        is_borrow_token_a,
-    );
+    let (post_loan_vault_balance, post_swapped_vault_balance) =
+        sort_token_amount_for_loan(&token_vault_a, &token_vault_b, is_borrow_token_a);
 
     // 1. Require that Loan (Borrowed) Token was the swapped to Swapped Token.
     // => Loan Token balance should decrease
@@ -363,32 +475,133 @@ pub fn open_trade_position<'info>(
         is_borrow_token_a,
     )?;
 
-    let collateral_token_vault = if is_collateral_token_a {
-        &ctx.accounts.token_vault_a
-    } else {
-        &ctx.accounts.token_vault_b
-    };
+    if params.liquidity_position_collateral {
+        // Lock enough liquidity of the liquidity position to cover the collateral, by moving its
+        // token to the globalpool's escrow. The liquidity stays in the pool and keeps earning fees.
+        let liquidity_position =
+            Account::<LiquidityPosition>::try_from(&liquidity_collateral_accounts[0])?;
+        let position_token_owner_account =
+            Account::<TokenAccount>::try_from(&liquidity_collateral_accounts[1])?;
+        let position_token_escrow =
+            Account::<TokenAccount>::try_from(&liquidity_collateral_accounts[2])?;
+
+        if liquidity_position.globalpool != ctx.accounts.globalpool.key()
+            || position_token_owner_account.mint != liquidity_position.position_mint
+            || position_token_escrow.mint != liquidity_position.position_mint
+            || position_token_escrow.owner != ctx.accounts.globalpool.key()
+        {
+            return Err(ErrorCode::InvalidLiquidityCollateralAccounts.into());
+        }
+
+        verify_position_authority(&position_token_owner_account, &ctx.accounts.owner)?;
+
+        let liquidity_collateral = loan_manager::calculate_liquidity_collateral(
+            &liquidity_position,
+            &ctx.accounts.globalpool,
+            collateral_amount,
+            is_collateral_token_a,
+        )?;
 
-    // Transfer collateral from trader to vault
-    transfer_from_owner_to_vault(
-        &ctx.accounts.owner,
-        &collateral_token_owner_account,
-        &collateral_token_vault,
-        &ctx.accounts.token_program,
-        collateral_amount,
-    )?;
+        transfer_from_owner_to_vault(
+            &ctx.accounts.owner,
+            &position_token_owner_account,
+            &position_token_escrow,
+            &ctx.accounts.token_program,
+            1,
+        )?;
 
-    ctx.accounts
-        .position
-        .update_collateral_amount(collateral_amount);
+        ctx.accounts
+            .position
+            .lock_liquidity_collateral(liquidity_position.key(), liquidity_collateral);
+    } else if params.margin_account_collateral {
+        // No collateral is transferred. The margin account backs the position instead, which is
+        // linked to it, and must stay above initial margin with the position's debt.
+        let mut margin_account = Account::<MarginAccount>::try_from(&margin_accounts[0])?;
+        if margin_account.owner != ctx.accounts.owner.key() {
+            return Err(ErrorCode::MarginPositionNotOwned.into());
+        }
+        if margin_account.is_liquidating {
+            return Err(ErrorCode::MarginAccountLiquidating.into());
+        }
+
+        let current_timestamp = Clock::get()?.unix_timestamp;
+        let mut health = margin_manager::calculate_margin_health(
+            &margin_account,
+            &margin_accounts[margin_manager::MARGIN_BACKED_OPEN_ACCOUNTS_LEN..],
+            current_timestamp,
+        )?;
+        margin_manager::add_position_to_health(
+            &mut health,
+            &ctx.accounts.position,
+            &ctx.accounts.globalpool,
+            [
+                &Account::<Mint>::try_from(&ctx.accounts.token_mint_a)?,
+                &Account::<Mint>::try_from(&ctx.accounts.token_mint_b)?,
+            ],
+            &margin_accounts[1..margin_manager::MARGIN_BACKED_OPEN_ACCOUNTS_LEN],
+            true,
+            current_timestamp,
+        )?;
+        if !health.meets_margin(MarginAccount::INITIAL_MARGIN_BPS) {
+            return Err(ErrorCode::MarginAccountUnhealthy.into());
+        }
+
+        margin_account.link_position(ctx.accounts.position.key())?;
+        margin_account.exit(&crate::ID)?;
+
+        ctx.accounts
+            .position
+            .back_with_margin_account(margin_account.key());
+    } else {
+        let collateral_token_vault = if is_collateral_token_a {
+            &token_vault_a
+        } else {
+            &token_vault_b
+        };
+
+        if let Some(native_sol_account) = native_sol_account.as_ref() {
+            if native_sol_account.is_token_a == is_collateral_token_a {
+                native_sol_account.wrap(collateral_amount)?;
+            }
+        }
+
+        // Transfer collateral from trader to vault
+        transfer_from_owner_to_vault(
+            &ctx.accounts.owner,
+            &collateral_token_owner_account,
+            &collateral_token_vault,
+            &ctx.accounts.token_program,
+            collateral_amount,
+        )?;
+
+        ctx.accounts
+            .position
+            .update_collateral_amount(collateral_amount);
+    }
 
     //
     // Interest payment on opening the trade position (ie. on loan)
+    // Perpetual positions pay funding over time instead (see `crank_funding`).
     //
 
+    if params.is_perpetual {
+        ctx.accounts
+            .globalpool
+            .update_after_loan(liquidity_delta, 0, is_collateral_token_a);
+
+        if let Some(native_sol_account) = native_sol_account.as_ref() {
+            native_sol_account.close()?;
+        }
+
+        emit_trade_position_opened(ctx.accounts, 0);
+
+        return Ok(());
+    }
+
     //
     // Calculate & transfer prorated interest from trader to vault (in collateral token).
-    // NOTE: This must come after the collateral calculation because it uses the collateral amount
+    // NOTE: This must come after the collateral calculation because it uses the collateral amount,
+    //       also charged on positions backed by a margin account, which don't transfer it
     // TODO: Allow payment of interest in borrowed token as well.
     //
 
@@ -400,11 +613,11 @@ pub fn open_trade_position<'info>(
 
     // follows the collateral_token_vault pattern, but modifiable later
     if is_interest_fee_in_a {
-        interest_fee_token_owner_account = &ctx.accounts.token_owner_account_a;
-        interest_fee_token_vault = &ctx.accounts.token_vault_a;
+        interest_fee_token_owner_account = &token_owner_account_a;
+        interest_fee_token_vault = &token_vault_a;
     } else {
-        interest_fee_token_owner_account = &ctx.accounts.token_owner_account_b;
-        interest_fee_token_vault = &ctx.accounts.token_vault_b;
+        interest_fee_token_owner_account = &token_owner_account_b;
+        interest_fee_token_vault = &token_vault_b;
     }
 
     let annual_interest_amount = interest_fee_multiplier_amount
@@ -425,6 +638,12 @@ pub fn open_trade_position<'info>(
     msg!("annual_interest_amount: {}", annual_interest_amount);
     msg!("prorated_interest_amount: {}", prorated_interest_amount);
 
+    if let Some(native_sol_account) = native_sol_account.as_ref() {
+        if native_sol_account.is_token_a == is_interest_fee_in_a {
+            native_sol_account.wrap(prorated_interest_amount)?;
+        }
+    }
+
     transfer_from_owner_to_vault(
         &ctx.accounts.owner,
         interest_fee_token_owner_account,
@@ -433,6 +652,10 @@ pub fn open_trade_position<'info>(
         prorated_interest_amount,
     )?;
 
+    if let Some(native_sol_account) = native_sol_account.as_ref() {
+        native_sol_account.close()?;
+    }
+
     //
     // Add the interest fee to the pool fee growth (for LP payout)
     //
@@ -443,5 +666,31 @@ pub fn open_trade_position<'info>(
         is_interest_fee_in_a,
     );
 
+    emit_trade_position_opened(ctx.accounts, prorated_interest_amount);
+
     Ok(())
 }
+
+fn emit_trade_position_opened(accounts: &OpenTradePosition, interest_amount: u64) {
+    let globalpool = &accounts.globalpool;
+    let position = &accounts.position;
+
+    emit!(TradePositionOpened {
+        globalpool: globalpool.key(),
+        position: position.key(),
+        owner: accounts.owner.key(),
+        tick_lower_index: position.tick_lower_index,
+        tick_upper_index: position.tick_upper_index,
+        liquidity_borrowed: position.liquidity_borrowed,
+        loan_token_swapped: position.loan_token_swapped,
+        trade_token_amount: position.trade_token_amount,
+        collateral_amount: position.collateral_amount,
+        interest_amount,
+        is_borrow_a: position.is_borrow_a(globalpool),
+        is_perpetual: position.is_perpetual,
+        sqrt_price: globalpool.sqrt_price,
+        tick_current_index: globalpool.tick_current_index,
+        fee_growth_global_a: globalpool.fee_growth_global_a,
+        fee_growth_global_b: globalpool.fee_growth_global_b,
+    });
+}
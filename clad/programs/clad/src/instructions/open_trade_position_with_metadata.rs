@@ -0,0 +1,62 @@
+use {
+    crate::{
+        instructions::open_trade_position::*,
+        util::{
+            create_position_metadata, position_metadata_uri, TRADE_POSITION_METADATA_NAME,
+            TRADE_POSITION_METADATA_SYMBOL,
+        },
+    },
+    anchor_lang::prelude::*,
+};
+
+#[derive(Accounts)]
+#[instruction(params: OpenTradePositionParams)]
+pub struct OpenTradePositionWithMetadata<'info> {
+    pub open_trade_position: OpenTradePosition<'info>,
+
+    /// CHECK: checked by the metadata program (PDA of the position mint)
+    #[account(mut)]
+    pub position_metadata_account: UncheckedAccount<'info>,
+
+    /// CHECK: address is checked
+    #[account(address = mpl_token_metadata::ID)]
+    pub metadata_program: UncheckedAccount<'info>,
+}
+
+///
+/// Opens a trade position, with Metaplex metadata for its position token.
+/// The metadata URI is built from the Globalpool and the loan's tick range.
+///
+pub fn open_trade_position_with_metadata(
+    ctx: Context<OpenTradePositionWithMetadata>,
+    params: &OpenTradePositionParams,
+) -> Result<()> {
+    let accounts = &ctx.accounts.open_trade_position;
+
+    create_position_metadata(
+        &accounts.globalpool,
+        &accounts.position_mint,
+        &ctx.accounts.position_metadata_account,
+        &accounts.owner,
+        &ctx.accounts.metadata_program,
+        &accounts.system_program,
+        &accounts.rent,
+        TRADE_POSITION_METADATA_NAME,
+        TRADE_POSITION_METADATA_SYMBOL,
+        position_metadata_uri(
+            &accounts.globalpool.key(),
+            params.tick_lower_index,
+            params.tick_upper_index,
+        ),
+    )?;
+
+    open_trade_position(
+        Context::new(
+            ctx.program_id,
+            &mut ctx.accounts.open_trade_position,
+            ctx.remaining_accounts,
+            ctx.bumps.clone(),
+        ),
+        params,
+    )
+}
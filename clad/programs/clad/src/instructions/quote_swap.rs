@@ -0,0 +1,144 @@
+use {
+    crate::{
+        manager::swap_manager,
+        state::{map_tick_array_data, Globalpool, TickArray},
+        util::{
+            calculate_transfer_fee_excluded_amount, calculate_transfer_fee_included_amount,
+            get_epoch_transfer_fee, load_tick_array_bitmap, load_tick_arrays, to_timestamp_u64,
+            TickSequence,
+        },
+    },
+    anchor_lang::{prelude::*, solana_program::program::set_return_data},
+    std::cell::RefCell,
+};
+
+#[derive(Accounts)]
+pub struct QuoteSwap<'info> {
+    pub globalpool: Box<Account<'info, Globalpool>>,
+
+    /// CHECK: address is checked
+    #[account(address = globalpool.token_mint_a)]
+    pub token_mint_a: UncheckedAccount<'info>,
+    /// CHECK: address is checked
+    #[account(address = globalpool.token_mint_b)]
+    pub token_mint_b: UncheckedAccount<'info>,
+
+    #[account(has_one = globalpool)]
+    pub tick_array_0: AccountLoader<'info, TickArray>,
+
+    #[account(has_one = globalpool)]
+    pub tick_array_1: AccountLoader<'info, TickArray>,
+
+    #[account(has_one = globalpool)]
+    pub tick_array_2: AccountLoader<'info, TickArray>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct QuoteSwapParams {
+    pub amount: u64,
+    pub sqrt_price_limit: u128,
+    pub amount_specified_is_input: bool,
+    pub a_to_b: bool,
+}
+
+// Returned by `quote_swap` through the return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct QuoteSwapResult {
+    pub amount_in: u64,  // Sent by the user, including the input transfer fee
+    pub amount_out: u64, // Received by the user, after the output transfer fee
+    pub fee_amount: u64, // Swap fee in the input token
+    pub end_sqrt_price: u128,
+    pub end_tick_index: i32,
+    pub ticks_crossed: u32,
+}
+
+pub fn quote_swap(ctx: Context<QuoteSwap>, params: &QuoteSwapParams) -> Result<()> {
+    let globalpool = &ctx.accounts.globalpool;
+
+    let timestamp = to_timestamp_u64(Clock::get()?.unix_timestamp)?;
+
+    let (tick_array_bitmap, remaining_accounts) =
+        load_tick_array_bitmap(ctx.remaining_accounts, &globalpool.key())?;
+    let remaining_tick_arrays = load_tick_arrays(remaining_accounts, &globalpool.key())?;
+
+    // The swap updates the tick arrays it crosses, so it runs on copies of the read-only accounts.
+    // Like in `swap`, a tick array passed more than once is only used at its first position.
+    let mut tick_array_keys: Vec<Pubkey> = vec![];
+    let mut tick_array_data: Vec<RefCell<Vec<u8>>> = vec![];
+    for tick_array in [
+        &ctx.accounts.tick_array_0,
+        &ctx.accounts.tick_array_1,
+        &ctx.accounts.tick_array_2,
+    ]
+    .into_iter()
+    .chain(remaining_tick_arrays.iter())
+    {
+        if tick_array_keys.contains(&tick_array.key()) {
+            continue;
+        }
+        tick_array_keys.push(tick_array.key());
+        tick_array_data.push(RefCell::new(
+            tick_array.as_ref().try_borrow_data()?.to_vec(),
+        ));
+    }
+
+    let tick_arrays = tick_array_data
+        .iter()
+        .map(|data| map_tick_array_data(data.borrow_mut()))
+        .collect();
+    let mut swap_tick_sequence = TickSequence::from_arrays_with_extensions(tick_arrays)?;
+    if let Some(tick_array_bitmap) = tick_array_bitmap.as_ref() {
+        swap_tick_sequence.set_tick_array_bitmap(tick_array_bitmap);
+    }
+
+    let QuoteSwapParams {
+        amount_specified_is_input,
+        a_to_b,
+        ..
+    } = *params;
+
+    let (token_mint_input, token_mint_output) = if a_to_b {
+        (&ctx.accounts.token_mint_a, &ctx.accounts.token_mint_b)
+    } else {
+        (&ctx.accounts.token_mint_b, &ctx.accounts.token_mint_a)
+    };
+    let transfer_fee_input = get_epoch_transfer_fee(token_mint_input)?;
+    let transfer_fee_output = get_epoch_transfer_fee(token_mint_output)?;
+
+    let swap_amount = if amount_specified_is_input {
+        calculate_transfer_fee_excluded_amount(&transfer_fee_input, params.amount)?
+    } else {
+        calculate_transfer_fee_included_amount(&transfer_fee_output, params.amount)?
+    };
+
+    let swap_update = swap_manager::swap(
+        globalpool,
+        &mut swap_tick_sequence,
+        swap_amount,
+        params.sqrt_price_limit,
+        amount_specified_is_input,
+        a_to_b,
+        timestamp,
+    )?;
+
+    let (swap_amount_input, swap_amount_output) = if a_to_b {
+        (swap_update.amount_a, swap_update.amount_b)
+    } else {
+        (swap_update.amount_b, swap_update.amount_a)
+    };
+
+    let result = QuoteSwapResult {
+        amount_in: calculate_transfer_fee_included_amount(&transfer_fee_input, swap_amount_input)?,
+        amount_out: calculate_transfer_fee_excluded_amount(
+            &transfer_fee_output,
+            swap_amount_output,
+        )?,
+        fee_amount: swap_update.fee_amount,
+        end_sqrt_price: swap_update.next_sqrt_price,
+        end_tick_index: swap_update.next_tick_index,
+        ticks_crossed: swap_update.ticks_crossed,
+    };
+    set_return_data(&result.try_to_vec()?);
+
+    Ok(())
+}
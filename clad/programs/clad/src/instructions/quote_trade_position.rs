@@ -0,0 +1,130 @@
+use {
+    crate::{
+        errors::ErrorCode,
+        manager::{
+            liquidity_manager::calculate_liquidity_token_deltas,
+            loan_manager::{
+                calculate_funding_amount, calculate_trade_position_repayment,
+                is_perpetual_position_healthy, next_funding_index, read_oracle_sqrt_price,
+                PERPETUAL_HEALTH_ACCOUNTS_LEN,
+            },
+        },
+        math::convert_to_liquidity_delta,
+        state::*,
+        util::to_timestamp_u64,
+    },
+    anchor_lang::{prelude::*, solana_program::program::set_return_data},
+};
+
+#[derive(Accounts)]
+pub struct QuoteTradePosition<'info> {
+    pub globalpool: Box<Account<'info, Globalpool>>,
+
+    #[account(has_one = globalpool)]
+    pub position: Box<Account<'info, TradePosition>>,
+}
+
+// Returned by `quote_trade_position` through the return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct QuoteTradePositionResult {
+    // Owed to the LPs at the current price
+    pub repay_amount_a: u64,
+    pub repay_amount_b: u64,
+    // Funding a perpetual position pays out of its collateral before being repaid
+    pub funding_amount: u64,
+    // Held by the position for the repayment: trade token, collateral & liquidity collateral
+    pub available_amount_a: u64,
+    pub available_amount_b: u64,
+    // Output of the swap needed to cover the repayment (at most one is non-zero)
+    pub swap_amount_needed_a: u64,
+    pub swap_amount_needed_b: u64,
+    // Whether anyone other than the owner can repay the position now
+    pub is_liquidatable: bool,
+}
+
+pub fn quote_trade_position(ctx: Context<QuoteTradePosition>) -> Result<()> {
+    let globalpool = &ctx.accounts.globalpool;
+
+    if ctx.accounts.position.is_leveraged_liquidity_position() {
+        return Err(ErrorCode::InvalidTradePositionType.into());
+    }
+
+    // Same as `repay_trade_position`, on a copy of the position as the funding isn't paid here
+    let clock = Clock::get()?;
+    let current_timestamp = to_timestamp_u64(clock.unix_timestamp)?;
+    let funding_index = next_funding_index(globalpool, current_timestamp)?;
+    let mut position: TradePosition = (**ctx.accounts.position).clone();
+    let funding_amount = calculate_funding_amount(&position, funding_index)?;
+    if position.is_perpetual {
+        position.update_after_funding(funding_index, funding_amount)?;
+    }
+
+    // Perpetual positions pass the oracle accounts pricing their health first in
+    // `remaining_accounts` (see `PERPETUAL_HEALTH_ACCOUNTS_LEN`)
+    let (is_liquidatable, remaining_accounts) = if position.is_perpetual {
+        if ctx.remaining_accounts.len() < PERPETUAL_HEALTH_ACCOUNTS_LEN {
+            return Err(ErrorCode::InvalidPerpetualHealthAccounts.into());
+        }
+        let (price_accounts, remaining_accounts) = ctx
+            .remaining_accounts
+            .split_at(PERPETUAL_HEALTH_ACCOUNTS_LEN);
+        let oracle_sqrt_price =
+            read_oracle_sqrt_price(globalpool, price_accounts, clock.unix_timestamp)?;
+        (
+            !is_perpetual_position_healthy(&position, globalpool, oracle_sqrt_price)?,
+            remaining_accounts,
+        )
+    } else {
+        let maturity_timestamp = position
+            .open_time
+            .checked_add(position.duration)
+            .ok_or(ErrorCode::AmountCalcOverflow)?;
+        (
+            current_timestamp >= maturity_timestamp,
+            ctx.remaining_accounts,
+        )
+    };
+
+    // Positions collateralized by a liquidity position pass it next
+    let (liquidity_collateral_delta_a, liquidity_collateral_delta_b) =
+        if position.has_liquidity_collateral() {
+            let liquidity_position = remaining_accounts
+                .first()
+                .ok_or(ErrorCode::InvalidLiquidityCollateralAccounts)?;
+            let liquidity_position = Account::<LiquidityPosition>::try_from(liquidity_position)?;
+            if liquidity_position.key() != position.liquidity_position {
+                return Err(ErrorCode::InvalidLiquidityCollateralAccounts.into());
+            }
+
+            calculate_liquidity_token_deltas(
+                liquidity_position.tick_lower_index,
+                liquidity_position.tick_upper_index,
+                globalpool.tick_current_index,
+                globalpool.sqrt_price,
+                convert_to_liquidity_delta(position.liquidity_collateral, false)?,
+            )?
+        } else {
+            (0, 0)
+        };
+
+    let repayment = calculate_trade_position_repayment(
+        &position,
+        globalpool,
+        liquidity_collateral_delta_a,
+        liquidity_collateral_delta_b,
+    )?;
+
+    let result = QuoteTradePositionResult {
+        repay_amount_a: repayment.repay_amount_a,
+        repay_amount_b: repayment.repay_amount_b,
+        funding_amount,
+        available_amount_a: repayment.available_amount_a,
+        available_amount_b: repayment.available_amount_b,
+        swap_amount_needed_a: repayment.swap_amount_needed_a,
+        swap_amount_needed_b: repayment.swap_amount_needed_b,
+        is_liquidatable,
+    };
+    set_return_data(&result.try_to_vec()?);
+
+    Ok(())
+}
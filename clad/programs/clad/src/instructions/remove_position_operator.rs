@@ -0,0 +1,46 @@
+use {
+    crate::{
+        state::*,
+        util::{get_position_mint, verify_position_authority},
+    },
+    anchor_lang::prelude::*,
+    anchor_spl::token::TokenAccount,
+};
+
+#[derive(Accounts)]
+pub struct RemovePositionOperator<'info> {
+    pub position_authority: Signer<'info>,
+
+    /// CHECK: safe, for receiving rent only
+    #[account(mut)]
+    pub receiver: UncheckedAccount<'info>,
+
+    /// CHECK: Liquidity or Trade Position, checked in the handler
+    pub position: UncheckedAccount<'info>,
+
+    #[account(constraint = position_token_account.amount == 1)]
+    pub position_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        close = receiver,
+        has_one = position,
+        seeds = [
+            b"position_operator".as_ref(),
+            position.key().as_ref(),
+            position_operator.operator.as_ref()
+        ],
+        bump = position_operator.bump[0],
+    )]
+    pub position_operator: Box<Account<'info, PositionOperator>>,
+}
+
+pub fn remove_position_operator(ctx: Context<RemovePositionOperator>) -> Result<()> {
+    if ctx.accounts.position_token_account.mint != get_position_mint(&ctx.accounts.position)? {
+        return Err(anchor_lang::error::ErrorCode::ConstraintRaw.into());
+    }
+    verify_position_authority(
+        &ctx.accounts.position_token_account,
+        &ctx.accounts.position_authority,
+    )
+}
@@ -0,0 +1,340 @@
+use {
+    crate::{
+        errors::ErrorCode,
+        events::LeveragedLiquidityPositionRepaid,
+        manager::{
+            liquidity_manager::{
+                calculate_liquidity_token_deltas, calculate_modify_liquidity,
+                sync_modify_liquidity_values,
+            },
+            loan_manager::is_leveraged_liquidity_position_healthy,
+        },
+        math::convert_to_liquidity_delta,
+        state::*,
+        util::{
+            to_timestamp_u64, transfer_from_owner_to_vault, transfer_from_vault_to_owner,
+            transfer_locked_position_token, verify_position_authority,
+        },
+    },
+    anchor_lang::prelude::*,
+    anchor_spl::{
+        associated_token::AssociatedToken,
+        token::{self, Mint, Token, TokenAccount},
+    },
+};
+
+#[derive(Accounts)]
+pub struct RepayLeveragedLiquidityPosition<'info> {
+    #[account(mut)]
+    pub liquidator: Signer<'info>,
+
+    /// CHECK: owner is the position's authority
+    pub owner: AccountInfo<'info>,
+
+    // The vaults are lent out while a flash loan is outstanding
+    #[account(
+        mut,
+        constraint = !globalpool.has_flash_loan() @ ErrorCode::FlashLoanInProgress
+    )]
+    pub globalpool: Box<Account<'info, Globalpool>>,
+
+    #[account(
+        mut,
+        has_one = globalpool,
+        has_one = liquidity_position,
+        seeds = [
+            b"trade_position".as_ref(),
+            position.position_mint.as_ref()
+        ],
+        bump = position.bump[0],
+    )]
+    pub position: Box<Account<'info, TradePosition>>,
+
+    #[account(
+        associated_token::mint = position.position_mint,
+        associated_token::authority = owner,
+    )]
+    pub position_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, has_one = globalpool)]
+    pub liquidity_position: Box<Account<'info, LiquidityPosition>>,
+
+    #[account(
+        mut,
+        associated_token::mint = liquidity_position.position_mint,
+        associated_token::authority = position,
+    )]
+    pub liquidity_position_token_account: Box<Account<'info, TokenAccount>>,
+
+    // Receives the unlocked liquidity position token
+    #[account(
+        init_if_needed,
+        payer = liquidator,
+        associated_token::mint = liquidity_position_mint,
+        associated_token::authority = owner,
+    )]
+    pub liquidity_position_owner_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(address = liquidity_position.position_mint)]
+    pub liquidity_position_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = globalpool.token_mint_a,
+        associated_token::authority = owner,
+    )]
+    pub token_owner_account_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = globalpool.token_mint_a,
+        associated_token::authority = liquidator,
+    )]
+    pub token_liquidator_account_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = globalpool.token_vault_a)]
+    pub token_vault_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = globalpool.token_mint_b,
+        associated_token::authority = owner,
+    )]
+    pub token_owner_account_b: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = globalpool.token_mint_b,
+        associated_token::authority = liquidator,
+    )]
+    pub token_liquidator_account_b: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = globalpool.token_vault_b)]
+    pub token_vault_b: Box<Account<'info, TokenAccount>>,
+
+    // Tick arrays of the liquidity position range
+    #[account(mut, has_one = globalpool)]
+    pub liquidity_tick_array_lower: AccountLoader<'info, TickArray>,
+
+    #[account(mut, has_one = globalpool)]
+    pub liquidity_tick_array_upper: AccountLoader<'info, TickArray>,
+
+    #[account(address = token::ID)]
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RepayLeveragedLiquidityPositionParams {
+    // Max tokens the repayer is willing to add if the liquidity position doesn't cover the loan
+    pub token_max_a: u64,
+    pub token_max_b: u64,
+}
+
+pub fn repay_leveraged_liquidity_position(
+    ctx: Context<RepayLeveragedLiquidityPosition>,
+    params: &RepayLeveragedLiquidityPositionParams,
+) -> Result<()> {
+    let is_liquidating = ctx.accounts.liquidator.key != ctx.accounts.owner.key;
+    verify_position_authority(&ctx.accounts.position_token_account, &ctx.accounts.owner)?;
+
+    if TradePosition::is_position_empty(&ctx.accounts.position) {
+        return Err(ErrorCode::InvalidRepaymentAmount.into());
+    }
+
+    // Liquidators can repay once the loan has matured or the position is below maintenance margin
+    if is_liquidating
+        && !ctx.accounts.position.has_matured()?
+        && is_leveraged_liquidity_position_healthy(
+            &ctx.accounts.position,
+            &ctx.accounts.liquidity_position,
+            &ctx.accounts.globalpool,
+        )?
+    {
+        return Err(ErrorCode::LeveragedPositionNotLiquidatable.into());
+    }
+
+    //
+    // 1. Withdraw all liquidity & fees of the locked liquidity position
+    //
+
+    let timestamp = to_timestamp_u64(Clock::get()?.unix_timestamp)?;
+    let liquidity = ctx.accounts.liquidity_position.liquidity;
+
+    let (withdrawn_a, withdrawn_b) = if liquidity > 0 {
+        let liquidity_delta = convert_to_liquidity_delta(liquidity, false)?;
+
+        let update = calculate_modify_liquidity(
+            &ctx.accounts.globalpool,
+            &ctx.accounts.liquidity_position,
+            &ctx.accounts.liquidity_tick_array_lower,
+            &ctx.accounts.liquidity_tick_array_upper,
+            liquidity_delta,
+            timestamp,
+        )?;
+
+        sync_modify_liquidity_values(
+            &mut ctx.accounts.globalpool,
+            &mut ctx.accounts.liquidity_position,
+            &ctx.accounts.liquidity_tick_array_lower,
+            &ctx.accounts.liquidity_tick_array_upper,
+            update,
+        )?;
+
+        calculate_liquidity_token_deltas(
+            ctx.accounts.liquidity_position.tick_lower_index,
+            ctx.accounts.liquidity_position.tick_upper_index,
+            ctx.accounts.globalpool.tick_current_index,
+            ctx.accounts.globalpool.sqrt_price,
+            liquidity_delta,
+        )?
+    } else {
+        (0, 0)
+    };
+
+    let available_a = withdrawn_a
+        .checked_add(ctx.accounts.liquidity_position.fee_owed_a)
+        .ok_or(ErrorCode::AmountCalcOverflow)?;
+    let available_b = withdrawn_b
+        .checked_add(ctx.accounts.liquidity_position.fee_owed_b)
+        .ok_or(ErrorCode::AmountCalcOverflow)?;
+
+    ctx.accounts.liquidity_position.reset_fees_owed();
+
+    //
+    // 2. Repay the loan at the current price. Any shortfall is paid by the repayer.
+    //
+
+    let liquidity_borrowed = ctx.accounts.position.liquidity_borrowed;
+    let loan_token_swapped = ctx.accounts.position.loan_token_swapped;
+
+    let (repay_delta_a, repay_delta_b) = calculate_liquidity_token_deltas(
+        ctx.accounts.position.tick_lower_index,
+        ctx.accounts.position.tick_upper_index,
+        ctx.accounts.globalpool.tick_current_index,
+        ctx.accounts.globalpool.sqrt_price,
+        liquidity_borrowed as i128,
+    )?;
+
+    let shortfall_a = repay_delta_a.saturating_sub(available_a);
+    let shortfall_b = repay_delta_b.saturating_sub(available_b);
+
+    if shortfall_a > params.token_max_a || shortfall_b > params.token_max_b {
+        return Err(ErrorCode::TokenMaxExceeded.into());
+    }
+
+    if shortfall_a > 0 {
+        transfer_from_owner_to_vault(
+            &ctx.accounts.liquidator,
+            &ctx.accounts.token_liquidator_account_a,
+            &ctx.accounts.token_vault_a,
+            &ctx.accounts.token_program,
+            shortfall_a,
+        )?;
+    }
+
+    if shortfall_b > 0 {
+        transfer_from_owner_to_vault(
+            &ctx.accounts.liquidator,
+            &ctx.accounts.token_liquidator_account_b,
+            &ctx.accounts.token_vault_b,
+            &ctx.accounts.token_program,
+            shortfall_b,
+        )?;
+    }
+
+    ctx.accounts
+        .position
+        .update_liquidity_swapped(-(loan_token_swapped as i64), 0)?;
+
+    ctx.accounts.globalpool.update_after_loan(
+        -(liquidity_borrowed as i128),
+        0,
+        false, // doesn't matter since interest_amount = 0 (repaying, not borrowing)
+    );
+
+    //
+    // 3. Return the leftover tokens to the owner.
+    //    If liquidating, the liquidator receives 10% of the leftover tokens.
+    //
+
+    let leftover_a = available_a.saturating_sub(repay_delta_a);
+    let leftover_b = available_b.saturating_sub(repay_delta_b);
+
+    let (liquidator_a, liquidator_b) = if is_liquidating {
+        (leftover_a / 10, leftover_b / 10)
+    } else {
+        (0, 0)
+    };
+
+    for (amount, token_vault, token_account) in [
+        (
+            liquidator_a,
+            &ctx.accounts.token_vault_a,
+            &ctx.accounts.token_liquidator_account_a,
+        ),
+        (
+            leftover_a - liquidator_a,
+            &ctx.accounts.token_vault_a,
+            &ctx.accounts.token_owner_account_a,
+        ),
+        (
+            liquidator_b,
+            &ctx.accounts.token_vault_b,
+            &ctx.accounts.token_liquidator_account_b,
+        ),
+        (
+            leftover_b - liquidator_b,
+            &ctx.accounts.token_vault_b,
+            &ctx.accounts.token_owner_account_b,
+        ),
+    ] {
+        if amount > 0 {
+            transfer_from_vault_to_owner(
+                &ctx.accounts.globalpool,
+                token_vault,
+                token_account,
+                &ctx.accounts.token_program,
+                amount,
+            )?;
+        }
+    }
+
+    //
+    // 4. Unlock the (now empty) liquidity position, so the owner can close it
+    //
+
+    transfer_locked_position_token(
+        &ctx.accounts.position,
+        &ctx.accounts.liquidity_position_token_account,
+        &ctx.accounts.liquidity_position_owner_account,
+        &ctx.accounts.token_program,
+    )?;
+
+    let globalpool = &ctx.accounts.globalpool;
+    emit!(LeveragedLiquidityPositionRepaid {
+        globalpool: globalpool.key(),
+        position: ctx.accounts.position.key(),
+        liquidity_position: ctx.accounts.liquidity_position.key(),
+        owner: ctx.accounts.owner.key(),
+        liquidator: ctx.accounts.liquidator.key(),
+        liquidity_repaid: liquidity_borrowed,
+        liquidity_withdrawn: liquidity,
+        repay_amount_a: repay_delta_a,
+        repay_amount_b: repay_delta_b,
+        shortfall_amount_a: shortfall_a,
+        shortfall_amount_b: shortfall_b,
+        leftover_to_owner_a: leftover_a - liquidator_a,
+        leftover_to_owner_b: leftover_b - liquidator_b,
+        leftover_to_liquidator_a: liquidator_a,
+        leftover_to_liquidator_b: liquidator_b,
+        sqrt_price: globalpool.sqrt_price,
+        tick_current_index: globalpool.tick_current_index,
+        fee_growth_global_a: globalpool.fee_growth_global_a,
+        fee_growth_global_b: globalpool.fee_growth_global_b,
+    });
+
+    Ok(())
+}
@@ -1,18 +1,35 @@
 use {
     crate::{
         errors,
+        events::{TradePositionLiquidated, TradePositionRepaid},
         manager::{
-            liquidity_manager::calculate_liquidity_token_deltas,
+            liquidity_manager::{
+                calculate_liquidity_token_deltas, calculate_modify_liquidity,
+                sync_modify_liquidity_values,
+            },
+            loan_manager::{
+                calculate_trade_position_repayment, is_perpetual_position_healthy,
+                next_funding_index, pay_funding, read_oracle_sqrt_price,
+                LIQUIDITY_COLLATERAL_REPAY_ACCOUNTS_LEN, PERPETUAL_HEALTH_ACCOUNTS_LEN,
+            },
+            margin_manager::{calculate_margin_draw, MARGIN_BACKED_REPAY_ACCOUNTS_LEN},
             swap_manager::execute_jupiter_swap_for_globalpool,
         },
+        math::convert_to_liquidity_delta,
         state::*,
-        util::{to_timestamp_u64, transfer_from_vault_to_owner, verify_position_authority},
+        util::{
+            get_mint_decimals, load_native_sol_account, load_position_operator,
+            load_token_owner_account, to_timestamp_u64, transfer_from_margin_vault_to_owner,
+            transfer_from_vault_to_owner, verify_position_authority,
+            verify_position_authority_or_operator,
+        },
     },
     anchor_lang::prelude::*,
     anchor_spl::{
         associated_token::AssociatedToken,
-        token::{self, Mint, Token, TokenAccount},
+        token::{self, Token, TokenAccount},
     },
+    spl_associated_token_account::get_associated_token_address,
 };
 
 #[derive(Accounts)]
@@ -24,7 +41,13 @@ pub struct RepayTradePosition<'info> {
     /// CHECK: owner is the position's authority
     pub owner: AccountInfo<'info>,
 
-    #[account(mut)]
+    // Hooks are only called by `swap` and the liquidity instructions, and the vaults are lent
+    // out while a flash loan is outstanding
+    #[account(
+        mut,
+        constraint = globalpool.get_hook_program().is_none() @ errors::ErrorCode::HookUnsupported,
+        constraint = !globalpool.has_flash_loan() @ errors::ErrorCode::FlashLoanInProgress
+    )]
     pub globalpool: Box<Account<'info, Globalpool>>,
 
     #[account(mut, has_one = globalpool)]
@@ -36,45 +59,43 @@ pub struct RepayTradePosition<'info> {
 	)]
     pub position_token_account: Box<Account<'info, TokenAccount>>,
 
-    #[account(
-        mut,
-        associated_token::mint = globalpool.token_mint_a,
-        associated_token::authority = owner,
-    )]
-    pub token_owner_account_a: Box<Account<'info, TokenAccount>>,
+    /// CHECK: mint & owner are checked in the handler, as it may be the temporary wSOL account
+    #[account(mut)]
+    pub token_owner_account_a: UncheckedAccount<'info>,
 
-    #[account(
-        mut,
-        associated_token::mint = globalpool.token_mint_a,
-        associated_token::authority = liquidator,
-    )]
-    pub token_liquidator_account_a: Box<Account<'info, TokenAccount>>,
+    /// CHECK: mint & owner are checked in the handler, once the token program is checked
+    #[account(mut)]
+    pub token_liquidator_account_a: UncheckedAccount<'info>,
 
+    /// CHECK: address is checked, loaded in the handler once the token program is checked
     #[account(mut, address = globalpool.token_vault_a)]
-    pub token_vault_a: Box<Account<'info, TokenAccount>>,
-
-    #[account(address = globalpool.token_mint_a)]
-    pub token_mint_a: Box<Account<'info, Mint>>,
+    pub token_vault_a: UncheckedAccount<'info>,
 
+    /// CHECK: address is checked
     #[account(
-        mut,
-        associated_token::mint = globalpool.token_mint_b,
-        associated_token::authority = owner,
+        address = globalpool.token_mint_a,
+        constraint = *token_mint_a.owner == token::ID @ errors::ErrorCode::Token2022MintUnsupported
     )]
-    pub token_owner_account_b: Box<Account<'info, TokenAccount>>,
+    pub token_mint_a: UncheckedAccount<'info>,
 
-    #[account(
-        mut,
-        associated_token::mint = globalpool.token_mint_b,
-        associated_token::authority = liquidator,
-    )]
-    pub token_liquidator_account_b: Box<Account<'info, TokenAccount>>,
+    /// CHECK: mint & owner are checked in the handler, as it may be the temporary wSOL account
+    #[account(mut)]
+    pub token_owner_account_b: UncheckedAccount<'info>,
 
+    /// CHECK: mint & owner are checked in the handler, once the token program is checked
+    #[account(mut)]
+    pub token_liquidator_account_b: UncheckedAccount<'info>,
+
+    /// CHECK: address is checked, loaded in the handler once the token program is checked
     #[account(mut, address = globalpool.token_vault_b)]
-    pub token_vault_b: Box<Account<'info, TokenAccount>>,
+    pub token_vault_b: UncheckedAccount<'info>,
 
-    #[account(address = globalpool.token_mint_b)]
-    pub token_mint_b: Box<Account<'info, Mint>>,
+    /// CHECK: address is checked
+    #[account(
+        address = globalpool.token_mint_b,
+        constraint = *token_mint_b.owner == token::ID @ errors::ErrorCode::Token2022MintUnsupported
+    )]
+    pub token_mint_b: UncheckedAccount<'info>,
 
     #[account(address = token::ID)]
     pub token_program: Program<'info, Token>,
@@ -84,6 +105,12 @@ pub struct RepayTradePosition<'info> {
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct RepayTradePositionParams {
+    // An owner repaying in native SOL mode passes the System Program as the first of
+    // `remaining_accounts`. An operator repaying for the owner then passes its PositionOperator.
+    // Liquidating a perpetual position then passes the oracle accounts pricing its health (see
+    // `PERPETUAL_HEALTH_ACCOUNTS_LEN`). If the position is backed by a margin account, or
+    // collateralized by a liquidity position, their accounts come next (see
+    // `MARGIN_BACKED_REPAY_ACCOUNTS_LEN` and `LIQUIDITY_COLLATERAL_REPAY_ACCOUNTS_LEN`)
     pub swap_instruction_data: Vec<u8>, // Jupiter swap data
 }
 
@@ -91,12 +118,115 @@ pub fn repay_trade_position(
     ctx: Context<RepayTradePosition>,
     params: &RepayTradePositionParams,
 ) -> Result<()> {
-    let is_liquidating = ctx.accounts.liquidator.key != ctx.accounts.owner.key;
-    verify_position_authority(&ctx.accounts.position_token_account, &ctx.accounts.owner)?;
+    // In native SOL mode, the profit returned in SOL is unwrapped to the owner, who must sign
+    let (native_sol_account, remaining_accounts) = load_native_sol_account(
+        ctx.remaining_accounts,
+        &ctx.accounts.owner,
+        [
+            &ctx.accounts.token_mint_a.to_account_info(),
+            &ctx.accounts.token_mint_b.to_account_info(),
+        ],
+        [
+            &ctx.accounts.token_owner_account_a,
+            &ctx.accounts.token_owner_account_b,
+        ],
+        [
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+        ],
+    )?;
+    if let Some(native_sol_account) = native_sol_account.as_ref() {
+        native_sol_account.open(0)?;
+    }
 
-    // if is_liquidating, then make sure the position has matured
-    if is_liquidating {
-        let current_timestamp = to_timestamp_u64(Clock::get()?.unix_timestamp).unwrap();
+    let token_owner_account_a = load_token_owner_account(
+        &ctx.accounts.token_owner_account_a,
+        &ctx.accounts.globalpool.token_mint_a,
+        Some(ctx.accounts.owner.key),
+    )?;
+    let token_owner_account_b = load_token_owner_account(
+        &ctx.accounts.token_owner_account_b,
+        &ctx.accounts.globalpool.token_mint_b,
+        Some(ctx.accounts.owner.key),
+    )?;
+    let token_liquidator_account_a = load_token_owner_account(
+        &ctx.accounts.token_liquidator_account_a,
+        &ctx.accounts.globalpool.token_mint_a,
+        Some(ctx.accounts.liquidator.key),
+    )?;
+    let token_liquidator_account_b = load_token_owner_account(
+        &ctx.accounts.token_liquidator_account_b,
+        &ctx.accounts.globalpool.token_mint_b,
+        Some(ctx.accounts.liquidator.key),
+    )?;
+    let mut token_vault_a = Box::new(Account::<TokenAccount>::try_from(
+        &ctx.accounts.token_vault_a,
+    )?);
+    let mut token_vault_b = Box::new(Account::<TokenAccount>::try_from(
+        &ctx.accounts.token_vault_b,
+    )?);
+
+    // An operator repays on behalf of the owner, so it neither needs the owner's signature nor
+    // earns the liquidation reward
+    let (position_operator, remaining_accounts) = load_position_operator(remaining_accounts)?;
+    let is_liquidating = match position_operator.as_deref() {
+        Some(position_operator) => {
+            verify_position_authority_or_operator(
+                &ctx.accounts.position_token_account,
+                &ctx.accounts.liquidator,
+                &ctx.accounts.position.key(),
+                Some(position_operator),
+                OPERATOR_REPAY_TRADE,
+            )?;
+            false
+        }
+        None => {
+            verify_position_authority(&ctx.accounts.position_token_account, &ctx.accounts.owner)?;
+            ctx.accounts.liquidator.key != ctx.accounts.owner.key
+        }
+    };
+
+    // Leveraged liquidity positions are repaid from their locked liquidity position
+    // (see `repay_leveraged_liquidity_position`)
+    if ctx.accounts.position.is_leveraged_liquidity_position() {
+        return Err(errors::ErrorCode::InvalidTradePositionType.into());
+    }
+
+    // Perpetual positions pay their funding (out of the collateral) before being valued
+    let clock = Clock::get()?;
+    let current_timestamp = to_timestamp_u64(clock.unix_timestamp)?;
+    let funding_index = next_funding_index(&ctx.accounts.globalpool, current_timestamp)?;
+    ctx.accounts
+        .globalpool
+        .update_funding_index(funding_index, current_timestamp);
+    pay_funding(&mut ctx.accounts.globalpool, &mut ctx.accounts.position)?;
+
+    // if is_liquidating, then make sure the position has matured (or, if perpetual, is unhealthy
+    // at the oracle price)
+    let remaining_accounts = if is_liquidating && ctx.accounts.position.is_perpetual {
+        if remaining_accounts.len() < PERPETUAL_HEALTH_ACCOUNTS_LEN {
+            return Err(errors::ErrorCode::InvalidPerpetualHealthAccounts.into());
+        }
+        let (price_accounts, remaining_accounts) =
+            remaining_accounts.split_at(PERPETUAL_HEALTH_ACCOUNTS_LEN);
+        let oracle_sqrt_price = read_oracle_sqrt_price(
+            &ctx.accounts.globalpool,
+            price_accounts,
+            clock.unix_timestamp,
+        )?;
+        require!(
+            !is_perpetual_position_healthy(
+                &ctx.accounts.position,
+                &ctx.accounts.globalpool,
+                oracle_sqrt_price
+            )?,
+            errors::ErrorCode::PerpetualPositionHealthy
+        );
+        remaining_accounts
+    } else {
+        remaining_accounts
+    };
+    if is_liquidating && !ctx.accounts.position.is_perpetual {
         let position_maturity_timestamp = ctx
             .accounts
             .position
@@ -109,6 +239,164 @@ pub fn repay_trade_position(
         );
     }
 
+    //
+    // Positions collateralized by a liquidity position withdraw the pledged liquidity, which then
+    // counts as collateral, and release the liquidity position token back to the owner.
+    //
+
+    let is_margin_backed = ctx.accounts.position.is_margin_backed();
+
+    let (margin_accounts, remaining_accounts) = if is_margin_backed {
+        if remaining_accounts.len() < MARGIN_BACKED_REPAY_ACCOUNTS_LEN {
+            return Err(errors::ErrorCode::InvalidMarginHealthAccounts.into());
+        }
+        remaining_accounts.split_at(MARGIN_BACKED_REPAY_ACCOUNTS_LEN)
+    } else {
+        remaining_accounts.split_at(0)
+    };
+
+    let has_liquidity_collateral = ctx.accounts.position.has_liquidity_collateral();
+
+    let (liquidity_collateral_accounts, swap_accounts) = if has_liquidity_collateral {
+        if remaining_accounts.len() < LIQUIDITY_COLLATERAL_REPAY_ACCOUNTS_LEN {
+            return Err(errors::ErrorCode::InvalidLiquidityCollateralAccounts.into());
+        }
+        remaining_accounts.split_at(LIQUIDITY_COLLATERAL_REPAY_ACCOUNTS_LEN)
+    } else {
+        remaining_accounts.split_at(0)
+    };
+
+    let (liquidity_collateral_delta_a, liquidity_collateral_delta_b) = if has_liquidity_collateral {
+        let mut liquidity_position =
+            Account::<LiquidityPosition>::try_from(&liquidity_collateral_accounts[0])?;
+        let position_token_escrow =
+            Account::<TokenAccount>::try_from(&liquidity_collateral_accounts[1])?;
+        let position_token_owner_account =
+            Account::<TokenAccount>::try_from(&liquidity_collateral_accounts[2])?;
+        let tick_array_lower =
+            AccountLoader::<TickArray>::try_from(&liquidity_collateral_accounts[3])?;
+        let tick_array_upper =
+            AccountLoader::<TickArray>::try_from(&liquidity_collateral_accounts[4])?;
+
+        let globalpool_key = ctx.accounts.globalpool.key();
+        if liquidity_position.key() != ctx.accounts.position.liquidity_position
+            || position_token_escrow.mint != liquidity_position.position_mint
+            || position_token_escrow.owner != globalpool_key
+            || position_token_owner_account.mint != liquidity_position.position_mint
+            || position_token_owner_account.owner != ctx.accounts.owner.key()
+            || tick_array_lower.load()?.globalpool != globalpool_key
+            || tick_array_upper.load()?.globalpool != globalpool_key
+        {
+            return Err(errors::ErrorCode::InvalidLiquidityCollateralAccounts.into());
+        }
+
+        let liquidity_delta =
+            convert_to_liquidity_delta(ctx.accounts.position.liquidity_collateral, false)?;
+
+        let update = calculate_modify_liquidity(
+            &ctx.accounts.globalpool,
+            &liquidity_position,
+            &tick_array_lower,
+            &tick_array_upper,
+            liquidity_delta,
+            current_timestamp,
+        )?;
+
+        sync_modify_liquidity_values(
+            &mut ctx.accounts.globalpool,
+            &mut liquidity_position,
+            &tick_array_lower,
+            &tick_array_upper,
+            update,
+        )?;
+
+        let liquidity_collateral_deltas = calculate_liquidity_token_deltas(
+            liquidity_position.tick_lower_index,
+            liquidity_position.tick_upper_index,
+            ctx.accounts.globalpool.tick_current_index,
+            ctx.accounts.globalpool.sqrt_price,
+            liquidity_delta,
+        )?;
+
+        liquidity_position.exit(&crate::ID)?;
+
+        transfer_from_vault_to_owner(
+            &ctx.accounts.globalpool,
+            &position_token_escrow,
+            &position_token_owner_account,
+            &ctx.accounts.token_program,
+            1,
+        )?;
+
+        ctx.accounts.position.release_liquidity_collateral();
+
+        liquidity_collateral_deltas
+    } else {
+        (0, 0)
+    };
+
+    //
+    // Positions backed by a margin account draw on its balances to settle the loan, and return
+    // whatever is left to it below.
+    //
+
+    let mut margin_backing = if is_margin_backed {
+        let margin_account = Account::<MarginAccount>::try_from(&margin_accounts[0])?;
+        let margin_vault_a = Account::<TokenAccount>::try_from(&margin_accounts[1])?;
+        let margin_vault_b = Account::<TokenAccount>::try_from(&margin_accounts[2])?;
+
+        if margin_account.key() != ctx.accounts.position.margin_account
+            || margin_vault_a.key()
+                != get_associated_token_address(
+                    &margin_account.key(),
+                    &ctx.accounts.globalpool.token_mint_a,
+                )
+            || margin_vault_b.key()
+                != get_associated_token_address(
+                    &margin_account.key(),
+                    &ctx.accounts.globalpool.token_mint_b,
+                )
+        {
+            return Err(errors::ErrorCode::InvalidMarginHealthAccounts.into());
+        }
+
+        Some((margin_account, margin_vault_a, margin_vault_b))
+    } else {
+        None
+    };
+
+    let (margin_delta_a, margin_delta_b) = match margin_backing.as_mut() {
+        Some((margin_account, margin_vault_a, margin_vault_b)) => {
+            let (margin_delta_a, margin_delta_b) = calculate_margin_draw(
+                &ctx.accounts.position,
+                &ctx.accounts.globalpool,
+                margin_account,
+            )?;
+
+            for (margin_vault, token_vault, margin_delta) in [
+                (&*margin_vault_a, &token_vault_a, margin_delta_a),
+                (&*margin_vault_b, &token_vault_b, margin_delta_b),
+            ] {
+                if margin_delta > 0 {
+                    margin_account.withdraw_collateral(&margin_vault.mint, margin_delta)?;
+                    transfer_from_margin_vault_to_owner(
+                        margin_account,
+                        margin_vault,
+                        token_vault,
+                        &ctx.accounts.token_program,
+                        margin_delta,
+                    )?;
+                }
+            }
+
+            token_vault_a.reload()?;
+            token_vault_b.reload()?;
+
+            (margin_delta_a, margin_delta_b)
+        }
+        None => (0, 0),
+    };
+
     //
     // WARNING:
     //
@@ -164,52 +452,33 @@ pub fn repay_trade_position(
     let is_borrow_a = ctx.accounts.position.is_borrow_a(&ctx.accounts.globalpool);
 
     let liquidity_borrowed = ctx.accounts.position.liquidity_borrowed;
-    let collateral_amount = ctx.accounts.position.collateral_amount;
     let trade_token_amount = ctx.accounts.position.trade_token_amount;
     let loan_token_swapped = ctx.accounts.position.loan_token_swapped;
     let tick_lower_index = ctx.accounts.position.tick_lower_index;
     let tick_upper_index = ctx.accounts.position.tick_upper_index;
-    let tick_current_index = ctx.accounts.globalpool.tick_current_index;
-    let sqrt_current_price = ctx.accounts.globalpool.sqrt_price;
-
-    let (repay_delta_a, repay_delta_b) = calculate_liquidity_token_deltas(
-        tick_lower_index,
-        tick_upper_index,
-        tick_current_index,
-        sqrt_current_price,
-        liquidity_borrowed as i128,
-    )?;
-
-    // This assumes the position has swapped all of loan token to the opposite (trade) token.
-    let (borrowed_delta_a, borrowed_delta_b) = if is_borrow_a {
-        (loan_token_swapped, 0)
-    } else {
-        (0, loan_token_swapped)
-    };
 
-    // This assumes the position has swapped all of loan token to the opposite (trade) token.
-    let (mut available_delta_a, mut available_delta_b) = if is_borrow_a {
-        (0, trade_token_amount + collateral_amount)
-    } else {
-        (trade_token_amount + collateral_amount, 0)
-    };
-
-    let swap_needed_delta_a =
-        std::cmp::max(0, repay_delta_a as i64 - available_delta_a as i64) as u64;
-    let swap_needed_delta_b =
-        std::cmp::max(0, repay_delta_b as i64 - available_delta_b as i64) as u64;
+    // Withdrawn liquidity collateral and drawn margin collateral are held in both tokens
+    let repayment = calculate_trade_position_repayment(
+        &ctx.accounts.position,
+        &ctx.accounts.globalpool,
+        liquidity_collateral_delta_a
+            .checked_add(margin_delta_a)
+            .ok_or(errors::ErrorCode::AmountCalcOverflow)?,
+        liquidity_collateral_delta_b
+            .checked_add(margin_delta_b)
+            .ok_or(errors::ErrorCode::AmountCalcOverflow)?,
+    )?;
 
-    msg!("repay_delta_a: {}", repay_delta_a);
-    msg!("borrowed_delta_a: {}", borrowed_delta_a);
-    msg!("available_delta_a: {}", available_delta_a);
-    msg!("swap_needed_delta_a: {}", swap_needed_delta_a);
-    msg!("repay_delta_b: {}", repay_delta_b);
-    msg!("borrowed_delta_b: {}", borrowed_delta_b);
-    msg!("available_delta_b: {}", available_delta_b);
-    msg!("swap_needed_delta_b: {}", swap_needed_delta_b);
+    let repay_delta_a = repayment.repay_amount_a;
+    let repay_delta_b = repayment.repay_amount_b;
+    let mut available_delta_a = repayment.available_amount_a;
+    let mut available_delta_b = repayment.available_amount_b;
+    let swap_needed_delta_a = repayment.swap_amount_needed_a;
+    let swap_needed_delta_b = repayment.swap_amount_needed_b;
 
     // If both values are non-zero, then the position is undercollateralized.
-    // This is a logic issue in the program and should NOT happen at all.
+    // This is a logic issue in the program and should NOT happen at all, except for positions
+    // backed by a margin account short of balance.
     //
     // For readers: Why is it that only one of the swap_needed_delta_a/b can be non-zero?
     // (1) When the position is in loss, LP's expected token is in collateral token (also the trader's swapped token)
@@ -227,31 +496,26 @@ pub fn repay_trade_position(
 
     // Should swap exact out if one of the above conditions are met
     if swap_needed_delta_a > 0 || swap_needed_delta_b > 0 {
-        // Trade token as in the token that was swapped to from loaned token
-        // e.g. loaned USDC, swapped to SOL for long position => SOL is trade token.
+        // Usually, the trade token (swapped to from the loaned token) is swapped back to the loaned
+        // token, e.g. loaned USDC, swapped to SOL for long position => SOL is trade token.
         // Trade token is also the collateral token, due to the way AMM converts token for LPs.
-        let trade_token_before_balance = if is_borrow_a {
-            ctx.accounts.token_vault_b.amount
-        } else {
-            ctx.accounts.token_vault_a.amount
-        };
-
+        // Positions backed by a margin account may also swap drawn loan token to collateral token.
         if swap_needed_delta_a > 0 {
             // Need more A. Swap from token B to token A
-            swap_in_before_balance = ctx.accounts.token_vault_b.amount;
-            swap_out_before_balance = ctx.accounts.token_vault_a.amount;
+            swap_in_before_balance = token_vault_b.amount;
+            swap_out_before_balance = token_vault_a.amount;
             swap_out_needed = swap_needed_delta_a;
         } else if swap_needed_delta_b > 0 {
             // Need more B. Swap from token A to token B
-            swap_in_before_balance = ctx.accounts.token_vault_a.amount;
-            swap_out_before_balance = ctx.accounts.token_vault_b.amount;
+            swap_in_before_balance = token_vault_a.amount;
+            swap_out_before_balance = token_vault_b.amount;
             swap_out_needed = swap_needed_delta_b;
         }
 
         // 1. Swap
         execute_jupiter_swap_for_globalpool(
             &ctx.accounts.globalpool,
-            &ctx.remaining_accounts,
+            swap_accounts,
             &params.swap_instruction_data,
         )?;
 
@@ -260,39 +524,19 @@ pub fn repay_trade_position(
         // (ii) NEED more check on swap_in_token_vault...
 
         // Reload vaults for get updated token balances
-        ctx.accounts.token_vault_a.reload()?;
-        ctx.accounts.token_vault_b.reload()?;
-
-        let trade_token_after_balance = if is_borrow_a {
-            ctx.accounts.token_vault_b.amount
-        } else {
-            ctx.accounts.token_vault_a.amount
-        };
+        token_vault_a.reload()?;
+        token_vault_b.reload()?;
 
         if swap_needed_delta_a > 0 {
             // Need more A. Swap from token B to token A
-            swap_in_after_balance = ctx.accounts.token_vault_b.amount;
-            swap_out_after_balance = ctx.accounts.token_vault_a.amount;
+            swap_in_after_balance = token_vault_b.amount;
+            swap_out_after_balance = token_vault_a.amount;
         } else if swap_needed_delta_b > 0 {
             // Need more B. Swap from token A to token B
-            swap_in_after_balance = ctx.accounts.token_vault_a.amount;
-            swap_out_after_balance = ctx.accounts.token_vault_b.amount;
+            swap_in_after_balance = token_vault_a.amount;
+            swap_out_after_balance = token_vault_b.amount;
         }
 
-        msg!("out_needed:      {}", swap_out_needed);
-        msg!("swap_in before:  {}", swap_in_before_balance);
-        msg!("swap_in after:   {}", swap_in_after_balance);
-        msg!(
-            "diff:            {}",
-            swap_in_before_balance - swap_in_after_balance
-        );
-        msg!("swap_out before: {}", swap_out_before_balance);
-        msg!("swap_out after:  {}", swap_out_after_balance);
-        msg!(
-            "diff:            {}",
-            swap_out_after_balance - swap_out_before_balance
-        );
-
         // Balances should increase & decrease correctly in direction (magnitude is not checked here)
         require!(
             (swap_in_after_balance < swap_in_before_balance)
@@ -312,19 +556,23 @@ pub fn repay_trade_position(
         //     errors::ErrorCode::InvalidLoanTradeSwapResult
         // );
 
-        // Trade token should decrease by at most `trade_token_amount + collateral_amount` (if used for swap)
-        // which is also used above in `available_delta_a` or `available_delta_b`
+        let swap_in_amount = swap_in_before_balance - swap_in_after_balance;
+
+        // Swap in token should decrease by at most its available amount (e.g. `trade_token_amount
+        // + collateral_amount` for the trade token), used above in `available_delta_a` or
+        // `available_delta_b`
+        let swap_in_available = if swap_needed_delta_a > 0 {
+            available_delta_b
+        } else {
+            available_delta_a
+        };
         require!(
-            // collateral vault should decrease by at most
-            trade_token_after_balance + trade_token_amount + collateral_amount
-                >= trade_token_before_balance,
+            swap_in_amount <= swap_in_available,
             errors::ErrorCode::InvalidLoanTradeSwapResult
         );
 
-        let swap_in_amount = swap_in_before_balance - swap_in_after_balance;
-
         // Update available token amounts to reflect the swap_in sent & swap_out received
-        if is_borrow_a {
+        if swap_needed_delta_a > 0 {
             // Swapped from token B to token A
             available_delta_b -= swap_in_amount;
             available_delta_a += swap_out_needed;
@@ -335,7 +583,13 @@ pub fn repay_trade_position(
         }
     }
 
-    // Only one of these tokens will be > 0. Otherwise, there's a logic issue in the program!
+    // The swap may not take the tokens needed to repay the other token
+    if available_delta_a < repay_delta_a || available_delta_b < repay_delta_b {
+        return Err(errors::ErrorCode::InvalidRepaymentAmount.into());
+    }
+
+    // Only one of these tokens will be > 0, unless the position was collateralized by a liquidity
+    // position. Otherwise, there's a logic issue in the program!
     let mut leftover_token_a: u64 = 0;
     let mut leftover_token_b: u64 = 0;
 
@@ -347,13 +601,6 @@ pub fn repay_trade_position(
         leftover_token_b = available_delta_b - repay_delta_b;
     }
 
-    msg!("available_delta_a: {}", available_delta_a);
-    msg!("repay_delta_a:     {}", repay_delta_a);
-    msg!("left_over_token_a: {}", leftover_token_a);
-    msg!("available_delta_b: {}", available_delta_b);
-    msg!("repay_delta_b:     {}", repay_delta_b);
-    msg!("left_over_token_b: {}", leftover_token_b);
-
     ctx.accounts
         .position
         .update_liquidity_swapped(-(loan_token_swapped as i64), -(trade_token_amount as i64))?;
@@ -390,20 +637,27 @@ pub fn repay_trade_position(
         collateral_to_return -= collateral_to_liquidator;
     }
 
+    // Positions backed by a margin account return the leftover collateral & profit to it
+    let (collateral_to_position, profit_to_owner) = if is_margin_backed {
+        (0, 0)
+    } else {
+        (collateral_to_return, profit_to_return)
+    };
+
     ctx.accounts
         .position
-        .update_collateral_amount(collateral_to_return);
+        .update_collateral_amount(collateral_to_position);
 
     if collateral_to_liquidator > 0 {
         let token_vault;
         let liquidator_token_account;
 
         if is_borrow_a {
-            token_vault = &ctx.accounts.token_vault_b;
-            liquidator_token_account = &ctx.accounts.token_liquidator_account_b;
+            token_vault = &token_vault_b;
+            liquidator_token_account = &token_liquidator_account_b;
         } else {
-            token_vault = &ctx.accounts.token_vault_a;
-            liquidator_token_account = &ctx.accounts.token_liquidator_account_a;
+            token_vault = &token_vault_a;
+            liquidator_token_account = &token_liquidator_account_a;
         }
 
         transfer_from_vault_to_owner(
@@ -415,16 +669,56 @@ pub fn repay_trade_position(
         )?;
     }
 
-    if profit_to_return > 0 {
+    if let Some((mut margin_account, margin_vault_a, margin_vault_b)) = margin_backing {
+        let (margin_return_a, margin_return_b) = if is_borrow_a {
+            (profit_to_return, collateral_to_return)
+        } else {
+            (collateral_to_return, profit_to_return)
+        };
+
+        for (token_vault, margin_vault, token_mint, margin_return) in [
+            (
+                &token_vault_a,
+                &margin_vault_a,
+                &ctx.accounts.token_mint_a,
+                margin_return_a,
+            ),
+            (
+                &token_vault_b,
+                &margin_vault_b,
+                &ctx.accounts.token_mint_b,
+                margin_return_b,
+            ),
+        ] {
+            if margin_return > 0 {
+                margin_account.deposit_collateral(
+                    token_mint.key(),
+                    get_mint_decimals(token_mint)?,
+                    margin_return,
+                )?;
+                transfer_from_vault_to_owner(
+                    &ctx.accounts.globalpool,
+                    token_vault,
+                    margin_vault,
+                    &ctx.accounts.token_program,
+                    margin_return,
+                )?;
+            }
+        }
+
+        margin_account.exit(&crate::ID)?;
+    }
+
+    if profit_to_owner > 0 {
         let token_vault;
         let owner_token_account;
 
         if is_borrow_a {
-            token_vault = &ctx.accounts.token_vault_a;
-            owner_token_account = &ctx.accounts.token_owner_account_a;
+            token_vault = &token_vault_a;
+            owner_token_account = &token_owner_account_a;
         } else {
-            token_vault = &ctx.accounts.token_vault_b;
-            owner_token_account = &ctx.accounts.token_owner_account_b;
+            token_vault = &token_vault_b;
+            owner_token_account = &token_owner_account_b;
         }
 
         transfer_from_vault_to_owner(
@@ -432,9 +726,52 @@ pub fn repay_trade_position(
             token_vault,
             owner_token_account,
             &ctx.accounts.token_program,
-            profit_to_return,
+            profit_to_owner,
         )?;
     }
 
+    if let Some(native_sol_account) = native_sol_account.as_ref() {
+        native_sol_account.close()?;
+    }
+
+    let globalpool = &ctx.accounts.globalpool;
+    if is_liquidating {
+        emit!(TradePositionLiquidated {
+            globalpool: globalpool.key(),
+            position: ctx.accounts.position.key(),
+            owner: ctx.accounts.owner.key(),
+            liquidator: ctx.accounts.liquidator.key(),
+            tick_lower_index,
+            tick_upper_index,
+            liquidity_repaid: liquidity_borrowed,
+            repay_amount_a: repay_delta_a,
+            repay_amount_b: repay_delta_b,
+            collateral_returned: collateral_to_return,
+            collateral_to_liquidator,
+            profit_returned: profit_to_return,
+            sqrt_price: globalpool.sqrt_price,
+            tick_current_index: globalpool.tick_current_index,
+            fee_growth_global_a: globalpool.fee_growth_global_a,
+            fee_growth_global_b: globalpool.fee_growth_global_b,
+        });
+    } else {
+        emit!(TradePositionRepaid {
+            globalpool: globalpool.key(),
+            position: ctx.accounts.position.key(),
+            owner: ctx.accounts.owner.key(),
+            tick_lower_index,
+            tick_upper_index,
+            liquidity_repaid: liquidity_borrowed,
+            repay_amount_a: repay_delta_a,
+            repay_amount_b: repay_delta_b,
+            collateral_returned: collateral_to_return,
+            profit_returned: profit_to_return,
+            sqrt_price: globalpool.sqrt_price,
+            tick_current_index: globalpool.tick_current_index,
+            fee_growth_global_a: globalpool.fee_growth_global_a,
+            fee_growth_global_b: globalpool.fee_growth_global_b,
+        });
+    }
+
     Ok(())
 }
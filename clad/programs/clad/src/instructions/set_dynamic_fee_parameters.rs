@@ -0,0 +1,32 @@
+use {crate::state::*, anchor_lang::prelude::*};
+
+#[derive(Accounts)]
+pub struct SetDynamicFeeParameters<'info> {
+    #[account(mut)]
+    pub globalpool: Account<'info, Globalpool>,
+
+    #[account(address = globalpool.fee_authority)]
+    pub fee_authority: Signer<'info>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SetDynamicFeeParametersParams {
+    pub variable_fee_control: u32,
+    pub max_volatility_accumulator: u32,
+    pub filter_period: u16,
+    pub decay_period: u16,
+    pub reduction_factor: u16,
+}
+
+pub fn set_dynamic_fee_parameters(
+    ctx: Context<SetDynamicFeeParameters>,
+    params: &SetDynamicFeeParametersParams,
+) -> Result<()> {
+    ctx.accounts.globalpool.update_dynamic_fee_parameters(
+        params.variable_fee_control,
+        params.max_volatility_accumulator,
+        params.filter_period,
+        params.decay_period,
+        params.reduction_factor,
+    )
+}
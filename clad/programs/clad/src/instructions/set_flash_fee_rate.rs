@@ -0,0 +1,16 @@
+use {crate::state::*, anchor_lang::prelude::*};
+
+#[derive(Accounts)]
+pub struct SetFlashFeeRate<'info> {
+    #[account(mut)]
+    pub globalpool: Account<'info, Globalpool>,
+
+    #[account(address = globalpool.fee_authority)]
+    pub fee_authority: Signer<'info>,
+}
+
+pub fn set_flash_fee_rate(ctx: Context<SetFlashFeeRate>, flash_fee_rate: u16) -> Result<()> {
+    ctx.accounts
+        .globalpool
+        .update_flash_fee_rate(flash_fee_rate)
+}
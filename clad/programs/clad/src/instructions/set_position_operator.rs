@@ -0,0 +1,69 @@
+use {
+    crate::{
+        state::*,
+        util::{get_position_mint, verify_position_authority},
+    },
+    anchor_lang::prelude::*,
+    anchor_spl::token::TokenAccount,
+};
+
+#[derive(Accounts)]
+pub struct SetPositionOperator<'info> {
+    #[account(mut)]
+    pub position_authority: Signer<'info>,
+
+    /// CHECK: Liquidity or Trade Position, checked in the handler
+    pub position: UncheckedAccount<'info>,
+
+    #[account(constraint = position_token_account.amount == 1)]
+    pub position_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: any account can be approved as operator
+    pub operator: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = position_authority,
+        space = PositionOperator::LEN,
+        seeds = [
+            b"position_operator".as_ref(),
+            position.key().as_ref(),
+            operator.key().as_ref()
+        ],
+        bump,
+    )]
+    pub position_operator: Box<Account<'info, PositionOperator>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SetPositionOperatorParams {
+    pub permissions: u8,
+}
+
+pub fn set_position_operator(
+    ctx: Context<SetPositionOperator>,
+    params: &SetPositionOperatorParams,
+) -> Result<()> {
+    let position_operator_bump = *ctx
+        .bumps
+        .get("position_operator")
+        .ok_or(ProgramError::InvalidSeeds)?;
+
+    if ctx.accounts.position_token_account.mint != get_position_mint(&ctx.accounts.position)? {
+        return Err(anchor_lang::error::ErrorCode::ConstraintRaw.into());
+    }
+    verify_position_authority(
+        &ctx.accounts.position_token_account,
+        &ctx.accounts.position_authority,
+    )?;
+
+    ctx.accounts.position_operator.update(
+        ctx.accounts.position.key(),
+        ctx.accounts.operator.key(),
+        ctx.accounts.position_token_account.owner,
+        params.permissions,
+        position_operator_bump,
+    )
+}
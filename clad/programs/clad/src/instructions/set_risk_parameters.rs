@@ -0,0 +1,30 @@
+use {crate::state::*, anchor_lang::prelude::*};
+
+#[derive(Accounts)]
+pub struct SetRiskParameters<'info> {
+    #[account(mut)]
+    pub globalpool: Account<'info, Globalpool>,
+
+    #[account(address = globalpool.fee_authority)]
+    pub fee_authority: Signer<'info>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SetRiskParametersParams {
+    pub max_borrowed_liquidity_bps: u16,
+    pub max_tick_utilization_bps: u16,
+    pub max_position_notional_a: u64,
+    pub max_position_notional_b: u64,
+}
+
+pub fn set_risk_parameters(
+    ctx: Context<SetRiskParameters>,
+    params: &SetRiskParametersParams,
+) -> Result<()> {
+    ctx.accounts.globalpool.update_risk_parameters(
+        params.max_borrowed_liquidity_bps,
+        params.max_tick_utilization_bps,
+        params.max_position_notional_a,
+        params.max_position_notional_b,
+    )
+}
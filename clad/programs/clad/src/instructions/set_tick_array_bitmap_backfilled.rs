@@ -0,0 +1,35 @@
+use {
+    crate::{errors::ErrorCode, program::Clad as CladProgram, state::*},
+    anchor_lang::prelude::*,
+};
+
+#[derive(Accounts)]
+pub struct SetTickArrayBitmapBackfilled<'info> {
+    pub globalpool: Account<'info, Globalpool>,
+
+    #[account(
+        mut,
+        has_one = globalpool,
+        seeds = [b"tick_array_bitmap", globalpool.key().as_ref()],
+        bump
+    )]
+    pub tick_array_bitmap: Account<'info, TickArrayBitmap>,
+
+    // Protocol admin: the upgrade authority of the program. Swaps skip the tick arrays a
+    // backfilled bitmap doesn't record, so a pool's own fee authority can't attest to it.
+    pub admin: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, CladProgram>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key())
+            @ ErrorCode::InvalidProtocolAdmin
+    )]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+pub fn set_tick_array_bitmap_backfilled(ctx: Context<SetTickArrayBitmapBackfilled>) -> Result<()> {
+    ctx.accounts.tick_array_bitmap.set_backfilled();
+    Ok(())
+}
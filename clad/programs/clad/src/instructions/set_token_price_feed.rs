@@ -0,0 +1,52 @@
+use {
+    crate::{errors::ErrorCode, program::Clad as CladProgram, state::*},
+    anchor_lang::prelude::*,
+    anchor_spl::token::Mint,
+};
+
+#[derive(Accounts)]
+pub struct SetTokenPriceFeed<'info> {
+    // Protocol admin: the upgrade authority of the program. Globalpools are created permissionlessly,
+    // so their fee authorities can't be trusted with the prices margin accounts are valued at.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, CladProgram>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key())
+            @ ErrorCode::InvalidProtocolAdmin
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub token_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = TokenPriceFeed::LEN,
+        seeds = [b"token_price_feed".as_ref(), token_mint.key().as_ref()],
+        bump,
+    )]
+    pub token_price_feed: Box<Account<'info, TokenPriceFeed>>,
+
+    pub price_feed: Account<'info, PriceFeed>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn set_token_price_feed(ctx: Context<SetTokenPriceFeed>) -> Result<()> {
+    let bump = *ctx
+        .bumps
+        .get("token_price_feed")
+        .ok_or(ProgramError::InvalidSeeds)?;
+
+    ctx.accounts.token_price_feed.update(
+        ctx.accounts.token_mint.key(),
+        ctx.accounts.price_feed.key(),
+        bump,
+    );
+
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use {
+    crate::{
+        errors::ErrorCode,
+        manager::liquidity_manager::calculate_fee_growths,
+        state::*,
+        util::{
+            mint_position_token_and_remove_authority, to_timestamp_u64, verify_position_authority,
+        },
+    },
+    anchor_lang::prelude::*,
+    anchor_spl::{
+        associated_token::AssociatedToken,
+        token::{self, Mint, Token, TokenAccount},
+    },
+};
+
+#[derive(Accounts)]
+pub struct SplitLiquidityPosition<'info> {
+    #[account(mut)]
+    pub position_authority: Signer<'info>,
+
+    pub globalpool: Box<Account<'info, Globalpool>>,
+
+    #[account(
+        mut,
+        has_one = globalpool,
+        constraint = !position.is_range_order @ ErrorCode::RangeOrderPositionUnsupported
+    )]
+    pub position: Box<Account<'info, LiquidityPosition>>,
+    #[account(
+        constraint = position_token_account.mint == position.position_mint,
+        constraint = position_token_account.amount == 1
+    )]
+    pub position_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = position_authority,
+        space = LiquidityPosition::LEN,
+        seeds = [
+            b"liquidity_position".as_ref(),
+            new_position_mint.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub new_position: Box<Account<'info, LiquidityPosition>>,
+
+    #[account(
+        init,
+        payer = position_authority,
+        mint::authority = globalpool,
+        mint::decimals = 0,
+    )]
+    pub new_position_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = position_authority,
+        associated_token::mint = new_position_mint,
+        associated_token::authority = position_authority,
+    )]
+    pub new_position_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(has_one = globalpool)]
+    pub tick_array_lower: AccountLoader<'info, TickArray>,
+    #[account(has_one = globalpool)]
+    pub tick_array_upper: AccountLoader<'info, TickArray>,
+
+    #[account(address = token::ID)]
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SplitLiquidityPositionParams {
+    pub liquidity_amount: u128,
+}
+
+pub fn split_liquidity_position(
+    ctx: Context<SplitLiquidityPosition>,
+    params: &SplitLiquidityPositionParams,
+) -> Result<()> {
+    verify_position_authority(
+        &ctx.accounts.position_token_account,
+        &ctx.accounts.position_authority,
+    )?;
+
+    let globalpool = &ctx.accounts.globalpool;
+
+    // Settle the fees earned so far, so that both positions start from the same checkpoints
+    let position_update = &calculate_fee_growths(
+        globalpool,
+        &ctx.accounts.position,
+        &ctx.accounts.tick_array_lower,
+        &ctx.accounts.tick_array_upper,
+        to_timestamp_u64(Clock::get()?.unix_timestamp)?,
+    )?;
+    ctx.accounts.position.update(position_update);
+
+    let tick_lower_index = ctx.accounts.position.tick_lower_index;
+    let tick_upper_index = ctx.accounts.position.tick_upper_index;
+    ctx.accounts.new_position.open_position(
+        globalpool,
+        ctx.accounts.new_position_mint.key(),
+        tick_lower_index,
+        tick_upper_index,
+    )?;
+
+    // No tokens move: the ticks of the range keep the same total liquidity
+    ctx.accounts
+        .position
+        .split_into(&mut ctx.accounts.new_position, params.liquidity_amount)?;
+
+    mint_position_token_and_remove_authority(
+        globalpool,
+        &ctx.accounts.new_position_mint,
+        &ctx.accounts.new_position_token_account,
+        &ctx.accounts.token_program,
+    )
+}
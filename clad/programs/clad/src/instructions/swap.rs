@@ -1,33 +1,59 @@
 use {
     crate::{
         errors::ErrorCode,
-        manager::swap_manager,
-        state::{Globalpool, TickArray},
-        util::{update_and_swap_globalpool, to_timestamp_u64, TickSequence},
+        events::Swapped,
+        manager::{
+            hook_manager::{load_globalpool_hook, HookOperation, HookParams},
+            swap_manager,
+        },
+        state::{load_tick_array_mut, Globalpool, TickArray},
+        util::{
+            calculate_transfer_fee_excluded_amount, calculate_transfer_fee_included_amount,
+            get_epoch_transfer_fee, load_native_sol_account, load_tick_array_bitmap,
+            load_tick_arrays, to_timestamp_u64, update_and_swap_globalpool, TickSequence,
+        },
     },
     anchor_lang::prelude::*,
-    anchor_spl::token::{self, Token, TokenAccount},
 };
 
 #[derive(Accounts)]
 pub struct Swap<'info> {
-    #[account(address = token::ID)]
-    pub token_program: Program<'info, Token>,
+    /// CHECK: owner of token_mint_a, checked by constraint
+    #[account(constraint = token_program_a.key() == *token_mint_a.owner @ ErrorCode::InvalidTokenProgram)]
+    pub token_program_a: UncheckedAccount<'info>,
+    /// CHECK: owner of token_mint_b, checked by constraint
+    #[account(constraint = token_program_b.key() == *token_mint_b.owner @ ErrorCode::InvalidTokenProgram)]
+    pub token_program_b: UncheckedAccount<'info>,
 
     pub token_authority: Signer<'info>,
 
-    #[account(mut)]
+    // The vaults are lent out while a flash loan is outstanding
+    #[account(
+        mut,
+        constraint = !globalpool.has_flash_loan() @ ErrorCode::FlashLoanInProgress
+    )]
     pub globalpool: Box<Account<'info, Globalpool>>,
 
-    #[account(mut, constraint = token_owner_account_a.mint == globalpool.token_mint_a)]
-    pub token_owner_account_a: Box<Account<'info, TokenAccount>>,
+    /// CHECK: address is checked
+    #[account(address = globalpool.token_mint_a)]
+    pub token_mint_a: UncheckedAccount<'info>,
+    /// CHECK: address is checked
+    #[account(address = globalpool.token_mint_b)]
+    pub token_mint_b: UncheckedAccount<'info>,
+
+    /// CHECK: mint & authority are checked by the token program
+    #[account(mut)]
+    pub token_owner_account_a: UncheckedAccount<'info>,
+    /// CHECK: address is checked
     #[account(mut, address = globalpool.token_vault_a)]
-    pub token_vault_a: Box<Account<'info, TokenAccount>>,
+    pub token_vault_a: UncheckedAccount<'info>,
 
-    #[account(mut, constraint = token_owner_account_b.mint == globalpool.token_mint_b)]
-    pub token_owner_account_b: Box<Account<'info, TokenAccount>>,
+    /// CHECK: mint & authority are checked by the token program
+    #[account(mut)]
+    pub token_owner_account_b: UncheckedAccount<'info>,
+    /// CHECK: address is checked
     #[account(mut, address = globalpool.token_vault_b)]
-    pub token_vault_b: Box<Account<'info, TokenAccount>>,
+    pub token_vault_b: UncheckedAccount<'info>,
 
     #[account(mut, has_one = globalpool)]
     pub tick_array_0: AccountLoader<'info, TickArray>,
@@ -41,59 +67,172 @@ pub struct Swap<'info> {
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct SwapParams {
-    amount: u64,
-    other_amount_threshold: u64,
-    sqrt_price_limit: u128,
-    amount_specified_is_input: bool,
-    a_to_b: bool, // Zero for one
+    pub amount: u64,
+    pub other_amount_threshold: u64,
+    pub sqrt_price_limit: u128,
+    pub amount_specified_is_input: bool,
+    pub a_to_b: bool, // Zero for one
 }
 
 pub fn swap(ctx: Context<Swap>, params: &SwapParams) -> Result<()> {
     let globalpool = &mut ctx.accounts.globalpool;
 
     let timestamp = to_timestamp_u64(Clock::get()?.unix_timestamp)?;
-    let mut swap_tick_sequence = TickSequence::new(
-        ctx.accounts.tick_array_0.load_mut().unwrap(),
-        ctx.accounts.tick_array_1.load_mut().ok(),
-        ctx.accounts.tick_array_2.load_mut().ok(),
-    );
+
+    // The remaining accounts are the optional System Program enabling native SOL mode, the optional
+    // TickArrayBitmap, the tick arrays following tick_array_2 in the swap direction, then the hook
+    // program of the globalpool followed by its accounts, if it has a hook
+    let (hook, remaining_accounts) = load_globalpool_hook(globalpool, ctx.remaining_accounts)?;
+    let (native_sol_account, remaining_accounts) = load_native_sol_account(
+        remaining_accounts,
+        &ctx.accounts.token_authority,
+        [&ctx.accounts.token_mint_a, &ctx.accounts.token_mint_b],
+        [
+            &ctx.accounts.token_owner_account_a,
+            &ctx.accounts.token_owner_account_b,
+        ],
+        [&ctx.accounts.token_program_a, &ctx.accounts.token_program_b],
+    )?;
+    let (tick_array_bitmap, remaining_accounts) =
+        load_tick_array_bitmap(remaining_accounts, &globalpool.key())?;
+    let remaining_tick_arrays = load_tick_arrays(remaining_accounts, &globalpool.key())?;
+
+    let mut tick_arrays = vec![load_tick_array_mut(&ctx.accounts.tick_array_0).unwrap()];
+    tick_arrays.extend(load_tick_array_mut(&ctx.accounts.tick_array_1).ok());
+    tick_arrays.extend(load_tick_array_mut(&ctx.accounts.tick_array_2).ok());
+    for tick_array in remaining_tick_arrays.iter() {
+        tick_arrays.push(load_tick_array_mut(tick_array)?);
+    }
+    let mut swap_tick_sequence = TickSequence::from_arrays_with_extensions(tick_arrays)?;
+    if let Some(tick_array_bitmap) = tick_array_bitmap.as_ref() {
+        swap_tick_sequence.set_tick_array_bitmap(tick_array_bitmap);
+    }
 
     let SwapParams { amount_specified_is_input, a_to_b, other_amount_threshold, .. } = *params;
 
+    // In native SOL mode, SOL paid in is wrapped up to the most the swap can take
+    if let Some(native_sol_account) = native_sol_account.as_ref() {
+        let is_native_input = native_sol_account.is_token_a == a_to_b;
+        native_sol_account.open(match (is_native_input, amount_specified_is_input) {
+            (false, _) => 0,
+            (true, true) => params.amount,
+            (true, false) => other_amount_threshold,
+        })?;
+    }
+
+    let (token_mint_input, token_mint_output) = if a_to_b {
+        (&ctx.accounts.token_mint_a, &ctx.accounts.token_mint_b)
+    } else {
+        (&ctx.accounts.token_mint_b, &ctx.accounts.token_mint_a)
+    };
+    let transfer_fee_input = get_epoch_transfer_fee(token_mint_input)?;
+    let transfer_fee_output = get_epoch_transfer_fee(token_mint_output)?;
+
+    // The swap is calculated on the amounts that actually reach (input) or leave (output) the vaults
+    let swap_amount = if amount_specified_is_input {
+        calculate_transfer_fee_excluded_amount(&transfer_fee_input, params.amount)?
+    } else {
+        calculate_transfer_fee_included_amount(&transfer_fee_output, params.amount)?
+    };
+
+    let pre_sqrt_price = globalpool.sqrt_price;
+    let pre_tick_index = globalpool.tick_current_index;
+
     let swap_update = swap_manager::swap(
         &globalpool,
         &mut swap_tick_sequence,
-        params.amount,
+        swap_amount,
         params.sqrt_price_limit,
         amount_specified_is_input,
         a_to_b,
         timestamp,
     )?;
 
+    let (swap_amount_input, swap_amount_output) = if a_to_b {
+        (swap_update.amount_a, swap_update.amount_b)
+    } else {
+        (swap_update.amount_b, swap_update.amount_a)
+    };
+    // Sent by the user, and received by the user
+    let transfer_amount_input =
+        calculate_transfer_fee_included_amount(&transfer_fee_input, swap_amount_input)?;
+    let received_amount_output =
+        calculate_transfer_fee_excluded_amount(&transfer_fee_output, swap_amount_output)?;
+
     if amount_specified_is_input {
-        if (a_to_b && other_amount_threshold > swap_update.amount_b)
-            || (!a_to_b && other_amount_threshold > swap_update.amount_a)
-        {
+        if other_amount_threshold > received_amount_output {
             return Err(ErrorCode::AmountOutBelowMinimum.into());
         }
     } else {
-        if (a_to_b && other_amount_threshold < swap_update.amount_a)
-            || (!a_to_b && other_amount_threshold < swap_update.amount_b)
-        {
+        if other_amount_threshold < transfer_amount_input {
             return Err(ErrorCode::AmountInAboveMaximum.into());
         }
     }
     msg!("swap_update: {:?}", swap_update);
 
+    let (transfer_amount_a, transfer_amount_b) = if a_to_b {
+        (transfer_amount_input, swap_amount_output)
+    } else {
+        (swap_amount_output, transfer_amount_input)
+    };
+
+    // The hook may veto the swap, given the amounts entering and leaving the vaults
+    let (token_delta_a, token_delta_b) = if a_to_b {
+        (swap_amount_input as i128, -(swap_amount_output as i128))
+    } else {
+        (-(swap_amount_output as i128), swap_amount_input as i128)
+    };
+    let hook_params = HookParams::new(
+        HookOperation::Swap,
+        globalpool,
+        ctx.accounts.token_authority.key,
+        Pubkey::default(),
+        0,
+        token_delta_a,
+        token_delta_b,
+    );
+    if let Some(hook) = hook.as_ref() {
+        hook.before_operation(globalpool, &ctx.accounts.token_authority, &hook_params)?;
+    }
+
     update_and_swap_globalpool(
         globalpool,
         &ctx.accounts.token_authority,
+        &ctx.accounts.token_mint_a,
+        &ctx.accounts.token_mint_b,
         &ctx.accounts.token_owner_account_a,
         &ctx.accounts.token_owner_account_b,
         &ctx.accounts.token_vault_a,
         &ctx.accounts.token_vault_b,
-        &ctx.accounts.token_program,
+        &ctx.accounts.token_program_a,
+        &ctx.accounts.token_program_b,
         swap_update,
+        transfer_amount_a,
+        transfer_amount_b,
+        timestamp,
         a_to_b,
-    )
+    )?;
+
+    if let Some(native_sol_account) = native_sol_account.as_ref() {
+        native_sol_account.close()?;
+    }
+
+    if let Some(hook) = hook.as_ref() {
+        hook.after_operation(globalpool, &ctx.accounts.token_authority, hook_params)?;
+    }
+
+    emit!(Swapped {
+        globalpool: globalpool.key(),
+        a_to_b,
+        amount_in: swap_amount_input,
+        amount_out: swap_amount_output,
+        pre_sqrt_price,
+        post_sqrt_price: globalpool.sqrt_price,
+        pre_tick_index,
+        post_tick_index: globalpool.tick_current_index,
+        fee_growth_global_a: globalpool.fee_growth_global_a,
+        fee_growth_global_b: globalpool.fee_growth_global_b,
+    });
+
+    Ok(())
 }
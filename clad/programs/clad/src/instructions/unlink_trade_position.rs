@@ -0,0 +1,60 @@
+use {
+    crate::{errors::ErrorCode, manager::margin_manager::calculate_margin_health, state::*},
+    anchor_lang::prelude::*,
+};
+
+#[derive(Accounts)]
+pub struct UnlinkTradePosition<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"margin_account".as_ref(), owner.key().as_ref()],
+        bump = margin_account.bump[0],
+    )]
+    pub margin_account: Box<Account<'info, MarginAccount>>,
+
+    /// CHECK: linked trade position, which may have been closed after repayment
+    pub position: UncheckedAccount<'info>,
+    // remaining accounts: registered price feeds & linked positions after unlinking, see `calculate_margin_health`
+}
+
+pub fn unlink_trade_position(ctx: Context<UnlinkTradePosition>) -> Result<()> {
+    let position = if ctx.accounts.position.data_is_empty() {
+        None
+    } else {
+        Some(Account::<TradePosition>::try_from(&ctx.accounts.position)?)
+    };
+    let is_position_repaid = position
+        .as_ref()
+        .map_or(true, |position| TradePosition::is_position_empty(position));
+
+    // Positions backed by the margin account have no collateral of their own to stand on
+    if !is_position_repaid && position.map_or(false, |position| position.is_margin_backed()) {
+        return Err(ErrorCode::MarginBackedPositionNotRepaid.into());
+    }
+
+    ctx.accounts
+        .margin_account
+        .unlink_position(&ctx.accounts.position.key())?;
+
+    // Repaid positions carry no debt and can always be unlinked. Otherwise, the rest of the
+    // account must stay above maintenance margin without the position's assets.
+    if !is_position_repaid {
+        if ctx.accounts.margin_account.is_liquidating {
+            return Err(ErrorCode::MarginAccountLiquidating.into());
+        }
+
+        let health = calculate_margin_health(
+            &ctx.accounts.margin_account,
+            ctx.remaining_accounts,
+            Clock::get()?.unix_timestamp,
+        )?;
+        if !health.is_healthy() {
+            return Err(ErrorCode::MarginAccountUnhealthy.into());
+        }
+    }
+
+    Ok(())
+}
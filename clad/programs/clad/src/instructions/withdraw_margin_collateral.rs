@@ -0,0 +1,88 @@
+use {
+    crate::{
+        errors::ErrorCode,
+        manager::margin_manager::calculate_margin_health,
+        state::*,
+        util::{load_token_owner_account, transfer_from_margin_vault_to_owner},
+    },
+    anchor_lang::prelude::*,
+    anchor_spl::token::{self, Token, TokenAccount},
+    spl_associated_token_account::get_associated_token_address,
+};
+
+#[derive(Accounts)]
+pub struct WithdrawMarginCollateral<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"margin_account".as_ref(), owner.key().as_ref()],
+        bump = margin_account.bump[0],
+    )]
+    pub margin_account: Box<Account<'info, MarginAccount>>,
+
+    /// CHECK: the token program is checked, the margin account holds collateral of the mint
+    #[account(constraint = *token_mint.owner == token::ID @ ErrorCode::Token2022MintUnsupported)]
+    pub token_mint: UncheckedAccount<'info>,
+
+    /// CHECK: mint is checked in the handler, once the token program is checked
+    #[account(mut)]
+    pub token_owner_account: UncheckedAccount<'info>,
+
+    /// CHECK: address is checked, loaded in the handler once the token program is checked
+    #[account(
+        mut,
+        address = get_associated_token_address(&margin_account.key(), &token_mint.key())
+    )]
+    pub margin_vault: UncheckedAccount<'info>,
+
+    #[account(address = token::ID)]
+    pub token_program: Program<'info, Token>,
+    // remaining accounts: registered price feeds & linked positions, see `calculate_margin_health`
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct WithdrawMarginCollateralParams {
+    pub amount: u64,
+}
+
+pub fn withdraw_margin_collateral(
+    ctx: Context<WithdrawMarginCollateral>,
+    params: &WithdrawMarginCollateralParams,
+) -> Result<()> {
+    if ctx.accounts.margin_account.is_liquidating {
+        return Err(ErrorCode::MarginAccountLiquidating.into());
+    }
+
+    ctx.accounts
+        .margin_account
+        .withdraw_collateral(&ctx.accounts.token_mint.key(), params.amount)?;
+
+    // The remaining collateral must keep the linked positions above maintenance margin
+    let health = calculate_margin_health(
+        &ctx.accounts.margin_account,
+        ctx.remaining_accounts,
+        Clock::get()?.unix_timestamp,
+    )?;
+    if !health.is_healthy() {
+        return Err(ErrorCode::MarginAccountUnhealthy.into());
+    }
+
+    let token_mint = ctx.accounts.token_mint.key();
+    let margin_vault = load_token_owner_account(
+        &ctx.accounts.margin_vault,
+        &token_mint,
+        Some(&ctx.accounts.margin_account.key()),
+    )?;
+    let token_owner_account =
+        load_token_owner_account(&ctx.accounts.token_owner_account, &token_mint, None)?;
+
+    transfer_from_margin_vault_to_owner(
+        &ctx.accounts.margin_account,
+        &margin_vault,
+        &token_owner_account,
+        &ctx.accounts.token_program,
+        params.amount,
+    )
+}
@@ -4,12 +4,15 @@ declare_id!("GH4aPZ5bXQr3MhN6MrejxKLTj6gUdyrGZvieagrfA3ke");
 
 #[doc(hidden)]
 pub mod errors;
+pub mod events;
 #[doc(hidden)]
 pub mod instructions;
 #[doc(hidden)]
 pub mod manager;
 #[doc(hidden)]
 pub mod math;
+#[cfg(any(feature = "quote", test))]
+pub mod quote;
 pub mod state;
 #[doc(hidden)]
 pub mod tests;
@@ -40,7 +43,12 @@ pub mod clad {
         return instructions::initialize_clad(ctx, &params);
     }
 
-    /// Initializes a tick_array account to represent a tick-range in a Globalpool.
+    /// Initializes a tick_array account to represent a tick-range in a Globalpool, and records it
+    /// in the Globalpool's tick_array_bitmap, which is created along with its first tick_array.
+    /// A bitmap created here is not backfilled, see `backfill_tick_array_bitmap`.
+    ///
+    /// Breaking change: the `tick_array_bitmap` account, the PDA of `[b"tick_array_bitmap", globalpool]`,
+    /// is now required after `tick_array`. The `funder` pays its rent if it doesn't exist yet.
     ///
     /// ### Parameters
     /// - `start_tick_index` - The starting tick index for this tick-array.
@@ -56,21 +64,107 @@ pub mod clad {
         return instructions::initialize_tick_array(ctx, &params);
     }
 
+    /// Closes a tick_array account whose ticks hold no liquidity, refunding its rent to the funder
+    /// that initialized it and clearing it from the tick_array_bitmap.
+    ///
+    /// ### Authority
+    /// - `funder` - The account that paid for the tick_array in `initialize_tick_array`.
+    ///
+    /// #### Special Errors
+    /// - `TickArrayExtensionMissing` - The tick_array was created before range orders, without recording its funder.
+    /// - `InvalidTickArrayFunder` - The funder didn't initialize the tick_array.
+    /// - `TickArrayNotClosable` - A tick has liquidity or borrowed liquidity, or range orders of a
+    ///                            tick, filled or not, are unclaimed, as their claims rely on its
+    ///                            fill count.
+    pub fn close_tick_array(ctx: Context<CloseTickArray>) -> Result<()> {
+        return instructions::close_tick_array(ctx);
+    }
+
+    /// Reallocates a tick_array account created before range orders, appending the extension
+    /// that holds the range orders of its ticks. Its address and ticks are unchanged. Range orders
+    /// can't be opened on ticks of a tick_array until it is migrated. Anyone can pay for the
+    /// migration, migrating a tick_array twice does nothing. Migrated tick_arrays have no recorded
+    /// funder, so they can't be closed.
+    pub fn migrate_tick_array(ctx: Context<MigrateTickArray>) -> Result<()> {
+        return instructions::migrate_tick_array(ctx);
+    }
+
+    /// Records tick_arrays that predate the Globalpool's tick_array_bitmap, passed as remaining
+    /// accounts, creating the bitmap if the Globalpool has none. Permissionless, only accounts that
+    /// are tick_arrays of the Globalpool are recorded.
+    ///
+    /// #### Special Errors
+    /// - `ConstraintHasOne` - A provided tick_array belongs to another Globalpool.
+    pub fn backfill_tick_array_bitmap(ctx: Context<BackfillTickArrayBitmap>) -> Result<()> {
+        return instructions::backfill_tick_array_bitmap(ctx);
+    }
+
+    /// Marks the tick_array_bitmap of this Globalpool as backfilled, once it records every
+    /// tick_array of the Globalpool. Swaps only skip tick_arrays with a backfilled bitmap.
+    /// Bitmaps created along with their Globalpool by `create_pool_with_liquidity` are backfilled.
+    ///
+    /// ### Authority
+    /// - `admin` - protocol admin, i.e. the upgrade authority of the program.
+    ///
+    /// #### Special Errors
+    /// - `InvalidProtocolAdmin` - The signer is not the upgrade authority of the program.
+    pub fn set_tick_array_bitmap_backfilled(
+        ctx: Context<SetTickArrayBitmapBackfilled>,
+    ) -> Result<()> {
+        return instructions::set_tick_array_bitmap_backfilled(ctx);
+    }
+
     /// Initializes a Globalpool account.
     /// Fee rate is set to the default values on the config and supplied fee_tier.
+    /// Token A and B may be SPL Token or Token-2022 mints. Only the transfer fee and interest
+    /// bearing extensions are supported. Trade, loan & flash loan instructions only support
+    /// SPL Token vaults.
     ///
     /// ### Parameters
     /// - `tick_spacing` - The desired tick spacing for this pool.
     /// - `initial_sqrt_price` - The desired initial sqrt-price for this pool
+    /// - `hook_program` - The optional program called before and after each swap and liquidity
+    ///                    change, which may veto them (see `hook_manager`). It can't be changed later.
     ///
     /// #### Special Errors
     /// `InvalidTokenMintOrder` - The order of mints have to be ordered by
+    /// `InvalidHookProgram` - The hook program is Clad itself.
     /// `SqrtPriceOutOfBounds` - provided initial_sqrt_price is not between 2^-64 to 2^64
+    /// `InvalidTokenProgram` - A token program is not SPL Token / Token-2022 or does not own its mint.
+    /// `UnsupportedTokenMintExtension` - A mint has an unsupported Token-2022 extension.
     ///
     pub fn create_pool(ctx: Context<CreatePool>, params: CreatePoolParams) -> Result<()> {
         return instructions::create_pool(ctx, &params);
     }
 
+    /// Initializes a Globalpool, as `create_pool` does, along with its tick array bitmap, the tick
+    /// arrays holding the ticks of a seed position, and the seed position funded with its initial
+    /// deposit. Either all of them are created or none is.
+    ///
+    /// ### Authority
+    /// - `funder` - pays for the accounts, deposits the tokens and owns the seed position.
+    ///
+    /// ### Parameters
+    /// - `pool` - The params of `create_pool`.
+    /// - `full_range` - Whether the seed position spans the full range of usable ticks.
+    /// - `tick_lower_index` - The lower tick of the seed position, if not full range.
+    /// - `tick_upper_index` - The upper tick of the seed position, if not full range.
+    /// - `liquidity_amount` - The amount of liquidity to deposit.
+    /// - `token_max_a` - The maximum amount of tokenA to deposit, including transfer fees.
+    /// - `token_max_b` - The maximum amount of tokenB to deposit, including transfer fees.
+    ///
+    /// #### Special Errors
+    /// - `InvalidTickIndex` - The seed position ticks are not usable ticks of the pool.
+    /// - `LiquidityZero` - Provided liquidity amount is zero.
+    /// - `TokenMaxExceeded` - The required token to perform this operation exceeds the user defined amount.
+    /// - `HookUnsupported` - The pool has a hook program, so it must be funded with `increase_liquidity`.
+    pub fn create_pool_with_liquidity(
+        ctx: Context<CreatePoolWithLiquidity>,
+        params: CreatePoolWithLiquidityParams,
+    ) -> Result<()> {
+        return instructions::create_pool_with_liquidity(ctx, &params);
+    }
+
     /// Open a position in a Globalpool. A unique token will be minted to represent the liquidity position
     /// in the users wallet. The position will start off with 0 liquidity.
     ///
@@ -88,6 +182,23 @@ pub mod clad {
         return instructions::open_liquidity_position(ctx, &params);
     }
 
+    /// Open a position in a Globalpool, like `open_liquidity_position`, and create Metaplex
+    /// metadata for the position token so that wallets can recognize it.
+    ///
+    /// ### Parameters
+    /// - `tick_lower_index` - The tick specifying the lower end of the position range.
+    /// - `tick_upper_index` - The tick specifying the upper end of the position range.
+    ///
+    /// #### Special Errors
+    /// - `InvalidTickIndex` - If a provided tick is out of bounds, out of order or not a multiple of
+    ///                        the tick-spacing in this pool.
+    pub fn open_liquidity_position_with_metadata(
+        ctx: Context<OpenLiquidityPositionWithMetadata>,
+        params: OpenLiquidityPositionParams,
+    ) -> Result<()> {
+        return instructions::open_liquidity_position_with_metadata(ctx, &params);
+    }
+
     /// Close a position in a Globalpool. Burns the liquidity position token in the owner's wallet.
     ///
     /// ### Authority
@@ -99,6 +210,110 @@ pub mod clad {
         return instructions::close_liquidity_position(ctx);
     }
 
+    /// Move part of the liquidity of a position into a new position with the same range. A new
+    /// position token is minted to the position authority. Fees owed are split in proportion to the
+    /// liquidity, and no tokens are transferred.
+    ///
+    /// ### Authority
+    /// - `position_authority` - authority that owns the token corresponding to this desired position,
+    ///                          or its delegate.
+    ///
+    /// ### Parameters
+    /// - `liquidity_amount` - The amount of liquidity to move into the new position.
+    ///
+    /// #### Special Errors
+    /// - `InvalidSplitLiquidity` - The liquidity amount is zero or exceeds the position's liquidity.
+    /// - `RangeOrderPositionUnsupported` - The position holds a range order.
+    pub fn split_liquidity_position(
+        ctx: Context<SplitLiquidityPosition>,
+        params: SplitLiquidityPositionParams,
+    ) -> Result<()> {
+        return instructions::split_liquidity_position(ctx, &params);
+    }
+
+    /// Merge a position into another position with the same range in the same Globalpool, moving
+    /// over its liquidity and fees owed without transferring any tokens. The merged position is
+    /// closed and its token burned.
+    ///
+    /// ### Authority
+    /// - `position_authority` - authority that owns the tokens of both positions.
+    ///
+    /// #### Special Errors
+    /// - `PositionRangeMismatch` - The positions have different ranges.
+    /// - `RangeOrderPositionUnsupported` - One of the positions holds a range order.
+    pub fn merge_liquidity_positions(ctx: Context<MergeLiquidityPositions>) -> Result<()> {
+        return instructions::merge_liquidity_positions(ctx);
+    }
+
+    /// Initialize a PositionBundle. A unique token will be minted to represent the bundle in the
+    /// users wallet. The bundle can hold up to 256 liquidity positions.
+    pub fn initialize_position_bundle(ctx: Context<InitializePositionBundle>) -> Result<()> {
+        return instructions::initialize_position_bundle(ctx);
+    }
+
+    /// Delete a PositionBundle. Burns the position bundle token in the owner's wallet.
+    ///
+    /// ### Authority
+    /// - "position_bundle_authority" - The authority that owns the position bundle token.
+    ///
+    /// #### Special Errors
+    /// - `PositionBundleNotDeletable` - The bundle still has opened positions.
+    pub fn delete_position_bundle(ctx: Context<DeletePositionBundle>) -> Result<()> {
+        return instructions::delete_position_bundle(ctx);
+    }
+
+    /// Open a liquidity position in a Globalpool at an index of a PositionBundle. The position is
+    /// represented by the position bundle token, and will start off with 0 liquidity.
+    ///
+    /// ### Authority
+    /// - "position_authority" - The authority that owns the position bundle token.
+    ///
+    /// ### Parameters
+    /// - `bundle_index` - The index of the position in the bundle (0 to 255).
+    /// - `tick_lower_index` - The tick specifying the lower end of the position range.
+    /// - `tick_upper_index` - The tick specifying the upper end of the position range.
+    ///
+    /// #### Special Errors
+    /// - `InvalidBundleIndex` - The bundle index is out of range.
+    /// - `BundledPositionAlreadyOpened` - The bundle index is already in use.
+    /// - `InvalidTickIndex` - If a provided tick is out of bounds, out of order or not a multiple of
+    ///                        the tick-spacing in this pool.
+    pub fn open_bundled_liquidity_position(
+        ctx: Context<OpenBundledLiquidityPosition>,
+        params: OpenBundledLiquidityPositionParams,
+    ) -> Result<()> {
+        return instructions::open_bundled_liquidity_position(ctx, &params);
+    }
+
+    /// Close a bundled liquidity position, freeing its index in the PositionBundle.
+    ///
+    /// ### Authority
+    /// - "position_authority" - The authority that owns the position bundle token.
+    ///
+    /// #### Special Errors
+    /// - `CloseLiquidityPositionNotEmpty` - The provided liquidity position account is not empty.
+    /// - `BundledPositionAlreadyClosed` - The bundle index is not in use.
+    pub fn close_bundled_liquidity_position(
+        ctx: Context<CloseBundledLiquidityPosition>,
+        bundle_index: u16,
+    ) -> Result<()> {
+        return instructions::close_bundled_liquidity_position(ctx, bundle_index);
+    }
+
+    /// Open a trade position, borrowing liquidity from out-of-range ticks and swapping it with
+    /// Jupiter. The owner deposits collateral and, unless perpetual, pays interest upfront.
+    ///
+    /// With `margin_account_collateral`, the owner's MarginAccount backs the position instead of
+    /// the deposited collateral. The position is linked to the account, which must remain above
+    /// initial margin (20%), and draws on the account's balances when repaid.
+    ///
+    /// In native SOL mode, enabled by passing the System Program as the first remaining account, the
+    /// SOL side token account is the temporary wSOL account of `owner`, funded with the collateral
+    /// and interest paid in SOL.
+    ///
+    /// #### Special Errors
+    /// - `Token2022MintUnsupported` - A token of the Globalpool is a Token-2022 mint.
+    /// - `HookUnsupported` - The Globalpool has a hook program.
     pub fn open_trade_position(
         ctx: Context<OpenTradePosition>,
         params: OpenTradePositionParams,
@@ -106,6 +321,40 @@ pub mod clad {
         return instructions::open_trade_position(ctx, &params);
     }
 
+    /// Open a trade position, like `open_trade_position`, and create Metaplex metadata for the
+    /// position token so that wallets can recognize it.
+    pub fn open_trade_position_with_metadata(
+        ctx: Context<OpenTradePositionWithMetadata>,
+        params: OpenTradePositionParams,
+    ) -> Result<()> {
+        return instructions::open_trade_position_with_metadata(ctx, &params);
+    }
+
+    /// Repay the loan of a trade position and return the leftover collateral to the owner. Anyone
+    /// can liquidate a matured (or unhealthy perpetual) position for a share of the collateral.
+    ///
+    /// ### Authority
+    /// - `owner` - authority that owns the token corresponding to this position. An operator
+    ///             permitted to repay signs as `liquidator` instead, passing its PositionOperator as
+    ///             the first remaining account, and takes no share of the collateral.
+    ///
+    /// In native SOL mode, enabled by passing the System Program as the first remaining account
+    /// (before any PositionOperator), the owner's SOL side token account is its temporary wSOL
+    /// account, closed to pay out the profit in SOL. The owner must sign.
+    ///
+    /// Positions backed by a margin account draw its balances of the pool tokens into the vaults to
+    /// settle the loan, and return the leftover collateral & profit to it. The margin vaults of
+    /// both pool tokens must exist.
+    ///
+    /// Liquidating a perpetual position passes, after any PositionOperator, the mints of the pool
+    /// tokens followed by the TokenPriceFeed & Pyth price account of each. Its health is checked at
+    /// these oracle prices, which a swap in the pool can't move.
+    ///
+    /// #### Special Errors
+    /// - `Token2022MintUnsupported` - A token of the Globalpool is a Token-2022 mint.
+    /// - `HookUnsupported` - The Globalpool has a hook program.
+    /// - `InvalidPerpetualHealthAccounts` - The oracle accounts of a liquidated perpetual position are missing or don't match the pool tokens.
+    /// - `OraclePriceOutOfRange` - The oracle prices are outside of the supported sqrt price range.
     pub fn repay_trade_position(
         ctx: Context<RepayTradePosition>,
         params: RepayTradePositionParams,
@@ -113,24 +362,85 @@ pub mod clad {
         return instructions::repay_trade_position(ctx, &params);
     }
 
+    /// Close a repaid trade position, returning any collateral left to the owner.
+    ///
+    /// In native SOL mode, enabled by passing the System Program as the first remaining account, the
+    /// SOL side token account is the temporary wSOL account of `owner`, closed to pay out SOL.
     pub fn close_trade_position(ctx: Context<CloseTradePosition>) -> Result<()> {
         return instructions::close_trade_position(ctx);
     }
 
+    /// Open a leveraged liquidity position. Liquidity is borrowed from out-of-range ticks (like
+    /// `open_trade_position`) and deposited, together with the owner's margin, into a new liquidity
+    /// position. The liquidity position token is locked by the trade position until the loan is repaid.
+    ///
+    /// ### Parameters
+    /// - `loan_liquidity_amount` - The liquidity to borrow from the loan range.
+    /// - `loan_tick_lower_index` - The lower tick of the loan range.
+    /// - `loan_tick_upper_index` - The upper tick of the loan range.
+    /// - `loan_duration` - Lifetime of the loan, in seconds.
+    /// - `borrow_a` - True if borrowing token A, false if borrowing token B.
+    /// - `tick_lower_index` - The lower tick of the liquidity position.
+    /// - `tick_upper_index` - The upper tick of the liquidity position.
+    /// - `liquidity_amount` - The liquidity to deposit into the liquidity position.
+    /// - `token_max_a` - The maximum margin of tokenA the owner is willing to deposit.
+    /// - `token_max_b` - The maximum margin of tokenB the owner is willing to deposit.
+    ///
+    /// #### Special Errors
+    /// - `LeveragedLiquidityLoanNotDeposited` - The liquidity position requires less than the borrowed amount.
+    /// - `LeveragedPositionUnhealthy` - The margin does not cover the maintenance margin of the loan.
+    /// - `TokenMaxExceeded` - The required margin exceeds the user defined amount.
+    /// - `HookUnsupported` - The Globalpool has a hook program.
+    pub fn open_leveraged_liquidity_position(
+        ctx: Context<OpenLeveragedLiquidityPosition>,
+        params: OpenLeveragedLiquidityPositionParams,
+    ) -> Result<()> {
+        return instructions::open_leveraged_liquidity_position(ctx, &params);
+    }
+
+    /// Repay a leveraged liquidity position by withdrawing its locked liquidity position, then
+    /// unlock the liquidity position token to the owner. Leftover tokens are returned to the owner.
+    ///
+    /// ### Authority
+    /// - `owner` - The owner of the trade position token, or any liquidator if the loan has matured
+    ///             or the position is below maintenance margin (receives 10% of the leftover tokens).
+    ///
+    /// ### Parameters
+    /// - `token_max_a` - The maximum amount of tokenA the repayer is willing to add to cover the loan.
+    /// - `token_max_b` - The maximum amount of tokenB the repayer is willing to add to cover the loan.
+    ///
+    /// #### Special Errors
+    /// - `LeveragedPositionNotLiquidatable` - The loan has not matured and the position is healthy.
+    /// - `TokenMaxExceeded` - The loan shortfall exceeds the user defined amount.
+    pub fn repay_leveraged_liquidity_position(
+        ctx: Context<RepayLeveragedLiquidityPosition>,
+        params: RepayLeveragedLiquidityPositionParams,
+    ) -> Result<()> {
+        return instructions::repay_leveraged_liquidity_position(ctx, &params);
+    }
+
     /// Add liquidity to a position in the Globalpool. This call also updates the position's accrued fees.
     ///
+    /// In native SOL mode, enabled by passing the System Program as the first remaining account, the
+    /// SOL side token account is the temporary wSOL account of `position_authority`, funded with up
+    /// to its token max in lamports and closed once the liquidity is added.
+    ///
+    /// If the Globalpool has a hook program, it is passed last among the remaining accounts,
+    /// followed by the accounts of the hook, and called before and after the deposit.
+    ///
     /// ### Authority
     /// - `position_authority` - authority that owns the token corresponding to this desired position.
     ///
     /// ### Parameters
     /// - `liquidity_amount` - The total amount of Liquidity the user is willing to deposit.
-    /// - `token_max_a` - The maximum amount of tokenA the user is willing to deposit.
-    /// - `token_max_b` - The maximum amount of tokenB the user is willing to deposit.
+    /// - `token_max_a` - The maximum amount of tokenA the user is willing to deposit, including transfer fees.
+    /// - `token_max_b` - The maximum amount of tokenB the user is willing to deposit, including transfer fees.
     ///
     /// #### Special Errors
     /// - `LiquidityZero` - Provided liquidity amount is zero.
     /// - `LiquidityTooHigh` - Provided liquidity exceeds u128::max.
     /// - `TokenMaxExceeded` - The required token to perform this operation exceeds the user defined amount.
+    /// - `RangeOrderPositionUnsupported` - The position holds a range order.
     pub fn increase_liquidity(
         ctx: Context<ModifyLiquidity>,
         params: IncreaseLiquidityParams,
@@ -138,20 +448,53 @@ pub mod clad {
         return instructions::increase_liquidity(ctx, &params);
     }
 
+    /// Add the maximal liquidity that an amount of one token deposits in a position at the current
+    /// price. The other token, if the price is in the position range, is deposited as needed.
+    /// The hook program of the Globalpool, if any, is called as by `increase_liquidity`.
+    ///
+    /// ### Authority
+    /// - `position_authority` - authority that owns the token corresponding to this desired position.
+    ///
+    /// ### Parameters
+    /// - `token_amount` - The amount of the input token to deposit, including transfer fees.
+    /// - `is_token_a` - True if the input token is tokenA, false if it is tokenB.
+    /// - `sqrt_price_lower_limit` - The minimum sqrt price of the Globalpool to deposit at.
+    /// - `sqrt_price_upper_limit` - The maximum sqrt price of the Globalpool to deposit at.
+    ///
+    /// #### Special Errors
+    /// - `SqrtPriceOutOfRange` - The Globalpool price is outside of the sqrt price limits.
+    /// - `InvalidDepositToken` - The price is out of the position range, on the side of the other token.
+    /// - `LiquidityZero` - The token amount is too small to deposit any liquidity.
+    /// - `RangeOrderPositionUnsupported` - The position holds a range order.
+    pub fn increase_liquidity_by_token_amount(
+        ctx: Context<ModifyLiquidity>,
+        params: IncreaseLiquidityByTokenAmountParams,
+    ) -> Result<()> {
+        return instructions::increase_liquidity_by_token_amount(ctx, &params);
+    }
+
     /// Withdraw liquidity from a position in the Globalpool. This call also updates the position's accrued fees.
     ///
+    /// In native SOL mode, enabled by passing the System Program as the first remaining account, the
+    /// SOL side token account is the temporary wSOL account of `position_authority`, closed once the
+    /// liquidity is withdrawn to pay out SOL.
+    ///
+    /// If the Globalpool has a hook program, it is passed last among the remaining accounts,
+    /// followed by the accounts of the hook, and called before and after the withdrawal.
+    ///
     /// ### Authority
     /// - `position_authority` - authority that owns the token corresponding to this desired position.
     ///
     /// ### Parameters
     /// - `liquidity_amount` - The total amount of Liquidity the user desires to withdraw.
-    /// - `token_min_a` - The minimum amount of tokenA the user is willing to withdraw.
-    /// - `token_min_b` - The minimum amount of tokenB the user is willing to withdraw.
+    /// - `token_min_a` - The minimum amount of tokenA the user is willing to receive, net of transfer fees.
+    /// - `token_min_b` - The minimum amount of tokenB the user is willing to receive, net of transfer fees.
     ///
     /// #### Special Errors
     /// - `LiquidityZero` - Provided liquidity amount is zero.
     /// - `LiquidityTooHigh` - Provided liquidity exceeds u128::max.
     /// - `TokenMinSubceeded` - The required token to perform this operation subceeds the user defined amount.
+    /// - `RangeOrderPositionUnsupported` - The position holds a range order, see `claim_range_order`.
     pub fn decrease_liquidity(
         ctx: Context<ModifyLiquidity>,
         params: DecreaseLiquidityParams,
@@ -159,18 +502,131 @@ pub mod clad {
         return instructions::decrease_liquidity(ctx, &params);
     }
 
-    /// Collect fees accrued for this position.
+    /// Place a range order in an empty position spanning one tick spacing. The order holds only the
+    /// token it converts from, and is filled once a swap fully crosses its range: its liquidity is
+    /// then removed from the pool, so a later price reversal cannot convert it back.
+    /// Range orders don't earn fees: while the price is within their range, swap fees are shared by
+    /// the rest of the active liquidity.
     ///
     /// ### Authority
     /// - `position_authority` - authority that owns the token corresponding to this desired position.
     ///
+    /// ### Parameters
+    /// - `liquidity_amount` - The amount of Liquidity to place.
+    /// - `token_max` - The maximum amount of the input token the user is willing to deposit, including transfer fees.
+    /// - `a_to_b` - The order converts tokenA to tokenB, and lies above the current price. Otherwise it lies below.
+    ///
+    /// #### Special Errors
+    /// - `LiquidityZero` - Provided liquidity amount is zero.
+    /// - `InvalidRangeOrderTicks` - The position doesn't span one tick spacing of a tick_array on the input token side of the price.
+    /// - `RangeOrderPositionNotEmpty` - The position has liquidity or fees owed.
+    /// - `RangeOrderDirectionMismatch` - The fill tick holds unfilled range orders in the other direction.
+    /// - `TickArrayExtensionMissing` - The tick_array of the fill tick has to be migrated, see `migrate_tick_array`.
+    /// - `TokenMaxExceeded` - The required token to perform this operation exceeds the user defined amount.
+    /// - `HookUnsupported` - The Globalpool has a hook program.
+    pub fn open_range_order(
+        ctx: Context<ModifyLiquidity>,
+        params: OpenRangeOrderParams,
+    ) -> Result<()> {
+        return instructions::open_range_order(ctx, &params);
+    }
+
+    /// Claim a range order. A filled order withdraws the token it was converted into, an unfilled
+    /// order is cancelled and withdraws its remaining tokens. The position is left empty.
+    ///
+    /// ### Authority
+    /// - `position_authority` - authority that owns the token corresponding to this desired position.
+    ///
+    /// ### Parameters
+    /// - `token_min_a` - The minimum amount of tokenA the user is willing to receive, net of transfer fees.
+    /// - `token_min_b` - The minimum amount of tokenB the user is willing to receive, net of transfer fees.
+    ///
+    /// #### Special Errors
+    /// - `NotRangeOrderPosition` - The position doesn't hold a range order.
+    /// - `TokenMinSubceeded` - The required token to perform this operation subceeds the user defined amount.
+    pub fn claim_range_order(
+        ctx: Context<ModifyLiquidity>,
+        params: ClaimRangeOrderParams,
+    ) -> Result<()> {
+        return instructions::claim_range_order(ctx, &params);
+    }
+
+    /// Collect fees accrued for this position.
+    ///
+    /// In native SOL mode, enabled by passing the System Program as the first remaining account, the
+    /// SOL side token account is the temporary wSOL account of `position_authority`, closed once the
+    /// fees are collected to pay out SOL.
+    ///
+    /// ### Authority
+    /// - `position_authority` - authority that owns the token corresponding to this desired position,
+    ///                          or an operator permitted to collect fees. An operator passes its
+    ///                          PositionOperator as the next remaining account and can only collect
+    ///                          to the owner's token accounts.
+    ///
     /// ### Special Errors
     /// - `TickNotFound` - Provided tick array account does not contain the tick for this position.
     /// - `LiquidityZero` - Position has zero liquidity and therefore already has the most updated fees values.
+    /// - `RangeOrderPositionUnsupported` - The position holds a range order, which doesn't earn fees.
+    /// - `PositionOperatorNotPermitted` - The operator isn't permitted to collect fees.
+    /// - `InvalidOperatorTokenAccount` - An operator collects to token accounts the owner doesn't own.
     pub fn collect_fees(ctx: Context<CollectFees>) -> Result<()> {
         return instructions::collect_fees(ctx);
     }
 
+    /// Reinvest the fees accrued for this position as liquidity of the position. The fees are
+    /// rebalanced to the position's token ratio at the current price with a swap in this Globalpool,
+    /// which takes the tick-arrays (and remaining accounts) of `swap`. Leftover dust stays owed.
+    ///
+    /// ### Authority
+    /// - `position_authority` - authority that owns the token corresponding to this desired position,
+    ///                          its delegate, or an operator permitted to compound fees. An operator
+    ///                          passes its PositionOperator as the first remaining account.
+    ///
+    /// ### Parameters
+    /// - `sqrt_price_lower_limit` - The minimum sqrt price of the Globalpool, before and after the swap.
+    /// - `sqrt_price_upper_limit` - The maximum sqrt price of the Globalpool, before and after the swap.
+    ///
+    /// ### Special Errors
+    /// - `SqrtPriceOutOfRange` - The Globalpool price is outside of the sqrt price limits.
+    /// - `LiquidityZero` - The fees are too small to deposit any liquidity.
+    /// - `RangeOrderPositionUnsupported` - The position holds a range order, which doesn't earn fees.
+    /// - `PositionOperatorNotPermitted` - The operator isn't permitted to compound fees.
+    /// - `HookUnsupported` - The Globalpool has a hook program.
+    pub fn compound_fees(ctx: Context<CompoundFees>, params: CompoundFeesParams) -> Result<()> {
+        return instructions::compound_fees(ctx, &params);
+    }
+
+    /// Approve an operator to call some instructions on a Liquidity or Trade Position on behalf of
+    /// its owner, or update the operator's permissions. The approval ends when the position token
+    /// is transferred.
+    ///
+    /// ### Authority
+    /// - `position_authority` - authority that owns the token corresponding to this position, or its
+    ///                          delegate.
+    ///
+    /// ### Parameters
+    /// - `permissions` - Bitmask of OPERATOR_COLLECT_FEES, OPERATOR_COMPOUND_FEES and
+    ///                   OPERATOR_REPAY_TRADE.
+    ///
+    /// ### Special Errors
+    /// - `InvalidPositionAccount` - The position isn't a Liquidity or Trade Position.
+    /// - `InvalidPositionOperatorPermissions` - The permissions set unknown bits.
+    pub fn set_position_operator(
+        ctx: Context<SetPositionOperator>,
+        params: SetPositionOperatorParams,
+    ) -> Result<()> {
+        return instructions::set_position_operator(ctx, &params);
+    }
+
+    /// Revoke an operator of a Liquidity or Trade Position, closing its PositionOperator.
+    ///
+    /// ### Authority
+    /// - `position_authority` - authority that owns the token corresponding to this position, or its
+    ///                          delegate.
+    pub fn remove_position_operator(ctx: Context<RemovePositionOperator>) -> Result<()> {
+        return instructions::remove_position_operator(ctx);
+    }
+
     /// Collect the protocol fees accrued in this Globalpool
     ///
     /// ### Authority
@@ -179,23 +635,233 @@ pub mod clad {
         return instructions::collect_protocol_fees(ctx);
     }
 
+    /// Borrow tokens from the vaults of this Globalpool within a single transaction. The loan must
+    /// be repaid by a `flash_repay` of the same Globalpool later in the transaction. The liquidity
+    /// and price of the pool are not changed. Until the loan is repaid, swaps, liquidity changes
+    /// and loans of the Globalpool fail with `FlashLoanInProgress`.
+    ///
+    /// ### Parameters
+    /// - `amount_a` - The amount of token A to borrow.
+    /// - `amount_b` - The amount of token B to borrow.
+    ///
+    /// The borrower receives the amounts less the transfer fee of the mints, if any.
+    ///
+    /// #### Special Errors
+    /// - `FlashLoanAmountZero` - Both amounts are zero.
+    /// - `FlashLoanInProgress` - The Globalpool already has an outstanding flash loan.
+    /// - `FlashRepayMissing` - No `flash_repay` of this Globalpool follows in the transaction.
+    /// - `FlashBorrowCpiNotAllowed` - The instruction was invoked via CPI.
+    /// - `HookUnsupported` - The Globalpool has a hook program.
+    pub fn flash_borrow(ctx: Context<FlashBorrow>, params: FlashBorrowParams) -> Result<()> {
+        return instructions::flash_borrow(ctx, &params);
+    }
+
+    /// Repay the outstanding flash loan of this Globalpool, plus the flash fee, which is paid to
+    /// the LPs through the fee growth. The repayer also pays the transfer fee of the mints, if any,
+    /// so that the vaults receive the full amounts.
+    ///
+    /// #### Special Errors
+    /// - `FlashLoanNotFound` - The Globalpool has no outstanding flash loan.
+    pub fn flash_repay(ctx: Context<FlashRepay>) -> Result<()> {
+        return instructions::flash_repay(ctx);
+    }
+
+    /// Accrue the funding index of this Globalpool at its current utilization. Permissionless.
+    /// Perpetual trade positions passed as remaining accounts pay the funding they owe to the LPs
+    /// out of their collateral.
+    ///
+    /// #### Special Errors
+    /// - `PositionNotPerpetual` - A provided trade position is not perpetual.
+    pub fn crank_funding(ctx: Context<CrankFunding>) -> Result<()> {
+        return instructions::crank_funding(ctx);
+    }
+
+    /// Set the flash loan fee rate of this Globalpool.
+    ///
+    /// ### Authority
+    /// - `fee_authority` - assigned fee authority of the Globalpool.
+    ///
+    /// ### Parameters
+    /// - `flash_fee_rate` - The flash fee rate, in hundredths of a basis point.
+    ///
+    /// #### Special Errors
+    /// - `FeeRateMaxExceeded` - If the provided flash_fee_rate exceeds MAX_FEE_RATE.
+    pub fn set_flash_fee_rate(ctx: Context<SetFlashFeeRate>, flash_fee_rate: u16) -> Result<()> {
+        return instructions::set_flash_fee_rate(ctx, flash_fee_rate);
+    }
+
+    /// Set the loan risk caps of this Globalpool. A cap of zero is disabled.
+    ///
+    /// ### Authority
+    /// - `fee_authority` - assigned fee authority of the Globalpool.
+    ///
+    /// ### Parameters
+    /// - `max_borrowed_liquidity_bps` - Max total borrowed liquidity as a share of `liquidity_available`, in basis points.
+    /// - `max_tick_utilization_bps` - Max borrowed liquidity as a share of a tick's gross liquidity, in basis points.
+    /// - `max_position_notional_a` - Max amount of token A borrowed by a single trade position.
+    /// - `max_position_notional_b` - Max amount of token B borrowed by a single trade position.
+    ///
+    /// #### Special Errors
+    /// - `InvalidRiskParameter` - A basis point cap exceeds 10_000.
+    pub fn set_risk_parameters(
+        ctx: Context<SetRiskParameters>,
+        params: SetRiskParametersParams,
+    ) -> Result<()> {
+        return instructions::set_risk_parameters(ctx, &params);
+    }
+
+    /// Set the dynamic fee parameters of this Globalpool. Swaps pay the base `fee_rate` plus a
+    /// variable fee growing with the square of the recent price movement (in tick groups of
+    /// `tick_spacing` ticks). Setting the parameters resets the volatility state.
+    ///
+    /// ### Authority
+    /// - `fee_authority` - assigned fee authority of the Globalpool.
+    ///
+    /// ### Parameters
+    /// - `variable_fee_control` - Scale of the variable fee. Zero disables the dynamic fee.
+    /// - `max_volatility_accumulator` - Cap of the volatility accumulator (tick groups crossed * 10_000).
+    /// - `filter_period` - Seconds during which swaps keep the same volatility reference.
+    /// - `decay_period` - Seconds after which the volatility reference is reset to zero.
+    /// - `reduction_factor` - Share of the volatility accumulator kept as reference, in basis points.
+    ///
+    /// #### Special Errors
+    /// - `InvalidDynamicFeeParameter` - The reduction factor exceeds 10_000, or the filter period is
+    ///                                  not shorter than the decay period.
+    pub fn set_dynamic_fee_parameters(
+        ctx: Context<SetDynamicFeeParameters>,
+        params: SetDynamicFeeParametersParams,
+    ) -> Result<()> {
+        return instructions::set_dynamic_fee_parameters(ctx, &params);
+    }
+
+    /// Register the Pyth price feed of a token mint, used to value margin collateral & positions.
+    /// Creates the TokenPriceFeed account of the mint, or replaces its price feed.
+    ///
+    /// ### Authority
+    /// - `admin` - protocol admin, i.e. the upgrade authority of the program.
+    ///
+    /// #### Special Errors
+    /// - `InvalidProtocolAdmin` - The signer is not the upgrade authority of the program.
+    pub fn set_token_price_feed(ctx: Context<SetTokenPriceFeed>) -> Result<()> {
+        return instructions::set_token_price_feed(ctx);
+    }
+
+    /// Initializes the cross-margin account of the owner.
+    pub fn initialize_margin_account(ctx: Context<InitializeMarginAccount>) -> Result<()> {
+        return instructions::initialize_margin_account(ctx);
+    }
+
+    /// Deposit collateral into the margin account. The mint needs a price feed registered by the
+    /// protocol admin (see `set_token_price_feed`).
+    ///
+    /// ### Authority
+    /// - `owner` - owner of the margin account.
+    ///
+    /// ### Parameters
+    /// - `amount` - The amount of collateral to deposit.
+    ///
+    /// #### Special Errors
+    /// - `AccountNotInitialized` - No price feed is registered for the mint.
+    /// - `MarginCollateralSlotsFull` - The margin account already holds the max number of collateral mints.
+    /// - `Token2022MintUnsupported` - The mint is a Token-2022 mint.
+    pub fn deposit_margin_collateral(
+        ctx: Context<DepositMarginCollateral>,
+        params: DepositMarginCollateralParams,
+    ) -> Result<()> {
+        return instructions::deposit_margin_collateral(ctx, &params);
+    }
+
+    /// Withdraw collateral from the margin account. The account has to remain healthy afterwards.
+    ///
+    /// ### Authority
+    /// - `owner` - owner of the margin account.
+    ///
+    /// ### Parameters
+    /// - `amount` - The amount of collateral to withdraw.
+    ///
+    /// #### Special Errors
+    /// - `MarginAccountLiquidating` - The margin account is being liquidated.
+    /// - `MarginAccountUnhealthy` - The withdrawal would put the account below maintenance margin.
+    /// - `Token2022MintUnsupported` - The mint is a Token-2022 mint.
+    pub fn withdraw_margin_collateral(
+        ctx: Context<WithdrawMarginCollateral>,
+        params: WithdrawMarginCollateralParams,
+    ) -> Result<()> {
+        return instructions::withdraw_margin_collateral(ctx, &params);
+    }
+
+    /// Link a trade position to the margin account, so that it shares the account's collateral.
+    /// The position token has to be held in the owner's associated token account. Once transferred
+    /// away, the position's debt still counts towards the account's health, but its assets don't.
+    ///
+    /// ### Authority
+    /// - `owner` - owner of the margin account & the position token.
+    ///
+    /// #### Special Errors
+    /// - `MarginPositionSlotsFull` - The margin account already holds the max number of positions.
+    /// - `MarginPositionAlreadyLinked` - The position is already linked.
+    pub fn link_trade_position(ctx: Context<LinkTradePosition>) -> Result<()> {
+        return instructions::link_trade_position(ctx);
+    }
+
+    /// Unlink a trade position from the margin account. Open positions can only be unlinked
+    /// if the account remains healthy afterwards.
+    ///
+    /// ### Authority
+    /// - `owner` - owner of the margin account.
+    ///
+    /// #### Special Errors
+    /// - `MarginPositionNotLinked` - The position is not linked to the margin account.
+    /// - `MarginAccountUnhealthy` - Unlinking would put the account below maintenance margin.
+    /// - `MarginBackedPositionNotRepaid` - The position is backed by the account and not repaid.
+    pub fn unlink_trade_position(ctx: Context<UnlinkTradePosition>) -> Result<()> {
+        return instructions::unlink_trade_position(ctx);
+    }
+
+    /// Liquidate an unhealthy margin account. All linked positions are force-matured so that
+    /// they can be repaid by liquidators. Positions backed by the account settle their debt, and
+    /// the liquidator's share, out of its collateral.
+    ///
+    /// #### Special Errors
+    /// - `MarginAccountHealthy` - The margin account is above maintenance margin.
+    pub fn liquidate_margin_account(ctx: Context<LiquidateMarginAccount>) -> Result<()> {
+        return instructions::liquidate_margin_account(ctx);
+    }
+
     /// Perform a swap in this Globalpool
     ///
+    /// Swaps crossing more than three tick-arrays pass the following tick-arrays as remaining accounts,
+    /// writable and ordered in the swap direction. If the Globalpool's tick_array_bitmap is passed as
+    /// the first remaining account and is backfilled, tick-arrays it records as uninitialized can be
+    /// left out of the sequence.
+    ///
+    /// In native SOL mode, enabled by passing the System Program before any other remaining account,
+    /// the SOL side token account is the temporary wSOL account of `token_authority`. The input is
+    /// wrapped from its lamports (up to `other_amount_threshold` for exact output swaps), and the
+    /// account is closed after the swap, unwrapping the output and any unused input.
+    ///
+    /// If the Globalpool has a hook program, it is passed last among the remaining accounts,
+    /// followed by the accounts of the hook, and called before and after the swap.
+    ///
     /// ### Authority
     /// - "token_authority" - The authority to withdraw tokens from the input token account.
     ///
     /// ### Parameters
     /// - `amount` - The amount of input or output token to swap from (depending on amount_specified_is_input).
-    /// - `other_amount_threshold` - The maximum/minimum of input/output token to swap into (depending on amount_specified_is_input).
+    ///              Transfer fees are included in an input amount and excluded from an output amount.
+    /// - `other_amount_threshold` - The maximum/minimum of input/output token to swap into (depending on amount_specified_is_input),
+    ///                              likewise including/excluding transfer fees.
     /// - `sqrt_price_limit` - The maximum/minimum price the swap will swap to.
     /// - `amount_specified_is_input` - Specifies the token the parameter `amount`represents. If true, the amount represents the input token of the swap.
     /// - `a_to_b` - The direction of the swap. True if swapping from A to B. False if swapping from B to A.
     ///
     /// #### Special Errors
     /// - `ZeroTradableAmount` - User provided parameter `amount` is 0.
+    /// - `InvalidNativeSolAccount` - In native SOL mode, the SOL side token account isn't the temporary wSOL account.
     /// - `InvalidSqrtPriceLimitDirection` - User provided parameter `sqrt_price_limit` does not match the direction of the trade.
     /// - `SqrtPriceOutOfBounds` - User provided parameter `sqrt_price_limit` is over Whirlppool's max/min bounds for sqrt-price.
-    /// - `InvalidTickArraySequence` - User provided tick-arrays are not in sequential order required to proceed in this trade direction.
+    /// - `InvalidTickArraySequence` - User provided tick-arrays are not in sequential order required to proceed in this trade direction,
+    ///                                or skip tick-arrays that are initialized.
     /// - `TickArraySequenceInvalidIndex` - The swap loop attempted to access an invalid array index during the query of the next initialized tick.
     /// - `TickArrayIndexOutofBounds` - The swap loop attempted to access an invalid array index during tick crossing.
     /// - `LiquidityOverflow` - Liquidity value overflowed 128bits during tick crossing.
@@ -203,4 +869,40 @@ pub mod clad {
     pub fn swap(ctx: Context<Swap>, params: SwapParams) -> Result<()> {
         return instructions::swap(ctx, &params);
     }
+
+    /// Quote a swap in this Globalpool without changing any state. The result is a borsh-encoded
+    /// `QuoteSwapResult`, returned through `set_return_data` so callers can read it after CPI.
+    ///
+    /// Takes the tick-arrays and remaining accounts of `swap`, all read-only. The crossed
+    /// tick-arrays are copied to the heap, so quotes going through more than three tick-arrays
+    /// may need a larger heap frame.
+    ///
+    /// ### Parameters
+    /// - `amount` - The amount of input or output token to swap from (depending on amount_specified_is_input),
+    ///              including/excluding transfer fees like in `swap`.
+    /// - `sqrt_price_limit` - The maximum/minimum price the swap will swap to.
+    /// - `amount_specified_is_input` - Specifies the token the parameter `amount`represents. If true, the amount represents the input token of the swap.
+    /// - `a_to_b` - The direction of the swap. True if swapping from A to B. False if swapping from B to A.
+    ///
+    /// #### Special Errors
+    /// Same as `swap`, except for the threshold errors.
+    pub fn quote_swap(ctx: Context<QuoteSwap>, params: QuoteSwapParams) -> Result<()> {
+        return instructions::quote_swap(ctx, &params);
+    }
+
+    /// Quote the repayment of a trade position at the current price without changing any state.
+    /// The result is a borsh-encoded `QuoteTradePositionResult`, returned through
+    /// `set_return_data` so callers can read it after CPI.
+    ///
+    /// Perpetual positions pass the oracle accounts of `repay_trade_position` first in the
+    /// remaining accounts, to tell whether they are liquidatable. Positions collateralized by a
+    /// liquidity position then pass it.
+    ///
+    /// #### Special Errors
+    /// - `InvalidTradePositionType` - The position is a leveraged liquidity position.
+    /// - `InvalidLiquidityCollateralAccounts` - The liquidity position collateralizing the position is missing.
+    /// - `InvalidPerpetualHealthAccounts` - The oracle accounts of a perpetual position are missing or don't match the pool tokens.
+    pub fn quote_trade_position(ctx: Context<QuoteTradePosition>) -> Result<()> {
+        return instructions::quote_trade_position(ctx);
+    }
 }
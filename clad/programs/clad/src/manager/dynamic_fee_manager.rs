@@ -0,0 +1,231 @@
+use crate::{
+    math::{
+        MAX_FEE_RATE, REDUCTION_FACTOR_DENOMINATOR, VARIABLE_FEE_CONTROL_DENOMINATOR,
+        VOLATILITY_ACCUMULATOR_SCALE_FACTOR,
+    },
+    state::{Globalpool, VolatilityUpdate},
+};
+
+//
+// Effective fee rate of a swap: the Globalpool's base `fee_rate` plus a variable fee which grows
+// with the square of the volatility accumulator, i.e. the tick groups the price moved away from
+// the reference of recent swaps.
+//
+#[derive(Debug)]
+pub struct DynamicFee {
+    base_fee_rate: u16,
+    variable_fee_control: u32,
+    max_volatility_accumulator: u32,
+    tick_spacing: u16,
+    volatility: VolatilityUpdate,
+}
+
+impl DynamicFee {
+    // Volatility state at the start of a swap. Swaps within `filter_period` of the last update
+    // share its reference; afterwards the reference moves to the current tick and keeps a decayed
+    // share of the accumulator, or none after `decay_period`.
+    pub fn new(globalpool: &Globalpool, timestamp: u64) -> Self {
+        let mut volatility = VolatilityUpdate {
+            volatility_reference: globalpool.volatility_reference,
+            volatility_accumulator: globalpool.volatility_accumulator,
+            tick_group_index_reference: globalpool.tick_group_index_reference,
+        };
+
+        if globalpool.is_dynamic_fee_enabled() {
+            let elapsed = timestamp.saturating_sub(globalpool.last_volatility_update);
+
+            if elapsed >= globalpool.filter_period as u64 {
+                volatility.tick_group_index_reference =
+                    tick_group_index(globalpool.tick_current_index, globalpool.tick_spacing);
+                volatility.volatility_reference = if elapsed < globalpool.decay_period as u64 {
+                    ((volatility.volatility_accumulator as u64)
+                        * (globalpool.reduction_factor as u64)
+                        / (REDUCTION_FACTOR_DENOMINATOR as u64)) as u32
+                } else {
+                    0
+                };
+            }
+        }
+
+        DynamicFee {
+            base_fee_rate: globalpool.fee_rate,
+            variable_fee_control: globalpool.variable_fee_control,
+            max_volatility_accumulator: globalpool.max_volatility_accumulator,
+            tick_spacing: globalpool.tick_spacing,
+            volatility,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.variable_fee_control > 0
+    }
+
+    // Accumulates the distance of `tick_index` from the reference tick group
+    pub fn update_volatility_accumulator(&mut self, tick_index: i32) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let tick_group_delta = (tick_group_index(tick_index, self.tick_spacing)
+            - self.volatility.tick_group_index_reference)
+            .unsigned_abs() as u64;
+
+        let volatility_accumulator = (self.volatility.volatility_reference as u64)
+            .saturating_add(tick_group_delta * VOLATILITY_ACCUMULATOR_SCALE_FACTOR as u64);
+
+        self.volatility.volatility_accumulator =
+            volatility_accumulator.min(self.max_volatility_accumulator as u64) as u32;
+    }
+
+    pub fn fee_rate(&self) -> u16 {
+        if !self.is_enabled() {
+            return self.base_fee_rate;
+        }
+
+        let crossed =
+            (self.volatility.volatility_accumulator as u128) * (self.tick_spacing as u128);
+        let denominator = (VARIABLE_FEE_CONTROL_DENOMINATOR as u128)
+            * (VOLATILITY_ACCUMULATOR_SCALE_FACTOR as u128)
+            * (VOLATILITY_ACCUMULATOR_SCALE_FACTOR as u128);
+
+        // Rounded up. Saturation is fine, as the fee rate is capped at MAX_FEE_RATE.
+        let variable_fee_rate = (self.variable_fee_control as u128)
+            .saturating_mul(crossed)
+            .saturating_mul(crossed)
+            .saturating_add(denominator - 1)
+            / denominator;
+
+        (self.base_fee_rate as u128)
+            .saturating_add(variable_fee_rate)
+            .min(MAX_FEE_RATE as u128) as u16
+    }
+
+    pub fn volatility_update(&self) -> VolatilityUpdate {
+        self.volatility
+    }
+}
+
+fn tick_group_index(tick_index: i32, tick_spacing: u16) -> i32 {
+    tick_index.div_euclid(tick_spacing.max(1) as i32)
+}
+
+#[cfg(test)]
+mod dynamic_fee_tests {
+    use {super::*, crate::state::globalpool_builder::GlobalpoolBuilder};
+
+    fn dynamic_fee_globalpool(tick_current_index: i32) -> Globalpool {
+        let mut globalpool = GlobalpoolBuilder::new()
+            .tick_spacing(64)
+            .fee_rate(3000)
+            .tick_current_index(tick_current_index)
+            .build();
+        globalpool
+            .update_dynamic_fee_parameters(4_000, 350_000, 30, 600, 5_000)
+            .unwrap();
+        globalpool
+    }
+
+    #[test]
+    fn disabled_uses_base_fee_rate() {
+        let globalpool = GlobalpoolBuilder::new()
+            .tick_spacing(64)
+            .fee_rate(3000)
+            .build();
+        let mut dynamic_fee = DynamicFee::new(&globalpool, 1_000);
+        dynamic_fee.update_volatility_accumulator(64 * 100);
+
+        assert_eq!(dynamic_fee.fee_rate(), 3000);
+        assert_eq!(dynamic_fee.volatility_update(), VolatilityUpdate::default());
+    }
+
+    #[test]
+    fn no_movement_no_variable_fee() {
+        let globalpool = dynamic_fee_globalpool(100);
+        let mut dynamic_fee = DynamicFee::new(&globalpool, 1_000);
+        dynamic_fee.update_volatility_accumulator(120);
+
+        assert_eq!(dynamic_fee.fee_rate(), 3000);
+    }
+
+    #[test]
+    fn variable_fee_grows_with_tick_movement() {
+        let globalpool = dynamic_fee_globalpool(0);
+        let mut dynamic_fee = DynamicFee::new(&globalpool, 1_000);
+
+        // 1 tick group: 4_000 * (10_000 * 64)^2 / (100_000 * 10_000^2) = 163.84
+        dynamic_fee.update_volatility_accumulator(64);
+        assert_eq!(
+            dynamic_fee.volatility_update().volatility_accumulator,
+            10_000
+        );
+        assert_eq!(dynamic_fee.fee_rate(), 3000 + 164);
+
+        // Movement is measured from the reference in either direction
+        dynamic_fee.update_volatility_accumulator(-128);
+        assert_eq!(
+            dynamic_fee.volatility_update().volatility_accumulator,
+            20_000
+        );
+        assert_eq!(dynamic_fee.fee_rate(), 3000 + 656);
+    }
+
+    #[test]
+    fn volatility_accumulator_capped() {
+        let globalpool = dynamic_fee_globalpool(0);
+        let mut dynamic_fee = DynamicFee::new(&globalpool, 1_000);
+        dynamic_fee.update_volatility_accumulator(64 * 1_000);
+
+        assert_eq!(
+            dynamic_fee.volatility_update().volatility_accumulator,
+            350_000
+        );
+        assert_eq!(dynamic_fee.fee_rate(), MAX_FEE_RATE);
+    }
+
+    #[test]
+    fn reference_kept_within_filter_period() {
+        let mut globalpool = dynamic_fee_globalpool(64 * 3);
+        globalpool.volatility_reference = 5_000;
+        globalpool.volatility_accumulator = 30_000;
+        globalpool.tick_group_index_reference = 0;
+        globalpool.last_volatility_update = 1_000;
+
+        let dynamic_fee = DynamicFee::new(&globalpool, 1_010);
+        assert_eq!(
+            dynamic_fee.volatility_update(),
+            VolatilityUpdate {
+                volatility_reference: 5_000,
+                volatility_accumulator: 30_000,
+                tick_group_index_reference: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn reference_decayed_after_filter_period() {
+        let mut globalpool = dynamic_fee_globalpool(64 * 3);
+        globalpool.volatility_accumulator = 30_000;
+        globalpool.last_volatility_update = 1_000;
+
+        let dynamic_fee = DynamicFee::new(&globalpool, 1_100);
+        assert_eq!(dynamic_fee.volatility_update().volatility_reference, 15_000);
+        assert_eq!(
+            dynamic_fee.volatility_update().tick_group_index_reference,
+            3
+        );
+    }
+
+    #[test]
+    fn reference_reset_after_decay_period() {
+        let mut globalpool = dynamic_fee_globalpool(-64 * 3 - 1);
+        globalpool.volatility_accumulator = 30_000;
+        globalpool.last_volatility_update = 1_000;
+
+        let dynamic_fee = DynamicFee::new(&globalpool, 1_600);
+        assert_eq!(dynamic_fee.volatility_update().volatility_reference, 0);
+        assert_eq!(
+            dynamic_fee.volatility_update().tick_group_index_reference,
+            -4
+        );
+    }
+}
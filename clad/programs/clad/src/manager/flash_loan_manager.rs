@@ -0,0 +1,105 @@
+use {
+    crate::{errors::ErrorCode, instruction::FlashRepay, math::*},
+    anchor_lang::{
+        prelude::*,
+        solana_program::sysvar::instructions::{
+            get_instruction_relative, load_current_index_checked, load_instruction_at_checked,
+        },
+        Discriminator,
+    },
+};
+
+// Index of the globalpool in the accounts of `flash_repay`
+pub const FLASH_REPAY_GLOBALPOOL_ACCOUNT_INDEX: usize = 1;
+
+// Fee of a flash loan of `amount`, rounded up (`flash_fee_rate` is in hundredths of a basis point)
+pub fn calculate_flash_fee(amount: u64, flash_fee_rate: u16) -> Result<u64> {
+    let fee = div_round_up(
+        (amount as u128) * (flash_fee_rate as u128),
+        FEE_RATE_MUL_VALUE,
+    )?;
+
+    Ok(u64::try_from(fee).map_err(|_| ErrorCode::AmountCalcOverflow)?)
+}
+
+//
+// Checks, via instruction introspection, that the current (top-level) instruction is followed by
+// a `flash_repay` of the same globalpool later in the transaction.
+//
+pub fn verify_flash_repay_follows(
+    instructions_sysvar: &AccountInfo,
+    globalpool: &Pubkey,
+) -> Result<()> {
+    // A flash borrow invoked via CPI could be followed by the repay of another top-level instruction
+    if get_instruction_relative(0, instructions_sysvar)?.program_id != crate::ID {
+        return Err(ErrorCode::FlashBorrowCpiNotAllowed.into());
+    }
+
+    let mut index = load_current_index_checked(instructions_sysvar)? as usize + 1;
+
+    while let Ok(instruction) = load_instruction_at_checked(index, instructions_sysvar) {
+        if instruction.program_id == crate::ID
+            && instruction.data.get(..8) == Some(&FlashRepay::DISCRIMINATOR[..])
+            && instruction
+                .accounts
+                .get(FLASH_REPAY_GLOBALPOOL_ACCOUNT_INDEX)
+                .map_or(false, |account| account.pubkey == *globalpool)
+        {
+            return Ok(());
+        }
+
+        index += 1;
+    }
+
+    Err(ErrorCode::FlashRepayMissing.into())
+}
+
+#[cfg(test)]
+mod calculate_flash_fee_tests {
+    use super::*;
+
+    #[test]
+    fn zero_fee_rate() {
+        assert_eq!(calculate_flash_fee(1_000_000, 0).unwrap(), 0);
+    }
+
+    #[test]
+    fn fee_rate_applied() {
+        // 3000 => 0.3%
+        assert_eq!(calculate_flash_fee(1_000_000, 3000).unwrap(), 3000);
+    }
+
+    #[test]
+    fn fee_rounded_up() {
+        assert_eq!(calculate_flash_fee(1, 3000).unwrap(), 1);
+        assert_eq!(calculate_flash_fee(1_000_001, 3000).unwrap(), 3001);
+    }
+
+    #[test]
+    fn max_amount_does_not_overflow() {
+        assert_eq!(
+            calculate_flash_fee(u64::MAX, MAX_FEE_RATE).unwrap(),
+            u64::MAX / 100 + 1
+        );
+    }
+}
+
+#[cfg(test)]
+mod flash_repay_tests {
+    use {super::*, crate::state::globalpool_builder::GlobalpoolBuilder};
+
+    #[test]
+    fn fees_paid_to_fee_growth() {
+        let mut globalpool = GlobalpoolBuilder::new().liquidity(1 << 64).build();
+        globalpool.update_after_flash_borrow(1_000_000, 0);
+        assert!(globalpool.has_flash_loan());
+
+        globalpool.update_after_flash_repay(3000, 0);
+
+        // fee * 2^64 / liquidity
+        assert_eq!(globalpool.fee_growth_global_a, 3000);
+        assert_eq!(globalpool.fee_growth_global_b, 0);
+        assert_eq!(globalpool.liquidity_available, 1 << 64);
+        assert!(!globalpool.has_flash_loan());
+    }
+}
@@ -0,0 +1,274 @@
+use {
+    crate::{errors::ErrorCode, state::Globalpool},
+    anchor_lang::{
+        prelude::*,
+        solana_program::{instruction::Instruction, program::invoke},
+    },
+};
+
+//
+// A Globalpool may register a hook program at creation, which Clad calls before and after each
+// swap and liquidity change. The hook vetoes the operation by failing. It is never given signer
+// privileges, so it can move neither the pool's funds nor the user's.
+//
+// Hooks implement two instructions, as Anchor encodes them: `before_operation(params: HookParams)`
+// and `after_operation(params: HookParams)`. Their accounts are the globalpool and the authority
+// of the operation (read-only), then the accounts following the hook program in the remaining
+// accounts of the Clad instruction (with their signer flags dropped).
+//
+
+// First 8 bytes of sha256("global:before_operation")
+pub const HOOK_BEFORE_OPERATION_DISCRIMINATOR: [u8; 8] = [11, 23, 41, 90, 5, 76, 48, 222];
+// First 8 bytes of sha256("global:after_operation")
+pub const HOOK_AFTER_OPERATION_DISCRIMINATOR: [u8; 8] = [154, 142, 117, 78, 72, 71, 52, 56];
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookOperation {
+    Swap,
+    IncreaseLiquidity,
+    DecreaseLiquidity,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct HookParams {
+    pub operation: HookOperation,
+    pub globalpool: Pubkey,
+    pub authority: Pubkey,
+    // Liquidity position of liquidity changes, the default key for swaps
+    pub position: Pubkey,
+
+    // Deltas of the pool: positive amounts enter the vaults, negative amounts leave them
+    pub liquidity_delta: i128,
+    pub token_delta_a: i128,
+    pub token_delta_b: i128,
+
+    // Pool state before the operation for `before_operation`, after it for `after_operation`
+    pub sqrt_price: u128,
+    pub tick_current_index: i32,
+    pub liquidity_available: u128,
+}
+
+impl HookParams {
+    pub fn new(
+        operation: HookOperation,
+        globalpool: &Account<Globalpool>,
+        authority: &Pubkey,
+        position: Pubkey,
+        liquidity_delta: i128,
+        token_delta_a: i128,
+        token_delta_b: i128,
+    ) -> Self {
+        HookParams {
+            operation,
+            globalpool: globalpool.key(),
+            authority: *authority,
+            position,
+            liquidity_delta,
+            token_delta_a,
+            token_delta_b,
+            sqrt_price: 0,
+            tick_current_index: 0,
+            liquidity_available: 0,
+        }
+        .with_state(globalpool)
+    }
+
+    pub fn with_state(self, globalpool: &Globalpool) -> Self {
+        HookParams {
+            sqrt_price: globalpool.sqrt_price,
+            tick_current_index: globalpool.tick_current_index,
+            liquidity_available: globalpool.liquidity_available,
+            ..self
+        }
+    }
+}
+
+pub struct GlobalpoolHook<'a, 'info> {
+    program: &'a AccountInfo<'info>,
+    accounts: &'a [AccountInfo<'info>],
+}
+
+// Splits the hook program of the globalpool, and the hook accounts following it, off the end of
+// the remaining accounts
+pub fn load_globalpool_hook<'a, 'info>(
+    globalpool: &Globalpool,
+    accounts: &'a [AccountInfo<'info>],
+) -> Result<(Option<GlobalpoolHook<'a, 'info>>, &'a [AccountInfo<'info>])> {
+    let hook_program = match globalpool.get_hook_program() {
+        Some(hook_program) => hook_program,
+        None => return Ok((None, accounts)),
+    };
+
+    let index = accounts
+        .iter()
+        .position(|account| account.key() == hook_program)
+        .ok_or(ErrorCode::HookProgramMissing)?;
+    let (accounts, hook_accounts) = accounts.split_at(index);
+    if !hook_accounts[0].executable {
+        return Err(ErrorCode::InvalidHookProgram.into());
+    }
+
+    Ok((
+        Some(GlobalpoolHook {
+            program: &hook_accounts[0],
+            accounts: &hook_accounts[1..],
+        }),
+        accounts,
+    ))
+}
+
+impl<'a, 'info> GlobalpoolHook<'a, 'info> {
+    pub fn before_operation(
+        &self,
+        globalpool: &Account<'info, Globalpool>,
+        authority: &AccountInfo<'info>,
+        params: &HookParams,
+    ) -> Result<()> {
+        self.invoke(
+            HOOK_BEFORE_OPERATION_DISCRIMINATOR,
+            globalpool,
+            authority,
+            params,
+        )
+    }
+
+    // `params` are those of `before_operation`, updated with the pool state after the operation
+    pub fn after_operation(
+        &self,
+        globalpool: &Account<'info, Globalpool>,
+        authority: &AccountInfo<'info>,
+        params: HookParams,
+    ) -> Result<()> {
+        // Hooks reading the globalpool account see the state after the operation
+        globalpool.exit(&crate::ID)?;
+
+        self.invoke(
+            HOOK_AFTER_OPERATION_DISCRIMINATOR,
+            globalpool,
+            authority,
+            &params.with_state(globalpool),
+        )
+    }
+
+    fn invoke(
+        &self,
+        discriminator: [u8; 8],
+        globalpool: &Account<'info, Globalpool>,
+        authority: &AccountInfo<'info>,
+        params: &HookParams,
+    ) -> Result<()> {
+        let mut data = discriminator.to_vec();
+        data.extend(params.try_to_vec()?);
+
+        let mut accounts = vec![
+            AccountMeta::new_readonly(globalpool.key(), false),
+            AccountMeta::new_readonly(authority.key(), false),
+        ];
+        accounts.extend(self.accounts.iter().map(|account| {
+            if account.is_writable {
+                AccountMeta::new(account.key(), false)
+            } else {
+                AccountMeta::new_readonly(account.key(), false)
+            }
+        }));
+
+        let mut account_infos = vec![
+            self.program.clone(),
+            globalpool.to_account_info(),
+            authority.clone(),
+        ];
+        account_infos.extend_from_slice(self.accounts);
+
+        invoke(
+            &Instruction {
+                program_id: self.program.key(),
+                accounts,
+                data,
+            },
+            &account_infos,
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod hook_interface_tests {
+    use {
+        super::*, crate::state::globalpool_builder::GlobalpoolBuilder,
+        anchor_lang::solana_program::hash::hash,
+    };
+
+    #[test]
+    fn discriminators_match_anchor_instructions() {
+        assert_eq!(
+            HOOK_BEFORE_OPERATION_DISCRIMINATOR,
+            hash(b"global:before_operation").to_bytes()[..8]
+        );
+        assert_eq!(
+            HOOK_AFTER_OPERATION_DISCRIMINATOR,
+            hash(b"global:after_operation").to_bytes()[..8]
+        );
+    }
+
+    #[test]
+    fn with_state_keeps_deltas() {
+        let params = HookParams {
+            operation: HookOperation::Swap,
+            globalpool: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            position: Pubkey::default(),
+            liquidity_delta: 0,
+            token_delta_a: 1_000,
+            token_delta_b: -990,
+            sqrt_price: 1 << 64,
+            tick_current_index: 0,
+            liquidity_available: 5_000,
+        };
+        let globalpool = GlobalpoolBuilder::new()
+            .sqrt_price(2 << 64)
+            .tick_current_index(6931)
+            .liquidity(7_000)
+            .build();
+
+        assert_eq!(
+            params.clone().with_state(&globalpool),
+            HookParams {
+                sqrt_price: 2 << 64,
+                tick_current_index: 6931,
+                liquidity_available: 7_000,
+                ..params
+            }
+        );
+    }
+
+    #[test]
+    fn no_hook_by_default() {
+        let mut globalpool = GlobalpoolBuilder::new().build();
+        assert_eq!(globalpool.get_hook_program(), None);
+
+        globalpool.initialize_hook_program(None).unwrap();
+        assert_eq!(globalpool.get_hook_program(), None);
+    }
+
+    #[test]
+    fn hook_program_set_at_creation() {
+        let hook_program = Pubkey::new_unique();
+        let mut globalpool = GlobalpoolBuilder::new().build();
+
+        globalpool
+            .initialize_hook_program(Some(hook_program))
+            .unwrap();
+        assert_eq!(globalpool.get_hook_program(), Some(hook_program));
+    }
+
+    #[test]
+    fn clad_cannot_be_its_own_hook() {
+        let mut globalpool = GlobalpoolBuilder::new().build();
+
+        assert!(globalpool.initialize_hook_program(Some(crate::ID)).is_err());
+        assert!(globalpool
+            .initialize_hook_program(Some(Pubkey::default()))
+            .is_err());
+    }
+}
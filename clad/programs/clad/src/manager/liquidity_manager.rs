@@ -7,7 +7,10 @@ use {
     },
     crate::{
         errors::ErrorCode,
-        math::{get_amount_delta_a, get_amount_delta_b, sqrt_price_from_tick_index},
+        math::{
+            get_amount_delta_a, get_amount_delta_b, get_liquidity_delta_a, get_liquidity_delta_b,
+            mul_u256, sqrt_price_from_tick_index, U256Muldiv,
+        },
         state::*,
     },
     anchor_lang::prelude::{AccountLoader, *},
@@ -187,6 +190,144 @@ pub fn calculate_liquidity_token_deltas(
     Ok((delta_a, delta_b))
 }
 
+// Maximal liquidity that `token_amount` of Token A (or B) deposits in the position range at the
+// current price. Inverse of `calculate_liquidity_token_deltas`, rounded down so that the deposited
+// delta of the input token never exceeds `token_amount`.
+pub fn calculate_liquidity_from_token_amount(
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+    tick_current_index: i32,
+    sqrt_current_price: u128,
+    token_amount: u64,
+    is_token_a: bool,
+) -> Result<u128> {
+    let sqrt_lower_price = sqrt_price_from_tick_index(tick_lower_index);
+    let sqrt_upper_price = sqrt_price_from_tick_index(tick_upper_index);
+
+    // A price exactly on the lower tick has no Token B in range
+    let is_below_position =
+        tick_current_index < tick_lower_index || sqrt_current_price <= sqrt_lower_price;
+
+    let liquidity = if is_below_position {
+        // current price below position (or at its lower end), only Token A is deposited
+        if !is_token_a {
+            return Err(ErrorCode::InvalidDepositToken.into());
+        }
+        get_liquidity_delta_a(sqrt_lower_price, sqrt_upper_price, token_amount, false)?
+    } else if tick_current_index < tick_upper_index {
+        // current tick inside position
+        if is_token_a {
+            get_liquidity_delta_a(sqrt_current_price, sqrt_upper_price, token_amount, false)?
+        } else {
+            get_liquidity_delta_b(sqrt_lower_price, sqrt_current_price, token_amount, false)?
+        }
+    } else {
+        // current tick above position, only Token B is deposited
+        if is_token_a {
+            return Err(ErrorCode::InvalidDepositToken.into());
+        }
+        get_liquidity_delta_b(sqrt_lower_price, sqrt_upper_price, token_amount, false)?
+    };
+
+    if liquidity == 0 {
+        return Err(ErrorCode::LiquidityZero.into());
+    }
+
+    Ok(liquidity)
+}
+
+// Maximal liquidity that both `amount_a` & `amount_b` cover in the position range at the current
+// price. Zero if they are too small to deposit any liquidity.
+pub fn calculate_liquidity_from_token_amounts(
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+    tick_current_index: i32,
+    sqrt_current_price: u128,
+    amount_a: u64,
+    amount_b: u64,
+) -> Result<u128> {
+    let sqrt_lower_price = sqrt_price_from_tick_index(tick_lower_index);
+    let sqrt_upper_price = sqrt_price_from_tick_index(tick_upper_index);
+
+    if tick_current_index < tick_lower_index || sqrt_current_price <= sqrt_lower_price {
+        // current price below position (or at its lower end), only Token A is deposited
+        Ok(get_liquidity_delta_a(
+            sqrt_lower_price,
+            sqrt_upper_price,
+            amount_a,
+            false,
+        )?)
+    } else if tick_current_index < tick_upper_index {
+        let liquidity_a =
+            get_liquidity_delta_a(sqrt_current_price, sqrt_upper_price, amount_a, false)?;
+        let liquidity_b =
+            get_liquidity_delta_b(sqrt_lower_price, sqrt_current_price, amount_b, false)?;
+        Ok(std::cmp::min(liquidity_a, liquidity_b))
+    } else {
+        // current tick above position, only Token B is deposited
+        Ok(get_liquidity_delta_b(
+            sqrt_lower_price,
+            sqrt_upper_price,
+            amount_b,
+            false,
+        )?)
+    }
+}
+
+//
+// Swap turning `amount_a` & `amount_b` into the token ratio of the position range at the current
+// price, ignoring the price impact and fee of the swap itself. Returns the input amount and the
+// direction (true if A to B).
+//
+// With liquidity_a & liquidity_b the liquidity each amount covers on its own, the swap ends at
+// the average of both weighted by the value (in Token B) of each token per unit of liquidity:
+//   weight_a = price * (1 / sqrt_price - 1 / sqrt_upper_price) = sqrt_price * (sqrt_upper_price - sqrt_price) / sqrt_upper_price
+//   weight_b = sqrt_price - sqrt_lower_price
+//
+pub fn calculate_rebalance_swap(
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+    tick_current_index: i32,
+    sqrt_current_price: u128,
+    amount_a: u64,
+    amount_b: u64,
+) -> Result<(u64, bool)> {
+    let sqrt_lower_price = sqrt_price_from_tick_index(tick_lower_index);
+    let sqrt_upper_price = sqrt_price_from_tick_index(tick_upper_index);
+
+    // Out of range, all of the other token is swapped
+    if tick_current_index < tick_lower_index || sqrt_current_price <= sqrt_lower_price {
+        return Ok((amount_b, false));
+    } else if tick_current_index >= tick_upper_index {
+        return Ok((amount_a, true));
+    }
+
+    let liquidity_a = get_liquidity_delta_a(sqrt_current_price, sqrt_upper_price, amount_a, false)?;
+    let liquidity_b = get_liquidity_delta_b(sqrt_lower_price, sqrt_current_price, amount_b, false)?;
+
+    let weight_a = mul_u256(sqrt_current_price, sqrt_upper_price - sqrt_current_price)
+        .div(U256Muldiv::new(0, sqrt_upper_price), false)
+        .0
+        .try_into_u128()?;
+    let weight_b = sqrt_current_price - sqrt_lower_price;
+
+    let liquidity = mul_u256(liquidity_a, weight_a)
+        .add(mul_u256(liquidity_b, weight_b))
+        .div(U256Muldiv::new(0, weight_a + weight_b), false)
+        .0
+        .try_into_u128()?;
+
+    if liquidity_a > liquidity_b {
+        let kept_amount_a =
+            get_amount_delta_a(sqrt_current_price, sqrt_upper_price, liquidity, true)?;
+        Ok((amount_a.saturating_sub(kept_amount_a), true))
+    } else {
+        let kept_amount_b =
+            get_amount_delta_b(sqrt_lower_price, sqrt_current_price, liquidity, true)?;
+        Ok((amount_b.saturating_sub(kept_amount_b), false))
+    }
+}
+
 // Liquidity Position sync liquidity values
 pub fn sync_modify_liquidity_values<'info>(
     globalpool: &mut Globalpool,
@@ -1173,6 +1314,7 @@ mod calculate_modify_liquidity_unit_tests {
                         fee_growth_outside_a: to_x64(15),
                         // 15 = 35 - 20
                         fee_growth_outside_b: to_x64(15),
+                        ..Default::default()
                     }
                 );
 
@@ -3307,3 +3449,189 @@ mod calculate_modify_liquidity_unit_tests {
         }
     }
 }
+
+#[cfg(test)]
+mod calculate_liquidity_from_token_amount_tests {
+    use super::*;
+
+    fn assert_deposit(tick_current_index: i32, token_amount: u64, is_token_a: bool) -> (u64, u64) {
+        let sqrt_current_price = sqrt_price_from_tick_index(tick_current_index);
+        let liquidity = calculate_liquidity_from_token_amount(
+            -1280,
+            1280,
+            tick_current_index,
+            sqrt_current_price,
+            token_amount,
+            is_token_a,
+        )
+        .unwrap();
+
+        let deltas = calculate_liquidity_token_deltas(
+            -1280,
+            1280,
+            tick_current_index,
+            sqrt_current_price,
+            liquidity as i128,
+        )
+        .unwrap();
+        // The liquidity is the maximal one the input token amount covers, up to rounding
+        let delta = if is_token_a { deltas.0 } else { deltas.1 };
+        assert!(delta <= token_amount && delta + 1 >= token_amount);
+
+        deltas
+    }
+
+    #[test]
+    fn in_range_deposits_both_tokens() {
+        let (_, delta_b) = assert_deposit(0, 1_000_000, true);
+        assert!(delta_b > 0);
+
+        let (delta_a, delta_b) = assert_deposit(640, 1_000_000, false);
+        assert!(delta_a > 0 && delta_a < delta_b);
+    }
+
+    #[test]
+    fn out_of_range_deposits_one_token() {
+        assert_eq!(assert_deposit(-2000, 1_000_000, true).1, 0);
+        assert_eq!(assert_deposit(1280, 1_000_000, false).0, 0);
+    }
+
+    #[test]
+    fn out_of_range_rejects_other_token() {
+        let sqrt_price = sqrt_price_from_tick_index(-2000);
+        let result =
+            calculate_liquidity_from_token_amount(-1280, 1280, -2000, sqrt_price, 1_000_000, false);
+        assert_eq!(result.unwrap_err(), ErrorCode::InvalidDepositToken.into());
+
+        let sqrt_price = sqrt_price_from_tick_index(1280);
+        let result =
+            calculate_liquidity_from_token_amount(-1280, 1280, 1280, sqrt_price, 1_000_000, true);
+        assert_eq!(result.unwrap_err(), ErrorCode::InvalidDepositToken.into());
+    }
+
+    #[test]
+    fn price_on_lower_tick_deposits_token_a_only() {
+        // The pool is initialized exactly on the lower tick, so no Token B is in range
+        assert_eq!(assert_deposit(-1280, 1_000_000, true).1, 0);
+
+        let sqrt_price = sqrt_price_from_tick_index(-1280);
+        let result =
+            calculate_liquidity_from_token_amount(-1280, 1280, -1280, sqrt_price, 1_000_000, false);
+        assert_eq!(result.unwrap_err(), ErrorCode::InvalidDepositToken.into());
+    }
+
+    #[test]
+    fn zero_liquidity() {
+        let sqrt_price = sqrt_price_from_tick_index(0);
+        let result = calculate_liquidity_from_token_amount(-1280, 1280, 0, sqrt_price, 0, true);
+        assert_eq!(result.unwrap_err(), ErrorCode::LiquidityZero.into());
+    }
+}
+
+#[cfg(test)]
+mod calculate_rebalance_swap_tests {
+    use super::*;
+
+    const TICK_LOWER_INDEX: i32 = -1280;
+    const TICK_UPPER_INDEX: i32 = 1280;
+
+    fn rebalance(tick_current_index: i32, amount_a: u64, amount_b: u64) -> (u64, bool) {
+        calculate_rebalance_swap(
+            TICK_LOWER_INDEX,
+            TICK_UPPER_INDEX,
+            tick_current_index,
+            sqrt_price_from_tick_index(tick_current_index),
+            amount_a,
+            amount_b,
+        )
+        .unwrap()
+    }
+
+    // Liquidity deposited after swapping at the current price, and the leftover amounts
+    fn deposit_after_swap(
+        tick_current_index: i32,
+        amount_a: u64,
+        amount_b: u64,
+    ) -> (u128, u64, u64) {
+        let sqrt_price = sqrt_price_from_tick_index(tick_current_index);
+        let (swap_amount, a_to_b) = rebalance(tick_current_index, amount_a, amount_b);
+
+        // price of A in B is 1.0001^tick
+        let price = 1.0001f64.powi(tick_current_index);
+        let (amount_a, amount_b) = if a_to_b {
+            (
+                amount_a - swap_amount,
+                amount_b + (swap_amount as f64 * price) as u64,
+            )
+        } else {
+            (
+                amount_a + (swap_amount as f64 / price) as u64,
+                amount_b - swap_amount,
+            )
+        };
+
+        let liquidity = calculate_liquidity_from_token_amounts(
+            TICK_LOWER_INDEX,
+            TICK_UPPER_INDEX,
+            tick_current_index,
+            sqrt_price,
+            amount_a,
+            amount_b,
+        )
+        .unwrap();
+        let (delta_a, delta_b) = calculate_liquidity_token_deltas(
+            TICK_LOWER_INDEX,
+            TICK_UPPER_INDEX,
+            tick_current_index,
+            sqrt_price,
+            liquidity as i128,
+        )
+        .unwrap();
+
+        (liquidity, amount_a - delta_a, amount_b - delta_b)
+    }
+
+    #[test]
+    fn excess_token_a_swapped_to_b() {
+        let (swap_amount, a_to_b) = rebalance(0, 1_000_000, 0);
+        assert!(a_to_b);
+        // Around half, as the range is centered on the price
+        assert!(swap_amount > 490_000 && swap_amount < 510_000);
+
+        let (liquidity, leftover_a, leftover_b) = deposit_after_swap(0, 1_000_000, 0);
+        assert!(liquidity > 0);
+        assert!(leftover_a + leftover_b < 10);
+    }
+
+    #[test]
+    fn excess_token_b_swapped_to_a() {
+        let (swap_amount, a_to_b) = rebalance(640, 200_000, 1_000_000);
+        assert!(!a_to_b);
+        assert!(swap_amount > 0);
+
+        let (_, leftover_a, leftover_b) = deposit_after_swap(640, 200_000, 1_000_000);
+        assert!(leftover_a + leftover_b < 10);
+    }
+
+    #[test]
+    fn balanced_amounts_not_swapped() {
+        let sqrt_price = sqrt_price_from_tick_index(0);
+        let (amount_a, amount_b) = calculate_liquidity_token_deltas(
+            TICK_LOWER_INDEX,
+            TICK_UPPER_INDEX,
+            0,
+            sqrt_price,
+            1_000_000_000,
+        )
+        .unwrap();
+
+        let (swap_amount, _) = rebalance(0, amount_a, amount_b);
+        assert!(swap_amount < 5);
+    }
+
+    #[test]
+    fn out_of_range_swaps_other_token() {
+        assert_eq!(rebalance(-2000, 100, 1_000), (1_000, false));
+        assert_eq!(rebalance(1280, 100, 1_000), (100, true));
+    }
+}
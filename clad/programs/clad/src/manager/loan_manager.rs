@@ -1,12 +1,45 @@
 use {
     super::{
         globalpool_manager::next_globalpool_liquidity,
+        liquidity_manager::calculate_liquidity_token_deltas,
+        margin_manager::{oracle_sqrt_price, read_token_price, TOKEN_PRICE_ACCOUNTS_LEN},
         tick_manager::next_tick_modify_liquidity_update_from_loan,
     },
-    crate::{errors::ErrorCode, math::*, state::*},
+    crate::{errors::ErrorCode, math::*, state::*, util::get_mint_decimals},
     anchor_lang::prelude::{AccountLoader, *},
+    solana_program::clock::UnixTimestamp,
 };
 
+// Min annual interest rate of loans: 1% => 100 bps
+pub const MIN_LOAN_INTEREST_ANNUAL_BPS: u128 = 100;
+
+// Fixed-term loans must be at least 1 hour long and at most 10 days (in seconds)
+pub const MIN_LOAN_DURATION: u64 = 3600;
+pub const MAX_LOAN_DURATION: u64 = 864_000;
+
+// Leading `remaining_accounts` of trade positions collateralized by a liquidity position
+// open: [liquidity_position, position_token_owner_account, position_token_escrow]
+// repay: [liquidity_position, position_token_escrow, position_token_owner_account,
+//         tick_array_lower, tick_array_upper]
+pub const LIQUIDITY_COLLATERAL_OPEN_ACCOUNTS_LEN: usize = 3;
+pub const LIQUIDITY_COLLATERAL_REPAY_ACCOUNTS_LEN: usize = 5;
+
+// Leading `remaining_accounts` pricing a perpetual trade position for its health check:
+// [token_mint_a, token_mint_b, token_price_feed_a, price_feed_a, token_price_feed_b, price_feed_b]
+pub const PERPETUAL_HEALTH_ACCOUNTS_LEN: usize = 2 + 2 * TOKEN_PRICE_ACCOUNTS_LEN;
+
+// Amounts to repay a trade position at the current price, see `calculate_trade_position_repayment`
+#[derive(Debug, PartialEq)]
+pub struct TradePositionRepayment {
+    pub repay_amount_a: u64,
+    pub repay_amount_b: u64,
+    pub available_amount_a: u64,
+    pub available_amount_b: u64,
+    // Output of the swap needed to cover the repayment (at most one is non-zero)
+    pub swap_amount_needed_a: u64,
+    pub swap_amount_needed_b: u64,
+}
+
 #[derive(Debug)]
 pub struct ModifyLoanUpdate {
     pub globalpool_liquidity: u128,
@@ -66,6 +99,15 @@ pub fn calculate_modify_loan<'info>(
         {
             return Err(ErrorCode::InsufficientLiquidityToBorrow.into());
         }
+
+        check_loan_risk_limits(
+            globalpool,
+            position,
+            tick_lower,
+            tick_upper,
+            liquidity_delta,
+            borrowed_amount as u64,
+        )?;
     }
 
     //
@@ -135,6 +177,79 @@ pub fn calculate_modify_loan<'info>(
     })
 }
 
+//
+// Verify that borrowing `liquidity_delta` (worth `borrowed_amount` of the loan token) keeps the
+// globalpool within its risk caps. Caps set to zero are disabled.
+//
+pub fn check_loan_risk_limits(
+    globalpool: &Globalpool,
+    position: &TradePosition,
+    tick_lower: &Tick,
+    tick_upper: &Tick,
+    liquidity_delta: i128,
+    borrowed_amount: u64,
+) -> Result<()> {
+    let liquidity_delta_u128 = liquidity_delta.abs() as u128;
+
+    // Total borrowed liquidity as a share of the pool's available liquidity
+    if globalpool.max_borrowed_liquidity_bps > 0 {
+        let next_liquidity_borrowed = globalpool
+            .liquidity_borrowed
+            .checked_add(liquidity_delta_u128)
+            .ok_or(ErrorCode::LiquidityOverflow)?;
+
+        if mul_u256(next_liquidity_borrowed, RISK_BPS_MUL_VALUE).gt(mul_u256(
+            globalpool.liquidity_available,
+            globalpool.max_borrowed_liquidity_bps as u128,
+        )) {
+            return Err(ErrorCode::BorrowedLiquidityCapExceeded.into());
+        }
+    }
+
+    // Borrowed liquidity as a share of each lending tick's gross liquidity
+    if globalpool.max_tick_utilization_bps > 0 {
+        for tick in [tick_lower, tick_upper] {
+            let liquidity_gross = tick.liquidity_gross;
+            if liquidity_gross == 0 {
+                continue;
+            }
+
+            let liquidity_borrowed = std::cmp::max(tick.liquidity_borrowed, 0) as u128;
+            let next_liquidity_borrowed = liquidity_borrowed
+                .checked_add(liquidity_delta_u128)
+                .ok_or(ErrorCode::LiquidityOverflow)?;
+
+            if mul_u256(next_liquidity_borrowed, RISK_BPS_MUL_VALUE).gt(mul_u256(
+                liquidity_gross,
+                globalpool.max_tick_utilization_bps as u128,
+            )) {
+                return Err(ErrorCode::TickUtilizationCapExceeded.into());
+            }
+        }
+    }
+
+    // Loan token amount of the position (same borrow direction rule as `calculate_loan_liquidity_token_delta`)
+    let is_borrow_token_a = globalpool.tick_current_index < position.tick_lower_index;
+    let max_position_notional = if is_borrow_token_a {
+        globalpool.max_position_notional_a
+    } else {
+        globalpool.max_position_notional_b
+    };
+
+    if max_position_notional > 0 {
+        let next_notional = position
+            .total_borrowed_amount()
+            .checked_add(borrowed_amount)
+            .ok_or(ErrorCode::AmountCalcOverflow)?;
+
+        if next_notional > max_position_notional {
+            return Err(ErrorCode::PositionNotionalCapExceeded.into());
+        }
+    }
+
+    Ok(())
+}
+
 //
 // Simple linear interest rate based on utilization of tick liquidity gross.
 //
@@ -146,7 +261,7 @@ pub fn _calculate_loan_interest_rate_annual(
     liquidity_borrowed: u128,
     round_up: bool,
 ) -> Result<u16> {
-    let min_bps = 100_u128; // min annual: 1% => 100bps
+    let min_bps = MIN_LOAN_INTEREST_ANNUAL_BPS;
 
     let multiplier = U256Muldiv::new(0, 100_u128); // 1% => 100 bps
     let tick_lower_denom = U256Muldiv::new(0, tick_lower_liquidity_gross);
@@ -188,6 +303,39 @@ pub fn _calculate_loan_interest_rate_annual(
     Ok(utilization)
 }
 
+//
+// Verify that the loan range can be borrowed from at the current tick, in the requested token.
+//
+pub fn validate_loan_tick_range(
+    current_tick_index: i32,
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+    borrow_a: bool,
+) -> Result<()> {
+    if tick_lower_index > tick_upper_index {
+        return Err(ErrorCode::InvalidTickRange.into());
+    }
+
+    // Require that both TickArrays (from which token liquidity is borrowed) are either
+    // below or above the current globalpool tick since a trader can only borrow one asset.
+    if (tick_lower_index < current_tick_index && tick_upper_index > current_tick_index)
+        || (tick_upper_index == current_tick_index)
+        || (tick_lower_index == current_tick_index)
+    {
+        return Err(ErrorCode::InvalidTickRangeAgainstCurrentTick.into());
+    }
+
+    // Require that if borrow_a = true, then the Ticks are ABOVE the current globalpool tick.
+    // Conversely, if borrow_a = false, then the Ticks are BELOW the current globalpool tick.
+    if (borrow_a && tick_lower_index < current_tick_index)
+        || (!borrow_a && tick_upper_index > current_tick_index)
+    {
+        return Err(ErrorCode::InvalidTickRangeAgainstBorrowCondition.into());
+    }
+
+    Ok(())
+}
+
 pub fn calculate_loan_liquidity_token_delta(
     current_tick_index: i32,
     tick_lower_index: i32,
@@ -262,3 +410,749 @@ pub fn calculate_collateral(
 
     Ok(collateral_amount)
 }
+
+//
+// Annual funding rate of perpetual trade positions, based on the pool-wide utilization of the
+// available liquidity (same linear model as `_calculate_loan_interest_rate_annual`).
+//
+pub fn calculate_funding_rate_annual(globalpool: &Globalpool) -> Result<u16> {
+    if globalpool.liquidity_available == 0 {
+        return Ok(MIN_LOAN_INTEREST_ANNUAL_BPS as u16);
+    }
+
+    _calculate_loan_interest_rate_annual(
+        globalpool.liquidity_available,
+        globalpool.liquidity_available,
+        globalpool.liquidity_borrowed,
+        true,
+    )
+}
+
+//
+// Accrue the funding rate since the last update into the funding index.
+// Pools created before funding existed (never updated) start accruing from `timestamp`.
+//
+pub fn next_funding_index(globalpool: &Globalpool, timestamp: u64) -> Result<u128> {
+    if globalpool.funding_updated_at == 0 || timestamp <= globalpool.funding_updated_at {
+        return Ok(globalpool.funding_index);
+    }
+
+    let elapsed = timestamp - globalpool.funding_updated_at;
+    let funding_rate = calculate_funding_rate_annual(globalpool)?;
+
+    globalpool
+        .funding_index
+        .checked_add(funding_rate as u128 * elapsed as u128)
+        .ok_or(ErrorCode::AmountCalcOverflow.into())
+}
+
+//
+// Funding owed by a perpetual position since its last payment, charged on the collateral like the
+// upfront interest of fixed-term positions. Capped at the remaining collateral.
+//
+pub fn calculate_funding_amount(position: &TradePosition, funding_index: u128) -> Result<u64> {
+    if !position.is_perpetual || TradePosition::is_position_empty(position) {
+        return Ok(0);
+    }
+
+    let funding_index_delta = funding_index.saturating_sub(position.funding_index_checkpoint);
+
+    let (funding_amount, _) = mul_u256(position.collateral_amount as u128, funding_index_delta)
+        .div(U256Muldiv::new(0, ANNUAL_INTEREST_DENOMINATOR), false);
+
+    if funding_amount.gte(U256Muldiv::new(0, position.collateral_amount as u128)) {
+        Ok(position.collateral_amount)
+    } else {
+        Ok(funding_amount.try_into_u128()? as u64)
+    }
+}
+
+//
+// Pays the funding owed by a perpetual position to the LPs (via fee growth) out of its collateral,
+// which is already held in the pool vault. Returns the amount paid.
+//
+pub fn pay_funding(globalpool: &mut Globalpool, position: &mut TradePosition) -> Result<u64> {
+    if !position.is_perpetual {
+        return Ok(0);
+    }
+
+    let funding_amount = calculate_funding_amount(position, globalpool.funding_index)?;
+    position.update_after_funding(globalpool.funding_index, funding_amount)?;
+
+    let is_funding_in_a = position.token_mint_collateral.eq(&globalpool.token_mint_a);
+    globalpool.update_after_loan(0, funding_amount, is_funding_in_a);
+
+    Ok(funding_amount)
+}
+
+//
+// Sqrt price of the globalpool tokens at the Pyth prices registered by the protocol admin, read
+// from the `PERPETUAL_HEALTH_ACCOUNTS_LEN` accounts of `price_accounts`
+//
+pub fn read_oracle_sqrt_price(
+    globalpool: &Globalpool,
+    price_accounts: &[AccountInfo],
+    current_timestamp: UnixTimestamp,
+) -> Result<u128> {
+    if price_accounts.len() != PERPETUAL_HEALTH_ACCOUNTS_LEN
+        || price_accounts[0].key() != globalpool.token_mint_a
+        || price_accounts[1].key() != globalpool.token_mint_b
+    {
+        return Err(ErrorCode::InvalidPerpetualHealthAccounts.into());
+    }
+    let (price_accounts_a, price_accounts_b) =
+        price_accounts[2..].split_at(TOKEN_PRICE_ACCOUNTS_LEN);
+
+    let price_a = read_token_price(
+        price_accounts_a,
+        &globalpool.token_mint_a,
+        current_timestamp,
+    )?;
+    let price_b = read_token_price(
+        price_accounts_b,
+        &globalpool.token_mint_b,
+        current_timestamp,
+    )?;
+
+    oracle_sqrt_price(
+        &price_a,
+        get_mint_decimals(&price_accounts[0])?,
+        &price_b,
+        get_mint_decimals(&price_accounts[1])?,
+    )
+}
+
+//
+// A perpetual position is healthy if its trade token & collateral cover the amount owed to the
+// LPs at the oracle price, plus the maintenance margin. All values are denominated in the held
+// (collateral) token. The pool price is not used, as a swap could move it to force a liquidation.
+//
+pub fn is_perpetual_position_healthy(
+    position: &TradePosition,
+    globalpool: &Globalpool,
+    oracle_sqrt_price: u128,
+) -> Result<bool> {
+    if TradePosition::is_position_empty(position) {
+        return Ok(true);
+    }
+
+    let (repay_delta_a, repay_delta_b) = calculate_liquidity_token_deltas(
+        position.tick_lower_index,
+        position.tick_upper_index,
+        tick_index_from_sqrt_price(&oracle_sqrt_price),
+        oracle_sqrt_price,
+        position.liquidity_borrowed as i128,
+    )?;
+
+    let asset_amount = position
+        .trade_token_amount
+        .checked_add(position.collateral_amount)
+        .ok_or(ErrorCode::AmountCalcOverflow)?;
+
+    let sqrt_price = oracle_sqrt_price;
+    let is_borrow_a = position.token_mint_collateral.eq(&globalpool.token_mint_b);
+
+    let debt_amount = if is_borrow_a {
+        // Held in Token B
+        amount_a_in_b(repay_delta_a, sqrt_price).add(U256Muldiv::new(0, repay_delta_b as u128))
+    } else {
+        // Held in Token A
+        amount_b_in_a(repay_delta_b, sqrt_price).add(U256Muldiv::new(0, repay_delta_a as u128))
+    };
+
+    Ok(is_above_maintenance_margin(
+        U256Muldiv::new(0, asset_amount as u128),
+        debt_amount,
+    ))
+}
+
+//
+// A leveraged liquidity position is healthy if the tokens (& fees) of its locked liquidity
+// position cover the amount owed to the LPs at the current price, plus the maintenance margin.
+// All values are denominated in Token B.
+//
+pub fn is_leveraged_liquidity_position_healthy(
+    position: &TradePosition,
+    liquidity_position: &LiquidityPosition,
+    globalpool: &Globalpool,
+) -> Result<bool> {
+    if TradePosition::is_position_empty(position) {
+        return Ok(true);
+    }
+
+    let sqrt_price = globalpool.sqrt_price;
+
+    let (repay_delta_a, repay_delta_b) = calculate_liquidity_token_deltas(
+        position.tick_lower_index,
+        position.tick_upper_index,
+        globalpool.tick_current_index,
+        sqrt_price,
+        position.liquidity_borrowed as i128,
+    )?;
+
+    let (liquidity_delta_a, liquidity_delta_b) = if liquidity_position.liquidity > 0 {
+        calculate_liquidity_token_deltas(
+            liquidity_position.tick_lower_index,
+            liquidity_position.tick_upper_index,
+            globalpool.tick_current_index,
+            sqrt_price,
+            -(liquidity_position.liquidity as i128),
+        )?
+    } else {
+        (0, 0)
+    };
+
+    let asset_amount_a = liquidity_delta_a
+        .checked_add(liquidity_position.fee_owed_a)
+        .ok_or(ErrorCode::AmountCalcOverflow)?;
+    let asset_amount_b = liquidity_delta_b
+        .checked_add(liquidity_position.fee_owed_b)
+        .ok_or(ErrorCode::AmountCalcOverflow)?;
+
+    let asset_amount =
+        amount_a_in_b(asset_amount_a, sqrt_price).add(U256Muldiv::new(0, asset_amount_b as u128));
+    let debt_amount =
+        amount_a_in_b(repay_delta_a, sqrt_price).add(U256Muldiv::new(0, repay_delta_b as u128));
+
+    Ok(is_above_maintenance_margin(asset_amount, debt_amount))
+}
+
+//
+// Liquidity of a liquidity position to pledge in place of `collateral_amount` of the collateral
+// token. Since the value of liquidity moves with the price, the pledged liquidity must be worth
+// the collateral amount plus the maintenance margin at the current price (rounded up).
+//
+pub fn calculate_liquidity_collateral(
+    liquidity_position: &LiquidityPosition,
+    globalpool: &Globalpool,
+    collateral_amount: u64,
+    is_collateral_token_a: bool,
+) -> Result<u128> {
+    if liquidity_position.liquidity == 0 {
+        return Err(ErrorCode::InsufficientCollateral.into());
+    }
+
+    let sqrt_price = globalpool.sqrt_price;
+
+    let (amount_a, amount_b) = calculate_liquidity_token_deltas(
+        liquidity_position.tick_lower_index,
+        liquidity_position.tick_upper_index,
+        globalpool.tick_current_index,
+        sqrt_price,
+        -(liquidity_position.liquidity as i128),
+    )?;
+
+    let liquidity_value = if is_collateral_token_a {
+        U256Muldiv::new(0, amount_a as u128).add(amount_b_in_a(amount_b, sqrt_price))
+    } else {
+        amount_a_in_b(amount_a, sqrt_price).add(U256Muldiv::new(0, amount_b as u128))
+    };
+
+    if liquidity_value.is_zero() {
+        return Err(ErrorCode::InsufficientCollateral.into());
+    }
+
+    // liquidity * collateral * (1 + maintenance) / value
+    let liquidity_collateral = div_round_up_if_u256(
+        mul_u256(liquidity_position.liquidity, collateral_amount as u128).mul(U256Muldiv::new(
+            0,
+            TradePosition::BPS_MUL_VALUE + TradePosition::MAINTENANCE_MARGIN_BPS,
+        )),
+        liquidity_value.mul(U256Muldiv::new(0, TradePosition::BPS_MUL_VALUE)),
+        true,
+    )?;
+
+    // Fails if the whole liquidity position isn't worth enough
+    if liquidity_collateral > liquidity_position.liquidity {
+        return Err(ErrorCode::InsufficientCollateral.into());
+    }
+
+    // Always pledge some liquidity, so the position stays locked even without collateral
+    Ok(std::cmp::max(liquidity_collateral, 1))
+}
+
+//
+// Amounts owed to the LPs by a trade position at the current price, and held by the position to
+// repay them. This assumes the position has swapped all of its loan token to the trade token, held
+// along with the collateral. Collateral withdrawn from a liquidity position or drawn from a margin
+// account is held in both tokens.
+//
+pub fn calculate_trade_position_repayment(
+    position: &TradePosition,
+    globalpool: &Globalpool,
+    extra_collateral_a: u64,
+    extra_collateral_b: u64,
+) -> Result<TradePositionRepayment> {
+    let (repay_amount_a, repay_amount_b) = calculate_liquidity_token_deltas(
+        position.tick_lower_index,
+        position.tick_upper_index,
+        globalpool.tick_current_index,
+        globalpool.sqrt_price,
+        position.liquidity_borrowed as i128,
+    )?;
+
+    let held_amount = position
+        .trade_token_amount
+        .checked_add(position.collateral_amount)
+        .ok_or(ErrorCode::AmountCalcOverflow)?;
+    let (held_amount_a, held_amount_b) = if position.is_borrow_a(globalpool) {
+        (0, held_amount)
+    } else {
+        (held_amount, 0)
+    };
+
+    let available_amount_a = held_amount_a
+        .checked_add(extra_collateral_a)
+        .ok_or(ErrorCode::AmountCalcOverflow)?;
+    let available_amount_b = held_amount_b
+        .checked_add(extra_collateral_b)
+        .ok_or(ErrorCode::AmountCalcOverflow)?;
+
+    Ok(TradePositionRepayment {
+        repay_amount_a,
+        repay_amount_b,
+        available_amount_a,
+        available_amount_b,
+        swap_amount_needed_a: repay_amount_a.saturating_sub(available_amount_a),
+        swap_amount_needed_b: repay_amount_b.saturating_sub(available_amount_b),
+    })
+}
+
+// Token A amount valued in Token B at the current price (price of A in B is sqrt_price^2 / 2^128)
+fn amount_a_in_b(amount_a: u64, sqrt_price: u128) -> U256Muldiv {
+    mul_u256(amount_a as u128, sqrt_price)
+        .shift_right(64)
+        .mul(U256Muldiv::new(0, sqrt_price))
+        .shift_right(64)
+}
+
+// Token B amount valued in Token A at the current price
+fn amount_b_in_a(amount_b: u64, sqrt_price: u128) -> U256Muldiv {
+    let (amount_in_a, _) = U256Muldiv::new(0, amount_b as u128)
+        .shift_left(128)
+        .div(mul_u256(sqrt_price, sqrt_price), false);
+    amount_in_a
+}
+
+// (asset - debt) >= debt * maintenance  <=>  asset >= debt * (1 + maintenance)
+fn is_above_maintenance_margin(asset_amount: U256Muldiv, debt_amount: U256Muldiv) -> bool {
+    asset_amount
+        .mul(U256Muldiv::new(0, TradePosition::BPS_MUL_VALUE))
+        .gte(debt_amount.mul(U256Muldiv::new(
+            0,
+            TradePosition::BPS_MUL_VALUE + TradePosition::MAINTENANCE_MARGIN_BPS,
+        )))
+}
+
+#[cfg(test)]
+mod check_loan_risk_limits_tests {
+    use {
+        super::check_loan_risk_limits,
+        crate::state::{globalpool_builder::GlobalpoolBuilder, tick_builder::TickBuilder, *},
+    };
+
+    fn build_position(tick_lower_index: i32, tick_upper_index: i32) -> TradePosition {
+        TradePosition {
+            tick_lower_index,
+            tick_upper_index,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn ok_caps_disabled() {
+        let globalpool = GlobalpoolBuilder::new().liquidity(0).build();
+        let tick = TickBuilder::default().liquidity_gross(100).build();
+        let position = build_position(128, 256);
+
+        check_loan_risk_limits(&globalpool, &position, &tick, &tick, 100, u64::MAX).unwrap();
+    }
+
+    #[test]
+    fn ok_within_caps() {
+        let globalpool = GlobalpoolBuilder::new()
+            .liquidity(1_000)
+            .liquidity_borrowed(200)
+            .max_borrowed_liquidity_bps(5_000)
+            .max_tick_utilization_bps(5_000)
+            .max_position_notional_a(1_000)
+            .build();
+        let tick = TickBuilder::default()
+            .liquidity_gross(1_000)
+            .liquidity_borrowed(200)
+            .build();
+        let position = build_position(128, 256);
+
+        check_loan_risk_limits(&globalpool, &position, &tick, &tick, 300, 1_000).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "BorrowedLiquidityCapExceeded")]
+    fn borrowed_liquidity_cap_exceeded() {
+        let globalpool = GlobalpoolBuilder::new()
+            .liquidity(1_000)
+            .liquidity_borrowed(200)
+            .max_borrowed_liquidity_bps(5_000)
+            .build();
+        let tick = TickBuilder::default().liquidity_gross(10_000).build();
+        let position = build_position(128, 256);
+
+        check_loan_risk_limits(&globalpool, &position, &tick, &tick, 301, 1).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "TickUtilizationCapExceeded")]
+    fn tick_utilization_cap_exceeded() {
+        let globalpool = GlobalpoolBuilder::new()
+            .max_tick_utilization_bps(2_500)
+            .build();
+        let tick_lower = TickBuilder::default().liquidity_gross(1_000).build();
+        let tick_upper = TickBuilder::default()
+            .liquidity_gross(1_000)
+            .liquidity_borrowed(100)
+            .build();
+        let position = build_position(128, 256);
+
+        check_loan_risk_limits(&globalpool, &position, &tick_lower, &tick_upper, 200, 1).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "PositionNotionalCapExceeded")]
+    fn position_notional_cap_exceeded_borrow_b() {
+        let globalpool = GlobalpoolBuilder::new()
+            .tick_current_index(512)
+            .max_position_notional_a(u64::MAX)
+            .max_position_notional_b(1_000)
+            .build();
+        let tick = TickBuilder::default().liquidity_gross(1_000).build();
+        let position = build_position(128, 256);
+
+        check_loan_risk_limits(&globalpool, &position, &tick, &tick, 100, 1_001).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod funding_tests {
+    use {super::*, crate::state::globalpool_builder::GlobalpoolBuilder};
+
+    fn build_perpetual_position(collateral_amount: u64, funding_index: u128) -> TradePosition {
+        TradePosition {
+            is_perpetual: true,
+            funding_index_checkpoint: funding_index,
+            collateral_amount,
+            loan_token_swapped: 1_000,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn funding_index_accrues_min_rate() {
+        let globalpool = GlobalpoolBuilder::new()
+            .liquidity(1_000)
+            .funding_index(500)
+            .funding_updated_at(1_000)
+            .build();
+
+        let funding_index = next_funding_index(&globalpool, 1_060).unwrap();
+        assert_eq!(funding_index, 500 + 100 * 60);
+    }
+
+    #[test]
+    fn funding_index_accrues_utilization_rate() {
+        // 30x borrowed liquidity => 3_000 bps
+        let globalpool = GlobalpoolBuilder::new()
+            .liquidity(1_000)
+            .liquidity_borrowed(30_000)
+            .funding_updated_at(1_000)
+            .build();
+
+        let funding_index = next_funding_index(&globalpool, 1_010).unwrap();
+        assert_eq!(funding_index, 3_000 * 10);
+    }
+
+    #[test]
+    fn funding_index_not_accrued_before_first_update() {
+        let globalpool = GlobalpoolBuilder::new().liquidity(1_000).build();
+
+        let funding_index = next_funding_index(&globalpool, 1_000_000).unwrap();
+        assert_eq!(funding_index, 0);
+    }
+
+    #[test]
+    fn funding_amount_prorated() {
+        let position = build_perpetual_position(1_000_000, 0);
+
+        // Same divisor as the upfront interest of fixed-term positions: a rate of 1 charges 1% of
+        // the collateral over a full year
+        let funding_amount = calculate_funding_amount(&position, 31_536_000).unwrap();
+        assert_eq!(funding_amount, 10_000);
+    }
+
+    #[test]
+    fn funding_amount_capped_at_collateral() {
+        let position = build_perpetual_position(1_000, 0);
+
+        let funding_amount = calculate_funding_amount(&position, u128::MAX).unwrap();
+        assert_eq!(funding_amount, 1_000);
+    }
+
+    #[test]
+    fn funding_amount_zero_for_fixed_term_position() {
+        let position = TradePosition {
+            is_perpetual: false,
+            ..build_perpetual_position(1_000_000, 0)
+        };
+
+        let funding_amount = calculate_funding_amount(&position, 100 * 31_536_000).unwrap();
+        assert_eq!(funding_amount, 0);
+    }
+
+    #[test]
+    fn perpetual_position_health() {
+        // Borrowed B below the current tick, holding A as trade token & collateral
+        let tick_current_index = 1024;
+        let globalpool = Globalpool {
+            token_mint_a: Pubkey::new_unique(),
+            token_mint_b: Pubkey::new_unique(),
+            ..GlobalpoolBuilder::new()
+                .tick_current_index(tick_current_index)
+                .sqrt_price(sqrt_price_from_tick_index(tick_current_index))
+                .build()
+        };
+
+        let liquidity_borrowed = 1_000_000_000;
+        let (_, repay_delta_b) = calculate_liquidity_token_deltas(
+            0,
+            128,
+            tick_current_index,
+            globalpool.sqrt_price,
+            liquidity_borrowed as i128,
+        )
+        .unwrap();
+
+        // Debt valued in A at the current price
+        let debt_in_a = repay_delta_b as f64 / 1.0001f64.powi(tick_current_index);
+
+        let build_position = |asset_amount: f64| TradePosition {
+            tick_lower_index: 0,
+            tick_upper_index: 128,
+            liquidity_borrowed,
+            loan_token_swapped: repay_delta_b,
+            trade_token_amount: asset_amount as u64,
+            token_mint_loan: globalpool.token_mint_b,
+            token_mint_collateral: globalpool.token_mint_a,
+            ..build_perpetual_position(0, 0)
+        };
+
+        let oracle_sqrt_price = globalpool.sqrt_price;
+        assert!(is_perpetual_position_healthy(
+            &build_position(debt_in_a * 1.2),
+            &globalpool,
+            oracle_sqrt_price
+        )
+        .unwrap());
+        assert!(!is_perpetual_position_healthy(
+            &build_position(debt_in_a * 1.05),
+            &globalpool,
+            oracle_sqrt_price
+        )
+        .unwrap());
+
+        // Moving the pool price doesn't change the health, which is measured at the oracle price
+        let manipulated_globalpool = Globalpool {
+            tick_current_index: 0,
+            sqrt_price: sqrt_price_from_tick_index(0),
+            ..globalpool
+        };
+        assert!(is_perpetual_position_healthy(
+            &build_position(debt_in_a * 1.2),
+            &manipulated_globalpool,
+            oracle_sqrt_price
+        )
+        .unwrap());
+    }
+}
+
+#[cfg(test)]
+mod leveraged_liquidity_tests {
+    use {
+        super::*,
+        crate::state::{
+            globalpool_builder::GlobalpoolBuilder,
+            liquidity_position_builder::LiquidityPositionBuilder,
+        },
+    };
+
+    #[test]
+    fn ok_loan_range_above_current_tick_borrow_a() {
+        validate_loan_tick_range(0, 128, 256, true).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "InvalidTickRangeAgainstCurrentTick")]
+    fn loan_range_contains_current_tick() {
+        validate_loan_tick_range(128, 0, 256, false).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "InvalidTickRangeAgainstBorrowCondition")]
+    fn loan_range_below_current_tick_borrow_a() {
+        validate_loan_tick_range(512, 128, 256, true).unwrap();
+    }
+
+    #[test]
+    fn leveraged_liquidity_position_health() {
+        // Borrowed B below the current tick, so only B is owed
+        let tick_current_index = 1024;
+        let globalpool = Globalpool {
+            token_mint_a: Pubkey::new_unique(),
+            token_mint_b: Pubkey::new_unique(),
+            ..GlobalpoolBuilder::new()
+                .tick_current_index(tick_current_index)
+                .sqrt_price(sqrt_price_from_tick_index(tick_current_index))
+                .build()
+        };
+
+        let liquidity_borrowed = 1_000_000_000;
+        let (_, repay_delta_b) = calculate_liquidity_token_deltas(
+            0,
+            128,
+            tick_current_index,
+            globalpool.sqrt_price,
+            liquidity_borrowed as i128,
+        )
+        .unwrap();
+
+        let position = TradePosition {
+            tick_lower_index: 0,
+            tick_upper_index: 128,
+            liquidity_borrowed,
+            loan_token_swapped: repay_delta_b,
+            token_mint_loan: globalpool.token_mint_b,
+            token_mint_collateral: globalpool.token_mint_a,
+            ..Default::default()
+        };
+
+        // Value the liquidity position by its fees only, at exactly the maintenance margin
+        let build_liquidity_position = |fee_owed_b: u64| {
+            LiquidityPositionBuilder::new(896, 1152)
+                .fee_owed_b(fee_owed_b)
+                .build()
+        };
+        let maintenance_amount = (repay_delta_b * 11 + 9) / 10;
+
+        assert!(is_leveraged_liquidity_position_healthy(
+            &position,
+            &build_liquidity_position(maintenance_amount),
+            &globalpool
+        )
+        .unwrap());
+        assert!(!is_leveraged_liquidity_position_healthy(
+            &position,
+            &build_liquidity_position(maintenance_amount - 1),
+            &globalpool
+        )
+        .unwrap());
+    }
+
+    fn liquidity_collateral_globalpool() -> Globalpool {
+        GlobalpoolBuilder::new()
+            .tick_current_index(1024)
+            .sqrt_price(sqrt_price_from_tick_index(1024))
+            .build()
+    }
+
+    #[test]
+    fn liquidity_collateral_with_maintenance_margin() {
+        // Below the current tick, the liquidity position only holds Token B
+        let globalpool = liquidity_collateral_globalpool();
+        let liquidity_position = LiquidityPositionBuilder::new(0, 128)
+            .liquidity(1_000_000_000)
+            .build();
+        let (_, amount_b) =
+            calculate_liquidity_token_deltas(0, 128, 1024, globalpool.sqrt_price, -1_000_000_000)
+                .unwrap();
+
+        let liquidity_collateral =
+            calculate_liquidity_collateral(&liquidity_position, &globalpool, amount_b / 2, false)
+                .unwrap();
+
+        // Half of the position plus 10% maintenance margin
+        assert!(liquidity_collateral >= 550_000_000);
+        assert!(liquidity_collateral < 551_000_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "InsufficientCollateral")]
+    fn liquidity_collateral_exceeds_position() {
+        let globalpool = liquidity_collateral_globalpool();
+        let liquidity_position = LiquidityPositionBuilder::new(0, 128)
+            .liquidity(1_000_000_000)
+            .build();
+        let (_, amount_b) =
+            calculate_liquidity_token_deltas(0, 128, 1024, globalpool.sqrt_price, -1_000_000_000)
+                .unwrap();
+
+        // The whole position doesn't cover the maintenance margin
+        calculate_liquidity_collateral(&liquidity_position, &globalpool, amount_b, false).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod trade_position_repayment_tests {
+    use {super::*, crate::state::globalpool_builder::GlobalpoolBuilder};
+
+    // Borrowed B below the current tick, so only B is owed and the position holds A
+    fn build_borrow_b(
+        trade_token_amount: u64,
+        collateral_amount: u64,
+    ) -> (TradePosition, Globalpool) {
+        let globalpool = Globalpool {
+            token_mint_a: Pubkey::new_unique(),
+            token_mint_b: Pubkey::new_unique(),
+            ..GlobalpoolBuilder::new()
+                .tick_current_index(1024)
+                .sqrt_price(sqrt_price_from_tick_index(1024))
+                .build()
+        };
+        let position = TradePosition {
+            tick_lower_index: 0,
+            tick_upper_index: 128,
+            liquidity_borrowed: 1_000_000_000,
+            trade_token_amount,
+            collateral_amount,
+            token_mint_loan: globalpool.token_mint_b,
+            token_mint_collateral: globalpool.token_mint_a,
+            ..Default::default()
+        };
+        (position, globalpool)
+    }
+
+    #[test]
+    fn repayment_swaps_held_token_for_owed_token() {
+        let (position, globalpool) = build_borrow_b(1_000, 100);
+        let (_, repay_amount_b) =
+            calculate_liquidity_token_deltas(0, 128, 1024, globalpool.sqrt_price, 1_000_000_000)
+                .unwrap();
+
+        let repayment = calculate_trade_position_repayment(&position, &globalpool, 10, 20).unwrap();
+        assert_eq!(
+            repayment,
+            TradePositionRepayment {
+                repay_amount_a: 0,
+                repay_amount_b,
+                available_amount_a: 1_110,
+                available_amount_b: 20,
+                swap_amount_needed_a: 0,
+                swap_amount_needed_b: repay_amount_b - 20,
+            }
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "AmountCalcOverflow")]
+    fn repayment_held_amount_overflow() {
+        let (position, globalpool) = build_borrow_b(u64::MAX, 1);
+        calculate_trade_position_repayment(&position, &globalpool, 0, 0).unwrap();
+    }
+}
@@ -0,0 +1,600 @@
+use {
+    super::liquidity_manager::calculate_liquidity_token_deltas,
+    crate::{errors::ErrorCode, math::*, state::*},
+    anchor_lang::prelude::*,
+    anchor_spl::token::{Mint, TokenAccount},
+    solana_program::clock::UnixTimestamp,
+    spl_associated_token_account::get_associated_token_address,
+};
+
+// Accounts required to price a token: [token_price_feed, price_feed], i.e. the TokenPriceFeed
+// registered for the mint by the protocol admin, and the Pyth price feed it points to
+pub const TOKEN_PRICE_ACCOUNTS_LEN: usize = 2;
+
+// Remaining accounts required to value each linked position, in order:
+// [trade_position, position_token_account, globalpool, token_mint_a, token_mint_b,
+//  token_price_feed_a, price_feed_a, token_price_feed_b, price_feed_b]
+// where position_token_account is the associated token account of the owner for the position mint
+pub const MARGIN_POSITION_ACCOUNTS_LEN: usize = 5 + 2 * TOKEN_PRICE_ACCOUNTS_LEN;
+
+// Leading `remaining_accounts` of trade positions backed by a margin account
+// open: [margin_account, token_price_feed_a, price_feed_a, token_price_feed_b, price_feed_b],
+//       then the accounts valuing the margin account (see `calculate_margin_health`)
+// repay: [margin_account, margin_vault_a, margin_vault_b]
+pub const MARGIN_BACKED_OPEN_ACCOUNTS_LEN: usize = 1 + 2 * TOKEN_PRICE_ACCOUNTS_LEN;
+pub const MARGIN_BACKED_REPAY_ACCOUNTS_LEN: usize = 3;
+
+// Values are in USD scaled by 10^VALUE_DECIMALS
+pub const VALUE_DECIMALS: i32 = 12;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct MarginHealth {
+    pub collateral_value: u128, // Value of the collateral deposited in the margin account
+    pub asset_value: u128,      // Value of trade tokens & collateral held by linked positions
+    pub debt_value: u128,       // Value of the tokens linked positions owe to the LPs
+}
+
+impl MarginHealth {
+    // Healthy if the combined equity covers the maintenance margin of the combined debt
+    pub fn is_healthy(&self) -> bool {
+        self.meets_margin(MarginAccount::MAINTENANCE_MARGIN_BPS)
+    }
+
+    // (collateral + asset - debt) >= debt * margin  <=>  collateral + asset >= debt * (1 + margin)
+    pub fn meets_margin(&self, margin_bps: u64) -> bool {
+        U256Muldiv::new(0, self.collateral_value)
+            .add(U256Muldiv::new(0, self.asset_value))
+            .mul(U256Muldiv::new(0, MarginAccount::BPS_MUL_VALUE as u128))
+            .gte(mul_u256(
+                self.debt_value,
+                (MarginAccount::BPS_MUL_VALUE + margin_bps) as u128,
+            ))
+    }
+
+    fn add_collateral_value(&mut self, collateral_value: u128) -> Result<()> {
+        self.collateral_value = self
+            .collateral_value
+            .checked_add(collateral_value)
+            .ok_or(ErrorCode::AmountCalcOverflow)?;
+        Ok(())
+    }
+
+    fn add_position_value(&mut self, asset_value: u128, debt_value: u128) -> Result<()> {
+        self.asset_value = self
+            .asset_value
+            .checked_add(asset_value)
+            .ok_or(ErrorCode::AmountCalcOverflow)?;
+        self.debt_value = self
+            .debt_value
+            .checked_add(debt_value)
+            .ok_or(ErrorCode::AmountCalcOverflow)?;
+        Ok(())
+    }
+}
+
+// Number of remaining accounts taken by `calculate_margin_health` for the margin account
+pub fn margin_health_accounts_len(margin_account: &MarginAccount) -> usize {
+    margin_account.deposited_collaterals().count() * TOKEN_PRICE_ACCOUNTS_LEN
+        + margin_account.linked_positions().count() * MARGIN_POSITION_ACCOUNTS_LEN
+}
+
+//
+// Calculates the combined health of a margin account using the registered Pyth prices.
+//
+// `remaining_accounts` holds `TOKEN_PRICE_ACCOUNTS_LEN` accounts per deposited collateral (in slot
+// order), followed by `MARGIN_POSITION_ACCOUNTS_LEN` accounts per linked position (in slot order).
+//
+pub fn calculate_margin_health<'info>(
+    margin_account: &MarginAccount,
+    remaining_accounts: &[AccountInfo<'info>],
+    current_timestamp: UnixTimestamp,
+) -> Result<MarginHealth> {
+    let collaterals: Vec<&MarginCollateral> = margin_account.deposited_collaterals().collect();
+    let positions: Vec<&Pubkey> = margin_account.linked_positions().collect();
+
+    if remaining_accounts.len() != margin_health_accounts_len(margin_account) {
+        return Err(ErrorCode::InvalidMarginHealthAccounts.into());
+    }
+
+    let mut health = MarginHealth::default();
+
+    let (collateral_accounts, position_accounts) =
+        remaining_accounts.split_at(collaterals.len() * TOKEN_PRICE_ACCOUNTS_LEN);
+
+    for (collateral, price_accounts) in collaterals
+        .iter()
+        .zip(collateral_accounts.chunks(TOKEN_PRICE_ACCOUNTS_LEN))
+    {
+        let price = read_token_price(price_accounts, &collateral.mint, current_timestamp)?;
+        health.add_collateral_value(token_value(
+            collateral.amount,
+            collateral.decimals,
+            &price,
+            false,
+        )?)?;
+    }
+
+    for (position_key, accounts) in positions
+        .iter()
+        .zip(position_accounts.chunks(MARGIN_POSITION_ACCOUNTS_LEN))
+    {
+        if accounts[0].key != *position_key {
+            return Err(ErrorCode::InvalidMarginHealthAccounts.into());
+        }
+
+        // Positions closed after repayment carry no value or debt
+        if accounts[0].data_is_empty() {
+            continue;
+        }
+
+        let position = Account::<TradePosition>::try_from(&accounts[0])?;
+
+        // Positions are valued while the owner holds their token. Once it is transferred away, the
+        // position keeps counting its debt but none of its assets, so that the account can still
+        // be liquidated.
+        if accounts[1].key()
+            != get_associated_token_address(&margin_account.owner, &position.position_mint)
+        {
+            return Err(ErrorCode::InvalidMarginHealthAccounts.into());
+        }
+        let is_position_held = !accounts[1].data_is_empty()
+            && Account::<TokenAccount>::try_from(&accounts[1])?.amount == 1;
+
+        if accounts[2].key() != position.globalpool {
+            return Err(ErrorCode::InvalidMarginHealthAccounts.into());
+        }
+        let globalpool = Account::<Globalpool>::try_from(&accounts[2])?;
+
+        if accounts[3].key() != globalpool.token_mint_a
+            || accounts[4].key() != globalpool.token_mint_b
+        {
+            return Err(ErrorCode::InvalidMarginHealthAccounts.into());
+        }
+        let token_mint_a = Account::<Mint>::try_from(&accounts[3])?;
+        let token_mint_b = Account::<Mint>::try_from(&accounts[4])?;
+
+        add_position_to_health(
+            &mut health,
+            &position,
+            &globalpool,
+            [&token_mint_a, &token_mint_b],
+            &accounts[5..],
+            is_position_held,
+            current_timestamp,
+        )?;
+    }
+
+    Ok(health)
+}
+
+//
+// Adds the value of a trade position to the health, at the registered prices of its globalpool
+// tokens. `price_accounts` holds `TOKEN_PRICE_ACCOUNTS_LEN` accounts for token A, then for token B.
+// Used for linked positions, and for a position being opened against the account. The assets of
+// positions whose token is no longer held by the owner are not counted.
+//
+pub fn add_position_to_health(
+    health: &mut MarginHealth,
+    position: &TradePosition,
+    globalpool: &Globalpool,
+    token_mints: [&Mint; 2],
+    price_accounts: &[AccountInfo],
+    is_position_held: bool,
+    current_timestamp: UnixTimestamp,
+) -> Result<()> {
+    if price_accounts.len() != 2 * TOKEN_PRICE_ACCOUNTS_LEN {
+        return Err(ErrorCode::InvalidMarginHealthAccounts.into());
+    }
+    let (price_accounts_a, price_accounts_b) = price_accounts.split_at(TOKEN_PRICE_ACCOUNTS_LEN);
+
+    let price_a = read_token_price(
+        price_accounts_a,
+        &globalpool.token_mint_a,
+        current_timestamp,
+    )?;
+    let price_b = read_token_price(
+        price_accounts_b,
+        &globalpool.token_mint_b,
+        current_timestamp,
+    )?;
+
+    let (asset_value, debt_value) = calculate_position_value(
+        position,
+        globalpool,
+        &price_a,
+        token_mints[0].decimals,
+        &price_b,
+        token_mints[1].decimals,
+    )?;
+
+    if is_position_held {
+        health.add_position_value(asset_value, debt_value)
+    } else {
+        health.add_position_value(0, debt_value)
+    }
+}
+
+//
+// Calculates the value of a trade position's assets (trade token & collateral) and of its debt
+// (the tokens owed to the LPs at the current price). Debts are rounded up.
+//
+pub fn calculate_position_value(
+    position: &TradePosition,
+    globalpool: &Globalpool,
+    price_a: &OraclePrice,
+    decimals_a: u8,
+    price_b: &OraclePrice,
+    decimals_b: u8,
+) -> Result<(u128, u128)> {
+    if TradePosition::is_position_empty(position) {
+        return Ok((0, 0));
+    }
+
+    // Collateral in Token B implies loan in Token A, and vice versa.
+    let is_borrow_a = position.token_mint_collateral.eq(&globalpool.token_mint_b);
+
+    let (debt_a, debt_b) = calculate_liquidity_token_deltas(
+        position.tick_lower_index,
+        position.tick_upper_index,
+        globalpool.tick_current_index,
+        globalpool.sqrt_price,
+        position.liquidity_borrowed as i128,
+    )?;
+
+    // Trade token & collateral are both held in the token opposite of the loan
+    let asset_amount = position
+        .trade_token_amount
+        .checked_add(position.collateral_amount)
+        .ok_or(ErrorCode::AmountCalcOverflow)?;
+
+    let asset_value = if is_borrow_a {
+        token_value(asset_amount, decimals_b, price_b, false)?
+    } else {
+        token_value(asset_amount, decimals_a, price_a, false)?
+    };
+
+    let debt_value = token_value(debt_a, decimals_a, price_a, true)?
+        .checked_add(token_value(debt_b, decimals_b, price_b, true)?)
+        .ok_or(ErrorCode::AmountCalcOverflow)?;
+
+    Ok((asset_value, debt_value))
+}
+
+//
+// Margin collateral a margin-backed trade position draws into the pool vaults to settle its loan,
+// returned as (amount_a, amount_b). In its collateral token, it draws up to the collateral an
+// isolated position would have posted. In its loan token, which is swapped into the collateral
+// token when the trade token falls short, it draws up to the loan token it swapped at opening.
+// Whatever the repayment leaves is returned to the margin account.
+//
+pub fn calculate_margin_draw(
+    position: &TradePosition,
+    globalpool: &Globalpool,
+    margin_account: &MarginAccount,
+) -> Result<(u64, u64)> {
+    let is_borrow_a = position.token_mint_collateral.eq(&globalpool.token_mint_b);
+
+    let sqrt_price_lower = sqrt_price_from_tick_index(position.tick_lower_index);
+    let sqrt_price_upper = sqrt_price_from_tick_index(position.tick_upper_index);
+
+    // Worst case: the whole loan is repaid in the collateral token
+    let worst_case_amount = if is_borrow_a {
+        get_amount_delta_b(
+            sqrt_price_lower,
+            sqrt_price_upper,
+            position.liquidity_borrowed,
+            true,
+        )?
+    } else {
+        get_amount_delta_a(
+            sqrt_price_lower,
+            sqrt_price_upper,
+            position.liquidity_borrowed,
+            true,
+        )?
+    };
+    let collateral_needed = worst_case_amount.saturating_sub(position.trade_token_amount);
+
+    let balance = |mint: &Pubkey| {
+        margin_account
+            .get_collateral(mint)
+            .map_or(0, |collateral| collateral.amount)
+    };
+    let collateral_drawn =
+        std::cmp::min(balance(&position.token_mint_collateral), collateral_needed);
+    let loan_drawn = std::cmp::min(
+        balance(&position.token_mint_loan),
+        position.loan_token_swapped,
+    );
+
+    if is_borrow_a {
+        Ok((loan_drawn, collateral_drawn))
+    } else {
+        Ok((collateral_drawn, loan_drawn))
+    }
+}
+
+//
+// Price of a token, read from the Pyth price feed registered for its mint by the protocol admin.
+// `price_accounts` holds [token_price_feed, price_feed]. TokenPriceFeed accounts can only be
+// created by the protocol admin, at the address derived from their mint.
+//
+pub fn read_token_price(
+    price_accounts: &[AccountInfo],
+    mint: &Pubkey,
+    current_timestamp: UnixTimestamp,
+) -> Result<OraclePrice> {
+    if price_accounts.len() != TOKEN_PRICE_ACCOUNTS_LEN {
+        return Err(ErrorCode::InvalidMarginHealthAccounts.into());
+    }
+
+    let token_price_feed = Account::<TokenPriceFeed>::try_from(&price_accounts[0])?;
+    if token_price_feed.mint != *mint {
+        return Err(ErrorCode::PriceFeedNotSet.into());
+    }
+
+    if price_accounts[1].key() != token_price_feed.price_feed {
+        return Err(ErrorCode::InvalidOracleAccount.into());
+    }
+
+    let price_feed = Account::<PriceFeed>::try_from(&price_accounts[1])?;
+    price_feed.read_price(current_timestamp)
+}
+
+//
+// Value of a token amount (scaled to decimal exponent) at an oracle price, in USD scaled by
+// 10^VALUE_DECIMALS: amount * price * 10^(VALUE_DECIMALS + price exponent - decimals)
+//
+pub fn token_value(amount: u64, decimals: u8, price: &OraclePrice, round_up: bool) -> Result<u128> {
+    let value = mul_u256(amount as u128, price.price_with_expo as u128);
+    let exponent = VALUE_DECIMALS + price.exponent - decimals as i32;
+
+    let scale = 10u128
+        .checked_pow(exponent.unsigned_abs())
+        .ok_or(ErrorCode::AmountCalcOverflow)?;
+
+    if exponent >= 0 {
+        Ok(value.mul(U256Muldiv::new(0, scale)).try_into_u128()?)
+    } else {
+        Ok(div_round_up_if_u256(
+            value,
+            U256Muldiv::new(0, scale),
+            round_up,
+        )?)
+    }
+}
+
+//
+// Sqrt price of token A in token B at oracle prices, as a Q64.64 like `Globalpool::sqrt_price`:
+// sqrt(price_a / price_b * 10^(decimals_b - decimals_a)) * 2^64
+//
+pub fn oracle_sqrt_price(
+    price_a: &OraclePrice,
+    decimals_a: u8,
+    price_b: &OraclePrice,
+    decimals_b: u8,
+) -> Result<u128> {
+    let exponent = price_a.exponent - price_b.exponent + decimals_b as i32 - decimals_a as i32;
+    let price =
+        price_a.price_with_expo as f64 / price_b.price_with_expo as f64 * 10f64.powi(exponent);
+    let sqrt_price = price.sqrt() * TO_Q64 as f64;
+
+    if !sqrt_price.is_finite()
+        || sqrt_price < MIN_SQRT_PRICE_X64 as f64
+        || sqrt_price > MAX_SQRT_PRICE_X64 as f64
+    {
+        return Err(ErrorCode::OraclePriceOutOfRange.into());
+    }
+
+    Ok(sqrt_price as u128)
+}
+
+#[cfg(test)]
+mod margin_manager_tests {
+    use {
+        super::*,
+        crate::{math::sqrt_price_from_tick_index, state::globalpool_builder::GlobalpoolBuilder},
+    };
+
+    fn build_globalpool(tick_current_index: i32) -> Globalpool {
+        Globalpool {
+            token_mint_a: Pubkey::new_unique(),
+            token_mint_b: Pubkey::new_unique(),
+            ..GlobalpoolBuilder::new()
+                .tick_spacing(128)
+                .tick_current_index(tick_current_index)
+                .sqrt_price(sqrt_price_from_tick_index(tick_current_index))
+                .build()
+        }
+    }
+
+    // Pyth price with 8 decimals
+    fn build_price(price: u64) -> OraclePrice {
+        OraclePrice {
+            price: price as f64,
+            price_with_expo: price * 100_000_000,
+            exponent: -8,
+        }
+    }
+
+    fn usd(amount: u128) -> u128 {
+        amount * 10u128.pow(VALUE_DECIMALS as u32)
+    }
+
+    #[test]
+    fn healthy_above_maintenance_margin() {
+        let health = MarginHealth {
+            collateral_value: usd(10),
+            asset_value: usd(100),
+            debt_value: usd(100),
+        };
+        assert!(health.is_healthy());
+        assert!(!health.meets_margin(MarginAccount::INITIAL_MARGIN_BPS));
+    }
+
+    #[test]
+    fn unhealthy_below_maintenance_margin() {
+        let health = MarginHealth {
+            collateral_value: usd(5),
+            asset_value: usd(100),
+            debt_value: usd(100),
+        };
+        assert!(!health.is_healthy());
+    }
+
+    #[test]
+    fn token_value_scales_decimals() {
+        // 2.5 tokens with 6 decimals at 20 USD
+        assert_eq!(
+            token_value(2_500_000, 6, &build_price(20), false).unwrap(),
+            usd(50)
+        );
+        // 1.5 tokens with 9 decimals at 1 USD
+        assert_eq!(
+            token_value(1_500_000_000, 9, &build_price(1), false).unwrap(),
+            usd(3) / 2
+        );
+    }
+
+    #[test]
+    fn token_value_rounds_debt_up() {
+        // 1 token unit with 18 decimals at 1 USD is worth 10^-18 USD
+        let price = build_price(1);
+        assert_eq!(token_value(1, 18, &price, false).unwrap(), 0);
+        assert_eq!(token_value(1, 18, &price, true).unwrap(), 1);
+    }
+
+    #[test]
+    fn empty_position_has_no_value() {
+        let globalpool = build_globalpool(0);
+        let position = TradePosition::default();
+
+        let (asset_value, debt_value) = calculate_position_value(
+            &position,
+            &globalpool,
+            &build_price(1),
+            6,
+            &build_price(1),
+            6,
+        )
+        .unwrap();
+        assert_eq!(asset_value, 0);
+        assert_eq!(debt_value, 0);
+    }
+
+    #[test]
+    fn long_position_value() {
+        // Borrowed B below the current tick, holding A as trade token & collateral
+        let globalpool = build_globalpool(1024);
+        let position = TradePosition {
+            globalpool: Pubkey::new_unique(),
+            tick_lower_index: 0,
+            tick_upper_index: 128,
+            liquidity_borrowed: 1_000_000_000,
+            loan_token_swapped: 6_420_000,
+            trade_token_amount: 2_000_000,
+            collateral_amount: 500_000,
+            token_mint_loan: globalpool.token_mint_b,
+            token_mint_collateral: globalpool.token_mint_a,
+            ..Default::default()
+        };
+
+        let (asset_value, debt_value) = calculate_position_value(
+            &position,
+            &globalpool,
+            &build_price(20),
+            6,
+            &build_price(1),
+            6,
+        )
+        .unwrap();
+
+        // 2.5 A at 20 USD
+        assert_eq!(asset_value, usd(50));
+
+        // Only B is owed since the current tick is above the loan range
+        let (_, debt_b) =
+            calculate_liquidity_token_deltas(0, 128, 1024, globalpool.sqrt_price, 1_000_000_000)
+                .unwrap();
+        assert_eq!(
+            debt_value,
+            token_value(debt_b, 6, &build_price(1), true).unwrap()
+        );
+    }
+
+    #[test]
+    fn margin_draw_capped_by_isolated_collateral() {
+        let globalpool = build_globalpool(1024);
+        let position = TradePosition {
+            tick_lower_index: 0,
+            tick_upper_index: 128,
+            liquidity_borrowed: 1_000_000_000,
+            loan_token_swapped: 6_420_000,
+            trade_token_amount: 2_000_000,
+            token_mint_loan: globalpool.token_mint_b,
+            token_mint_collateral: globalpool.token_mint_a,
+            ..Default::default()
+        };
+
+        let mut margin_account = MarginAccount::default();
+        margin_account
+            .deposit_collateral(globalpool.token_mint_a, 6, u64::MAX)
+            .unwrap();
+        margin_account
+            .deposit_collateral(globalpool.token_mint_b, 6, 1_000)
+            .unwrap();
+
+        let worst_case_a = get_amount_delta_a(
+            sqrt_price_from_tick_index(0),
+            sqrt_price_from_tick_index(128),
+            1_000_000_000,
+            true,
+        )
+        .unwrap();
+
+        assert_eq!(
+            calculate_margin_draw(&position, &globalpool, &margin_account).unwrap(),
+            (worst_case_a - 2_000_000, 1_000)
+        );
+    }
+
+    #[test]
+    fn margin_draw_without_balance() {
+        let globalpool = build_globalpool(1024);
+        let position = TradePosition {
+            tick_lower_index: 0,
+            tick_upper_index: 128,
+            liquidity_borrowed: 1_000_000_000,
+            loan_token_swapped: 6_420_000,
+            trade_token_amount: 2_000_000,
+            token_mint_loan: globalpool.token_mint_b,
+            token_mint_collateral: globalpool.token_mint_a,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            calculate_margin_draw(&position, &globalpool, &MarginAccount::default()).unwrap(),
+            (0, 0)
+        );
+    }
+
+    #[test]
+    fn oracle_sqrt_price_of_token_prices() {
+        assert_eq!(
+            oracle_sqrt_price(&build_price(5), 6, &build_price(5), 6).unwrap(),
+            TO_Q64
+        );
+        // A is worth 4 B
+        assert_eq!(
+            oracle_sqrt_price(&build_price(100), 6, &build_price(25), 6).unwrap(),
+            2 * TO_Q64
+        );
+
+        // 1 A (9 decimals) is worth 100 B (6 decimals), i.e. 0.1 B unit per A unit
+        let sqrt_price = oracle_sqrt_price(&build_price(100), 9, &build_price(1), 6).unwrap();
+        assert!((sqrt_price as f64 / TO_Q64 as f64 - 0.1f64.sqrt()).abs() < 1e-12);
+
+        assert!(oracle_sqrt_price(&build_price(0), 6, &build_price(1), 6).is_err());
+        assert!(oracle_sqrt_price(&build_price(1), 6, &build_price(0), 6).is_err());
+    }
+}
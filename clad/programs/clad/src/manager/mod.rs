@@ -1,6 +1,11 @@
+pub mod dynamic_fee_manager;
+pub mod flash_loan_manager;
+pub mod hook_manager;
 pub mod liquidity_manager;
 pub mod loan_manager;
+pub mod margin_manager;
 pub mod position_manager;
+pub mod range_order_manager;
 pub mod swap_manager;
 pub mod tick_manager;
 pub mod globalpool_manager;
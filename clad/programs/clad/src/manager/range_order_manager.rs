@@ -0,0 +1,400 @@
+use crate::{
+    errors::ErrorCode,
+    math::{
+        add_liquidity_delta, get_amount_delta_a, get_amount_delta_b, sqrt_price_from_tick_index,
+    },
+    state::{
+        load_range_order, store_range_order, Globalpool, LiquidityPosition, RangeOrderTick, Tick,
+        TickArray,
+    },
+};
+use anchor_lang::prelude::AccountLoader;
+
+// A range order spans one tick spacing and only holds the token it converts from, so it lies
+// above the price when converting A to B and below it when converting B to A.
+// Both of its ticks are in the same tick array, so a swap filling the order at one of them can
+// always remove it from the other.
+pub fn validate_range_order(
+    globalpool: &Globalpool,
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+    a_to_b: bool,
+) -> Result<(), ErrorCode> {
+    let is_single_tick_spacing =
+        tick_upper_index - tick_lower_index == globalpool.tick_spacing as i32;
+    let is_single_tick_array =
+        Tick::get_start_tick_index(tick_lower_index, globalpool.tick_spacing)
+            == Tick::get_start_tick_index(tick_upper_index, globalpool.tick_spacing);
+    let is_input_side = if a_to_b {
+        globalpool.tick_current_index < tick_lower_index
+    } else {
+        globalpool.tick_current_index >= tick_upper_index
+    };
+
+    if !is_single_tick_spacing || !is_single_tick_array || !is_input_side {
+        return Err(ErrorCode::InvalidRangeOrderTicks);
+    }
+
+    Ok(())
+}
+
+// Registers a range order of `liquidity` with the unfilled orders of its fill tick
+pub fn next_range_order_open_update(
+    range_order: &RangeOrderTick,
+    liquidity: u128,
+    a_to_b: bool,
+) -> Result<RangeOrderTick, ErrorCode> {
+    if range_order.liquidity > 0 && range_order.a_to_b != a_to_b {
+        return Err(ErrorCode::RangeOrderDirectionMismatch);
+    }
+
+    Ok(RangeOrderTick {
+        liquidity: range_order
+            .liquidity
+            .checked_add(liquidity)
+            .ok_or(ErrorCode::LiquidityOverflow)?,
+        a_to_b,
+        unclaimed_count: range_order
+            .unclaimed_count
+            .checked_add(1)
+            .ok_or(ErrorCode::AmountCalcOverflow)?,
+        ..*range_order
+    })
+}
+
+// Removes a cancelled range order of `liquidity` from the unfilled orders of its fill tick
+pub fn next_range_order_cancel_update(
+    range_order: &RangeOrderTick,
+    liquidity: u128,
+) -> Result<RangeOrderTick, ErrorCode> {
+    Ok(RangeOrderTick {
+        liquidity: range_order
+            .liquidity
+            .checked_sub(liquidity)
+            .ok_or(ErrorCode::LiquidityUnderflow)?,
+        ..next_range_order_claim_update(range_order)?
+    })
+}
+
+// Unregisters a claimed range order from its fill tick. Filled orders have no liquidity left there.
+pub fn next_range_order_claim_update(
+    range_order: &RangeOrderTick,
+) -> Result<RangeOrderTick, ErrorCode> {
+    Ok(RangeOrderTick {
+        unclaimed_count: range_order
+            .unclaimed_count
+            .checked_sub(1)
+            .ok_or(ErrorCode::AmountCalcOverflow)?,
+        ..*range_order
+    })
+}
+
+// Adds `liquidity_delta` of range orders to the net range order liquidity of one of their ticks,
+// when they are opened, or removes it when they are cancelled or filled
+pub fn next_range_order_liquidity_net_update(
+    range_order: &RangeOrderTick,
+    liquidity_delta: i128,
+    is_upper_tick: bool,
+) -> Result<RangeOrderTick, ErrorCode> {
+    let liquidity_net = if is_upper_tick {
+        range_order.liquidity_net.checked_sub(liquidity_delta)
+    } else {
+        range_order.liquidity_net.checked_add(liquidity_delta)
+    }
+    .ok_or(ErrorCode::LiquidityNetError)?;
+
+    Ok(RangeOrderTick {
+        liquidity_net,
+        ..*range_order
+    })
+}
+
+// Range order liquidity is active while the price is within its range, like the pool liquidity.
+// Orders are only opened outside of their range, but may be cancelled within it.
+pub fn next_range_order_liquidity(
+    globalpool: &Globalpool,
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+    liquidity_delta: i128,
+) -> Result<u128, ErrorCode> {
+    if globalpool.tick_current_index < tick_upper_index
+        && globalpool.tick_current_index >= tick_lower_index
+    {
+        add_liquidity_delta(globalpool.range_order_liquidity, liquidity_delta)
+    } else {
+        Ok(globalpool.range_order_liquidity)
+    }
+}
+
+// Adds the unfilled liquidity of a range order being opened, or removes it when cancelled, from
+// the range order liquidity of its ticks and of the globalpool
+pub fn sync_range_order_liquidity<'info>(
+    globalpool: &mut Globalpool,
+    position: &LiquidityPosition,
+    tick_array: &AccountLoader<'info, TickArray>,
+    liquidity_delta: i128,
+) -> anchor_lang::Result<()> {
+    // Both ticks of a range order are in the same tick array
+    for (tick_index, is_upper_tick) in [
+        (position.tick_lower_index, false),
+        (position.tick_upper_index, true),
+    ] {
+        let range_order = load_range_order(tick_array, tick_index, globalpool.tick_spacing)?;
+        store_range_order(
+            tick_array,
+            tick_index,
+            globalpool.tick_spacing,
+            &next_range_order_liquidity_net_update(&range_order, liquidity_delta, is_upper_tick)?,
+        )?;
+    }
+
+    let range_order_liquidity = next_range_order_liquidity(
+        globalpool,
+        position.tick_lower_index,
+        position.tick_upper_index,
+        liquidity_delta,
+    )?;
+    globalpool.update_range_order_liquidity(range_order_liquidity);
+
+    Ok(())
+}
+
+// Orders are filled in batches, each fill incrementing the fill count of the tick
+pub fn is_range_order_filled(position: &LiquidityPosition, range_order: &RangeOrderTick) -> bool {
+    range_order.fill_count != position.range_order_fill_count
+}
+
+// Amount of the output token owed to a filled range order, rounded down
+pub fn calculate_range_order_filled_amount(position: &LiquidityPosition) -> Result<u64, ErrorCode> {
+    let sqrt_lower_price = sqrt_price_from_tick_index(position.tick_lower_index);
+    let sqrt_upper_price = sqrt_price_from_tick_index(position.tick_upper_index);
+
+    if position.range_order_a_to_b {
+        get_amount_delta_b(
+            sqrt_lower_price,
+            sqrt_upper_price,
+            position.liquidity,
+            false,
+        )
+    } else {
+        get_amount_delta_a(
+            sqrt_lower_price,
+            sqrt_upper_price,
+            position.liquidity,
+            false,
+        )
+    }
+}
+
+#[cfg(test)]
+mod range_order_manager_tests {
+    use {
+        super::*,
+        crate::{
+            manager::liquidity_manager::calculate_liquidity_token_deltas,
+            state::{
+                globalpool_builder::GlobalpoolBuilder,
+                liquidity_position_builder::LiquidityPositionBuilder,
+            },
+        },
+    };
+
+    #[test]
+    fn validate_range_order_sides() {
+        let globalpool = GlobalpoolBuilder::new()
+            .tick_spacing(64)
+            .tick_current_index(100)
+            .build();
+
+        // A to B orders lie above the price
+        assert!(validate_range_order(&globalpool, 128, 192, true).is_ok());
+        assert!(validate_range_order(&globalpool, 128, 192, false).is_err());
+        // B to A orders lie below the price
+        assert!(validate_range_order(&globalpool, 0, 64, false).is_ok());
+        assert!(validate_range_order(&globalpool, 0, 64, true).is_err());
+        // The price must not be within the range
+        assert!(validate_range_order(&globalpool, 64, 128, true).is_err());
+        assert!(validate_range_order(&globalpool, 64, 128, false).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "InvalidRangeOrderTicks")]
+    fn validate_range_order_width() {
+        let globalpool = GlobalpoolBuilder::new()
+            .tick_spacing(64)
+            .tick_current_index(100)
+            .build();
+
+        validate_range_order(&globalpool, 128, 256, true).unwrap();
+    }
+
+    #[test]
+    fn validate_range_order_tick_arrays() {
+        let globalpool = GlobalpoolBuilder::new()
+            .tick_spacing(64)
+            .tick_current_index(-100)
+            .build();
+
+        // Tick arrays of tick spacing 64 start at multiples of 5_632
+        assert!(validate_range_order(&globalpool, 5_504, 5_568, true).is_ok());
+        assert!(validate_range_order(&globalpool, 5_568, 5_632, true).is_err());
+        assert!(validate_range_order(&globalpool, 5_632, 5_696, true).is_ok());
+        assert!(validate_range_order(&globalpool, -5_696, -5_632, false).is_err());
+        assert!(validate_range_order(&globalpool, -5_632, -5_568, false).is_ok());
+    }
+
+    #[test]
+    fn open_and_cancel_update() {
+        let range_order = RangeOrderTick {
+            liquidity: 0,
+            fill_count: 5,
+            // Direction of filled orders doesn't restrict new orders
+            a_to_b: false,
+            unclaimed_count: 1,
+            liquidity_net: 0,
+        };
+
+        let opened = next_range_order_open_update(&range_order, 1_000, true).unwrap();
+        assert_eq!(
+            opened,
+            RangeOrderTick {
+                liquidity: 1_000,
+                fill_count: 5,
+                a_to_b: true,
+                unclaimed_count: 2,
+                liquidity_net: 0,
+            }
+        );
+
+        let opened = next_range_order_open_update(&opened, 500, true).unwrap();
+        assert_eq!(opened.liquidity, 1_500);
+        assert_eq!(opened.unclaimed_count, 3);
+
+        let cancelled = next_range_order_cancel_update(&opened, 1_000).unwrap();
+        assert_eq!(
+            cancelled,
+            RangeOrderTick {
+                liquidity: 500,
+                fill_count: 5,
+                a_to_b: true,
+                unclaimed_count: 2,
+                liquidity_net: 0,
+            }
+        );
+
+        // The filled order opened before them leaves no liquidity to remove
+        let claimed = next_range_order_claim_update(&cancelled).unwrap();
+        assert_eq!(
+            claimed,
+            RangeOrderTick {
+                liquidity: 500,
+                fill_count: 5,
+                a_to_b: true,
+                unclaimed_count: 1,
+                liquidity_net: 0,
+            }
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "RangeOrderDirectionMismatch")]
+    fn open_update_direction_mismatch() {
+        let range_order = RangeOrderTick {
+            liquidity: 1_000,
+            fill_count: 0,
+            a_to_b: true,
+            unclaimed_count: 1,
+            liquidity_net: 0,
+        };
+
+        next_range_order_open_update(&range_order, 1_000, false).unwrap();
+    }
+
+    #[test]
+    fn liquidity_net_update() {
+        let range_order = RangeOrderTick::default();
+
+        let lower = next_range_order_liquidity_net_update(&range_order, 1_000, false).unwrap();
+        let upper = next_range_order_liquidity_net_update(&range_order, 1_000, true).unwrap();
+        assert_eq!({ lower.liquidity_net }, 1_000);
+        assert_eq!({ upper.liquidity_net }, -1_000);
+
+        // Filled or cancelled liquidity is removed again
+        let lower = next_range_order_liquidity_net_update(&lower, -1_000, false).unwrap();
+        let upper = next_range_order_liquidity_net_update(&upper, -1_000, true).unwrap();
+        assert_eq!(lower, range_order);
+        assert_eq!(upper, range_order);
+    }
+
+    #[test]
+    fn range_order_liquidity_within_range() {
+        let mut globalpool = GlobalpoolBuilder::new()
+            .tick_spacing(64)
+            .tick_current_index(150)
+            .build();
+        globalpool.update_range_order_liquidity(1_500);
+
+        assert_eq!(
+            next_range_order_liquidity(&globalpool, 128, 192, -1_000).unwrap(),
+            500
+        );
+        assert_eq!(
+            next_range_order_liquidity(&globalpool, 192, 256, -1_000).unwrap(),
+            1_500
+        );
+        assert_eq!(
+            next_range_order_liquidity(&globalpool, 64, 128, -1_000).unwrap(),
+            1_500
+        );
+    }
+
+    #[test]
+    fn filled_after_fill_count_changes() {
+        let mut position = LiquidityPositionBuilder::new(128, 192)
+            .liquidity(1_000)
+            .build();
+        position.open_range_order(true, 5);
+        assert_eq!(position.range_order_fill_tick_index(), 192);
+
+        let mut range_order = RangeOrderTick {
+            liquidity: 1_000,
+            fill_count: 5,
+            a_to_b: true,
+            unclaimed_count: 1,
+            liquidity_net: 0,
+        };
+        assert!(!is_range_order_filled(&position, &range_order));
+
+        range_order.fill_count = 6;
+        assert!(is_range_order_filled(&position, &range_order));
+    }
+
+    #[test]
+    fn filled_amount_is_fully_converted() {
+        for a_to_b in [true, false] {
+            let mut position = LiquidityPositionBuilder::new(128, 192)
+                .liquidity(1_000_000_000)
+                .build();
+            position.open_range_order(a_to_b, 0);
+
+            // Withdrawing the position with the price past the range, rounded down
+            let tick_current_index = if a_to_b { 192 } else { 127 };
+            let (delta_a, delta_b) = calculate_liquidity_token_deltas(
+                128,
+                192,
+                tick_current_index,
+                sqrt_price_from_tick_index(tick_current_index),
+                -1_000_000_000,
+            )
+            .unwrap();
+
+            let filled_amount = calculate_range_order_filled_amount(&position).unwrap();
+            if a_to_b {
+                assert_eq!((delta_a, delta_b), (0, filled_amount));
+            } else {
+                assert_eq!((delta_a, delta_b), (filled_amount, 0));
+            }
+            assert!(filled_amount > 0);
+        }
+    }
+}
@@ -1,6 +1,13 @@
 use {
     crate::{
-        errors::ErrorCode, manager::tick_manager::next_tick_cross_update, math::*, state::*,
+        errors::ErrorCode,
+        manager::{
+            dynamic_fee_manager::DynamicFee,
+            range_order_manager::next_range_order_liquidity_net_update,
+            tick_manager::{next_tick_cross_update, next_tick_range_order_fill_update},
+        },
+        math::*,
+        state::*,
         util::TickSequence,
     },
     anchor_lang::prelude::*,
@@ -13,10 +20,18 @@ pub struct PostSwapUpdate {
     pub amount_a: u64,
     pub amount_b: u64,
     pub next_liquidity: u128,
+    // Part of `next_liquidity` held by unfilled range orders
+    pub next_range_order_liquidity: u128,
     pub next_tick_index: i32,
     pub next_sqrt_price: u128,
     pub next_fee_growth_global: u128,
     pub next_protocol_fee: u64,
+    pub next_volatility: VolatilityUpdate,
+    // Swap fee paid in the input token, including the protocol fee
+    pub fee_amount: u64,
+    // Initialized ticks crossed, and the index of the last tick array the swap reached
+    pub ticks_crossed: u32,
+    pub last_array_index: usize,
 }
 
 pub fn swap(
@@ -26,7 +41,7 @@ pub fn swap(
     sqrt_price_limit: u128,
     amount_specified_is_input: bool,
     a_to_b: bool,
-    _timestamp: u64,
+    timestamp: u64,
 ) -> Result<PostSwapUpdate> {
     if sqrt_price_limit < MIN_SQRT_PRICE_X64 || sqrt_price_limit > MAX_SQRT_PRICE_X64 {
         return Err(ErrorCode::SqrtPriceOutOfBounds.into());
@@ -43,7 +58,7 @@ pub fn swap(
     }
 
     let tick_spacing = globalpool.tick_spacing;
-    let fee_rate = globalpool.fee_rate;
+    let mut dynamic_fee = DynamicFee::new(globalpool, timestamp);
     let protocol_fee_rate = globalpool.protocol_fee_rate;
 
     let mut amount_remaining: u64 = amount;
@@ -51,8 +66,12 @@ pub fn swap(
     let mut curr_sqrt_price = globalpool.sqrt_price;
     let mut curr_tick_index = globalpool.tick_current_index;
     let mut curr_liquidity = globalpool.liquidity_available;
+    let mut curr_range_order_liquidity = globalpool.range_order_liquidity;
     let mut curr_protocol_fee: u64 = 0;
     let mut curr_array_index: usize = 0;
+    let mut curr_fee_amount: u64 = 0;
+    let mut ticks_crossed: u32 = 0;
+    let mut last_array_index: usize = 0;
     let mut curr_fee_growth_global_input = if a_to_b {
         globalpool.fee_growth_global_a
     } else {
@@ -67,13 +86,17 @@ pub fn swap(
                 a_to_b,
                 curr_array_index,
             )?;
+        last_array_index = next_array_index;
 
         let (next_tick_sqrt_price, sqrt_price_target) =
             get_next_sqrt_prices(next_tick_index, sqrt_price_limit, a_to_b);
 
+        // The variable fee of each step reflects the price movement up to its start
+        dynamic_fee.update_volatility_accumulator(curr_tick_index);
+
         let swap_computation = compute_swap(
             amount_remaining,
-            fee_rate,
+            dynamic_fee.fee_rate(),
             curr_liquidity,
             curr_sqrt_price,
             sqrt_price_target,
@@ -105,10 +128,13 @@ pub fn swap(
                 .ok_or(ErrorCode::AmountCalcOverflow)?;
         }
 
+        curr_fee_amount += swap_computation.fee_amount;
+
         let (next_protocol_fee, next_fee_growth_global_input) = calculate_fees(
             swap_computation.fee_amount,
             protocol_fee_rate,
             curr_liquidity,
+            curr_range_order_liquidity,
             curr_protocol_fee,
             curr_fee_growth_global_input,
         );
@@ -127,7 +153,7 @@ pub fn swap(
                     (globalpool.fee_growth_global_a, curr_fee_growth_global_input)
                 };
 
-                let (update, next_liquidity) = calculate_update(
+                let (mut update, next_liquidity) = calculate_update(
                     &next_tick.unwrap(),
                     a_to_b,
                     curr_liquidity,
@@ -136,6 +162,23 @@ pub fn swap(
                 )?;
 
                 curr_liquidity = next_liquidity;
+                curr_range_order_liquidity = cross_range_orders(
+                    swap_tick_sequence,
+                    next_array_index,
+                    next_tick_index,
+                    tick_spacing,
+                    a_to_b,
+                    curr_range_order_liquidity,
+                )?;
+                ticks_crossed += 1;
+                fill_range_orders(
+                    swap_tick_sequence,
+                    &mut update,
+                    next_array_index,
+                    next_tick_index,
+                    tick_spacing,
+                    a_to_b,
+                )?;
                 swap_tick_sequence.update_tick(
                     next_array_index,
                     next_tick_index,
@@ -175,6 +218,8 @@ pub fn swap(
         curr_sqrt_price = swap_computation.next_price;
     }
 
+    dynamic_fee.update_volatility_accumulator(curr_tick_index);
+
     let (amount_a, amount_b) = if a_to_b == amount_specified_is_input {
         (amount - amount_remaining, amount_calculated)
     } else {
@@ -185,17 +230,25 @@ pub fn swap(
         amount_a,
         amount_b,
         next_liquidity: curr_liquidity,
+        next_range_order_liquidity: curr_range_order_liquidity,
         next_tick_index: curr_tick_index,
         next_sqrt_price: curr_sqrt_price,
         next_fee_growth_global: curr_fee_growth_global_input,
         next_protocol_fee: curr_protocol_fee,
+        next_volatility: dynamic_fee.volatility_update(),
+        fee_amount: curr_fee_amount,
+        ticks_crossed,
+        last_array_index,
     })
 }
 
+// Range orders earn no fees, so fee growth is shared by the rest of the active liquidity.
+// Fees paid to range order liquidity alone go to the protocol rather than being stranded.
 fn calculate_fees(
     fee_amount: u64,
     protocol_fee_rate: u16,
     curr_liquidity: u128,
+    curr_range_order_liquidity: u128,
     curr_protocol_fee: u64,
     curr_fee_growth_global_input: u128,
 ) -> (u64, u128) {
@@ -208,9 +261,12 @@ fn calculate_fees(
         next_protocol_fee = next_protocol_fee.wrapping_add(delta);
     }
 
-    if curr_liquidity > 0 {
+    let fee_liquidity = curr_liquidity.saturating_sub(curr_range_order_liquidity);
+    if fee_liquidity > 0 {
         next_fee_growth_global_input = next_fee_growth_global_input
-            .wrapping_add(((global_fee as u128) << Q64_RESOLUTION) / curr_liquidity);
+            .wrapping_add(((global_fee as u128) << Q64_RESOLUTION) / fee_liquidity);
+    } else if curr_liquidity > 0 {
+        next_protocol_fee = next_protocol_fee.wrapping_add(global_fee);
     }
     (next_protocol_fee, next_fee_growth_global_input)
 }
@@ -244,6 +300,100 @@ fn calculate_update(
     Ok((update, next_liquidity))
 }
 
+// Updates the active range order liquidity when crossing a tick, like `calculate_update` does
+// for the active liquidity
+fn cross_range_orders(
+    swap_tick_sequence: &TickSequence,
+    array_index: usize,
+    tick_index: i32,
+    tick_spacing: u16,
+    a_to_b: bool,
+    range_order_liquidity: u128,
+) -> Result<u128> {
+    let liquidity_net =
+        match swap_tick_sequence.get_range_order(array_index, tick_index, tick_spacing)? {
+            Some(range_order) => range_order.liquidity_net,
+            None => return Ok(range_order_liquidity),
+        };
+
+    let signed_liquidity_net = if a_to_b {
+        -liquidity_net
+    } else {
+        liquidity_net
+    };
+    Ok(add_liquidity_delta(
+        range_order_liquidity,
+        signed_liquidity_net,
+    )?)
+}
+
+// Fills the range orders registered at a crossed tick, if it is crossed in their direction:
+// rightwards for orders converting A to B, leftwards for orders converting B to A.
+// Crossing has already removed their liquidity from the active liquidity. Removing it from both of
+// their ticks keeps a price reversal from converting it back.
+fn fill_range_orders(
+    swap_tick_sequence: &mut TickSequence,
+    update: &mut TickUpdate,
+    array_index: usize,
+    tick_index: i32,
+    tick_spacing: u16,
+    a_to_b: bool,
+) -> Result<()> {
+    // Tick arrays without an extension hold no range orders
+    let range_order =
+        match swap_tick_sequence.get_range_order(array_index, tick_index, tick_spacing)? {
+            Some(range_order) => *range_order,
+            None => return Ok(()),
+        };
+
+    if range_order.liquidity == 0 || range_order.a_to_b == a_to_b {
+        return Ok(());
+    }
+
+    // Orders converting A to B span [tick - tick_spacing, tick), orders converting B to A span
+    // [tick, tick + tick_spacing)
+    let other_tick_index = if a_to_b {
+        tick_index + tick_spacing as i32
+    } else {
+        tick_index - tick_spacing as i32
+    };
+
+    // Range orders don't span two tick arrays, so the other tick is in the same array
+    let other_tick = swap_tick_sequence.get_tick(array_index, other_tick_index, tick_spacing)?;
+    let other_update = next_tick_range_order_fill_update(
+        &TickUpdate::from(other_tick),
+        range_order.liquidity,
+        a_to_b,
+    )?;
+    swap_tick_sequence.update_tick(array_index, other_tick_index, tick_spacing, &other_update)?;
+
+    // The filled liquidity is no longer range order liquidity at either tick
+    let liquidity_delta = -(range_order.liquidity as i128);
+    let other_range_order = *swap_tick_sequence
+        .get_range_order(array_index, other_tick_index, tick_spacing)?
+        .ok_or(ErrorCode::TickArrayExtensionMissing)?;
+    swap_tick_sequence.update_range_order(
+        array_index,
+        other_tick_index,
+        tick_spacing,
+        &next_range_order_liquidity_net_update(&other_range_order, liquidity_delta, a_to_b)?,
+    )?;
+
+    *update = next_tick_range_order_fill_update(update, range_order.liquidity, !a_to_b)?;
+    swap_tick_sequence.update_range_order(
+        array_index,
+        tick_index,
+        tick_spacing,
+        &RangeOrderTick {
+            liquidity: 0,
+            fill_count: range_order.fill_count.wrapping_add(1),
+            ..next_range_order_liquidity_net_update(&range_order, liquidity_delta, !a_to_b)?
+        },
+    )?;
+
+    Ok(())
+}
+
 fn get_next_sqrt_prices(
     next_tick_index: i32,
     sqrt_price_limit: u128,
@@ -2483,3 +2633,264 @@ mod swap_error_tests {
         swap_test_info.run(&mut tick_sequence, 100);
     }
 }
+
+#[cfg(test)]
+mod swap_range_order_tests {
+    use {super::*, crate::util::test_utils::swap_test_fixture::*, std::cell::RefCell};
+
+    fn place_range_order(
+        tick_array: &RefCell<TickArray>,
+        extension: &RefCell<TickArrayExtension>,
+        tick_lower_index: i32,
+        liquidity: u128,
+        a_to_b: bool,
+    ) {
+        let mut tick_array = tick_array.borrow_mut();
+        let mut extension = extension.borrow_mut();
+        let tick_upper_index = tick_lower_index + TS_8 as i32;
+        let fill_tick_index = if a_to_b {
+            tick_upper_index
+        } else {
+            tick_lower_index
+        };
+        for (tick_index, liquidity_net) in [
+            (tick_lower_index, liquidity as i128),
+            (tick_upper_index, -(liquidity as i128)),
+        ] {
+            tick_array
+                .update_tick(
+                    tick_index,
+                    TS_8,
+                    &TickUpdate {
+                        initialized: true,
+                        liquidity_net,
+                        liquidity_gross: liquidity,
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+
+            let range_order = if tick_index == fill_tick_index {
+                RangeOrderTick {
+                    liquidity,
+                    fill_count: 0,
+                    a_to_b,
+                    unclaimed_count: 1,
+                    liquidity_net,
+                }
+            } else {
+                RangeOrderTick {
+                    liquidity_net,
+                    ..Default::default()
+                }
+            };
+            extension
+                .update_range_order(&tick_array, tick_index, TS_8, &range_order)
+                .unwrap();
+        }
+    }
+
+    fn build_fixture(
+        curr_tick_index: i32,
+        sqrt_price_limit_index: i32,
+        a_to_b: bool,
+    ) -> SwapTestFixture {
+        SwapTestFixture::new(SwapTestFixtureInfo {
+            tick_spacing: TS_8,
+            liquidity: 100_000,
+            curr_tick_index,
+            start_tick_index: 0,
+            trade_amount: 1_000_000,
+            sqrt_price_limit: sqrt_price_from_tick_index(sqrt_price_limit_index),
+            amount_specified_is_input: true,
+            a_to_b,
+            array_1_ticks: &vec![
+                TestTickInfo {
+                    index: 0,
+                    liquidity_net: 100_000,
+                    ..Default::default()
+                },
+                TestTickInfo {
+                    index: 64,
+                    liquidity_net: -100_000,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        })
+    }
+
+    fn build_tick_sequence<'a>(
+        swap_test_info: &'a SwapTestFixture,
+        extension: &'a RefCell<TickArrayExtension>,
+    ) -> TickSequence<'a> {
+        TickSequence::from_arrays_with_extensions(vec![(
+            swap_test_info.tick_arrays[0].borrow_mut(),
+            Some(extension.borrow_mut()),
+        )])
+        .unwrap()
+    }
+
+    fn assert_range_order_filled(
+        tick_sequence: &TickSequence,
+        fill_tick_index: i32,
+        other_tick_index: i32,
+    ) {
+        let fill_tick = tick_sequence.get_tick(0, fill_tick_index, TS_8).unwrap();
+        assert_eq!(fill_tick.initialized, false);
+        assert_eq!({ fill_tick.liquidity_net }, 0);
+        assert_eq!({ fill_tick.liquidity_gross }, 0);
+
+        let range_order = tick_sequence
+            .get_range_order(0, fill_tick_index, TS_8)
+            .unwrap()
+            .unwrap();
+        assert_eq!({ range_order.liquidity }, 0);
+        assert_eq!({ range_order.fill_count }, 1);
+        assert_eq!({ range_order.unclaimed_count }, 1);
+        assert_eq!({ range_order.liquidity_net }, 0);
+
+        let other_tick = tick_sequence.get_tick(0, other_tick_index, TS_8).unwrap();
+        assert_eq!(other_tick.initialized, false);
+        assert_eq!({ other_tick.liquidity_net }, 0);
+        assert_eq!({ other_tick.liquidity_gross }, 0);
+
+        let other_range_order = tick_sequence
+            .get_range_order(0, other_tick_index, TS_8)
+            .unwrap()
+            .unwrap();
+        assert_eq!({ other_range_order.liquidity_net }, 0);
+    }
+
+    #[test]
+    /// A rightward swap through a range order converting A to B.
+    /// |__c1__p1__[r1]____c2____p1__|
+    ///
+    /// Expectation:
+    /// The order is filled at its upper tick and removed from both of its ticks.
+    fn range_order_a_to_b_filled_by_rightward_swap() {
+        let swap_test_info = build_fixture(5, 40, false);
+        let extension = RefCell::new(TickArrayExtension::default());
+        place_range_order(&swap_test_info.tick_arrays[0], &extension, 16, 50_000, true);
+
+        let mut tick_sequence = build_tick_sequence(&swap_test_info, &extension);
+        let post_swap = swap_test_info.run(&mut tick_sequence, 100);
+
+        assert_eq!(post_swap.next_tick_index, 40);
+        assert_eq!(post_swap.next_liquidity, 100_000);
+        assert_eq!(post_swap.next_range_order_liquidity, 0);
+        assert_range_order_filled(&tick_sequence, 24, 16);
+    }
+
+    #[test]
+    /// A leftward swap through a range order converting B to A.
+    /// |__p1__c2__[r1]____c1____p1__|
+    ///
+    /// Expectation:
+    /// The order is filled at its lower tick and removed from both of its ticks.
+    fn range_order_b_to_a_filled_by_leftward_swap() {
+        let swap_test_info = build_fixture(40, 5, true);
+        let extension = RefCell::new(TickArrayExtension::default());
+        place_range_order(
+            &swap_test_info.tick_arrays[0],
+            &extension,
+            16,
+            50_000,
+            false,
+        );
+
+        let mut tick_sequence = build_tick_sequence(&swap_test_info, &extension);
+        let post_swap = swap_test_info.run(&mut tick_sequence, 100);
+
+        assert_eq!(post_swap.next_tick_index, 5);
+        assert_eq!(post_swap.next_liquidity, 100_000);
+        assert_eq!(post_swap.next_range_order_liquidity, 0);
+        assert_range_order_filled(&tick_sequence, 16, 24);
+    }
+
+    #[test]
+    /// A swap ending inside a range order converting A to B.
+    /// |__c1__p1__[r1__c2]________p1__|
+    ///
+    /// Expectation:
+    /// The order is partially converted and stays unfilled.
+    fn range_order_a_to_b_not_filled_within_range() {
+        let swap_test_info = build_fixture(5, 20, false);
+        let extension = RefCell::new(TickArrayExtension::default());
+        place_range_order(&swap_test_info.tick_arrays[0], &extension, 16, 50_000, true);
+
+        let mut tick_sequence = build_tick_sequence(&swap_test_info, &extension);
+        let post_swap = swap_test_info.run(&mut tick_sequence, 100);
+
+        assert_eq!(post_swap.next_tick_index, 20);
+        assert_eq!(post_swap.next_liquidity, 150_000);
+        assert_eq!(post_swap.next_range_order_liquidity, 50_000);
+
+        let fill_tick = tick_sequence.get_tick(0, 24, TS_8).unwrap();
+        assert_eq!({ fill_tick.liquidity_gross }, 50_000);
+        let range_order = tick_sequence.get_range_order(0, 24, TS_8).unwrap().unwrap();
+        assert_eq!({ range_order.liquidity }, 50_000);
+        assert_eq!({ range_order.fill_count }, 0);
+    }
+
+    #[test]
+    /// A swap through the ticks of a tick array created before range orders.
+    /// |__c1__p1__[p2]____c2____p1__|
+    ///
+    /// Expectation:
+    /// The ticks are crossed like any other, as the array has no extension holding range orders.
+    fn tick_array_without_extension() {
+        let swap_test_info = build_fixture(5, 40, false);
+        let extension = RefCell::new(TickArrayExtension::default());
+        place_range_order(&swap_test_info.tick_arrays[0], &extension, 16, 50_000, true);
+
+        let mut tick_sequence =
+            TickSequence::new(swap_test_info.tick_arrays[0].borrow_mut(), None, None);
+        let post_swap = swap_test_info.run(&mut tick_sequence, 100);
+
+        assert_eq!(post_swap.next_tick_index, 40);
+        assert_eq!(post_swap.next_liquidity, 100_000);
+        let tick = tick_sequence.get_tick(0, 16, TS_8).unwrap();
+        assert_eq!({ tick.liquidity_gross }, 50_000);
+    }
+
+    #[test]
+    /// Swap fees while a range order is within range.
+    ///
+    /// Expectation:
+    /// Fee growth is shared by the other active liquidity only, or goes to the protocol when
+    /// range orders hold all of it.
+    fn range_order_liquidity_earns_no_fees() {
+        assert_eq!(
+            calculate_fees(1_000, 0, 150_000, 50_000, 0, 0),
+            (0, (1_000u128 << Q64_RESOLUTION) / 100_000)
+        );
+        assert_eq!(calculate_fees(1_000, 0, 50_000, 50_000, 0, 0), (1_000, 0));
+        // Without liquidity the fee is dropped as before
+        assert_eq!(calculate_fees(1_000, 0, 0, 0, 0, 0), (0, 0));
+    }
+
+    #[test]
+    /// A leftward swap back out of a range order converting A to B, after entering it.
+    /// |__c2__p1__[r1__c1]________p1__|
+    ///
+    /// Expectation:
+    /// The order stays unfilled and its liquidity is no longer active.
+    fn range_order_a_to_b_left_without_fill() {
+        let mut swap_test_info = build_fixture(20, 5, true);
+        swap_test_info.globalpool.liquidity_available = 150_000;
+        swap_test_info.globalpool.range_order_liquidity = 50_000;
+        let extension = RefCell::new(TickArrayExtension::default());
+        place_range_order(&swap_test_info.tick_arrays[0], &extension, 16, 50_000, true);
+
+        let mut tick_sequence = build_tick_sequence(&swap_test_info, &extension);
+        let post_swap = swap_test_info.run(&mut tick_sequence, 100);
+
+        assert_eq!(post_swap.next_tick_index, 5);
+        assert_eq!(post_swap.next_liquidity, 100_000);
+        assert_eq!(post_swap.next_range_order_liquidity, 0);
+        let range_order = tick_sequence.get_range_order(0, 24, TS_8).unwrap().unwrap();
+        assert_eq!({ range_order.liquidity }, 50_000);
+        assert_eq!({ range_order.fill_count }, 0);
+    }
+}
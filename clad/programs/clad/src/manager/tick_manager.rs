@@ -99,6 +99,38 @@ pub fn next_tick_modify_liquidity_update_from_loan(
     Ok(update)
 }
 
+// Removes the liquidity of filled range orders from one of their ticks. Filled liquidity is no
+// longer provided on either side of the tick, so a later price reversal cannot convert it back.
+pub fn next_tick_range_order_fill_update(
+    update: &TickUpdate,
+    liquidity: u128,
+    is_upper_tick: bool,
+) -> Result<TickUpdate, ErrorCode> {
+    let liquidity_delta = i128::try_from(liquidity)?;
+
+    let liquidity_gross = update
+        .liquidity_gross
+        .checked_sub(liquidity)
+        .ok_or(ErrorCode::LiquidityUnderflow)?;
+
+    // Liquidity added at a lower tick is subtracted at its upper tick
+    let liquidity_net = if is_upper_tick {
+        update.liquidity_net.checked_add(liquidity_delta)
+    } else {
+        update.liquidity_net.checked_sub(liquidity_delta)
+    }
+    .ok_or(ErrorCode::LiquidityNetError)?;
+
+    Ok(TickUpdate {
+        initialized: liquidity_gross > 0,
+        liquidity_net,
+        liquidity_gross,
+        liquidity_borrowed: update.liquidity_borrowed,
+        fee_growth_outside_a: update.fee_growth_outside_a,
+        fee_growth_outside_b: update.fee_growth_outside_b,
+    })
+}
+
 // Calculates the fee growths inside of tick_lower and tick_upper based on their
 // index relative to tick_current_index.
 pub fn next_fee_growths_inside(
@@ -156,7 +188,7 @@ mod tick_manager_tests {
         errors::ErrorCode,
         manager::tick_manager::{
             next_fee_growths_inside, next_tick_cross_update, next_tick_modify_liquidity_update,
-            TickUpdate,
+            next_tick_range_order_fill_update, TickUpdate,
         },
         state::{tick_builder::TickBuilder, Tick},
     };
@@ -570,4 +602,76 @@ mod tick_manager_tests {
             );
         }
     }
+
+    #[test]
+    fn test_next_tick_range_order_fill_update() {
+        struct Test<'a> {
+            name: &'a str,
+            update: TickUpdate,
+            liquidity: u128,
+            is_upper_tick: bool,
+            expected_update: TickUpdate,
+        }
+
+        for test in [
+            Test {
+                name: "fill at upper tick keeps other liquidity",
+                update: TickUpdate {
+                    initialized: true,
+                    liquidity_net: -300,
+                    liquidity_gross: 500,
+                    fee_growth_outside_a: 100,
+                    fee_growth_outside_b: 200,
+                    ..Default::default()
+                },
+                liquidity: 200,
+                is_upper_tick: true,
+                expected_update: TickUpdate {
+                    initialized: true,
+                    liquidity_net: -100,
+                    liquidity_gross: 300,
+                    fee_growth_outside_a: 100,
+                    fee_growth_outside_b: 200,
+                    ..Default::default()
+                },
+            },
+            Test {
+                name: "fill at lower tick removes all liquidity",
+                update: TickUpdate {
+                    initialized: true,
+                    liquidity_net: 200,
+                    liquidity_gross: 200,
+                    fee_growth_outside_a: 100,
+                    ..Default::default()
+                },
+                liquidity: 200,
+                is_upper_tick: false,
+                expected_update: TickUpdate {
+                    initialized: false,
+                    fee_growth_outside_a: 100,
+                    ..Default::default()
+                },
+            },
+        ] {
+            // System under test
+            let update =
+                next_tick_range_order_fill_update(&test.update, test.liquidity, test.is_upper_tick)
+                    .unwrap();
+
+            assert_eq!(update, test.expected_update, "{}", test.name);
+        }
+    }
+
+    #[test]
+    fn test_next_tick_range_order_fill_update_underflow() {
+        let update = TickUpdate {
+            initialized: true,
+            liquidity_net: 100,
+            liquidity_gross: 100,
+            ..Default::default()
+        };
+
+        let err = next_tick_range_order_fill_update(&update, 200, false).unwrap_err();
+        assert_eq!(err, ErrorCode::LiquidityUnderflow);
+    }
 }
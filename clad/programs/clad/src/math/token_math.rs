@@ -24,6 +24,28 @@ pub const MAX_PROTOCOL_FEE_RATE: u16 = 2_500;
 // We want PROTOCOL_FEE_RATE_MUL_VALUE = 1/PROTOCOL_FEE_UNIT, so 1e4
 pub const PROTOCOL_FEE_RATE_MUL_VALUE: u128 = 10_000;
 
+// Loan risk caps (borrowed share of pool liquidity, tick utilization) are represented as basis points.
+// A cap of 10_000 allows borrowing up to 100% of the liquidity it applies to.
+pub const MAX_RISK_BPS: u16 = 10_000;
+
+// Assuming that risk caps are represented as basis points
+// We want RISK_BPS_MUL_VALUE = 1/BPS_UNIT, so 1e4
+pub const RISK_BPS_MUL_VALUE: u128 = 10_000;
+
+// Dynamic fee volatility is accumulated as tick groups (of `tick_spacing` ticks) crossed, scaled by 10_000.
+pub const VOLATILITY_ACCUMULATOR_SCALE_FACTOR: u32 = 10_000;
+
+// Reduction factor of the volatility reference is represented as basis points.
+pub const REDUCTION_FACTOR_DENOMINATOR: u16 = 10_000;
+
+// Variable fee rate = variable_fee_control * (volatility_accumulator * tick_spacing)^2
+//                     / (VARIABLE_FEE_CONTROL_DENOMINATOR * VOLATILITY_ACCUMULATOR_SCALE_FACTOR^2)
+pub const VARIABLE_FEE_CONTROL_DENOMINATOR: u32 = 100_000;
+
+// Annual loan interest (utilization) rates are divided by this value to get the interest per second.
+// 31,536,000 sec per yr * 100 bps per 1% (ignore leap years)
+pub const ANNUAL_INTEREST_DENOMINATOR: u128 = 3_153_600_000;
+
 //
 // Get change in token_a corresponding to a change in price
 //
@@ -108,12 +130,9 @@ pub fn get_liquidity_delta_a(
 ) -> Result<u128, ErrorCode> {
     let (sqrt_price_lower, sqrt_price_upper) = increasing_price_order(sqrt_price_0, sqrt_price_1);
 
-    let sqrt_mult = mul_u256(sqrt_price_lower, sqrt_price_upper)
-        .try_into_u128()
-        .unwrap();
-    let numerator = mul_u256(amount as u128, sqrt_mult)
-        .checked_shift_word_left()
-        .ok_or(ErrorCode::MultiplicationOverflow)?;
+    // Q64.64, fits in 128 bits as sqrt prices are below 2^96
+    let sqrt_mult = mul_u256(sqrt_price_lower, sqrt_price_upper).shift_word_right();
+    let numerator = sqrt_mult.mul(U256Muldiv::new(0, amount as u128));
     let denominator = U256Muldiv::new(0, sqrt_price_upper - sqrt_price_lower);
 
     let (quotient, remainder) = numerator.div(denominator, round_up);
@@ -141,7 +160,7 @@ pub fn get_liquidity_delta_b(
 ) -> Result<u128, ErrorCode> {
     let (sqrt_price_lower, sqrt_price_upper) = increasing_price_order(sqrt_price_0, sqrt_price_1);
 
-    let numerator = U256Muldiv::new(0, amount as u128);
+    let numerator = U256Muldiv::new(0, amount as u128).shift_word_left();
     let denominator = U256Muldiv::new(0, sqrt_price_upper - sqrt_price_lower);
 
     let (quotient, remainder) = numerator.div(denominator, round_up);
@@ -559,3 +578,49 @@ mod test_get_amount_delta {
         assert!(get_amount_delta_a(1 << 64, 2 << 64, u64::MAX as u128, true).is_ok());
     }
 }
+
+#[cfg(test)]
+mod test_get_liquidity_delta {
+    use super::{
+        get_amount_delta_a, get_amount_delta_b, get_liquidity_delta_a, get_liquidity_delta_b,
+    };
+
+    #[test]
+    fn test_get_liquidity_delta_ok() {
+        // Inverse of test_get_amount_delta_ok
+        assert_eq!(
+            get_liquidity_delta_a(4 << 64, 2 << 64, 1, false).unwrap(),
+            4
+        );
+        assert_eq!(
+            get_liquidity_delta_b(4 << 64, 2 << 64, 8, false).unwrap(),
+            4
+        );
+
+        // Rounded down unless requested
+        assert_eq!(
+            get_liquidity_delta_b(4 << 64, 2 << 64, 9, false).unwrap(),
+            4
+        );
+        assert_eq!(get_liquidity_delta_b(4 << 64, 2 << 64, 9, true).unwrap(), 5);
+    }
+
+    #[test]
+    fn test_get_liquidity_delta_round_trip() {
+        let sqrt_price_lower = 15_000_000_000_000_000_000;
+        let sqrt_price_upper = 21_000_000_000_000_000_000;
+        let amount = 1_000_000_000;
+
+        let liquidity_a =
+            get_liquidity_delta_a(sqrt_price_lower, sqrt_price_upper, amount, false).unwrap();
+        let amount_a =
+            get_amount_delta_a(sqrt_price_lower, sqrt_price_upper, liquidity_a, true).unwrap();
+        assert!(amount_a <= amount && amount_a + 1 >= amount);
+
+        let liquidity_b =
+            get_liquidity_delta_b(sqrt_price_lower, sqrt_price_upper, amount, false).unwrap();
+        let amount_b =
+            get_amount_delta_b(sqrt_price_lower, sqrt_price_upper, liquidity_b, true).unwrap();
+        assert!(amount_b <= amount && amount_b + 1 >= amount);
+    }
+}
@@ -0,0 +1,385 @@
+//! Off-chain quotes of swaps, computed by the same logic as the `swap` instruction.
+
+use {
+    crate::{
+        manager::swap_manager,
+        state::{Globalpool, TickArray, TickArrayBitmap, TickArrayExtension},
+        util::{
+            calculate_transfer_fee_excluded_amount, calculate_transfer_fee_included_amount,
+            TickSequence,
+        },
+    },
+    anchor_lang::prelude::*,
+    spl_token_2022::extension::transfer_fee::TransferFee,
+    std::cell::RefCell,
+};
+
+pub struct SwapQuoteParams<'a> {
+    pub globalpool: &'a Globalpool,
+    // Decoded tick arrays of the swap, ordered in the swap direction like the instruction's. Like
+    // in `swap`, a tick array passed more than once is only used at its first position.
+    pub tick_arrays: &'a [TickArray],
+    // Decoded extensions of the tick arrays at the same positions, none for tick arrays that are
+    // not migrated. Missing entries are treated as not migrated.
+    pub tick_array_extensions: &'a [Option<TickArrayExtension>],
+    pub tick_array_bitmap: Option<&'a TickArrayBitmap>,
+    pub amount: u64,
+    pub sqrt_price_limit: u128,
+    pub amount_specified_is_input: bool,
+    pub a_to_b: bool,
+    // Unix timestamp the swap is expected to land at, which the dynamic fee depends on
+    pub timestamp: u64,
+    // Transfer fees of the input & output mints for the current epoch, if any
+    pub transfer_fee_input: Option<TransferFee>,
+    pub transfer_fee_output: Option<TransferFee>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SwapQuote {
+    // Sent by the user, including the input transfer fee
+    pub amount_in: u64,
+    // Received by the user, after the output transfer fee
+    pub amount_out: u64,
+    // Swap fee in the input token
+    pub fee_amount: u64,
+    pub end_sqrt_price: u128,
+    pub end_tick_index: i32,
+    pub ticks_crossed: u32,
+    // Relative change of the pool price, e.g. 0.01 when the price moves by 1%
+    pub price_impact: f64,
+    // Tick arrays the swap goes through, laid out as the instruction takes them
+    pub tick_arrays: SwapQuoteTickArrays,
+}
+
+// Tick arrays of the `swap` instruction. When the swap goes through fewer than three tick arrays,
+// the last one is repeated, as `swap` ignores tick_array_1 & tick_array_2 when they repeat an
+// earlier tick array.
+#[derive(Debug, PartialEq)]
+pub struct SwapQuoteTickArrays {
+    pub tick_array_0: Pubkey,
+    pub tick_array_1: Pubkey,
+    pub tick_array_2: Pubkey,
+    // Following tick_array_2 in the swap direction, passed as remaining accounts after the
+    // TickArrayBitmap, if any
+    pub remaining_tick_arrays: Vec<Pubkey>,
+}
+
+impl SwapQuoteTickArrays {
+    fn new(tick_arrays: &[&TickArray]) -> Self {
+        let mut keys = tick_arrays
+            .iter()
+            .map(|tick_array| tick_array_address(tick_array));
+
+        let tick_array_0 = keys.next().unwrap_or_default();
+        let tick_array_1 = keys.next().unwrap_or(tick_array_0);
+        let tick_array_2 = keys.next().unwrap_or(tick_array_1);
+        SwapQuoteTickArrays {
+            tick_array_0,
+            tick_array_1,
+            tick_array_2,
+            remaining_tick_arrays: keys.collect(),
+        }
+    }
+
+    // All the tick arrays, in the order they are passed to the instruction
+    pub fn to_vec(&self) -> Vec<Pubkey> {
+        [self.tick_array_0, self.tick_array_1, self.tick_array_2]
+            .into_iter()
+            .chain(self.remaining_tick_arrays.iter().copied())
+            .collect()
+    }
+}
+
+pub fn quote_swap(params: &SwapQuoteParams) -> Result<SwapQuote> {
+    let globalpool = params.globalpool;
+    let a_to_b = params.a_to_b;
+
+    let mut unique_tick_arrays: Vec<(&TickArray, Option<TickArrayExtension>)> = vec![];
+    for (index, tick_array) in params.tick_arrays.iter().enumerate() {
+        if unique_tick_arrays
+            .iter()
+            .any(|(unique, _)| unique.start_tick_index == tick_array.start_tick_index)
+        {
+            continue;
+        }
+        let extension = params.tick_array_extensions.get(index).copied().flatten();
+        unique_tick_arrays.push((tick_array, extension));
+    }
+
+    let tick_arrays: Vec<(RefCell<TickArray>, Option<RefCell<TickArrayExtension>>)> =
+        unique_tick_arrays
+            .iter()
+            .map(|(tick_array, extension)| {
+                (RefCell::new(**tick_array), extension.map(RefCell::new))
+            })
+            .collect();
+    let mut swap_tick_sequence = TickSequence::from_arrays_with_extensions(
+        tick_arrays
+            .iter()
+            .map(|(tick_array, extension)| {
+                (
+                    tick_array.borrow_mut(),
+                    extension.as_ref().map(|extension| extension.borrow_mut()),
+                )
+            })
+            .collect(),
+    )?;
+    if let Some(tick_array_bitmap) = params.tick_array_bitmap {
+        swap_tick_sequence.set_tick_array_bitmap(tick_array_bitmap);
+    }
+
+    let swap_amount = if params.amount_specified_is_input {
+        calculate_transfer_fee_excluded_amount(&params.transfer_fee_input, params.amount)?
+    } else {
+        calculate_transfer_fee_included_amount(&params.transfer_fee_output, params.amount)?
+    };
+
+    let swap_update = swap_manager::swap(
+        globalpool,
+        &mut swap_tick_sequence,
+        swap_amount,
+        params.sqrt_price_limit,
+        params.amount_specified_is_input,
+        a_to_b,
+        params.timestamp,
+    )?;
+
+    let (swap_amount_input, swap_amount_output) = if a_to_b {
+        (swap_update.amount_a, swap_update.amount_b)
+    } else {
+        (swap_update.amount_b, swap_update.amount_a)
+    };
+
+    let tick_arrays = SwapQuoteTickArrays::new(
+        &unique_tick_arrays[..=swap_update.last_array_index]
+            .iter()
+            .map(|(tick_array, _)| *tick_array)
+            .collect::<Vec<_>>(),
+    );
+
+    Ok(SwapQuote {
+        amount_in: calculate_transfer_fee_included_amount(
+            &params.transfer_fee_input,
+            swap_amount_input,
+        )?,
+        amount_out: calculate_transfer_fee_excluded_amount(
+            &params.transfer_fee_output,
+            swap_amount_output,
+        )?,
+        fee_amount: swap_update.fee_amount,
+        end_sqrt_price: swap_update.next_sqrt_price,
+        end_tick_index: swap_update.next_tick_index,
+        ticks_crossed: swap_update.ticks_crossed,
+        price_impact: price_impact(globalpool.sqrt_price, swap_update.next_sqrt_price),
+        tick_arrays,
+    })
+}
+
+fn tick_array_address(tick_array: &TickArray) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"tick_array",
+            tick_array.globalpool.as_ref(),
+            { tick_array.start_tick_index }.to_string().as_bytes(),
+        ],
+        &crate::ID,
+    )
+    .0
+}
+
+fn price_impact(start_sqrt_price: u128, end_sqrt_price: u128) -> f64 {
+    let sqrt_price_ratio = end_sqrt_price as f64 / start_sqrt_price as f64;
+    (sqrt_price_ratio * sqrt_price_ratio - 1.0).abs()
+}
+
+#[cfg(test)]
+mod swap_quote_tests {
+    use {
+        super::*,
+        crate::{math::sqrt_price_from_tick_index, util::test_utils::swap_test_fixture::*},
+        spl_token_2022::pod::{PodU16, PodU64},
+    };
+
+    fn fixture(a_to_b: bool) -> SwapTestFixture {
+        // One initialized tick in each of the first two tick arrays of the swap direction
+        let (array_1_ticks, array_2_ticks, sqrt_price_limit) = if a_to_b {
+            (4_480, -2_048, sqrt_price_from_tick_index(-22_528))
+        } else {
+            (5_504, 12_288, sqrt_price_from_tick_index(33_791))
+        };
+        let liquidity_net = if a_to_b { 100_000 } else { -100_000 };
+
+        SwapTestFixture::new(SwapTestFixtureInfo {
+            tick_spacing: TS_128,
+            liquidity: 500_000,
+            curr_tick_index: 5_000,
+            start_tick_index: 0,
+            trade_amount: 150_000,
+            sqrt_price_limit,
+            amount_specified_is_input: true,
+            a_to_b,
+            array_1_ticks: &vec![TestTickInfo {
+                index: array_1_ticks,
+                liquidity_net,
+                ..Default::default()
+            }],
+            array_2_ticks: Some(&vec![TestTickInfo {
+                index: array_2_ticks,
+                liquidity_net,
+                ..Default::default()
+            }]),
+            array_3_ticks: Some(&vec![]),
+            fee_rate: 3_000,
+            protocol_fee_rate: 300,
+            ..Default::default()
+        })
+    }
+
+    fn quote_params<'a>(
+        swap_test_info: &'a SwapTestFixture,
+        tick_arrays: &'a [TickArray],
+        transfer_fee: Option<TransferFee>,
+    ) -> SwapQuoteParams<'a> {
+        SwapQuoteParams {
+            globalpool: &swap_test_info.globalpool,
+            tick_arrays,
+            tick_array_extensions: &[],
+            tick_array_bitmap: None,
+            amount: swap_test_info.trade_amount,
+            sqrt_price_limit: swap_test_info.sqrt_price_limit,
+            amount_specified_is_input: swap_test_info.amount_specified_is_input,
+            a_to_b: swap_test_info.a_to_b,
+            timestamp: 1_000,
+            transfer_fee_input: transfer_fee,
+            transfer_fee_output: transfer_fee,
+        }
+    }
+
+    fn tick_arrays(swap_test_info: &SwapTestFixture) -> Vec<TickArray> {
+        swap_test_info
+            .tick_arrays
+            .iter()
+            .map(|tick_array| *tick_array.borrow())
+            .collect()
+    }
+
+    #[test]
+    fn matches_swap() {
+        let swap_test_info = fixture(true);
+        let tick_arrays = tick_arrays(&swap_test_info);
+        let quote = quote_swap(&quote_params(&swap_test_info, &tick_arrays, None)).unwrap();
+
+        let mut tick_sequence = TickSequence::new(
+            swap_test_info.tick_arrays[0].borrow_mut(),
+            Some(swap_test_info.tick_arrays[1].borrow_mut()),
+            Some(swap_test_info.tick_arrays[2].borrow_mut()),
+        );
+        let post_swap = swap_test_info.run(&mut tick_sequence, 1_000);
+
+        assert_eq!(quote.amount_in, post_swap.amount_a);
+        assert_eq!(quote.amount_out, post_swap.amount_b);
+        assert_eq!(quote.fee_amount, post_swap.fee_amount);
+        assert_eq!(quote.end_sqrt_price, post_swap.next_sqrt_price);
+        assert_eq!(quote.end_tick_index, post_swap.next_tick_index);
+        assert_eq!(quote.ticks_crossed, post_swap.ticks_crossed);
+        assert!(quote.price_impact > 0.0);
+        assert_eq!(
+            quote.tick_arrays.to_vec(),
+            tick_arrays
+                .iter()
+                .map(tick_array_address)
+                .take(post_swap.last_array_index + 1)
+                .chain(std::iter::repeat(tick_array_address(
+                    &tick_arrays[post_swap.last_array_index]
+                )))
+                .take(3)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn crosses_ticks_across_tick_arrays() {
+        let swap_test_info = fixture(true);
+        let tick_arrays = tick_arrays(&swap_test_info);
+        let quote = quote_swap(&quote_params(&swap_test_info, &tick_arrays, None)).unwrap();
+
+        // Both initialized ticks are crossed, the second one in the 2nd tick array
+        assert_eq!(quote.ticks_crossed, 2);
+        // The 3rd tick array of the instruction repeats the 2nd one, which `swap` then ignores
+        let tick_array_1 = tick_array_address(&tick_arrays[1]);
+        assert_eq!(
+            quote.tick_arrays,
+            SwapQuoteTickArrays {
+                tick_array_0: tick_array_address(&tick_arrays[0]),
+                tick_array_1,
+                tick_array_2: tick_array_1,
+                remaining_tick_arrays: vec![],
+            }
+        );
+        assert!(quote.end_tick_index < -2_048);
+        // Fee of 0.3% on the input, rounded up at each step
+        assert!(quote.fee_amount >= 449 && quote.fee_amount <= 453);
+    }
+
+    #[test]
+    fn pads_single_tick_array() {
+        let swap_test_info = fixture(true);
+        let tick_arrays = tick_arrays(&swap_test_info);
+        let quote = quote_swap(&SwapQuoteParams {
+            amount: 100,
+            ..quote_params(&swap_test_info, &tick_arrays, None)
+        })
+        .unwrap();
+
+        let tick_array_0 = tick_array_address(&tick_arrays[0]);
+        assert_eq!(quote.tick_arrays.to_vec(), vec![tick_array_0; 3]);
+    }
+
+    #[test]
+    fn passes_extra_tick_arrays_as_remaining_accounts() {
+        let swap_test_info = fixture(true);
+        let mut tick_arrays = tick_arrays(&swap_test_info);
+        let mut tick_array_3 = tick_arrays[2];
+        tick_array_3.start_tick_index = -33_792;
+        tick_arrays.push(tick_array_3);
+        // Repeated tick arrays are only used at their first position, like in `swap`
+        tick_arrays.insert(1, tick_arrays[0]);
+
+        let quote = quote_swap(&SwapQuoteParams {
+            amount: 1_000_000_000,
+            sqrt_price_limit: sqrt_price_from_tick_index(-40_000),
+            ..quote_params(&swap_test_info, &tick_arrays, None)
+        })
+        .unwrap();
+
+        assert_eq!(quote.end_tick_index, -40_000);
+        assert_eq!(
+            quote.tick_arrays,
+            SwapQuoteTickArrays {
+                tick_array_0: tick_array_address(&tick_arrays[0]),
+                tick_array_1: tick_array_address(&tick_arrays[2]),
+                tick_array_2: tick_array_address(&tick_arrays[3]),
+                remaining_tick_arrays: vec![tick_array_address(&tick_arrays[4])],
+            }
+        );
+    }
+
+    #[test]
+    fn includes_transfer_fees() {
+        let transfer_fee = Some(TransferFee {
+            epoch: PodU64::from(0),
+            maximum_fee: PodU64::from(u64::MAX),
+            transfer_fee_basis_points: PodU16::from(100),
+        });
+        let swap_test_info = fixture(false);
+        let tick_arrays = tick_arrays(&swap_test_info);
+
+        let quote = quote_swap(&quote_params(&swap_test_info, &tick_arrays, None)).unwrap();
+        let quote_with_fees =
+            quote_swap(&quote_params(&swap_test_info, &tick_arrays, transfer_fee)).unwrap();
+
+        // The input transfer fee is taken before the swap, and the output one after it
+        assert_eq!(quote_with_fees.amount_in, quote.amount_in);
+        assert!(quote_with_fees.amount_out < quote.amount_out * 99 / 100 + 1);
+    }
+}
@@ -3,7 +3,8 @@ use {
         errors::ErrorCode,
         math::{
             add_liquidity_delta, tick_index_from_sqrt_price, MAX_FEE_RATE, MAX_PROTOCOL_FEE_RATE,
-            MAX_SQRT_PRICE_X64, MIN_SQRT_PRICE_X64, Q64_RESOLUTION,
+            MAX_RISK_BPS, MAX_SQRT_PRICE_X64, MIN_SQRT_PRICE_X64, Q64_RESOLUTION,
+            REDUCTION_FACTOR_DENOMINATOR,
         },
         util::to_timestamp_u64,
     },
@@ -58,6 +59,54 @@ pub struct Globalpool {
     pub inception_time: u64,
 
     pub fee_authority: Pubkey,
+
+    // Risk parameters for loans, set by the fee authority. Zero disables the cap.
+    // Max total borrowed liquidity as a share of `liquidity_available`, stored as basis points
+    pub max_borrowed_liquidity_bps: u16,
+    // Max borrowed liquidity as a share of a tick's `liquidity_gross`, stored as basis points
+    pub max_tick_utilization_bps: u16,
+    // Max amount of loan token borrowed by a single trade position (when borrowing A or B)
+    pub max_position_notional_a: u64,
+    pub max_position_notional_b: u64,
+
+    // Cumulative funding rate of perpetual trade positions (annual bps * seconds), updated by `crank_funding`
+    pub funding_index: u128,
+    pub funding_updated_at: u64, // UNIX time of the last funding index update (in seconds)
+
+    // Fee of flash loans, stored as hundredths of a basis point (like `fee_rate`), set by the fee authority
+    pub flash_fee_rate: u16,
+    // Amounts lent by the outstanding flash loan, to be repaid later in the same transaction
+    pub flash_loan_amount_a: u64,
+    pub flash_loan_amount_b: u64,
+
+    // Dynamic fee parameters, set by the fee authority. A zero `variable_fee_control` disables
+    // the variable fee, so swaps pay `fee_rate` only.
+    pub variable_fee_control: u32,
+    pub max_volatility_accumulator: u32,
+    pub filter_period: u16, // Seconds during which swaps share the same volatility reference
+    pub decay_period: u16,  // Seconds after which the volatility reference is reset
+    pub reduction_factor: u16, // Share of the accumulator kept as reference, stored as basis points
+
+    // Dynamic fee state, updated by swaps
+    pub volatility_reference: u32,
+    pub volatility_accumulator: u32,
+    pub tick_group_index_reference: i32,
+    pub last_volatility_update: u64,
+
+    // Program called before & after swaps and liquidity changes, which it may veto (see
+    // `hook_manager`). Set at creation only, the default key meaning no hook.
+    pub hook_program: Pubkey,
+
+    // Part of `liquidity_available` held by unfilled range orders, which earns no fees
+    pub range_order_liquidity: u128,
+}
+
+// Volatility state of the dynamic fee after a swap
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct VolatilityUpdate {
+    pub volatility_reference: u32,
+    pub volatility_accumulator: u32,
+    pub tick_group_index_reference: i32,
 }
 
 impl Globalpool {
@@ -138,25 +187,131 @@ impl Globalpool {
 
         self.inception_time = to_timestamp_u64(Clock::get()?.unix_timestamp)?;
 
+        // Risk caps are disabled until the fee authority sets them
+        self.max_borrowed_liquidity_bps = 0;
+        self.max_tick_utilization_bps = 0;
+        self.max_position_notional_a = 0;
+        self.max_position_notional_b = 0;
+
+        self.funding_index = 0;
+        self.funding_updated_at = self.inception_time;
+
+        self.flash_fee_rate = 0;
+        self.flash_loan_amount_a = 0;
+        self.flash_loan_amount_b = 0;
+
+        // Dynamic fee is disabled until the fee authority sets its parameters
+        self.variable_fee_control = 0;
+        self.max_volatility_accumulator = 0;
+        self.filter_period = 0;
+        self.decay_period = 0;
+        self.reduction_factor = 0;
+        self.reset_volatility();
+
+        Ok(())
+    }
+
+    pub fn update_risk_parameters(
+        &mut self,
+        max_borrowed_liquidity_bps: u16,
+        max_tick_utilization_bps: u16,
+        max_position_notional_a: u64,
+        max_position_notional_b: u64,
+    ) -> Result<()> {
+        if max_borrowed_liquidity_bps > MAX_RISK_BPS || max_tick_utilization_bps > MAX_RISK_BPS {
+            return Err(ErrorCode::InvalidRiskParameter.into());
+        }
+
+        self.max_borrowed_liquidity_bps = max_borrowed_liquidity_bps;
+        self.max_tick_utilization_bps = max_tick_utilization_bps;
+        self.max_position_notional_a = max_position_notional_a;
+        self.max_position_notional_b = max_position_notional_b;
+
+        Ok(())
+    }
+
+    pub fn update_flash_fee_rate(&mut self, flash_fee_rate: u16) -> Result<()> {
+        if flash_fee_rate > MAX_FEE_RATE {
+            return Err(ErrorCode::FeeRateMaxExceeded.into());
+        }
+        self.flash_fee_rate = flash_fee_rate;
+
+        Ok(())
+    }
+
+    pub fn update_dynamic_fee_parameters(
+        &mut self,
+        variable_fee_control: u32,
+        max_volatility_accumulator: u32,
+        filter_period: u16,
+        decay_period: u16,
+        reduction_factor: u16,
+    ) -> Result<()> {
+        if reduction_factor > REDUCTION_FACTOR_DENOMINATOR
+            || (variable_fee_control > 0 && filter_period >= decay_period)
+        {
+            return Err(ErrorCode::InvalidDynamicFeeParameter.into());
+        }
+
+        self.variable_fee_control = variable_fee_control;
+        self.max_volatility_accumulator = max_volatility_accumulator;
+        self.filter_period = filter_period;
+        self.decay_period = decay_period;
+        self.reduction_factor = reduction_factor;
+        self.reset_volatility();
+
         Ok(())
     }
 
+    pub fn is_dynamic_fee_enabled(&self) -> bool {
+        self.variable_fee_control > 0
+    }
+
+    // The next swap starts from a fresh volatility reference at the current tick
+    fn reset_volatility(&mut self) {
+        self.volatility_reference = 0;
+        self.volatility_accumulator = 0;
+        self.tick_group_index_reference = 0;
+        self.last_volatility_update = 0;
+    }
+
     pub fn update_liquidity(&mut self, liquidity: u128) {
         self.liquidity_available = liquidity;
     }
 
+    pub fn update_range_order_liquidity(&mut self, range_order_liquidity: u128) {
+        self.range_order_liquidity = range_order_liquidity;
+    }
+
+    // Liquidity sharing the fees paid to the pool
+    pub fn fee_liquidity(&self) -> u128 {
+        self.liquidity_available
+            .saturating_sub(self.range_order_liquidity)
+    }
+
     pub fn update_after_swap(
         &mut self,
         liquidity_available: u128,
+        range_order_liquidity: u128,
         tick_index: i32,
         sqrt_price: u128,
         fee_growth_global: u128,
         protocol_fee: u64,
+        volatility_update: &VolatilityUpdate,
+        timestamp: u64,
         is_token_fee_in_a: bool,
     ) {
+        if self.is_dynamic_fee_enabled() {
+            self.volatility_reference = volatility_update.volatility_reference;
+            self.volatility_accumulator = volatility_update.volatility_accumulator;
+            self.tick_group_index_reference = volatility_update.tick_group_index_reference;
+            self.last_volatility_update = timestamp;
+        }
+
         self.tick_current_index = tick_index;
         self.sqrt_price = sqrt_price;
         self.liquidity_available = liquidity_available;
+        self.range_order_liquidity = range_order_liquidity;
         if is_token_fee_in_a {
             // Add fees taken via a
             self.fee_growth_global_a = fee_growth_global;
@@ -184,44 +339,85 @@ impl Globalpool {
         interest_amount: u64,
         is_token_fee_in_a: bool,
     ) {
-        if interest_amount > 0 {
-            let liquidity_available = if self.liquidity_available > 0 {
-                self.liquidity_available
-            } else {
-                1 // If there's zero liquidity in the pool (or when there has been ZERO trade in the pool),
-                  // globalpool.liquidity_available is zero, so we need to set to 1 to avoid division by zero
-                  // when calculating the interest fee accrued from lending out.
-                  // This should never happen in practice (swaps happen), but we need to handle it just in case.
-            };
-
-            let accrued_interest_fee =
-                ((interest_amount as u128) << Q64_RESOLUTION) / liquidity_available;
-
-            if is_token_fee_in_a {
-                self.fee_growth_global_a = self
-                    .fee_growth_global_a
-                    .checked_add(accrued_interest_fee)
-                    .unwrap();
-            } else {
-                self.fee_growth_global_b = self
-                    .fee_growth_global_b
-                    .checked_add(accrued_interest_fee)
-                    .unwrap();
-            }
-        }
+        self.add_fee_growth(interest_amount, is_token_fee_in_a);
 
         // Update the amount AFTER interest amount modification (above)
         // liquidity_delta = borrowed (positive) or repaid (negative) amount of liquidity_u128
-        msg!("liquidity_delta: {}", liquidity_delta);
-        msg!("liquidity_available: {}", self.liquidity_available);
-        msg!("liquidity_borrowed: {}", self.liquidity_borrowed);
-
         // TODO: Only add/sub delta if it's in the same tick array as the current tick index (where liquidity_available is calculated)
 
         // self.liquidity_available =
         //     add_liquidity_delta(self.liquidity_available, -liquidity_delta).unwrap();
-        // self.liquidity_borrowed =
-        //     add_liquidity_delta(self.liquidity_borrowed, liquidity_delta).unwrap();
+
+        // Borrowed liquidity is tracked for the pool-wide borrow cap. Positions opened before
+        // the cap existed were never added, so repaying them saturates at zero.
+        self.liquidity_borrowed = add_liquidity_delta(self.liquidity_borrowed, liquidity_delta)
+            .unwrap_or(if liquidity_delta < 0 { 0 } else { u128::MAX });
+    }
+
+    // Lend `amount_a` & `amount_b` out of the vaults until `update_after_flash_repay`
+    pub fn update_after_flash_borrow(&mut self, amount_a: u64, amount_b: u64) {
+        self.flash_loan_amount_a = amount_a;
+        self.flash_loan_amount_b = amount_b;
+    }
+
+    pub fn has_flash_loan(&self) -> bool {
+        self.flash_loan_amount_a > 0 || self.flash_loan_amount_b > 0
+    }
+
+    // Flash loan fees are paid to LPs, like loan interest
+    pub fn update_after_flash_repay(&mut self, fee_a: u64, fee_b: u64) {
+        self.add_fee_growth(fee_a, true);
+        self.add_fee_growth(fee_b, false);
+        self.flash_loan_amount_a = 0;
+        self.flash_loan_amount_b = 0;
+    }
+
+    fn add_fee_growth(&mut self, fee_amount: u64, is_token_fee_in_a: bool) {
+        if fee_amount == 0 {
+            return;
+        }
+
+        let liquidity_available = if self.fee_liquidity() > 0 {
+            self.fee_liquidity()
+        } else {
+            1 // If there's zero liquidity in the pool (or when there has been ZERO trade in the pool),
+              // globalpool.liquidity_available is zero, so we need to set to 1 to avoid division by zero
+              // when calculating the interest fee accrued from lending out.
+              // This should never happen in practice (swaps happen), but we need to handle it just in case.
+        };
+
+        let accrued_fee = ((fee_amount as u128) << Q64_RESOLUTION) / liquidity_available;
+
+        if is_token_fee_in_a {
+            self.fee_growth_global_a = self.fee_growth_global_a.checked_add(accrued_fee).unwrap();
+        } else {
+            self.fee_growth_global_b = self.fee_growth_global_b.checked_add(accrued_fee).unwrap();
+        }
+    }
+
+    pub fn update_funding_index(&mut self, funding_index: u128, timestamp: u64) {
+        self.funding_index = funding_index;
+        self.funding_updated_at = timestamp;
+    }
+
+    // Hooks can't call back into Clad, so neither can Clad itself be the hook
+    pub fn initialize_hook_program(&mut self, hook_program: Option<Pubkey>) -> Result<()> {
+        if let Some(hook_program) = hook_program {
+            if hook_program == crate::ID || hook_program == Pubkey::default() {
+                return Err(ErrorCode::InvalidHookProgram.into());
+            }
+            self.hook_program = hook_program;
+        }
+
+        Ok(())
+    }
+
+    pub fn get_hook_program(&self) -> Option<Pubkey> {
+        if self.hook_program == Pubkey::default() {
+            None
+        } else {
+            Some(self.hook_program)
+        }
     }
 
     pub fn reset_protocol_fees_owed(&mut self) {
@@ -244,6 +440,13 @@ pub mod globalpool_builder {
         protocol_fee_rate: u16,
         fee_growth_global_a: u128,
         fee_growth_global_b: u128,
+        liquidity_borrowed: u128,
+        max_borrowed_liquidity_bps: u16,
+        max_tick_utilization_bps: u16,
+        max_position_notional_a: u64,
+        max_position_notional_b: u64,
+        funding_index: u128,
+        funding_updated_at: u64,
     }
 
     impl GlobalpoolBuilder {
@@ -291,6 +494,41 @@ pub mod globalpool_builder {
             self
         }
 
+        pub fn liquidity_borrowed(mut self, liquidity_borrowed: u128) -> Self {
+            self.liquidity_borrowed = liquidity_borrowed;
+            self
+        }
+
+        pub fn max_borrowed_liquidity_bps(mut self, max_borrowed_liquidity_bps: u16) -> Self {
+            self.max_borrowed_liquidity_bps = max_borrowed_liquidity_bps;
+            self
+        }
+
+        pub fn max_tick_utilization_bps(mut self, max_tick_utilization_bps: u16) -> Self {
+            self.max_tick_utilization_bps = max_tick_utilization_bps;
+            self
+        }
+
+        pub fn max_position_notional_a(mut self, max_position_notional_a: u64) -> Self {
+            self.max_position_notional_a = max_position_notional_a;
+            self
+        }
+
+        pub fn max_position_notional_b(mut self, max_position_notional_b: u64) -> Self {
+            self.max_position_notional_b = max_position_notional_b;
+            self
+        }
+
+        pub fn funding_index(mut self, funding_index: u128) -> Self {
+            self.funding_index = funding_index;
+            self
+        }
+
+        pub fn funding_updated_at(mut self, funding_updated_at: u64) -> Self {
+            self.funding_updated_at = funding_updated_at;
+            self
+        }
+
         pub fn build(self) -> Globalpool {
             Globalpool {
                 liquidity_available: self.liquidity,
@@ -301,6 +539,13 @@ pub mod globalpool_builder {
                 fee_growth_global_b: self.fee_growth_global_b,
                 fee_rate: self.fee_rate,
                 protocol_fee_rate: self.protocol_fee_rate,
+                liquidity_borrowed: self.liquidity_borrowed,
+                max_borrowed_liquidity_bps: self.max_borrowed_liquidity_bps,
+                max_tick_utilization_bps: self.max_tick_utilization_bps,
+                max_position_notional_a: self.max_position_notional_a,
+                max_position_notional_b: self.max_position_notional_b,
+                funding_index: self.funding_index,
+                funding_updated_at: self.funding_updated_at,
                 ..Default::default()
             }
         }
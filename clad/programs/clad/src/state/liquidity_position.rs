@@ -1,6 +1,7 @@
 use anchor_lang::prelude::*;
 
 use crate::errors::ErrorCode;
+use crate::math::{mul_u256, U256Muldiv};
 
 use super::{Tick, Globalpool};
 
@@ -19,6 +20,11 @@ pub struct LiquidityPosition {
     // Q64.64
     pub fee_growth_checkpoint_b: u128, // 16
     pub fee_owed_b: u64,               // 8
+
+    pub is_range_order: bool,     // 1
+    pub range_order_a_to_b: bool, // 1
+    // Fill count of the order's fill tick when the order was placed
+    pub range_order_fill_count: u64, // 8
 }
 
 impl LiquidityPosition {
@@ -63,6 +69,108 @@ impl LiquidityPosition {
         self.fee_owed_a = 0;
         self.fee_owed_b = 0;
     }
+
+    pub fn add_fees_owed(&mut self, fee_amount_a: u64, fee_amount_b: u64) {
+        self.fee_owed_a += fee_amount_a;
+        self.fee_owed_b += fee_amount_b;
+    }
+
+    // Split and merged positions share their range, and so their fee growth inside the range
+    fn check_same_range(&self, position: &LiquidityPosition) -> Result<()> {
+        if self.globalpool != position.globalpool
+            || self.tick_lower_index != position.tick_lower_index
+            || self.tick_upper_index != position.tick_upper_index
+        {
+            return Err(ErrorCode::PositionRangeMismatch.into());
+        }
+        Ok(())
+    }
+
+    // Moves `liquidity` and its share of the fees owed to `position`, a newly opened position with
+    // the same range. Fee growths of this position must be up to date.
+    pub fn split_into(&mut self, position: &mut LiquidityPosition, liquidity: u128) -> Result<()> {
+        self.check_same_range(position)?;
+        if liquidity == 0 || liquidity > self.liquidity {
+            return Err(ErrorCode::InvalidSplitLiquidity.into());
+        }
+
+        // Rounds down, leaving the remainder with this position
+        let fee_owed_a = self.fee_share(self.fee_owed_a, liquidity)?;
+        let fee_owed_b = self.fee_share(self.fee_owed_b, liquidity)?;
+
+        position.liquidity = liquidity;
+        position.fee_growth_checkpoint_a = self.fee_growth_checkpoint_a;
+        position.fee_growth_checkpoint_b = self.fee_growth_checkpoint_b;
+        position.fee_owed_a = fee_owed_a;
+        position.fee_owed_b = fee_owed_b;
+
+        self.liquidity -= liquidity;
+        self.fee_owed_a -= fee_owed_a;
+        self.fee_owed_b -= fee_owed_b;
+
+        Ok(())
+    }
+
+    fn fee_share(&self, fee_owed: u64, liquidity: u128) -> Result<u64> {
+        let (share, _) =
+            mul_u256(fee_owed as u128, liquidity).div(U256Muldiv::new(0, self.liquidity), false);
+        Ok(share.try_into_u128()? as u64)
+    }
+
+    // Moves all of the liquidity and fees owed of `position`, which has the same range, into this
+    // position, leaving `position` empty. Fee growths of both positions must be up to date.
+    pub fn merge_from(&mut self, position: &mut LiquidityPosition) -> Result<()> {
+        self.check_same_range(position)?;
+
+        // Positions without liquidity don't have their fee growths updated
+        if self.liquidity == 0 {
+            self.fee_growth_checkpoint_a = position.fee_growth_checkpoint_a;
+            self.fee_growth_checkpoint_b = position.fee_growth_checkpoint_b;
+        }
+
+        self.liquidity = self
+            .liquidity
+            .checked_add(position.liquidity)
+            .ok_or(ErrorCode::LiquidityOverflow)?;
+        self.fee_owed_a = self
+            .fee_owed_a
+            .checked_add(position.fee_owed_a)
+            .ok_or(ErrorCode::AmountCalcOverflow)?;
+        self.fee_owed_b = self
+            .fee_owed_b
+            .checked_add(position.fee_owed_b)
+            .ok_or(ErrorCode::AmountCalcOverflow)?;
+
+        position.liquidity = 0;
+        position.reset_fees_owed();
+
+        Ok(())
+    }
+
+    pub fn open_range_order(&mut self, a_to_b: bool, fill_count: u64) {
+        self.is_range_order = true;
+        self.range_order_a_to_b = a_to_b;
+        self.range_order_fill_count = fill_count;
+    }
+
+    // Empties the position once its range order is claimed. Range orders don't earn fees.
+    pub fn close_range_order(&mut self) {
+        self.liquidity = 0;
+        self.reset_fees_owed();
+        self.is_range_order = false;
+        self.range_order_a_to_b = false;
+        self.range_order_fill_count = 0;
+    }
+
+    // Tick at which the range order fills: the upper tick when converting A to B, as the price
+    // rises through the range, and the lower tick when converting B to A.
+    pub fn range_order_fill_tick_index(&self) -> i32 {
+        if self.range_order_a_to_b {
+            self.tick_upper_index
+        } else {
+            self.tick_lower_index
+        }
+    }
 }
 
 #[derive(Default, Debug, PartialEq)]
@@ -93,6 +201,9 @@ mod is_liquidity_position_empty_tests {
             fee_owed_a,
             fee_growth_checkpoint_b: 0,
             fee_owed_b,
+            is_range_order: false,
+            range_order_a_to_b: false,
+            range_order_fill_count: 0,
         }
     }
 
@@ -121,6 +232,108 @@ mod is_liquidity_position_empty_tests {
     }
 }
 
+#[cfg(test)]
+mod split_merge_liquidity_position_tests {
+    use super::liquidity_position_builder::LiquidityPositionBuilder;
+    use super::*;
+
+    fn build_position(liquidity: u128, fee_owed_a: u64, fee_owed_b: u64) -> LiquidityPosition {
+        let mut position = LiquidityPositionBuilder::new(-128, 128)
+            .liquidity(liquidity)
+            .fee_growth_checkpoint_a(100)
+            .fee_growth_checkpoint_b(200)
+            .fee_owed_a(fee_owed_a)
+            .fee_owed_b(fee_owed_b)
+            .build();
+        position.globalpool = Pubkey::default();
+        position
+    }
+
+    fn build_empty_position() -> LiquidityPosition {
+        let mut position = LiquidityPositionBuilder::new(-128, 128).build();
+        position.globalpool = Pubkey::default();
+        position
+    }
+
+    #[test]
+    fn split_moves_liquidity_and_fee_share() {
+        let mut position = build_position(1000, 101, 10);
+        let mut new_position = build_empty_position();
+        position.split_into(&mut new_position, 250).unwrap();
+
+        assert_eq!(new_position.liquidity, 250);
+        assert_eq!(new_position.fee_owed_a, 25);
+        assert_eq!(new_position.fee_owed_b, 2);
+        assert_eq!(new_position.fee_growth_checkpoint_a, 100);
+        assert_eq!(new_position.fee_growth_checkpoint_b, 200);
+
+        assert_eq!(position.liquidity, 750);
+        assert_eq!(position.fee_owed_a, 76);
+        assert_eq!(position.fee_owed_b, 8);
+    }
+
+    #[test]
+    fn split_all_liquidity() {
+        let mut position = build_position(1000, 101, 10);
+        let mut new_position = build_empty_position();
+        position.split_into(&mut new_position, 1000).unwrap();
+
+        assert_eq!(new_position.liquidity, 1000);
+        assert_eq!(new_position.fee_owed_a, 101);
+        assert_eq!(new_position.fee_owed_b, 10);
+        assert!(LiquidityPosition::is_position_empty(&position));
+    }
+
+    #[test]
+    fn split_invalid_liquidity() {
+        let mut position = build_position(1000, 0, 0);
+        let mut new_position = build_empty_position();
+        assert!(position.split_into(&mut new_position, 0).is_err());
+        assert!(position.split_into(&mut new_position, 1001).is_err());
+        assert_eq!(position.liquidity, 1000);
+    }
+
+    #[test]
+    fn split_range_mismatch() {
+        let mut position = build_position(1000, 0, 0);
+        let mut new_position = build_empty_position();
+        new_position.tick_upper_index = 256;
+        assert!(position.split_into(&mut new_position, 500).is_err());
+    }
+
+    #[test]
+    fn merge_sums_liquidity_and_fees() {
+        let mut position = build_position(1000, 10, 20);
+        let mut other_position = build_position(500, 5, 7);
+        position.merge_from(&mut other_position).unwrap();
+
+        assert_eq!(position.liquidity, 1500);
+        assert_eq!(position.fee_owed_a, 15);
+        assert_eq!(position.fee_owed_b, 27);
+        assert!(LiquidityPosition::is_position_empty(&other_position));
+    }
+
+    #[test]
+    fn merge_into_empty_position_takes_checkpoints() {
+        let mut position = build_empty_position();
+        let mut other_position = build_position(500, 5, 7);
+        position.merge_from(&mut other_position).unwrap();
+
+        assert_eq!(position.liquidity, 500);
+        assert_eq!(position.fee_growth_checkpoint_a, 100);
+        assert_eq!(position.fee_growth_checkpoint_b, 200);
+    }
+
+    #[test]
+    fn merge_range_mismatch() {
+        let mut position = build_position(1000, 0, 0);
+        let mut other_position = build_position(500, 0, 0);
+        other_position.globalpool = Pubkey::new_unique();
+        assert!(position.merge_from(&mut other_position).is_err());
+        assert_eq!(other_position.liquidity, 500);
+    }
+}
+
 #[cfg(test)]
 pub mod liquidity_position_builder {
     use anchor_lang::prelude::Pubkey;
@@ -0,0 +1,214 @@
+use {crate::errors::ErrorCode, anchor_lang::prelude::*};
+
+pub const MAX_MARGIN_COLLATERALS: usize = 4;
+pub const MAX_MARGIN_POSITIONS: usize = 4;
+
+#[derive(Copy, Clone, Default, Debug, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct MarginCollateral {
+    pub mint: Pubkey, // Mint of the collateral token (empty slot if default)
+    pub amount: u64,  // Amount held in the margin vault (scaled to decimal exponent)
+    pub decimals: u8, // Decimals of the mint, used to value the collateral
+}
+
+// Cross-margin account of an owner. Collateral is held in token vaults owned by this account and
+// counts towards the combined health of every linked TradePosition. Positions opened against the
+// account post no collateral of their own, and draw on its balances when repaid.
+#[account]
+#[derive(Default)]
+pub struct MarginAccount {
+    pub owner: Pubkey,
+    pub bump: [u8; 1],
+
+    pub collaterals: [MarginCollateral; MAX_MARGIN_COLLATERALS],
+    pub positions: [Pubkey; MAX_MARGIN_POSITIONS], // Linked TradePosition accounts (empty slot if default)
+
+    // Set when the combined health fell below maintenance. Linked positions are force-matured
+    // so that they can be repaid by liquidators, and no collateral can be withdrawn.
+    pub is_liquidating: bool,
+}
+
+impl MarginAccount {
+    pub const LEN: usize = 8 + std::mem::size_of::<MarginAccount>();
+
+    // Combined equity must be at least 10% of the combined debt (stored as basis points), and at
+    // least 20% after opening a position backed by the account
+    pub const MAINTENANCE_MARGIN_BPS: u64 = 1_000;
+    pub const INITIAL_MARGIN_BPS: u64 = 2_000;
+    pub const BPS_MUL_VALUE: u64 = 10_000;
+
+    pub fn seeds(&self) -> [&[u8]; 3] {
+        [
+            &b"margin_account"[..],
+            self.owner.as_ref(),
+            self.bump.as_ref(),
+        ]
+    }
+
+    pub fn initialize(&mut self, owner: Pubkey, bump: u8) -> Result<()> {
+        if self.owner != Pubkey::default() {
+            return Err(ProgramError::AccountAlreadyInitialized.into());
+        }
+
+        self.owner = owner;
+        self.bump = [bump];
+        self.collaterals = [MarginCollateral::default(); MAX_MARGIN_COLLATERALS];
+        self.positions = [Pubkey::default(); MAX_MARGIN_POSITIONS];
+        self.is_liquidating = false;
+
+        Ok(())
+    }
+
+    pub fn get_collateral(&self, mint: &Pubkey) -> Option<&MarginCollateral> {
+        self.collaterals
+            .iter()
+            .find(|collateral| collateral.mint.eq(mint))
+    }
+
+    pub fn deposit_collateral(&mut self, mint: Pubkey, decimals: u8, amount: u64) -> Result<()> {
+        let slot = match self.collaterals.iter().position(|c| c.mint.eq(&mint)) {
+            Some(slot) => slot,
+            None => self
+                .collaterals
+                .iter()
+                .position(|c| c.mint.eq(&Pubkey::default()))
+                .ok_or(ErrorCode::MarginCollateralSlotsFull)?,
+        };
+
+        let collateral = &mut self.collaterals[slot];
+        collateral.mint = mint;
+        collateral.decimals = decimals;
+        collateral.amount = collateral
+            .amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::AmountCalcOverflow)?;
+
+        Ok(())
+    }
+
+    pub fn withdraw_collateral(&mut self, mint: &Pubkey, amount: u64) -> Result<()> {
+        let collateral = self
+            .collaterals
+            .iter_mut()
+            .find(|c| c.mint.eq(mint))
+            .ok_or(ErrorCode::MarginCollateralNotFound)?;
+
+        collateral.amount = collateral
+            .amount
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientCollateral)?;
+
+        // Free up the slot for another mint
+        if collateral.amount == 0 {
+            *collateral = MarginCollateral::default();
+        }
+
+        Ok(())
+    }
+
+    pub fn link_position(&mut self, position: Pubkey) -> Result<()> {
+        if self.positions.contains(&position) {
+            return Err(ErrorCode::MarginPositionAlreadyLinked.into());
+        }
+
+        let slot = self
+            .positions
+            .iter()
+            .position(|p| p.eq(&Pubkey::default()))
+            .ok_or(ErrorCode::MarginPositionSlotsFull)?;
+        self.positions[slot] = position;
+
+        Ok(())
+    }
+
+    pub fn unlink_position(&mut self, position: &Pubkey) -> Result<()> {
+        let slot = self
+            .positions
+            .iter()
+            .position(|p| p.eq(position))
+            .ok_or(ErrorCode::MarginPositionNotLinked)?;
+        self.positions[slot] = Pubkey::default();
+
+        // Liquidation is over once every linked position has been unlinked
+        if self.linked_positions().count() == 0 {
+            self.is_liquidating = false;
+        }
+
+        Ok(())
+    }
+
+    pub fn linked_positions(&self) -> impl Iterator<Item = &Pubkey> {
+        self.positions
+            .iter()
+            .filter(|position| **position != Pubkey::default())
+    }
+
+    pub fn deposited_collaterals(&self) -> impl Iterator<Item = &MarginCollateral> {
+        self.collaterals
+            .iter()
+            .filter(|collateral| collateral.mint != Pubkey::default())
+    }
+}
+
+#[cfg(test)]
+mod margin_account_tests {
+    use super::*;
+
+    #[test]
+    fn deposit_and_withdraw_collateral() {
+        let mut margin_account = MarginAccount::default();
+        let mint = Pubkey::new_unique();
+
+        margin_account.deposit_collateral(mint, 6, 100).unwrap();
+        margin_account.deposit_collateral(mint, 6, 50).unwrap();
+        assert_eq!(margin_account.get_collateral(&mint).unwrap().amount, 150);
+
+        margin_account.withdraw_collateral(&mint, 150).unwrap();
+        assert_eq!(margin_account.get_collateral(&mint), None);
+        assert_eq!(margin_account.deposited_collaterals().count(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "MarginCollateralSlotsFull")]
+    fn deposit_collateral_slots_full() {
+        let mut margin_account = MarginAccount::default();
+        for _ in 0..=MAX_MARGIN_COLLATERALS {
+            margin_account
+                .deposit_collateral(Pubkey::new_unique(), 6, 1)
+                .unwrap();
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "InsufficientCollateral")]
+    fn withdraw_more_than_deposited() {
+        let mut margin_account = MarginAccount::default();
+        let mint = Pubkey::new_unique();
+        margin_account.deposit_collateral(mint, 6, 100).unwrap();
+        margin_account.withdraw_collateral(&mint, 101).unwrap();
+    }
+
+    #[test]
+    fn unlink_last_position_ends_liquidation() {
+        let mut margin_account = MarginAccount::default();
+        let position_0 = Pubkey::new_unique();
+        let position_1 = Pubkey::new_unique();
+        margin_account.link_position(position_0).unwrap();
+        margin_account.link_position(position_1).unwrap();
+        margin_account.is_liquidating = true;
+
+        margin_account.unlink_position(&position_0).unwrap();
+        assert!(margin_account.is_liquidating);
+
+        margin_account.unlink_position(&position_1).unwrap();
+        assert!(!margin_account.is_liquidating);
+    }
+
+    #[test]
+    #[should_panic(expected = "MarginPositionAlreadyLinked")]
+    fn link_position_twice() {
+        let mut margin_account = MarginAccount::default();
+        let position = Pubkey::new_unique();
+        margin_account.link_position(position).unwrap();
+        margin_account.link_position(position).unwrap();
+    }
+}
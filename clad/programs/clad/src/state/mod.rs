@@ -1,13 +1,25 @@
 pub mod clad;
 pub mod globalpool;
 pub mod liquidity_position;
+pub mod margin_account;
+pub mod position_bundle;
+pub mod position_operator;
 pub mod pyth;
 pub mod trade_position;
 pub mod tick;
+pub mod tick_array_extension;
+pub mod tick_array_bitmap;
+pub mod token_price_feed;
 
 pub use clad::*;
 pub use globalpool::*;
 pub use liquidity_position::*;
+pub use margin_account::*;
+pub use position_bundle::*;
+pub use position_operator::*;
 pub use pyth::*;
 pub use trade_position::*;
 pub use tick::*;
+pub use tick_array_extension::*;
+pub use tick_array_bitmap::*;
+pub use token_price_feed::*;
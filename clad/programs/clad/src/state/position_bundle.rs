@@ -0,0 +1,148 @@
+use {crate::errors::ErrorCode, anchor_lang::prelude::*};
+
+pub const POSITION_BITMAP_USIZE: usize = 32;
+pub const POSITION_BUNDLE_SIZE: u16 = 8 * POSITION_BITMAP_USIZE as u16; // 256 positions
+
+// Up to 256 liquidity positions represented by a single token (NFT). Each bundled position is a
+// LiquidityPosition whose `position_mint` is the bundle mint, at a PDA of the bundle index.
+#[account]
+#[derive(Default)]
+pub struct PositionBundle {
+    pub position_bundle_mint: Pubkey, // Mint of this 1/1 PositionBundle account (NFT)
+    pub position_bitmap: [u8; POSITION_BITMAP_USIZE], // Bit `i` is set if bundle index `i` is open
+    pub bump: [u8; 1],
+}
+
+impl PositionBundle {
+    pub const LEN: usize = 8 + std::mem::size_of::<PositionBundle>();
+
+    pub fn seeds(&self) -> [&[u8]; 3] {
+        [
+            &b"position_bundle"[..],
+            self.position_bundle_mint.as_ref(),
+            self.bump.as_ref(),
+        ]
+    }
+
+    pub fn initialize(&mut self, position_bundle_mint: Pubkey, bump: u8) {
+        self.position_bundle_mint = position_bundle_mint;
+        self.position_bitmap = [0; POSITION_BITMAP_USIZE];
+        self.bump = [bump];
+    }
+
+    // A bundle can only be deleted once all of its positions are closed
+    pub fn is_deletable(&self) -> bool {
+        self.position_bitmap.iter().all(|bitmap| *bitmap == 0)
+    }
+
+    pub fn is_bundle_index_in_use(&self, bundle_index: u16) -> bool {
+        if !Self::is_valid_bundle_index(bundle_index) {
+            return false;
+        }
+
+        let (byte, mask) = Self::bitmap_position(bundle_index);
+        self.position_bitmap[byte] & mask != 0
+    }
+
+    pub fn open_bundled_position(&mut self, bundle_index: u16) -> Result<()> {
+        if !Self::is_valid_bundle_index(bundle_index) {
+            return Err(ErrorCode::InvalidBundleIndex.into());
+        }
+
+        if self.is_bundle_index_in_use(bundle_index) {
+            return Err(ErrorCode::BundledPositionAlreadyOpened.into());
+        }
+
+        let (byte, mask) = Self::bitmap_position(bundle_index);
+        self.position_bitmap[byte] |= mask;
+
+        Ok(())
+    }
+
+    pub fn close_bundled_position(&mut self, bundle_index: u16) -> Result<()> {
+        if !Self::is_valid_bundle_index(bundle_index) {
+            return Err(ErrorCode::InvalidBundleIndex.into());
+        }
+
+        if !self.is_bundle_index_in_use(bundle_index) {
+            return Err(ErrorCode::BundledPositionAlreadyClosed.into());
+        }
+
+        let (byte, mask) = Self::bitmap_position(bundle_index);
+        self.position_bitmap[byte] &= !mask;
+
+        Ok(())
+    }
+
+    fn is_valid_bundle_index(bundle_index: u16) -> bool {
+        bundle_index < POSITION_BUNDLE_SIZE
+    }
+
+    fn bitmap_position(bundle_index: u16) -> (usize, u8) {
+        ((bundle_index / 8) as usize, 1 << (bundle_index % 8))
+    }
+}
+
+#[cfg(test)]
+mod position_bundle_tests {
+    use super::*;
+
+    #[test]
+    fn open_and_close_all_positions() {
+        let mut position_bundle = PositionBundle::default();
+        assert!(position_bundle.is_deletable());
+
+        for bundle_index in 0..POSITION_BUNDLE_SIZE {
+            assert!(!position_bundle.is_bundle_index_in_use(bundle_index));
+            position_bundle.open_bundled_position(bundle_index).unwrap();
+            assert!(position_bundle.is_bundle_index_in_use(bundle_index));
+        }
+        assert_eq!(
+            position_bundle.position_bitmap,
+            [u8::MAX; POSITION_BITMAP_USIZE]
+        );
+        assert!(!position_bundle.is_deletable());
+
+        for bundle_index in 0..POSITION_BUNDLE_SIZE {
+            position_bundle
+                .close_bundled_position(bundle_index)
+                .unwrap();
+            assert!(!position_bundle.is_bundle_index_in_use(bundle_index));
+        }
+        assert!(position_bundle.is_deletable());
+    }
+
+    #[test]
+    fn bitmap_layout() {
+        let mut position_bundle = PositionBundle::default();
+        position_bundle.open_bundled_position(0).unwrap();
+        position_bundle.open_bundled_position(9).unwrap();
+        position_bundle.open_bundled_position(255).unwrap();
+
+        assert_eq!(position_bundle.position_bitmap[0], 0b0000_0001);
+        assert_eq!(position_bundle.position_bitmap[1], 0b0000_0010);
+        assert_eq!(position_bundle.position_bitmap[31], 0b1000_0000);
+    }
+
+    #[test]
+    #[should_panic(expected = "InvalidBundleIndex")]
+    fn open_out_of_range() {
+        PositionBundle::default()
+            .open_bundled_position(POSITION_BUNDLE_SIZE)
+            .unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "BundledPositionAlreadyOpened")]
+    fn open_twice() {
+        let mut position_bundle = PositionBundle::default();
+        position_bundle.open_bundled_position(7).unwrap();
+        position_bundle.open_bundled_position(7).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "BundledPositionAlreadyClosed")]
+    fn close_unopened() {
+        PositionBundle::default().close_bundled_position(7).unwrap();
+    }
+}
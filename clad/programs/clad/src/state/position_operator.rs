@@ -0,0 +1,101 @@
+use anchor_lang::prelude::*;
+
+// Instructions a PositionOperator can be permitted to call on behalf of the position owner
+pub const OPERATOR_COLLECT_FEES: u8 = 1 << 0;
+pub const OPERATOR_COMPOUND_FEES: u8 = 1 << 1;
+// 1 << 2 is unassigned: no instruction lets an operator rebalance a position
+pub const OPERATOR_REPAY_TRADE: u8 = 1 << 3;
+
+pub const OPERATOR_PERMISSIONS_MASK: u8 =
+    OPERATOR_COLLECT_FEES | OPERATOR_COMPOUND_FEES | OPERATOR_REPAY_TRADE;
+
+// Lets `operator` call the permitted instructions on a LiquidityPosition or TradePosition, without
+// the full authority of a delegate of the position token. Operators can't withdraw principal, and
+// tokens they collect only go to the owner's accounts.
+#[account]
+#[derive(Default)]
+pub struct PositionOperator {
+    pub position: Pubkey,
+    pub operator: Pubkey,
+    // Owner of the position token that approved the operator. The approval lapses when the
+    // position token changes hands.
+    pub authority: Pubkey,
+    pub permissions: u8,
+    pub bump: [u8; 1],
+}
+
+impl PositionOperator {
+    pub const LEN: usize = 8 + std::mem::size_of::<PositionOperator>();
+
+    pub fn update(
+        &mut self,
+        position: Pubkey,
+        operator: Pubkey,
+        authority: Pubkey,
+        permissions: u8,
+        bump: u8,
+    ) -> Result<()> {
+        if permissions & !OPERATOR_PERMISSIONS_MASK != 0 {
+            return Err(crate::errors::ErrorCode::InvalidPositionOperatorPermissions.into());
+        }
+
+        self.position = position;
+        self.operator = operator;
+        self.authority = authority;
+        self.permissions = permissions;
+        self.bump = [bump];
+
+        Ok(())
+    }
+
+    pub fn has_permission(&self, permission: u8) -> bool {
+        self.permissions & permission == permission
+    }
+}
+
+#[cfg(test)]
+mod position_operator_tests {
+    use super::*;
+
+    #[test]
+    fn permissions_checked_by_bit() {
+        let mut position_operator = PositionOperator::default();
+        position_operator
+            .update(
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                OPERATOR_COLLECT_FEES | OPERATOR_COMPOUND_FEES,
+                255,
+            )
+            .unwrap();
+
+        assert!(position_operator.has_permission(OPERATOR_COLLECT_FEES));
+        assert!(position_operator.has_permission(OPERATOR_COMPOUND_FEES));
+        assert!(!position_operator.has_permission(OPERATOR_REPAY_TRADE));
+    }
+
+    #[test]
+    fn unknown_permissions_rejected() {
+        let mut position_operator = PositionOperator::default();
+        let result = position_operator.update(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            1 << 4,
+            255,
+        );
+        assert!(result.is_err());
+        assert_eq!(position_operator.permissions, 0);
+
+        // The unassigned bit between OPERATOR_COMPOUND_FEES and OPERATOR_REPAY_TRADE
+        let result = position_operator.update(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            1 << 2,
+            255,
+        );
+        assert!(result.is_err());
+    }
+}
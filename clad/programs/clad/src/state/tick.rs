@@ -109,6 +109,26 @@ impl Tick {
     pub fn bound_tick_index(tick_index: i32) -> i32 {
         tick_index.max(MIN_TICK_INDEX).min(MAX_TICK_INDEX)
     }
+
+    /// Get the start-tick-index of the tick array holding the tick index
+    ///
+    /// # Parameters
+    /// - `tick_index` - A i32 integer representing the tick index
+    /// - `tick_spacing` - A u8 integer of the tick spacing for this globalpool
+    pub fn get_start_tick_index(tick_index: i32, tick_spacing: u16) -> i32 {
+        let ticks_in_array = TICK_ARRAY_SIZE * tick_spacing as i32;
+        tick_index.div_euclid(ticks_in_array) * ticks_in_array
+    }
+
+    /// Get the lowest & highest usable tick indexes for the tick spacing, which bound a full-range
+    /// position
+    ///
+    /// # Parameters
+    /// - `tick_spacing` - A u8 integer of the tick spacing for this globalpool
+    pub fn full_range_tick_indexes(tick_spacing: u16) -> (i32, i32) {
+        let max_usable_tick_index = MAX_TICK_INDEX / tick_spacing as i32 * tick_spacing as i32;
+        (-max_usable_tick_index, max_usable_tick_index)
+    }
 }
 
 #[derive(Default, Debug, PartialEq)]
@@ -230,6 +250,16 @@ impl TickArray {
         Ok(())
     }
 
+    /// Check that the TickArray can be closed: no tick has liquidity or borrowed liquidity.
+    /// Range orders are checked on the extension, see `TickArrayExtension::is_closable`.
+    pub fn is_closable(&self) -> bool {
+        self.ticks.iter().all(|tick| {
+            let (liquidity_gross, liquidity_borrowed) =
+                (tick.liquidity_gross, tick.liquidity_borrowed);
+            liquidity_gross == 0 && liquidity_borrowed == 0
+        })
+    }
+
     /// Get the Tick object at the given tick-index & tick-spacing
     ///
     /// # Parameters
@@ -583,6 +613,48 @@ mod check_is_out_of_bounds_tests {
     }
 }
 
+#[cfg(test)]
+mod start_tick_index_tests {
+    use super::*;
+    const TS_8: u16 = 8;
+    const TS_128: u16 = 128;
+
+    #[test]
+    fn test_start_tick_index() {
+        assert_eq!(Tick::get_start_tick_index(0, TS_8), 0);
+        assert_eq!(Tick::get_start_tick_index(703, TS_8), 0);
+        assert_eq!(Tick::get_start_tick_index(704, TS_8), 704);
+        assert_eq!(Tick::get_start_tick_index(-1, TS_8), -704);
+        assert_eq!(Tick::get_start_tick_index(-704, TS_8), -704);
+    }
+
+    #[test]
+    fn test_full_range_start_ticks_are_valid() {
+        for tick_spacing in [1, TS_8, 64, TS_128] {
+            let (tick_lower_index, tick_upper_index) = Tick::full_range_tick_indexes(tick_spacing);
+            assert!(Tick::check_is_usable_tick(tick_lower_index, tick_spacing));
+            assert!(Tick::check_is_usable_tick(tick_upper_index, tick_spacing));
+            assert!(!Tick::check_is_usable_tick(
+                tick_lower_index - tick_spacing as i32,
+                tick_spacing
+            ));
+            assert!(!Tick::check_is_usable_tick(
+                tick_upper_index + tick_spacing as i32,
+                tick_spacing
+            ));
+
+            assert!(Tick::check_is_valid_start_tick(
+                Tick::get_start_tick_index(tick_lower_index, tick_spacing),
+                tick_spacing
+            ));
+            assert!(Tick::check_is_valid_start_tick(
+                Tick::get_start_tick_index(tick_upper_index, tick_spacing),
+                tick_spacing
+            ));
+        }
+    }
+}
+
 #[cfg(test)]
 mod array_update_tests {
     use super::*;
@@ -629,3 +701,34 @@ mod array_update_tests {
         assert_eq!(*result, expected);
     }
 }
+
+#[cfg(test)]
+mod array_closable_tests {
+    use super::{tick_builder::TickBuilder, *};
+
+    #[test]
+    fn empty_array_closable() {
+        let mut array = TickArray::default();
+        assert!(array.is_closable());
+
+        // Ticks left with fee growth but without liquidity don't prevent closing
+        array.ticks[3] = TickBuilder::default().fee_growth_outside_a(100).build();
+        assert!(array.is_closable());
+    }
+
+    #[test]
+    fn array_with_liquidity_not_closable() {
+        for tick in [
+            TickBuilder::default()
+                .initialized(true)
+                .liquidity_gross(100)
+                .liquidity_net(100)
+                .build(),
+            TickBuilder::default().liquidity_borrowed(100).build(),
+        ] {
+            let mut array = TickArray::default();
+            array.ticks[TICK_ARRAY_SIZE_USIZE - 1] = tick;
+            assert!(!array.is_closable());
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use {
+    super::{Tick, MAX_TICK_INDEX, MIN_TICK_INDEX, TICK_ARRAY_SIZE},
+    crate::errors::ErrorCode,
+    anchor_lang::prelude::*,
+};
+
+// Enough bits for every tick array of a Globalpool with a tick spacing of 1
+pub const TICK_ARRAY_BITMAP_USIZE: usize = 1264;
+
+// Records which TickArray start indices of a Globalpool are initialized, so that routers can plan
+// the tick arrays of a swap and swaps can skip the uninitialized ones.
+#[account]
+pub struct TickArrayBitmap {
+    pub globalpool: Pubkey,
+    // Bit `i` is set if the i-th tick array from the left-edge tick array is initialized
+    pub tick_array_bitmap: [u8; TICK_ARRAY_BITMAP_USIZE],
+    // Set once every TickArray of the Globalpool is recorded. Swaps only skip arrays after that.
+    pub backfilled: bool,
+}
+
+impl Default for TickArrayBitmap {
+    fn default() -> Self {
+        TickArrayBitmap {
+            globalpool: Pubkey::default(),
+            tick_array_bitmap: [0; TICK_ARRAY_BITMAP_USIZE],
+            backfilled: false,
+        }
+    }
+}
+
+impl TickArrayBitmap {
+    pub const LEN: usize = 8 + std::mem::size_of::<TickArrayBitmap>();
+
+    pub fn initialize(&mut self, globalpool: Pubkey, backfilled: bool) {
+        self.globalpool = globalpool;
+        self.tick_array_bitmap = [0; TICK_ARRAY_BITMAP_USIZE];
+        self.backfilled = backfilled;
+    }
+
+    pub fn set_backfilled(&mut self) {
+        self.backfilled = true;
+    }
+
+    pub fn is_initialized(&self, start_tick_index: i32, tick_spacing: u16) -> bool {
+        match Self::bitmap_position(start_tick_index, tick_spacing) {
+            Some((byte, mask)) => self.tick_array_bitmap[byte] & mask != 0,
+            None => false,
+        }
+    }
+
+    pub fn set_initialized(
+        &mut self,
+        start_tick_index: i32,
+        tick_spacing: u16,
+        initialized: bool,
+    ) -> Result<()> {
+        let (byte, mask) = Self::bitmap_position(start_tick_index, tick_spacing)
+            .ok_or(ErrorCode::InvalidStartTick)?;
+
+        if initialized {
+            self.tick_array_bitmap[byte] |= mask;
+        } else {
+            self.tick_array_bitmap[byte] &= !mask;
+        }
+
+        Ok(())
+    }
+
+    // Checks that no tick array between the two start indices, inclusive, is initialized
+    pub fn is_range_uninitialized(
+        &self,
+        first_start_tick_index: i32,
+        last_start_tick_index: i32,
+        tick_spacing: u16,
+    ) -> bool {
+        let ticks_in_array = TICK_ARRAY_SIZE * tick_spacing as i32;
+        let mut start_tick_index = first_start_tick_index.min(last_start_tick_index);
+
+        while start_tick_index <= first_start_tick_index.max(last_start_tick_index) {
+            if self.is_initialized(start_tick_index, tick_spacing) {
+                return false;
+            }
+            start_tick_index += ticks_in_array;
+        }
+
+        true
+    }
+
+    // Start index of the next initialized tick array after `start_tick_index` in the swap direction
+    pub fn next_initialized_start_tick_index(
+        &self,
+        start_tick_index: i32,
+        tick_spacing: u16,
+        a_to_b: bool,
+    ) -> Option<i32> {
+        let ticks_in_array = TICK_ARRAY_SIZE * tick_spacing as i32;
+        let step = if a_to_b {
+            -ticks_in_array
+        } else {
+            ticks_in_array
+        };
+
+        let mut next_start_tick_index = start_tick_index + step;
+        while Self::bitmap_position(next_start_tick_index, tick_spacing).is_some() {
+            if self.is_initialized(next_start_tick_index, tick_spacing) {
+                return Some(next_start_tick_index);
+            }
+            next_start_tick_index += step;
+        }
+
+        None
+    }
+
+    fn bitmap_position(start_tick_index: i32, tick_spacing: u16) -> Option<(usize, u8)> {
+        if tick_spacing == 0 || !Tick::check_is_valid_start_tick(start_tick_index, tick_spacing) {
+            return None;
+        }
+
+        // The left-edge tick array starts at or below MIN_TICK_INDEX
+        let ticks_in_array = TICK_ARRAY_SIZE * tick_spacing as i32;
+        let min_array_index = MIN_TICK_INDEX.div_euclid(ticks_in_array) - 1;
+        let bit = (start_tick_index.div_euclid(ticks_in_array) - min_array_index) as usize;
+
+        if bit >= TICK_ARRAY_BITMAP_USIZE * 8 || start_tick_index > MAX_TICK_INDEX {
+            return None;
+        }
+
+        Some((bit / 8, 1 << (bit % 8)))
+    }
+}
+
+#[cfg(test)]
+mod tick_array_bitmap_tests {
+    use super::*;
+
+    const TS_1: u16 = 1;
+    const TS_64: u16 = 64;
+
+    #[test]
+    fn set_initialized() {
+        let mut bitmap = TickArrayBitmap::default();
+        let ticks_in_array = TICK_ARRAY_SIZE * TS_64 as i32;
+
+        assert!(!bitmap.is_initialized(0, TS_64));
+        bitmap.set_initialized(0, TS_64, true).unwrap();
+        bitmap
+            .set_initialized(-3 * ticks_in_array, TS_64, true)
+            .unwrap();
+        assert!(bitmap.is_initialized(0, TS_64));
+        assert!(bitmap.is_initialized(-3 * ticks_in_array, TS_64));
+        assert!(!bitmap.is_initialized(ticks_in_array, TS_64));
+
+        bitmap.set_initialized(0, TS_64, false).unwrap();
+        assert!(!bitmap.is_initialized(0, TS_64));
+        assert!(bitmap.is_initialized(-3 * ticks_in_array, TS_64));
+    }
+
+    #[test]
+    fn edge_tick_arrays_fit() {
+        let mut bitmap = TickArrayBitmap::default();
+        let ticks_in_array = TICK_ARRAY_SIZE * TS_1 as i32;
+
+        let min_start_tick_index = MIN_TICK_INDEX.div_euclid(ticks_in_array) * ticks_in_array;
+        let max_start_tick_index = MAX_TICK_INDEX.div_euclid(ticks_in_array) * ticks_in_array;
+
+        bitmap
+            .set_initialized(min_start_tick_index, TS_1, true)
+            .unwrap();
+        bitmap
+            .set_initialized(max_start_tick_index, TS_1, true)
+            .unwrap();
+        assert!(bitmap.is_initialized(min_start_tick_index, TS_1));
+        assert!(bitmap.is_initialized(max_start_tick_index, TS_1));
+    }
+
+    #[test]
+    #[should_panic(expected = "InvalidStartTick")]
+    fn invalid_start_tick() {
+        let mut bitmap = TickArrayBitmap::default();
+        bitmap.set_initialized(64, TS_64, true).unwrap();
+    }
+
+    #[test]
+    fn uninitialized_range_and_next_initialized() {
+        let mut bitmap = TickArrayBitmap::default();
+        let ticks_in_array = TICK_ARRAY_SIZE * TS_64 as i32;
+
+        bitmap.set_initialized(0, TS_64, true).unwrap();
+        bitmap
+            .set_initialized(4 * ticks_in_array, TS_64, true)
+            .unwrap();
+
+        assert!(bitmap.is_range_uninitialized(ticks_in_array, 3 * ticks_in_array, TS_64));
+        assert!(!bitmap.is_range_uninitialized(ticks_in_array, 4 * ticks_in_array, TS_64));
+        assert!(!bitmap.is_range_uninitialized(0, ticks_in_array, TS_64));
+
+        assert_eq!(
+            bitmap.next_initialized_start_tick_index(0, TS_64, false),
+            Some(4 * ticks_in_array)
+        );
+        assert_eq!(
+            bitmap.next_initialized_start_tick_index(4 * ticks_in_array, TS_64, true),
+            Some(0)
+        );
+        assert_eq!(
+            bitmap.next_initialized_start_tick_index(0, TS_64, true),
+            None
+        );
+    }
+}
@@ -0,0 +1,265 @@
+use crate::{errors::ErrorCode, state::*};
+use anchor_lang::prelude::*;
+use std::{cell::RefMut, ops::DerefMut};
+
+#[repr(packed)]
+#[zero_copy]
+#[derive(Default, Debug, PartialEq)]
+pub struct RangeOrderTick {
+    // Unfilled range order liquidity, removed when the tick is crossed in the order direction
+    pub liquidity: u128,
+    // Number of range order batches filled at the tick
+    pub fill_count: u64,
+    // Range orders registered at the tick convert token A to token B
+    pub a_to_b: bool,
+    // Number of range orders registered at the tick, filled or not, that are not claimed yet
+    pub unclaimed_count: u32,
+    // Unfilled range order liquidity bounded by the tick, added when it is crossed rightwards
+    // like `Tick::liquidity_net`. It tracks the active range order liquidity, which earns no fees.
+    pub liquidity_net: i128,
+} // 45
+
+// Range order state of the ticks of a TickArray, stored after it in the same account. Tick arrays
+// created before range orders don't have one until `migrate_tick_array` reallocates them.
+#[repr(packed)]
+#[zero_copy]
+pub struct TickArrayExtension {
+    pub range_orders: [RangeOrderTick; TICK_ARRAY_SIZE_USIZE],
+    // Receives the rent when the tick array is closed. Unset for migrated tick arrays, whose
+    // funder was not recorded.
+    pub funder: Pubkey,
+}
+
+impl Default for TickArrayExtension {
+    #[inline]
+    fn default() -> TickArrayExtension {
+        TickArrayExtension {
+            range_orders: [RangeOrderTick::default(); TICK_ARRAY_SIZE_USIZE],
+            funder: Pubkey::default(),
+        }
+    }
+}
+
+impl TickArrayExtension {
+    pub const LEN: usize = std::mem::size_of::<TickArrayExtension>();
+
+    // Account size of a TickArray followed by its extension
+    pub const TICK_ARRAY_LEN: usize = TickArray::LEN + TickArrayExtension::LEN;
+
+    /// Initialize the extension of a new TickArray
+    ///
+    /// # Parameters
+    /// - `funder` - the account paying the rent of the tick array
+    pub fn initialize(&mut self, funder: Pubkey) {
+        self.funder = funder;
+    }
+
+    /// Get the range orders of the tick at the given tick-index & tick-spacing
+    ///
+    /// # Parameters
+    /// - `tick_array` - the TickArray this extension belongs to
+    /// - `tick_index` - the tick index the range orders are registered at
+    /// - `tick_spacing` - A u8 integer of the tick spacing for this globalpool
+    ///
+    /// # Errors
+    /// - `TickNotFound`: - The provided tick-index is not an initializable tick index in this Globalpool w/ this tick-spacing.
+    pub fn get_range_order(
+        &self,
+        tick_array: &TickArray,
+        tick_index: i32,
+        tick_spacing: u16,
+    ) -> Result<&RangeOrderTick> {
+        let offset = range_order_offset(tick_array, tick_index, tick_spacing)?;
+        Ok(&self.range_orders[offset])
+    }
+
+    /// Updates the range orders of the tick at the given tick-index & tick-spacing
+    ///
+    /// # Parameters
+    /// - `tick_array` - the TickArray this extension belongs to
+    /// - `tick_index` - the tick index the range orders are registered at
+    /// - `tick_spacing` - A u8 integer of the tick spacing for this globalpool
+    /// - `update` - the new range order values of the tick
+    ///
+    /// # Errors
+    /// - `TickNotFound`: - The provided tick-index is not an initializable tick index in this Globalpool w/ this tick-spacing.
+    pub fn update_range_order(
+        &mut self,
+        tick_array: &TickArray,
+        tick_index: i32,
+        tick_spacing: u16,
+        update: &RangeOrderTick,
+    ) -> Result<()> {
+        let offset = range_order_offset(tick_array, tick_index, tick_spacing)?;
+        self.range_orders[offset] = *update;
+        Ok(())
+    }
+
+    /// Check that no range order is left to claim, as claims rely on the fill counts of their ticks
+    pub fn is_closable(&self) -> bool {
+        self.range_orders.iter().all(|range_order| {
+            let unclaimed_count = range_order.unclaimed_count;
+            unclaimed_count == 0
+        })
+    }
+}
+
+fn range_order_offset(tick_array: &TickArray, tick_index: i32, tick_spacing: u16) -> Result<usize> {
+    if !tick_array.check_in_array_bounds(tick_index, tick_spacing)
+        || !Tick::check_is_usable_tick(tick_index, tick_spacing)
+    {
+        return Err(ErrorCode::TickNotFound.into());
+    }
+    let offset = tick_array.tick_offset(tick_index, tick_spacing)?;
+    if offset < 0 {
+        return Err(ErrorCode::TickNotFound.into());
+    }
+    Ok(offset as usize)
+}
+
+/// Load a TickArray along with its extension, which is `None` until the account is migrated.
+/// Both share one borrow of the account data, like `AccountLoader::load_mut`.
+pub fn load_tick_array_mut<'a>(
+    tick_array: &'a AccountLoader<TickArray>,
+) -> Result<(
+    RefMut<'a, TickArray>,
+    Option<RefMut<'a, TickArrayExtension>>,
+)> {
+    // Checks the discriminator, that the account is writable and not borrowed yet
+    drop(tick_array.load_mut()?);
+
+    Ok(map_tick_array_data(
+        tick_array.as_ref().try_borrow_mut_data()?,
+    ))
+}
+
+/// Load a TickArray being initialized along with its extension, like `AccountLoader::load_init`
+///
+/// # Errors
+/// - `TickArrayExtensionMissing` - The account wasn't allocated with room for the extension
+pub fn load_tick_array_init<'a>(
+    tick_array: &'a AccountLoader<TickArray>,
+) -> Result<(RefMut<'a, TickArray>, RefMut<'a, TickArrayExtension>)> {
+    // Checks that the account is writable and not initialized yet
+    drop(tick_array.load_init()?);
+
+    match map_tick_array_data(tick_array.as_ref().try_borrow_mut_data()?) {
+        (tick_array, Some(extension)) => Ok((tick_array, extension)),
+        (_, None) => Err(ErrorCode::TickArrayExtensionMissing.into()),
+    }
+}
+
+/// Map the data of a TickArray account, discriminator included, to the TickArray and its
+/// extension, which is `None` until the account is migrated
+pub fn map_tick_array_data<'a, D: DerefMut<Target = [u8]>>(
+    data: RefMut<'a, D>,
+) -> (
+    RefMut<'a, TickArray>,
+    Option<RefMut<'a, TickArrayExtension>>,
+) {
+    if data.len() < TickArrayExtension::TICK_ARRAY_LEN {
+        let tick_array = RefMut::map(data, |data| {
+            let data: &mut [u8] = &mut **data;
+            bytemuck::from_bytes_mut(&mut data[8..TickArray::LEN])
+        });
+        return (tick_array, None);
+    }
+
+    let (tick_array, extension) = RefMut::map_split(data, |data| {
+        let data: &mut [u8] = &mut **data;
+        let (tick_array, extension) = data.split_at_mut(TickArray::LEN);
+        (
+            bytemuck::from_bytes_mut(&mut tick_array[8..]),
+            bytemuck::from_bytes_mut(&mut extension[..TickArrayExtension::LEN]),
+        )
+    });
+    (tick_array, Some(extension))
+}
+
+/// Get the range orders of a tick from the extension of its TickArray
+///
+/// # Errors
+/// - `TickArrayExtensionMissing` - The TickArray has no extension
+/// - `TickNotFound`: - The provided tick-index is not an initializable tick index in this TickArray.
+pub fn load_range_order(
+    tick_array: &AccountLoader<TickArray>,
+    tick_index: i32,
+    tick_spacing: u16,
+) -> Result<RangeOrderTick> {
+    let (tick_array, extension) = load_tick_array_mut(tick_array)?;
+    let extension = extension.ok_or(ErrorCode::TickArrayExtensionMissing)?;
+    Ok(*extension.get_range_order(&tick_array, tick_index, tick_spacing)?)
+}
+
+/// Updates the range orders of a tick in the extension of its TickArray
+///
+/// # Errors
+/// - `TickArrayExtensionMissing` - The TickArray has no extension
+/// - `TickNotFound`: - The provided tick-index is not an initializable tick index in this TickArray.
+pub fn store_range_order(
+    tick_array: &AccountLoader<TickArray>,
+    tick_index: i32,
+    tick_spacing: u16,
+    update: &RangeOrderTick,
+) -> Result<()> {
+    let (tick_array, extension) = load_tick_array_mut(tick_array)?;
+    let mut extension = extension.ok_or(ErrorCode::TickArrayExtensionMissing)?;
+    extension.update_range_order(&tick_array, tick_index, tick_spacing, update)
+}
+
+#[cfg(test)]
+mod tick_array_extension_tests {
+    use super::*;
+
+    const TS_8: u16 = 8;
+
+    #[test]
+    fn update_and_get_range_order() {
+        let mut tick_array = TickArray::default();
+        tick_array.start_tick_index = 704;
+        let mut extension = TickArrayExtension::default();
+
+        let update = RangeOrderTick {
+            liquidity: 1500,
+            fill_count: 2,
+            a_to_b: true,
+            unclaimed_count: 3,
+            liquidity_net: -1500,
+        };
+        extension
+            .update_range_order(&tick_array, 712, TS_8, &update)
+            .unwrap();
+
+        assert_eq!(
+            *extension.get_range_order(&tick_array, 712, TS_8).unwrap(),
+            update
+        );
+        assert_eq!(extension.range_orders[1], update);
+        assert!(!extension.is_closable());
+    }
+
+    #[test]
+    fn range_order_not_in_array() {
+        let mut tick_array = TickArray::default();
+        tick_array.start_tick_index = 704;
+        let mut extension = TickArrayExtension::default();
+
+        let update = RangeOrderTick::default();
+        for tick_index in [696, 1408, 713] {
+            assert_eq!(
+                extension
+                    .update_range_order(&tick_array, tick_index, TS_8, &update)
+                    .unwrap_err(),
+                ErrorCode::TickNotFound.into()
+            );
+        }
+    }
+
+    #[test]
+    fn claimed_range_orders_closable() {
+        // Fill counts are only read by unclaimed orders
+        let mut extension = TickArrayExtension::default();
+        extension.range_orders[0].fill_count = 3;
+        assert!(extension.is_closable());
+    }
+}
@@ -0,0 +1,21 @@
+use anchor_lang::prelude::*;
+
+// Pyth price feed of a token mint, registered by the protocol admin (the upgrade authority of the
+// program). Margin collateral & margin-linked positions are only valued with registered feeds.
+#[account]
+#[derive(Default)]
+pub struct TokenPriceFeed {
+    pub mint: Pubkey,
+    pub price_feed: Pubkey,
+    pub bump: [u8; 1],
+}
+
+impl TokenPriceFeed {
+    pub const LEN: usize = 8 + std::mem::size_of::<TokenPriceFeed>();
+
+    pub fn update(&mut self, mint: Pubkey, price_feed: Pubkey, bump: u8) {
+        self.mint = mint;
+        self.price_feed = price_feed;
+        self.bump = [bump];
+    }
+}
@@ -29,6 +29,24 @@ pub struct TradePosition {
     pub open_time: u64,     // UNIX time at which the loan was opened (in seconds)
     pub duration: u64,      // The duration of the loan, in seconds
     pub interest_rate: u32, // Interest rate paid upfront, for accounting purposes
+
+    // Perpetual positions have no maturity. Instead of upfront interest, they pay funding out of
+    // the collateral as the globalpool's funding index grows.
+    pub is_perpetual: bool,
+    pub funding_index_checkpoint: u128, // Globalpool funding index at the last funding payment
+
+    // LiquidityPosition locked as collateral, whose token is held by this account until the loan is
+    // repaid (default if none)
+    pub liquidity_position: Pubkey,
+    pub bump: [u8; 1],
+
+    // Liquidity of `liquidity_position` pledged in place of a collateral token transfer. Its token
+    // is held by the globalpool until the loan is repaid (0 for leveraged liquidity positions).
+    pub liquidity_collateral: u128,
+
+    // MarginAccount backing this position in place of its own collateral, drawn on to settle the
+    // loan when repaid (default if none)
+    pub margin_account: Pubkey,
 }
 
 #[derive(Default, Debug, PartialEq)]
@@ -42,12 +60,25 @@ pub struct TradePositionUpdate {
 impl TradePosition {
     pub const LEN: usize = 8 + std::mem::size_of::<TradePosition>();
 
+    // Positions liquidated on health (perpetual & leveraged liquidity positions) can be liquidated
+    // once their equity falls below 10% of their debt (stored as basis points)
+    pub const MAINTENANCE_MARGIN_BPS: u128 = 1_000;
+    pub const BPS_MUL_VALUE: u128 = 10_000;
+
+    pub fn seeds(&self) -> [&[u8]; 3] {
+        [
+            &b"trade_position"[..],
+            self.position_mint.as_ref(),
+            self.bump.as_ref(),
+        ]
+    }
+
     pub fn is_position_empty(position: &TradePosition) -> bool {
         position.loan_token_swapped == 0
     }
 
     /// Collateral in Token A implies loan in Token B, and vice versa.
-    pub fn is_borrow_a(&self, globalpool: &Account<Globalpool>) -> bool {
+    pub fn is_borrow_a(&self, globalpool: &Globalpool) -> bool {
         self.token_mint_collateral.eq(&globalpool.token_mint_b)
     }
 
@@ -55,6 +86,64 @@ impl TradePosition {
         Ok(Clock::get()?.unix_timestamp as u64 > self.open_time + self.duration)
     }
 
+    // Makes the loan repayable by liquidators from `timestamp` on (see `repay_trade_position`).
+    pub fn force_mature(&mut self, timestamp: u64) {
+        if timestamp < self.open_time + self.duration {
+            self.duration = timestamp.saturating_sub(self.open_time);
+        }
+    }
+
+    pub fn lock_liquidity_position(&mut self, liquidity_position: Pubkey) {
+        self.liquidity_position = liquidity_position;
+    }
+
+    // Leveraged liquidity positions lock the liquidity position funded by the loan itself
+    pub fn is_leveraged_liquidity_position(&self) -> bool {
+        self.liquidity_position != Pubkey::default() && self.liquidity_collateral == 0
+    }
+
+    pub fn lock_liquidity_collateral(
+        &mut self,
+        liquidity_position: Pubkey,
+        liquidity_collateral: u128,
+    ) {
+        self.liquidity_position = liquidity_position;
+        self.liquidity_collateral = liquidity_collateral;
+    }
+
+    pub fn has_liquidity_collateral(&self) -> bool {
+        self.liquidity_collateral > 0
+    }
+
+    pub fn release_liquidity_collateral(&mut self) {
+        self.liquidity_position = Pubkey::default();
+        self.liquidity_collateral = 0;
+    }
+
+    pub fn back_with_margin_account(&mut self, margin_account: Pubkey) {
+        self.margin_account = margin_account;
+    }
+
+    pub fn is_margin_backed(&self) -> bool {
+        self.margin_account != Pubkey::default()
+    }
+
+    pub fn init_funding(&mut self, funding_index: u128) {
+        self.is_perpetual = true;
+        self.funding_index_checkpoint = funding_index;
+    }
+
+    // Pays `funding_amount` out of the collateral, up to the funding index `funding_index`.
+    pub fn update_after_funding(&mut self, funding_index: u128, funding_amount: u64) -> Result<()> {
+        self.collateral_amount = self
+            .collateral_amount
+            .checked_sub(funding_amount)
+            .ok_or(ErrorCode::InsufficientCollateral)?;
+        self.funding_index_checkpoint = funding_index;
+
+        Ok(())
+    }
+
     // Long:  borrowing Token B (quote) & swapping to Token A (base)
     // Short: borrowing Token A (base)  & swapping to Token B (quote)
     // Long  => collateral: Token A
@@ -78,6 +167,7 @@ impl TradePosition {
         tick_upper_index: i32,
         loan_duration: u64,
         interest_rate: u32,
+        bump: u8,
     ) -> Result<()> {
         if !Tick::check_is_usable_tick(tick_lower_index, globalpool.tick_spacing)
             || !Tick::check_is_usable_tick(tick_upper_index, globalpool.tick_spacing)
@@ -98,6 +188,7 @@ impl TradePosition {
         self.interest_rate = interest_rate;
 
         self.liquidity_borrowed = liquidity_borrowed;
+        self.bump = [bump];
 
         Ok(())
     }
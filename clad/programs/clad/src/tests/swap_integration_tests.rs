@@ -233,6 +233,89 @@ fn run_swap_integration_tests() {
     assert_eq!(total_cases, pass_cases);
 }
 
+#[test]
+/// Run the same collection of tests through the off-chain quote, which must match the swap_manager
+fn run_swap_quote_integration_tests() {
+    use crate::quote::{quote_swap, SwapQuoteParams};
+
+    let contents =
+        fs::read_to_string("src/tests/swap_test_cases.json").expect("Failure to read the file.");
+    let json: Vec<TestCase> = serde_json::from_str(&contents).expect("JSON was not well-formatted");
+
+    let mut fail_cases: u16 = 0;
+
+    for test in json.iter() {
+        let derived_start_tick = derive_start_tick(test.curr_tick_index, test.tick_spacing);
+        let last_tick_in_seq =
+            derive_last_tick_in_seq(derived_start_tick, test.tick_spacing, test.a_to_b);
+
+        let swap_test_info = SwapTestFixture::new(SwapTestFixtureInfo {
+            tick_spacing: test.tick_spacing,
+            liquidity: test.liquidity,
+            curr_tick_index: test.curr_tick_index,
+            start_tick_index: derived_start_tick,
+            trade_amount: test.trade_amount,
+            sqrt_price_limit: sqrt_price_from_tick_index(last_tick_in_seq),
+            amount_specified_is_input: test.amount_is_input,
+            a_to_b: test.a_to_b,
+            array_1_ticks: &vec![],
+            array_2_ticks: Some(&vec![]),
+            array_3_ticks: Some(&vec![]),
+            fee_rate: test.fee_rate,
+            protocol_fee_rate: test.protocol_fee_rate,
+            ..Default::default()
+        });
+        let tick_arrays: Vec<_> = swap_test_info
+            .tick_arrays
+            .iter()
+            .map(|tick_array| *tick_array.borrow())
+            .collect();
+
+        let quote = quote_swap(&SwapQuoteParams {
+            globalpool: &swap_test_info.globalpool,
+            tick_arrays: &tick_arrays,
+            tick_array_extensions: &[],
+            tick_array_bitmap: None,
+            amount: test.trade_amount,
+            sqrt_price_limit: swap_test_info.sqrt_price_limit,
+            amount_specified_is_input: test.amount_is_input,
+            a_to_b: test.a_to_b,
+            timestamp: 1643027024,
+            transfer_fee_input: None,
+            transfer_fee_output: None,
+        });
+
+        let expectation = &test.expectation;
+        let equal = match quote {
+            Err(e) => match derive_error(&expectation.exception) {
+                Some(expected_error) => anchor_lang::error!(expected_error).eq(&e),
+                None => false,
+            },
+            Ok(quote) => {
+                let (amount_a, amount_b) = if test.a_to_b {
+                    (quote.amount_in, quote.amount_out)
+                } else {
+                    (quote.amount_out, quote.amount_in)
+                };
+                expectation.exception.is_empty()
+                    && amount_a == expectation.amount_a
+                    && amount_b == expectation.amount_b
+                    && quote.end_tick_index == expectation.next_tick_index
+                    && quote.end_sqrt_price == expectation.next_sqrt_price
+            }
+        };
+
+        if !equal {
+            msg!("Test case {} - {}", test.test_id, test.description);
+            msg!("Fail - quote does not match the expectation.");
+            msg!("");
+
+            fail_cases += 1;
+        }
+    }
+    assert_eq!(fail_cases, 0);
+}
+
 fn assert_expectation(post_swap: &PostSwapUpdate, expectation: &Expectation) -> bool {
     let amount_a_equal = post_swap.amount_a.eq(&expectation.amount_a);
     let amount_b_equal = post_swap.amount_b.eq(&expectation.amount_b);
@@ -0,0 +1,95 @@
+use crate::state::Globalpool;
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+use mpl_token_metadata::instruction::create_metadata_accounts_v3;
+use solana_program::program::invoke_signed;
+
+pub const LIQUIDITY_POSITION_METADATA_NAME: &str = "Clad Liquidity Position";
+pub const LIQUIDITY_POSITION_METADATA_SYMBOL: &str = "CLAD-LP";
+pub const TRADE_POSITION_METADATA_NAME: &str = "Clad Trade Position";
+pub const TRADE_POSITION_METADATA_SYMBOL: &str = "CLAD-TP";
+
+// Off-chain metadata (JSON) of a position is served per Globalpool & tick range
+pub const POSITION_METADATA_URI_BASE: &str = "https://clad.finance/metadata/position";
+
+pub fn position_metadata_uri(
+    globalpool: &Pubkey,
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+) -> String {
+    format!(
+        "{}/{}?tick_lower={}&tick_upper={}",
+        POSITION_METADATA_URI_BASE, globalpool, tick_lower_index, tick_upper_index
+    )
+}
+
+// Creates the Metaplex metadata of a position token. Must be called before the globalpool (the
+// update authority) gives up the mint authority of the position mint.
+pub fn create_position_metadata<'info>(
+    globalpool: &Account<'info, Globalpool>,
+    position_mint: &Account<'info, Mint>,
+    position_metadata_account: &UncheckedAccount<'info>,
+    payer: &Signer<'info>,
+    metadata_program: &UncheckedAccount<'info>,
+    system_program: &Program<'info, System>,
+    rent: &Sysvar<'info, Rent>,
+    name: &str,
+    symbol: &str,
+    uri: String,
+) -> Result<()> {
+    invoke_signed(
+        &create_metadata_accounts_v3(
+            metadata_program.key(),
+            position_metadata_account.key(),
+            position_mint.key(),
+            globalpool.key(),
+            payer.key(),
+            globalpool.key(),
+            name.to_string(),
+            symbol.to_string(),
+            uri,
+            None,
+            0,
+            true,
+            true,
+            None,
+            None,
+            None,
+        ),
+        &[
+            position_metadata_account.to_account_info(),
+            position_mint.to_account_info(),
+            globalpool.to_account_info(),
+            payer.to_account_info(),
+            metadata_program.to_account_info(),
+            system_program.to_account_info(),
+            rent.to_account_info(),
+        ],
+        &[&globalpool.seeds()],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod position_metadata_uri_tests {
+    use super::*;
+    use mpl_token_metadata::state::MAX_URI_LENGTH;
+
+    #[test]
+    fn uri_from_globalpool_and_tick_range() {
+        let globalpool = Pubkey::new_unique();
+        assert_eq!(
+            position_metadata_uri(&globalpool, -443584, 443584),
+            format!(
+                "https://clad.finance/metadata/position/{}?tick_lower=-443584&tick_upper=443584",
+                globalpool
+            )
+        );
+    }
+
+    #[test]
+    fn uri_within_metaplex_limit() {
+        let uri = position_metadata_uri(&Pubkey::new_unique(), -443584, -443584);
+        assert!(uri.len() <= MAX_URI_LENGTH);
+    }
+}
@@ -1,11 +1,17 @@
+pub mod metadata;
+pub mod native_sol;
 pub mod swap_utils;
 pub mod tick_sequence;
 pub mod token;
+pub mod token_extensions;
 pub mod util;
 
+pub use metadata::*;
+pub use native_sol::*;
 pub use swap_utils::*;
 pub use tick_sequence::*;
 pub use token::*;
+pub use token_extensions::*;
 pub use util::*;
 
 #[cfg(test)]
@@ -0,0 +1,202 @@
+use {
+    crate::errors::ErrorCode,
+    anchor_lang::{
+        prelude::*,
+        solana_program::program_pack::Pack,
+        system_program::{self, Allocate, Assign, CreateAccount, Transfer},
+    },
+    anchor_spl::token::{self, CloseAccount, InitializeAccount3, SyncNative, TokenAccount},
+    spl_token::native_mint,
+};
+
+pub const NATIVE_SOL_ACCOUNT_SEED: &[u8] = b"native_sol";
+
+// Native SOL mode lets an authority pay and receive SOL instead of wSOL. It is enabled by passing
+// the System Program as the first of the remaining accounts, and the temporary wSOL account of the
+// authority (see `get_native_sol_account_address`) as its token owner account of the native mint.
+//
+// The temporary account is created and funded with the authority's lamports within the
+// instruction, then closed at its end, returning the rent and any wSOL left to the authority.
+pub struct NativeSolAccount<'info> {
+    // The temporary account holds the native mint of token A, otherwise of token B
+    pub is_token_a: bool,
+    account: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    native_mint: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+}
+
+pub fn get_native_sol_account_address(authority: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[NATIVE_SOL_ACCOUNT_SEED, authority.as_ref()], &crate::ID)
+}
+
+// Loads native SOL mode if the System Program is the first of the remaining accounts, returning
+// the accounts that follow it. The token owner account of the native mint, among the pool tokens,
+// must then be the temporary wSOL account of `authority`.
+pub fn load_native_sol_account<'a, 'info>(
+    accounts: &'a [AccountInfo<'info>],
+    authority: &AccountInfo<'info>,
+    token_mints: [&AccountInfo<'info>; 2],
+    token_owner_accounts: [&AccountInfo<'info>; 2],
+    token_programs: [&AccountInfo<'info>; 2],
+) -> Result<(Option<NativeSolAccount<'info>>, &'a [AccountInfo<'info>])> {
+    let system_program = match accounts.first() {
+        Some(account) if account.key() == system_program::ID => account,
+        _ => return Ok((None, accounts)),
+    };
+
+    let index = token_mints
+        .iter()
+        .position(|token_mint| token_mint.key() == native_mint::ID)
+        .ok_or(ErrorCode::NativeSolMintNotFound)?;
+
+    if token_owner_accounts[index].key() != get_native_sol_account_address(authority.key).0 {
+        return Err(ErrorCode::InvalidNativeSolAccount.into());
+    }
+    if token_programs[index].key() != token::ID {
+        return Err(ErrorCode::InvalidTokenProgram.into());
+    }
+    // The authority funds the temporary account with lamports
+    if !authority.is_signer || !authority.is_writable {
+        return Err(ErrorCode::InvalidNativeSolAuthority.into());
+    }
+
+    Ok((
+        Some(NativeSolAccount {
+            is_token_a: index == 0,
+            account: token_owner_accounts[index].clone(),
+            authority: authority.clone(),
+            native_mint: token_mints[index].clone(),
+            token_program: token_programs[index].clone(),
+            system_program: system_program.clone(),
+        }),
+        &accounts[1..],
+    ))
+}
+
+impl<'info> NativeSolAccount<'info> {
+    // Creates the temporary wSOL account, owned by the authority, and wraps `amount` lamports
+    pub fn open(&self, amount: u64) -> Result<()> {
+        let (_, bump) = get_native_sol_account_address(self.authority.key);
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            NATIVE_SOL_ACCOUNT_SEED,
+            self.authority.key.as_ref(),
+            &[bump],
+        ]];
+
+        let space = spl_token::state::Account::LEN;
+        let rent = Rent::get()?.minimum_balance(space);
+        let lamports = self.account.lamports();
+
+        if lamports == 0 {
+            system_program::create_account(
+                CpiContext::new_with_signer(
+                    self.system_program.clone(),
+                    CreateAccount {
+                        from: self.authority.clone(),
+                        to: self.account.clone(),
+                    },
+                    signer_seeds,
+                ),
+                rent,
+                space as u64,
+                &token::ID,
+            )?;
+        } else {
+            // Lamports sent to the address beforehand make `create_account` fail. They are kept
+            // as wSOL, and returned to the authority on close.
+            if lamports < rent {
+                self.transfer_lamports(rent - lamports)?;
+            }
+            system_program::allocate(
+                CpiContext::new_with_signer(
+                    self.system_program.clone(),
+                    Allocate {
+                        account_to_allocate: self.account.clone(),
+                    },
+                    signer_seeds,
+                ),
+                space as u64,
+            )?;
+            system_program::assign(
+                CpiContext::new_with_signer(
+                    self.system_program.clone(),
+                    Assign {
+                        account_to_assign: self.account.clone(),
+                    },
+                    signer_seeds,
+                ),
+                &token::ID,
+            )?;
+        }
+
+        token::initialize_account3(CpiContext::new(
+            self.token_program.clone(),
+            InitializeAccount3 {
+                account: self.account.clone(),
+                mint: self.native_mint.clone(),
+                authority: self.authority.clone(),
+            },
+        ))?;
+
+        self.wrap(amount)
+    }
+
+    // Wraps `amount` more lamports of the authority into the temporary wSOL account
+    pub fn wrap(&self, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+        self.transfer_lamports(amount)?;
+
+        token::sync_native(CpiContext::new(
+            self.token_program.clone(),
+            SyncNative {
+                account: self.account.clone(),
+            },
+        ))
+    }
+
+    // Closes the temporary wSOL account, unwrapping its wSOL and rent back to the authority
+    pub fn close(&self) -> Result<()> {
+        token::close_account(CpiContext::new(
+            self.token_program.clone(),
+            CloseAccount {
+                account: self.account.clone(),
+                destination: self.authority.clone(),
+                authority: self.authority.clone(),
+            },
+        ))
+    }
+
+    fn transfer_lamports(&self, amount: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                self.system_program.clone(),
+                Transfer {
+                    from: self.authority.clone(),
+                    to: self.account.clone(),
+                },
+            ),
+            amount,
+        )
+    }
+}
+
+// Loads a token owner account checked in the handler instead of by account constraints, as in
+// native SOL mode it is the temporary wSOL account, only created within the instruction
+pub fn load_token_owner_account<'info>(
+    token_owner_account: &AccountInfo<'info>,
+    token_mint: &Pubkey,
+    owner: Option<&Pubkey>,
+) -> Result<Account<'info, TokenAccount>> {
+    let token_owner_account = Account::<TokenAccount>::try_from(token_owner_account)?;
+    if token_owner_account.mint != *token_mint {
+        return Err(anchor_lang::error::ErrorCode::ConstraintTokenMint.into());
+    }
+    if owner.map_or(false, |owner| token_owner_account.owner != *owner) {
+        return Err(anchor_lang::error::ErrorCode::ConstraintTokenOwner.into());
+    }
+    Ok(token_owner_account)
+}
@@ -1,10 +1,12 @@
 use crate::errors::ErrorCode;
 use crate::state::*;
-use anchor_lang::prelude::*;
+use anchor_lang::{prelude::*, Discriminator};
 use std::cell::RefMut;
 
 pub struct TickSequence<'info> {
     arrays: Vec<RefMut<'info, TickArray>>,
+    extensions: Vec<Option<RefMut<'info, TickArrayExtension>>>,
+    tick_array_bitmap: Option<&'info TickArrayBitmap>,
 }
 
 impl<'info> TickSequence<'info> {
@@ -21,7 +23,53 @@ impl<'info> TickSequence<'info> {
         if ta2.is_some() {
             vec.push(ta2.unwrap());
         }
-        Self { arrays: vec }
+        let extensions = vec.iter().map(|_| None).collect();
+        Self {
+            arrays: vec,
+            extensions,
+            tick_array_bitmap: None,
+        }
+    }
+
+    /// Create a sequence of any number of tick arrays
+    ///
+    /// # Parameters
+    /// - `arrays` - the tick arrays, ordered in the search direction. The first holds the current tick index.
+    ///
+    /// # Errors
+    /// - `InvalidTickArraySequence` - No tick array was provided
+    pub fn from_arrays(arrays: Vec<RefMut<'info, TickArray>>) -> Result<Self> {
+        Self::from_arrays_with_extensions(arrays.into_iter().map(|array| (array, None)).collect())
+    }
+
+    /// Create a sequence of any number of tick arrays along with their extensions, which hold
+    /// the range orders filled by the swap
+    ///
+    /// # Parameters
+    /// - `arrays` - the tick arrays and their extensions, see `load_tick_array_mut`. Ordered in the search direction.
+    ///
+    /// # Errors
+    /// - `InvalidTickArraySequence` - No tick array was provided
+    pub fn from_arrays_with_extensions(
+        arrays: Vec<(
+            RefMut<'info, TickArray>,
+            Option<RefMut<'info, TickArrayExtension>>,
+        )>,
+    ) -> Result<Self> {
+        if arrays.is_empty() {
+            return Err(ErrorCode::InvalidTickArraySequence.into());
+        }
+        let (arrays, extensions) = arrays.into_iter().unzip();
+        Ok(Self {
+            arrays,
+            extensions,
+            tick_array_bitmap: None,
+        })
+    }
+
+    /// Lets the sequence skip over tick arrays which the bitmap records as uninitialized
+    pub fn set_tick_array_bitmap(&mut self, tick_array_bitmap: &'info TickArrayBitmap) {
+        self.tick_array_bitmap = Some(tick_array_bitmap);
     }
 
     /// Get the Tick object at the given tick-index & tick-spacing
@@ -76,6 +124,68 @@ impl<'info> TickSequence<'info> {
         }
     }
 
+    /// Get the range orders of the tick at the given tick-index & tick-spacing
+    ///
+    /// # Parameters
+    /// - `array_index` - the array index that the tick of this given tick-index would be stored in
+    /// - `tick_index` - the tick index the range orders are registered at
+    /// - `tick_spacing` - A u8 integer of the tick spacing for this globalpool
+    ///
+    /// # Returns
+    /// - `Option<&RangeOrderTick>`: The range orders of the tick, none if its array has no extension
+    /// - `TickArrayIndexOutofBounds` - The provided array-index is out of bounds
+    /// - `TickNotFound`: - The provided tick-index is not an initializable tick index in this Globalpool w/ this tick-spacing.
+    pub fn get_range_order(
+        &self,
+        array_index: usize,
+        tick_index: i32,
+        tick_spacing: u16,
+    ) -> Result<Option<&RangeOrderTick>> {
+        match (
+            self.arrays.get(array_index),
+            self.extensions.get(array_index),
+        ) {
+            (Some(array), Some(Some(extension))) => Ok(Some(extension.get_range_order(
+                array,
+                tick_index,
+                tick_spacing,
+            )?)),
+            (Some(_), Some(None)) => Ok(None),
+            _ => Err(ErrorCode::TickArrayIndexOutofBounds.into()),
+        }
+    }
+
+    /// Updates the range orders of the tick at the given tick-index & tick-spacing
+    ///
+    /// # Parameters
+    /// - `array_index` - the array index that the tick of this given tick-index would be stored in
+    /// - `tick_index` - the tick index the range orders are registered at
+    /// - `tick_spacing` - A u8 integer of the tick spacing for this globalpool
+    /// - `update` - the new range order values of the tick
+    ///
+    /// # Errors
+    /// - `TickArrayIndexOutofBounds` - The provided array-index is out of bounds
+    /// - `TickArrayExtensionMissing` - The tick array at the array-index has no extension
+    /// - `TickNotFound`: - The provided tick-index is not an initializable tick index in this Globalpool w/ this tick-spacing.
+    pub fn update_range_order(
+        &mut self,
+        array_index: usize,
+        tick_index: i32,
+        tick_spacing: u16,
+        update: &RangeOrderTick,
+    ) -> Result<()> {
+        match (
+            self.arrays.get(array_index),
+            self.extensions.get_mut(array_index),
+        ) {
+            (Some(array), Some(Some(extension))) => {
+                extension.update_range_order(array, tick_index, tick_spacing, update)
+            }
+            (Some(_), Some(None)) => Err(ErrorCode::TickArrayExtensionMissing.into()),
+            _ => Err(ErrorCode::TickArrayIndexOutofBounds.into()),
+        }
+    }
+
     pub fn get_tick_offset(
         &self,
         array_index: usize,
@@ -147,12 +257,32 @@ impl<'info> TickSequence<'info> {
                         }
                     }
 
+                    // Tick arrays missing between this array and the following one must be
+                    // recorded as uninitialized in the bitmap.
+                    let following_start_tick_index = self.arrays[array_index + 1].start_tick_index;
+                    let adjacent_start_tick_index = if a_to_b {
+                        next_array.start_tick_index - ticks_in_array
+                    } else {
+                        next_array.start_tick_index + ticks_in_array
+                    };
+
+                    if following_start_tick_index != adjacent_start_tick_index
+                        && !self.is_skippable(
+                            adjacent_start_tick_index,
+                            following_start_tick_index,
+                            tick_spacing,
+                            a_to_b,
+                        )
+                    {
+                        return Err(ErrorCode::InvalidTickArraySequence.into());
+                    }
+
                     // No initialized index found. Move the search-index to the 1st search position
                     // of the next array in sequence.
                     search_index = if a_to_b {
-                        next_array.start_tick_index - 1
+                        following_start_tick_index + ticks_in_array - 1
                     } else {
-                        next_array.start_tick_index + ticks_in_array - 1
+                        following_start_tick_index - 1
                     };
 
                     array_index += 1;
@@ -160,6 +290,87 @@ impl<'info> TickSequence<'info> {
             }
         }
     }
+
+    // Checks that the tick arrays from `adjacent_start_tick_index` up to, but excluding,
+    // `following_start_tick_index` are uninitialized
+    fn is_skippable(
+        &self,
+        adjacent_start_tick_index: i32,
+        following_start_tick_index: i32,
+        tick_spacing: u16,
+        a_to_b: bool,
+    ) -> bool {
+        // A bitmap that is not backfilled may miss tick arrays that predate it
+        let tick_array_bitmap = match self.tick_array_bitmap {
+            Some(tick_array_bitmap) if tick_array_bitmap.backfilled => tick_array_bitmap,
+            _ => return false,
+        };
+
+        let ticks_in_array = TICK_ARRAY_SIZE * tick_spacing as i32;
+        if a_to_b && following_start_tick_index < adjacent_start_tick_index {
+            tick_array_bitmap.is_range_uninitialized(
+                following_start_tick_index + ticks_in_array,
+                adjacent_start_tick_index,
+                tick_spacing,
+            )
+        } else if !a_to_b && following_start_tick_index > adjacent_start_tick_index {
+            tick_array_bitmap.is_range_uninitialized(
+                adjacent_start_tick_index,
+                following_start_tick_index - ticks_in_array,
+                tick_spacing,
+            )
+        } else {
+            false
+        }
+    }
+}
+
+// Loads the TickArrayBitmap of the globalpool if it is the first of the remaining accounts,
+// returning the accounts that follow it
+pub fn load_tick_array_bitmap<'a, 'info>(
+    accounts: &'a [AccountInfo<'info>],
+    globalpool: &Pubkey,
+) -> Result<(
+    Option<Account<'info, TickArrayBitmap>>,
+    &'a [AccountInfo<'info>],
+)> {
+    let is_tick_array_bitmap = match accounts.first() {
+        Some(account) => {
+            *account.owner == crate::ID
+                && account
+                    .try_borrow_data()?
+                    .starts_with(&TickArrayBitmap::discriminator())
+        }
+        None => false,
+    };
+
+    if !is_tick_array_bitmap {
+        return Ok((None, accounts));
+    }
+
+    let tick_array_bitmap = Account::<TickArrayBitmap>::try_from(&accounts[0])?;
+    if tick_array_bitmap.globalpool != *globalpool {
+        return Err(anchor_lang::error::ErrorCode::ConstraintHasOne.into());
+    }
+
+    Ok((Some(tick_array_bitmap), &accounts[1..]))
+}
+
+// Loads tick arrays of the globalpool passed as remaining accounts, keeping their order
+pub fn load_tick_arrays<'info>(
+    accounts: &[AccountInfo<'info>],
+    globalpool: &Pubkey,
+) -> Result<Vec<AccountLoader<'info, TickArray>>> {
+    accounts
+        .iter()
+        .map(|account| {
+            let tick_array = AccountLoader::<TickArray>::try_from(account)?;
+            if tick_array.load()?.globalpool != *globalpool {
+                return Err(anchor_lang::error::ErrorCode::ConstraintHasOne.into());
+            }
+            Ok(tick_array)
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -344,6 +555,68 @@ mod swap_tick_sequence_tests {
                 ErrorCode::TickArrayIndexOutofBounds.into()
             );
         }
+
+        #[test]
+        fn modify_tick_range_order() {
+            let ta0 = build_tick_array(11264, vec![50]);
+            let ta1 = build_tick_array(0, vec![25, 71]);
+            let ext1 = RefCell::new(TickArrayExtension::default());
+            let mut swap_tick_sequence = TickSequence::from_arrays_with_extensions(vec![
+                (ta0.borrow_mut(), None),
+                (ta1.borrow_mut(), Some(ext1.borrow_mut())),
+            ])
+            .unwrap();
+
+            let tick_index = 71 * TS_128 as i32;
+            swap_tick_sequence
+                .update_range_order(
+                    1,
+                    tick_index,
+                    TS_128,
+                    &RangeOrderTick {
+                        liquidity: 1500,
+                        fill_count: 2,
+                        a_to_b: true,
+                        unclaimed_count: 3,
+                        liquidity_net: 0,
+                    },
+                )
+                .unwrap();
+
+            assert_eq!(
+                swap_tick_sequence
+                    .get_range_order(1, tick_index, TS_128)
+                    .unwrap(),
+                Some(&RangeOrderTick {
+                    liquidity: 1500,
+                    fill_count: 2,
+                    a_to_b: true,
+                    unclaimed_count: 3,
+                    liquidity_net: 0,
+                })
+            );
+            let tick = swap_tick_sequence.get_tick(1, tick_index, TS_128).unwrap();
+            assert_eq!(tick.initialized, true);
+
+            // Arrays without an extension hold no range orders
+            assert_eq!(
+                swap_tick_sequence
+                    .get_range_order(0, 50 * TS_128 as i32 + 11264, TS_128)
+                    .unwrap(),
+                None
+            );
+            assert_eq!(
+                swap_tick_sequence
+                    .update_range_order(
+                        0,
+                        50 * TS_128 as i32 + 11264,
+                        TS_128,
+                        &RangeOrderTick::default()
+                    )
+                    .unwrap_err(),
+                ErrorCode::TickArrayExtensionMissing.into()
+            );
+        }
     }
 
     mod a_to_b {
@@ -438,6 +711,35 @@ mod swap_tick_sequence_tests {
                 .unwrap();
         }
     }
+    mod a_to_b_skip {
+        use super::*;
+
+        #[test]
+        /// In an a_to_b search, uninitialized tick arrays recorded in the bitmap can be skipped
+        fn skip_uninitialized_arrays_with_bitmap() {
+            let ticks_in_array = TICK_ARRAY_SIZE * TS_128 as i32;
+            let ta0 = build_tick_array(0, vec![]);
+            let ta1 = build_tick_array(-4 * ticks_in_array, vec![LAST_TICK_OFFSET]);
+
+            let mut tick_array_bitmap = TickArrayBitmap::default();
+            tick_array_bitmap.set_backfilled();
+            tick_array_bitmap.set_initialized(0, TS_128, true).unwrap();
+            tick_array_bitmap
+                .set_initialized(-4 * ticks_in_array, TS_128, true)
+                .unwrap();
+
+            let mut swap_tick_sequence =
+                TickSequence::new(ta0.borrow_mut(), Some(ta1.borrow_mut()), None);
+            swap_tick_sequence.set_tick_array_bitmap(&tick_array_bitmap);
+
+            let (array_index, tick_index) = swap_tick_sequence
+                .get_next_initialized_tick_index(100, TS_128, true, 0)
+                .unwrap();
+            assert_eq!(array_index, 1);
+            assert_eq!(tick_index, -3 * ticks_in_array - TS_128 as i32);
+        }
+    }
+
     mod b_to_a {
         use super::*;
 
@@ -494,6 +796,120 @@ mod swap_tick_sequence_tests {
                 .get_next_initialized_tick_index(TS_8 as i32 * -1 - 1, TS_8, false, 0)
                 .unwrap();
         }
+
+        #[test]
+        /// In an b_to_a search, search continues through any number of uninitialized tick-arrays
+        fn search_across_many_arrays() {
+            let ticks_in_array = TICK_ARRAY_SIZE * TS_8 as i32;
+            let tick_arrays: Vec<RefCell<TickArray>> = (0..5)
+                .map(|i| {
+                    let initialized_offsets = if i == 4 { vec![10] } else { vec![] };
+                    build_tick_array(i * ticks_in_array, initialized_offsets)
+                })
+                .collect();
+            let swap_tick_sequence = TickSequence::from_arrays(
+                tick_arrays.iter().map(|array| array.borrow_mut()).collect(),
+            )
+            .unwrap();
+
+            let (array_index, tick_index) = swap_tick_sequence
+                .get_next_initialized_tick_index(0, TS_8, false, 0)
+                .unwrap();
+            assert_eq!(array_index, 4);
+            assert_eq!(tick_index, 4 * ticks_in_array + 10 * TS_8 as i32);
+        }
+
+        #[test]
+        /// Tick arrays recorded as uninitialized in the bitmap can be left out of the sequence
+        fn skip_uninitialized_arrays_with_bitmap() {
+            let ticks_in_array = TICK_ARRAY_SIZE * TS_8 as i32;
+            let ta0 = build_tick_array(0, vec![]);
+            let ta1 = build_tick_array(3 * ticks_in_array, vec![10]);
+
+            let mut tick_array_bitmap = TickArrayBitmap::default();
+            tick_array_bitmap.set_backfilled();
+            tick_array_bitmap.set_initialized(0, TS_8, true).unwrap();
+            tick_array_bitmap
+                .set_initialized(3 * ticks_in_array, TS_8, true)
+                .unwrap();
+
+            let mut swap_tick_sequence =
+                TickSequence::new(ta0.borrow_mut(), Some(ta1.borrow_mut()), None);
+            swap_tick_sequence.set_tick_array_bitmap(&tick_array_bitmap);
+
+            let (array_index, tick_index) = swap_tick_sequence
+                .get_next_initialized_tick_index(0, TS_8, false, 0)
+                .unwrap();
+            assert_eq!(array_index, 1);
+            assert_eq!(tick_index, 3 * ticks_in_array + 10 * TS_8 as i32);
+        }
+
+        #[test]
+        #[should_panic(expected = "InvalidTickArraySequence")]
+        /// A skipped tick array that is initialized cannot be left out of the sequence
+        fn skip_initialized_array_with_bitmap() {
+            let ticks_in_array = TICK_ARRAY_SIZE * TS_8 as i32;
+            let ta0 = build_tick_array(0, vec![]);
+            let ta1 = build_tick_array(3 * ticks_in_array, vec![10]);
+
+            let mut tick_array_bitmap = TickArrayBitmap::default();
+            tick_array_bitmap.set_backfilled();
+            tick_array_bitmap
+                .set_initialized(2 * ticks_in_array, TS_8, true)
+                .unwrap();
+
+            let mut swap_tick_sequence =
+                TickSequence::new(ta0.borrow_mut(), Some(ta1.borrow_mut()), None);
+            swap_tick_sequence.set_tick_array_bitmap(&tick_array_bitmap);
+
+            swap_tick_sequence
+                .get_next_initialized_tick_index(0, TS_8, false, 0)
+                .unwrap();
+        }
+
+        #[test]
+        #[should_panic(expected = "InvalidTickArraySequence")]
+        /// A bitmap that is not backfilled may miss tick arrays, so it cannot be used to skip
+        fn skip_arrays_with_bitmap_not_backfilled() {
+            let ticks_in_array = TICK_ARRAY_SIZE * TS_8 as i32;
+            let ta0 = build_tick_array(0, vec![]);
+            let ta1 = build_tick_array(3 * ticks_in_array, vec![10]);
+
+            let mut tick_array_bitmap = TickArrayBitmap::default();
+            tick_array_bitmap.set_initialized(0, TS_8, true).unwrap();
+            tick_array_bitmap
+                .set_initialized(3 * ticks_in_array, TS_8, true)
+                .unwrap();
+
+            let mut swap_tick_sequence =
+                TickSequence::new(ta0.borrow_mut(), Some(ta1.borrow_mut()), None);
+            swap_tick_sequence.set_tick_array_bitmap(&tick_array_bitmap);
+
+            swap_tick_sequence
+                .get_next_initialized_tick_index(0, TS_8, false, 0)
+                .unwrap();
+        }
+
+        #[test]
+        #[should_panic(expected = "InvalidTickArraySequence")]
+        /// Without a bitmap, tick arrays must be adjacent
+        fn skip_arrays_without_bitmap() {
+            let ticks_in_array = TICK_ARRAY_SIZE * TS_8 as i32;
+            let ta0 = build_tick_array(0, vec![]);
+            let ta1 = build_tick_array(3 * ticks_in_array, vec![10]);
+            let swap_tick_sequence =
+                TickSequence::new(ta0.borrow_mut(), Some(ta1.borrow_mut()), None);
+
+            swap_tick_sequence
+                .get_next_initialized_tick_index(0, TS_8, false, 0)
+                .unwrap();
+        }
+
+        #[test]
+        #[should_panic(expected = "InvalidTickArraySequence")]
+        fn empty_sequence() {
+            TickSequence::from_arrays(vec![]).unwrap();
+        }
     }
 
     mod tick_bound {
@@ -1,7 +1,9 @@
-use crate::state::Globalpool;
+use super::get_mint_decimals;
+use crate::state::{Globalpool, MarginAccount, PositionBundle, TradePosition};
 use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
-use solana_program::program::invoke_signed;
+use solana_program::program::{invoke, invoke_signed};
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
 use spl_token::instruction::{burn_checked, close_account, mint_to, set_authority, AuthorityType};
 
 pub fn transfer_from_owner_to_vault<'info>(
@@ -45,6 +47,142 @@ pub fn transfer_from_vault_to_owner<'info>(
     )
 }
 
+// Token Interface (SPL Token or Token-2022) transfer into a Globalpool vault. The vault receives
+// `amount` less the transfer fee of the mint, if any.
+pub fn transfer_from_owner_to_vault_checked<'info>(
+    authority: &Signer<'info>,
+    token_mint: &UncheckedAccount<'info>,
+    token_owner_account: &UncheckedAccount<'info>,
+    token_vault: &UncheckedAccount<'info>,
+    token_program: &UncheckedAccount<'info>,
+    amount: u64,
+) -> Result<()> {
+    invoke(
+        &spl_token_2022::instruction::transfer_checked(
+            token_program.key,
+            token_owner_account.key,
+            token_mint.key,
+            token_vault.key,
+            authority.key,
+            &[],
+            amount,
+            get_mint_decimals(token_mint)?,
+        )?,
+        &[
+            token_owner_account.to_account_info(),
+            token_mint.to_account_info(),
+            token_vault.to_account_info(),
+            authority.to_account_info(),
+            token_program.to_account_info(),
+        ],
+    )?;
+    Ok(())
+}
+
+// Token Interface (SPL Token or Token-2022) transfer out of a Globalpool vault
+pub fn transfer_from_vault_to_owner_checked<'info>(
+    globalpool: &Account<'info, Globalpool>,
+    token_mint: &UncheckedAccount<'info>,
+    token_vault: &UncheckedAccount<'info>,
+    token_owner_account: &UncheckedAccount<'info>,
+    token_program: &UncheckedAccount<'info>,
+    amount: u64,
+) -> Result<()> {
+    invoke_signed(
+        &spl_token_2022::instruction::transfer_checked(
+            token_program.key,
+            token_vault.key,
+            token_mint.key,
+            token_owner_account.key,
+            &globalpool.key(),
+            &[],
+            amount,
+            get_mint_decimals(token_mint)?,
+        )?,
+        &[
+            token_vault.to_account_info(),
+            token_mint.to_account_info(),
+            token_owner_account.to_account_info(),
+            globalpool.to_account_info(),
+            token_program.to_account_info(),
+        ],
+        &[&globalpool.seeds()],
+    )?;
+    Ok(())
+}
+
+// Creates the Globalpool's associated token account of `token_mint`, if it doesn't exist yet
+pub fn create_token_vault<'info>(
+    funder: &Signer<'info>,
+    globalpool: &Account<'info, Globalpool>,
+    token_mint: &UncheckedAccount<'info>,
+    token_vault: &UncheckedAccount<'info>,
+    token_program: &UncheckedAccount<'info>,
+    associated_token_program: &UncheckedAccount<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    invoke(
+        &create_associated_token_account_idempotent(
+            funder.key,
+            &globalpool.key(),
+            token_mint.key,
+            token_program.key,
+        ),
+        &[
+            funder.to_account_info(),
+            token_vault.to_account_info(),
+            globalpool.to_account_info(),
+            token_mint.to_account_info(),
+            system_program.to_account_info(),
+            token_program.to_account_info(),
+            associated_token_program.to_account_info(),
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn transfer_from_margin_vault_to_owner<'info>(
+    margin_account: &Account<'info, MarginAccount>,
+    margin_vault: &Account<'info, TokenAccount>,
+    token_owner_account: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            Transfer {
+                from: margin_vault.to_account_info(),
+                to: token_owner_account.to_account_info(),
+                authority: margin_account.to_account_info(),
+            },
+            &[&margin_account.seeds()],
+        ),
+        amount,
+    )
+}
+
+// Releases a position token held in escrow by a trade position (e.g. a locked liquidity position)
+pub fn transfer_locked_position_token<'info>(
+    position: &Account<'info, TradePosition>,
+    position_token_escrow: &Account<'info, TokenAccount>,
+    position_token_owner_account: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+) -> Result<()> {
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            Transfer {
+                from: position_token_escrow.to_account_info(),
+                to: position_token_owner_account.to_account_info(),
+                authority: position.to_account_info(),
+            },
+            &[&position.seeds()],
+        ),
+        1,
+    )
+}
+
 pub fn burn_and_close_user_position_token<'info>(
     token_authority: &Signer<'info>,
     receiver: &UncheckedAccount<'info>,
@@ -156,3 +294,46 @@ fn remove_position_token_mint_authority<'info>(
     )?;
     Ok(())
 }
+
+pub fn mint_position_bundle_token_and_remove_authority<'info>(
+    position_bundle: &Account<'info, PositionBundle>,
+    position_bundle_mint: &Account<'info, Mint>,
+    position_bundle_token_account: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+) -> Result<()> {
+    invoke_signed(
+        &mint_to(
+            token_program.key,
+            position_bundle_mint.to_account_info().key,
+            position_bundle_token_account.to_account_info().key,
+            position_bundle.to_account_info().key,
+            &[position_bundle.to_account_info().key],
+            1,
+        )?,
+        &[
+            position_bundle_mint.to_account_info(),
+            position_bundle_token_account.to_account_info(),
+            position_bundle.to_account_info(),
+            token_program.to_account_info(),
+        ],
+        &[&position_bundle.seeds()],
+    )?;
+
+    invoke_signed(
+        &set_authority(
+            token_program.key,
+            position_bundle_mint.to_account_info().key,
+            Option::None,
+            AuthorityType::MintTokens,
+            position_bundle.to_account_info().key,
+            &[position_bundle.to_account_info().key],
+        )?,
+        &[
+            position_bundle_mint.to_account_info(),
+            position_bundle.to_account_info(),
+            token_program.to_account_info(),
+        ],
+        &[&position_bundle.seeds()],
+    )?;
+    Ok(())
+}
@@ -0,0 +1,231 @@
+use {
+    crate::{errors::ErrorCode, math::div_round_up},
+    anchor_lang::prelude::*,
+    spl_token_2022::{
+        extension::{
+            transfer_fee::{TransferFee, TransferFeeConfig, MAX_FEE_BASIS_POINTS},
+            ExtensionType, StateWithExtensions,
+        },
+        state::{Account as TokenAccount, Mint},
+    },
+};
+
+// Mint extensions a Globalpool can hold. Others (e.g. non-transferable, permanent delegate,
+// confidential transfer, default frozen accounts) would let the pool's reserves be locked or seized.
+pub const SUPPORTED_MINT_EXTENSIONS: [ExtensionType; 2] = [
+    ExtensionType::TransferFeeConfig,
+    ExtensionType::InterestBearingConfig,
+];
+
+pub fn is_supported_token_program(token_program: &Pubkey) -> bool {
+    *token_program == spl_token::ID || *token_program == spl_token_2022::ID
+}
+
+// Checks that `token_mint` is a mint of `token_program` without unsupported extensions
+pub fn validate_token_mint(token_mint: &AccountInfo, token_program: &AccountInfo) -> Result<()> {
+    if !is_supported_token_program(token_program.key) || token_mint.owner != token_program.key {
+        return Err(ErrorCode::InvalidTokenProgram.into());
+    }
+
+    let data = token_mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+
+    if mint
+        .get_extension_types()?
+        .iter()
+        .any(|extension| !SUPPORTED_MINT_EXTENSIONS.contains(extension))
+    {
+        return Err(ErrorCode::UnsupportedTokenMintExtension.into());
+    }
+
+    Ok(())
+}
+
+pub fn get_mint_decimals(token_mint: &AccountInfo) -> Result<u8> {
+    let data = token_mint.try_borrow_data()?;
+    Ok(StateWithExtensions::<Mint>::unpack(&data)?.base.decimals)
+}
+
+pub fn get_token_account_owner(token_account: &AccountInfo) -> Result<Pubkey> {
+    let data = token_account.try_borrow_data()?;
+    Ok(StateWithExtensions::<TokenAccount>::unpack(&data)?
+        .base
+        .owner)
+}
+
+// Transfer fee of the current epoch, if the mint has the transfer fee extension
+pub fn get_epoch_transfer_fee(token_mint: &AccountInfo) -> Result<Option<TransferFee>> {
+    if *token_mint.owner != spl_token_2022::ID {
+        return Ok(None);
+    }
+
+    let data = token_mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+
+    match mint.get_extension::<TransferFeeConfig>() {
+        Ok(transfer_fee_config) => Ok(Some(
+            *transfer_fee_config.get_epoch_fee(Clock::get()?.epoch),
+        )),
+        Err(_) => Ok(None),
+    }
+}
+
+// Amount received by the destination when `amount` is transferred
+pub fn calculate_transfer_fee_excluded_amount(
+    transfer_fee: &Option<TransferFee>,
+    amount: u64,
+) -> Result<u64> {
+    match transfer_fee {
+        None => Ok(amount),
+        Some(transfer_fee) => {
+            let fee = transfer_fee
+                .calculate_fee(amount)
+                .ok_or(ErrorCode::TransferFeeCalculationError)?;
+            Ok(amount - fee)
+        }
+    }
+}
+
+// Amount to transfer so that the destination receives `amount`
+pub fn calculate_transfer_fee_included_amount(
+    transfer_fee: &Option<TransferFee>,
+    amount: u64,
+) -> Result<u64> {
+    let transfer_fee = match transfer_fee {
+        Some(transfer_fee) if amount != 0 => transfer_fee,
+        _ => return Ok(amount),
+    };
+
+    let transfer_fee_basis_points = u16::from(transfer_fee.transfer_fee_basis_points);
+    let maximum_fee = u64::from(transfer_fee.maximum_fee);
+
+    if transfer_fee_basis_points == 0 {
+        return Ok(amount);
+    }
+
+    // With a 100% fee, the maximum fee is always taken
+    if transfer_fee_basis_points == MAX_FEE_BASIS_POINTS {
+        return Ok(amount
+            .checked_add(maximum_fee)
+            .ok_or(ErrorCode::TransferFeeCalculationError)?);
+    }
+
+    // amount = floor(pre_fee_amount * (MAX_FEE_BASIS_POINTS - bps) / MAX_FEE_BASIS_POINTS)
+    let pre_fee_amount = div_round_up(
+        (amount as u128) * (MAX_FEE_BASIS_POINTS as u128),
+        (MAX_FEE_BASIS_POINTS - transfer_fee_basis_points) as u128,
+    )?;
+
+    let fee = pre_fee_amount - amount as u128;
+    if fee >= maximum_fee as u128 {
+        return Ok(amount
+            .checked_add(maximum_fee)
+            .ok_or(ErrorCode::TransferFeeCalculationError)?);
+    }
+
+    Ok(u64::try_from(pre_fee_amount).map_err(|_| ErrorCode::TransferFeeCalculationError)?)
+}
+
+#[cfg(test)]
+mod transfer_fee_tests {
+    use {
+        super::*,
+        spl_token_2022::pod::{PodU16, PodU64},
+    };
+
+    fn transfer_fee(transfer_fee_basis_points: u16, maximum_fee: u64) -> Option<TransferFee> {
+        Some(TransferFee {
+            epoch: PodU64::from(0),
+            maximum_fee: PodU64::from(maximum_fee),
+            transfer_fee_basis_points: PodU16::from(transfer_fee_basis_points),
+        })
+    }
+
+    #[test]
+    fn no_transfer_fee() {
+        assert_eq!(
+            calculate_transfer_fee_excluded_amount(&None, 1_000).unwrap(),
+            1_000
+        );
+        assert_eq!(
+            calculate_transfer_fee_included_amount(&None, 1_000).unwrap(),
+            1_000
+        );
+
+        let zero_fee = transfer_fee(0, u64::MAX);
+        assert_eq!(
+            calculate_transfer_fee_excluded_amount(&zero_fee, 1_000).unwrap(),
+            1_000
+        );
+        assert_eq!(
+            calculate_transfer_fee_included_amount(&zero_fee, 1_000).unwrap(),
+            1_000
+        );
+    }
+
+    #[test]
+    fn fee_rate_applied() {
+        // 1% fee
+        let fee = transfer_fee(100, u64::MAX);
+        assert_eq!(
+            calculate_transfer_fee_excluded_amount(&fee, 10_000).unwrap(),
+            9_900
+        );
+        assert_eq!(
+            calculate_transfer_fee_included_amount(&fee, 9_900).unwrap(),
+            10_000
+        );
+        // Fee is rounded up, so 1 more token is needed
+        assert_eq!(
+            calculate_transfer_fee_included_amount(&fee, 9_901).unwrap(),
+            10_002
+        );
+        assert_eq!(
+            calculate_transfer_fee_excluded_amount(&fee, 10_002).unwrap(),
+            9_901
+        );
+    }
+
+    #[test]
+    fn maximum_fee_applied() {
+        let fee = transfer_fee(100, 50);
+        assert_eq!(
+            calculate_transfer_fee_excluded_amount(&fee, 1_000_000).unwrap(),
+            999_950
+        );
+        assert_eq!(
+            calculate_transfer_fee_included_amount(&fee, 999_950).unwrap(),
+            1_000_000
+        );
+    }
+
+    #[test]
+    fn full_fee_rate() {
+        let fee = transfer_fee(MAX_FEE_BASIS_POINTS, 100);
+        assert_eq!(
+            calculate_transfer_fee_excluded_amount(&fee, 1_000).unwrap(),
+            900
+        );
+        assert_eq!(
+            calculate_transfer_fee_included_amount(&fee, 900).unwrap(),
+            1_000
+        );
+        assert_eq!(calculate_transfer_fee_included_amount(&fee, 0).unwrap(), 0);
+    }
+
+    #[test]
+    fn included_amount_is_minimal() {
+        let fee = transfer_fee(333, 1_000_000);
+        for amount in 1..5_000 {
+            let included = calculate_transfer_fee_included_amount(&fee, amount).unwrap();
+            assert!(calculate_transfer_fee_excluded_amount(&fee, included).unwrap() >= amount);
+            assert!(calculate_transfer_fee_excluded_amount(&fee, included - 1).unwrap() < amount);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "TransferFeeCalculationError")]
+    fn included_amount_overflow() {
+        calculate_transfer_fee_included_amount(&transfer_fee(100, u64::MAX), u64::MAX).unwrap();
+    }
+}
@@ -1,8 +1,8 @@
 use {
-    crate::errors::ErrorCode,
+    crate::{errors::ErrorCode, state::*},
     anchor_lang::{
         prelude::{AccountInfo, Pubkey, *},
-        ToAccountInfo,
+        Discriminator, ToAccountInfo,
     },
     anchor_spl::token::TokenAccount,
     solana_program::program_option::COption,
@@ -11,6 +11,9 @@ use {
 
 /// Verify position authority for either Liquidity or Trade Position.
 ///
+/// Bundled liquidity positions are represented by the token of their PositionBundle, so the holder
+/// (or delegate) of the bundle token is the authority of every position in the bundle.
+///
 /// # Arguments
 ///
 /// * `position_token_account`
@@ -35,6 +38,87 @@ pub fn verify_position_authority<'info>(
     Ok(())
 }
 
+/// Verify the signer is the position authority, or an operator the position owner approved for the
+/// calling instruction.
+///
+/// An operator is only valid while the position token stays with the owner that approved it.
+///
+/// # Arguments
+///
+/// * `position_token_account`
+/// * `position_authority` - the owner, the delegate or the operator
+/// * `position` - key of the Liquidity or Trade Position
+/// * `position_operator` - PositionOperator passed by the caller, if the signer is an operator
+/// * `permission` - the OPERATOR_* permission the calling instruction requires
+pub fn verify_position_authority_or_operator<'info>(
+    position_token_account: &TokenAccount,
+    position_authority: &AccountInfo<'info>,
+    position: &Pubkey,
+    position_operator: Option<&PositionOperator>,
+    permission: u8,
+) -> Result<()> {
+    let position_operator = match position_operator {
+        Some(position_operator) => position_operator,
+        None => return verify_position_authority(position_token_account, position_authority),
+    };
+
+    if position_operator.position != *position
+        || position_operator.authority != position_token_account.owner
+    {
+        return Err(ErrorCode::InvalidPositionOperator.into());
+    }
+    validate_owner(&position_operator.operator, position_authority)?;
+    if !position_operator.has_permission(permission) {
+        return Err(ErrorCode::PositionOperatorNotPermitted.into());
+    }
+
+    Ok(())
+}
+
+// Loads the PositionOperator if it is the first of the remaining accounts, returning the accounts
+// that follow it
+pub fn load_position_operator<'a, 'info>(
+    accounts: &'a [AccountInfo<'info>],
+) -> Result<(
+    Option<Account<'info, PositionOperator>>,
+    &'a [AccountInfo<'info>],
+)> {
+    let is_position_operator = match accounts.first() {
+        Some(account) => {
+            *account.owner == crate::ID
+                && account
+                    .try_borrow_data()?
+                    .starts_with(&PositionOperator::discriminator())
+        }
+        None => false,
+    };
+
+    if !is_position_operator {
+        return Ok((None, accounts));
+    }
+
+    Ok((
+        Some(Account::<PositionOperator>::try_from(&accounts[0])?),
+        &accounts[1..],
+    ))
+}
+
+// Position mint of a LiquidityPosition or TradePosition account
+pub fn get_position_mint(position: &AccountInfo) -> Result<Pubkey> {
+    if *position.owner != crate::ID {
+        return Err(ErrorCode::InvalidPositionAccount.into());
+    }
+
+    let data = position.try_borrow_data()?;
+    if data.starts_with(&LiquidityPosition::discriminator()) {
+        Ok(LiquidityPosition::try_deserialize(&mut &data[..])?.position_mint)
+    } else if data.starts_with(&TradePosition::discriminator()) {
+        Ok(TradePosition::try_deserialize(&mut &data[..])?.position_mint)
+    } else {
+        Err(ErrorCode::InvalidPositionAccount.into())
+    }
+}
+
 fn validate_owner(expected_owner: &Pubkey, owner_account_info: &AccountInfo) -> Result<()> {
     if expected_owner != owner_account_info.key || !owner_account_info.is_signer {
         return Err(ErrorCode::MissingOrInvalidDelegate.into());
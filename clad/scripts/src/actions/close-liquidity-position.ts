@@ -134,6 +134,8 @@ async function main() {
     globalpool: globalpoolKey,
     position: positionKey,
     positionTokenAccount,
+    tokenMintA: tokenMintA.address,
+    tokenMintB: tokenMintB.address,
     tokenOwnerAccountA: authorityTokenAccountA,
     tokenOwnerAccountB: authorityTokenAccountB,
     tokenVaultA,
@@ -141,7 +143,8 @@ async function main() {
     tickArrayLower: tickArrayLowerKey,
     tickArrayUpper: tickArrayUpperKey,
     // sys
-    tokenProgram: TOKEN_PROGRAM_ID,
+    tokenProgramA: TOKEN_PROGRAM_ID,
+    tokenProgramB: TOKEN_PROGRAM_ID,
   }
 
   const collectFeesAccounts = decreaseLiquidityPositionAccounts
@@ -96,7 +96,8 @@ async function createPools(connection: Connection, program: anchor.Program<Clad>
       tokenVaultA: tokenVaultA,
       tokenVaultB: tokenVaultB,
       // sys
-      tokenProgram: TOKEN_PROGRAM_ID,
+      tokenProgramA: TOKEN_PROGRAM_ID,
+      tokenProgramB: TOKEN_PROGRAM_ID,
       associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
       systemProgram: SystemProgram.programId,
       rent: SYSVAR_RENT_PUBKEY,
@@ -210,6 +210,8 @@ async function main() {
   const swapAccounts = {
     tokenAuthority,
     globalpool: globalpoolKey,
+    tokenMintA: mintA.address,
+    tokenMintB: mintB.address,
     tokenOwnerAccountA: authorityTokenAccountA,
     tokenOwnerAccountB: authorityTokenAccountB,
     tokenVaultA,
@@ -218,7 +220,8 @@ async function main() {
     tickArray1: tickArrayKeys[1],
     tickArray2: tickArrayKeys[2],
     // sys
-    tokenProgram: TOKEN_PROGRAM_ID,
+    tokenProgramA: TOKEN_PROGRAM_ID,
+    tokenProgramB: TOKEN_PROGRAM_ID,
   }
 
   const swapParams = {
@@ -209,6 +209,8 @@ async function main() {
       globalpool: globalpoolKey,
       position: positionKey,
       positionTokenAccount,
+      tokenMintA: tokenMintAKey,
+      tokenMintB: tokenMintBKey,
       tokenOwnerAccountA: authorityTokenAccountA,
       tokenOwnerAccountB: authorityTokenAccountB,
       tokenVaultA,
@@ -216,7 +218,8 @@ async function main() {
       tickArrayLower: tickArrayLowerKey,
       tickArrayUpper: tickArrayUpperKey,
       // sys
-      tokenProgram: TOKEN_PROGRAM_ID,
+      tokenProgramA: TOKEN_PROGRAM_ID,
+      tokenProgramB: TOKEN_PROGRAM_ID,
     }
 
     const txId = await createTransactionChained(
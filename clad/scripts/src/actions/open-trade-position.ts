@@ -264,6 +264,8 @@ async function main() {
     tickUpperIndex,
     borrowA,
     loanDuration,
+    isPerpetual: false,
+    liquidityPositionCollateral: false,
     swapInstructionData: swapInstruction.data,
   }
 
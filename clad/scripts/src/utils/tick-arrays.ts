@@ -31,6 +31,23 @@ export function getTickArrayKey(
   )[0]
 }
 
+/**
+ * Get the TickArrayBitmap key of a globalpool
+ * 
+ * @param globalpool 
+ * @param programId 
+ * @returns 
+ */
+export function getTickArrayBitmapKey(
+  globalpool: PublicKey,
+  programId: PublicKey
+) {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from('tick_array_bitmap'), globalpool.toBuffer()],
+    programId
+  )[0]
+}
+
 /**
  * Get TickArray key from any valid tick index, which doesn't have to be a start index.
  * 
@@ -70,6 +87,7 @@ export async function initTickArray(
     funder: provider.wallet.publicKey,
     globalpool,
     tickArray: tickArrayKey,
+    tickArrayBitmap: getTickArrayBitmapKey(globalpool, program.programId),
     systemProgram: SystemProgram.programId,
   }
 
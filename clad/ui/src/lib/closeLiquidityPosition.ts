@@ -102,6 +102,8 @@ export async function closeLiquidityPosition(params: CloseLiquidityPositionParam
 		globalpool: globalpoolKey,
 		position: positionKey,
 		positionTokenAccount,
+		tokenMintA: tokenMintAKey,
+		tokenMintB: tokenMintBKey,
 		tokenOwnerAccountA,
 		tokenOwnerAccountB,
 		tokenVaultA,
@@ -109,7 +111,8 @@ export async function closeLiquidityPosition(params: CloseLiquidityPositionParam
 		tickArrayLower: tickArrayLowerKey,
 		tickArrayUpper: tickArrayUpperKey,
 		// sys
-		tokenProgram: TOKEN_PROGRAM_ID,
+		tokenProgramA: TOKEN_PROGRAM_ID,
+		tokenProgramB: TOKEN_PROGRAM_ID,
 	}
 
 	const collectFeesAccounts = decreaseLiquidityPositionAccounts
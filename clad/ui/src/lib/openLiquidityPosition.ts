@@ -163,6 +163,8 @@ export async function openLiquidityPosition(params: OpenLiquidityPositionParams)
     globalpool: globalpoolKey,
     position: positionKey,
     positionTokenAccount,
+    tokenMintA: tokenMintAKey,
+    tokenMintB: tokenMintBKey,
     tokenOwnerAccountA: authorityTokenAccountA,
     tokenOwnerAccountB: authorityTokenAccountB,
     tokenVaultA,
@@ -170,7 +172,8 @@ export async function openLiquidityPosition(params: OpenLiquidityPositionParams)
     tickArrayLower: tickArrayLowerKey,
     tickArrayUpper: tickArrayUpperKey,
     // sys
-    tokenProgram: TOKEN_PROGRAM_ID,
+    tokenProgramA: TOKEN_PROGRAM_ID,
+    tokenProgramB: TOKEN_PROGRAM_ID,
   }
 
   await program.methods
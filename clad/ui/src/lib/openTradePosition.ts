@@ -260,6 +260,8 @@ export async function openTradePosition(params: OpenTradePositionParams) {
     tickUpperIndex,
     borrowA: isBorrowA,
     loanDuration: new BN(loanDuration),
+    isPerpetual: false,
+    liquidityPositionCollateral: false,
     swapInstructionData: swapInstruction.data,
   }
 
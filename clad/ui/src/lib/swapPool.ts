@@ -128,6 +128,8 @@ export async function swapPool(params: SwapPoolParams) {
   const swapAccounts = {
     tokenAuthority,
     globalpool: globalpoolKey,
+    tokenMintA,
+    tokenMintB,
     tokenOwnerAccountA: authorityTokenAccountA,
     tokenOwnerAccountB: authorityTokenAccountB,
     tokenVaultA,
@@ -136,7 +138,8 @@ export async function swapPool(params: SwapPoolParams) {
     tickArray1: tickArrayKeys[1],
     tickArray2: tickArrayKeys[2],
     // sys
-    tokenProgram: TOKEN_PROGRAM_ID,
+    tokenProgramA: TOKEN_PROGRAM_ID,
+    tokenProgramB: TOKEN_PROGRAM_ID,
   }
 
   const swapParams = {